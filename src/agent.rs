@@ -3,9 +3,9 @@ use serde_json::Value;
 use std::sync::Arc;
 use tokio::sync::mpsc;
 
-use crate::budget::{Budget, LoopDetector};
+use crate::budget::{Budget, LoopDetector, SpendDecision};
 use crate::cache::FileCache;
-use crate::client::{Client, ContentPart, Message, MessageContent, Tool, ToolCall};
+use crate::client::{Client, ContentPart, Message, MessageContent, ModelResponse, Tool, ToolCall};
 use crate::history::History;
 use crate::hooks::{self, HookConfig};
 use crate::mcp::McpClient;
@@ -13,6 +13,12 @@ use crate::tools::{self};
 use crate::tui::UiEvent;
 
 const MAX_TOOL_CALLS: usize = 40;
+/// Semantic hits ranked against the task text for focus files.
+pub(crate) const FOCUS_SEMANTIC_HITS: usize = 8;
+/// Semantic hits merged into an `orient` result.
+pub(crate) const ORIENT_SEMANTIC_HITS: usize = 6;
+/// Attached MCP resources may fill at most 1/N of the context window.
+const RESOURCE_CONTEXT_SHARE: usize = 4;
 
 const SYSTEM_PROMPT_BASE: &str = "You are PareCode, a coding assistant. \
 Complete tasks in minimum tool calls. A project index is pre-loaded.\n\
@@ -65,15 +71,28 @@ Do not read files unless strictly necessary. Keep responses short.";
         tool_calls: vec![],
     }];
 
+    config.spend.lock().unwrap().begin_task();
+    enforce_spend_limits(config, None, &ui_tx).await?;
+
     let tx_clone = ui_tx.clone();
     let response = client
-        .chat(QUICK_SYSTEM, &messages, &quick_tools, move |chunk| {
-            let _ = tx_clone.send(UiEvent::Chunk(chunk.to_string()));
-        })
+        .chat_with_retry(
+            QUICK_SYSTEM,
+            &messages,
+            &quick_tools,
+            move |chunk| {
+                let _ = tx_clone.send(UiEvent::Chunk(chunk.to_string()));
+            },
+            retry_notifier(&ui_tx),
+        )
         .await?;
 
     let total_input = response.input_tokens;
     let total_output = response.output_tokens;
+    let cache_read = response.cache_read_tokens;
+    let cache_write = response.cache_write_tokens;
+    let cost_usd = client.cost_of(&response);
+    config.spend.lock().unwrap().add(total_input + total_output, cost_usd);
 
     // Send token stats so TUI tracks inflight usage (survives cancel/crash)
     let _ = ui_tx.send(UiEvent::TokenStats {
@@ -81,8 +100,11 @@ Do not read files unless strictly necessary. Keep responses short.";
         _output: total_output,
         total_input,
         total_output,
+        total_cache_read: cache_read,
+        total_cache_write: cache_write,
+        total_cost: cost_usd,
         tool_calls: 0,
-    }); 
+    });
 
     // Execute at most one tool call
     if let Some(tc) = response.tool_calls.first() {
@@ -102,6 +124,10 @@ Do not read files unless strictly necessary. Keep responses short.";
     let _ = ui_tx.send(UiEvent::AgentDone {
         input_tokens: total_input,
         output_tokens: total_output,
+        cache_read_tokens: cache_read,
+        cache_write_tokens: cache_write,
+        cost_usd,
+        model: client.model.clone(),
         tool_calls: response.tool_calls.len().min(1),
         compressed_count: 0,
         duration_secs: task_start.elapsed().as_secs() as u32,
@@ -129,33 +155,29 @@ pub async fn run_tui(
         .ok()
         .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
         .unwrap_or_else(|| "unknown".to_string());
-    // MCP tools are appended alongside native tools each turn
-    let mcp_tools = config.mcp.all_tools().await;
-    let mcp_tool_defs: Vec<Tool> = mcp_tools.iter().map(|mt| Tool {
-        name: mt.qualified_name.clone(),
-        description: mt.description.clone(),
-        parameters: mt.input_schema.clone(),
-    }).collect();
-
     let mut messages: Vec<Message> = Vec::new();
+    // Index into [primary, fallbacks..] — sticky for the rest of the task once failed over.
+    let mut active_client = 0usize;
     let mut total_input_tokens = 0u32;
+    let mut total_cache_read = 0u32;
+    let mut total_cache_write = 0u32;
+    let mut total_cost: Option<f64> = None;
     let mut total_output_tokens = 0u32;
     let mut tool_call_count = 0usize;
     let mut turn: usize = 0;
     // Cache tool list — rebuild only when phase key changes.
-    let mut cached_tools: Option<(/*key:*/ (usize, bool), Vec<Tool>)> = None;
+    let mut cached_tools: Option<(/*key:*/ (usize, bool, u64), Vec<Tool>)> = None;
 
     let budget = Budget::new(config.context_tokens);
     let mut history = History::default();
     let mut cache = shared_cache.lock().await;
     cache.next_turn(); // advance turn counter at task boundary
     let mut loop_detector = LoopDetector::default();
+    config.spend.lock().unwrap().begin_task();
 
     // Fetch git status once so build_system_prompt stays pure.
     let git_status: Option<String> = if config.git_context {
-        std::env::current_dir()
-            .ok()
-            .and_then(|cwd| crate::git::GitRepo::open(&cwd))
+        task_repo(config)
             .and_then(|repo| repo.status_short().ok())
             .filter(|s| !s.trim().is_empty())
     } else {
@@ -166,6 +188,8 @@ pub async fn run_tui(
     let system_tokens = crate::budget::estimate_tokens(system_prompt);
 
     // ── PIE context assembly ─────────────────────────────────────────────────
+    // Re-embed files edited since the last task, then rank symbols by the task text
+    let semantic = semantic_hits(config, task, FOCUS_SEMANTIC_HITS, true).await;
     let pie_ctx = config.project_graph.as_ref()
         .map(|graph| crate::pie::build_pie_context(
             task,
@@ -173,27 +197,34 @@ pub async fn run_tui(
             graph,
             config.project_narrative.as_deref(),
             config.flow_paths.as_deref(),
+            &semantic,
         ))
         .unwrap_or_else(crate::pie::PieContext::empty);
 
     let symbol_preload = crate::pie::build_symbol_preload(&config.attached_symbols);
-    let user_content = format!("{}{}{}", symbol_preload, pie_ctx.user_prefix, build_user_message(task, &attached));
+    let resource_budget = config.context_tokens as usize / RESOURCE_CONTEXT_SHARE;
+    let resource_preload = config.mcp.resource_preload(&config.attached_resources, resource_budget).await;
+    let user_content = format!(
+        "{}{}{}{}",
+        symbol_preload,
+        resource_preload,
+        pie_ctx.user_prefix,
+        build_user_message(task, &attached)
+    );
     messages.extend(pie_ctx.injection_messages);
 
     // ── Git checkpoint ────────────────────────────────────────────────────────
     // WIP checkpoint so /undo can restore dirty state.
     let checkpoint_hash: Option<String> = if config.git_context {
-        std::env::current_dir().ok().and_then(|cwd| {
-            crate::git::GitRepo::open(&cwd).and_then(|repo| {
-                let summary: String = task
-                    .lines()
-                    .next()
-                    .unwrap_or(task)
-                    .chars()
-                    .take(60)
-                    .collect();
-                repo.checkpoint(&summary).ok()
-            })
+        task_repo(config).and_then(|repo| {
+            let summary: String = task
+                .lines()
+                .next()
+                .unwrap_or(task)
+                .chars()
+                .take(60)
+                .collect();
+            repo.checkpoint(&summary).ok()
         })
     } else {
         None
@@ -224,9 +255,15 @@ pub async fn run_tui(
         tool_calls: vec![],
     });
 
+    let mut turn_config = config.clone();
     loop {
         cache.next_turn();
         turn += 1;
+        // Tools this turn see the watcher's latest graph
+        if let Some(graph) = config.live_graph.read().unwrap().clone() {
+            turn_config.project_graph = Some(graph);
+        }
+        let config = &turn_config;
 
         // ── Hard tool-call budget ─────────────────────────────────────────────
         if tool_call_count >= MAX_TOOL_CALLS {
@@ -247,30 +284,50 @@ pub async fn run_tui(
 
         // ── Phase-adaptive tool selection ────────────────────────────────────
         let has_graph = config.project_graph.is_some();
-        let tool_key = (turn, has_graph);
+        // MCP tools are appended alongside native tools; a server re-listing them bumps the revision
+        let tool_key = (turn, has_graph, config.mcp.revision());
         let tools = match &cached_tools {
             Some((key, t)) if *key == tool_key => t.clone(),
             _ => {
                 let mut t = tools::tools_for_turn(turn, has_graph);
-                t.extend(mcp_tool_defs.iter().cloned());
+                if has_graph && config.semantic.is_some() {
+                    t.push(tools::def(tools::pie_tool::semantic_search_definition()));
+                }
+                t.extend(config.mcp.all_tools().await.into_iter().map(|mt| Tool {
+                    name: mt.qualified_name,
+                    description: mt.description,
+                    parameters: mt.input_schema,
+                }));
                 cached_tools = Some((tool_key, t.clone()));
                 t
             }
         };
 
+        // ── Spend limits ──────────────────────────────────────────────────────
+        // Pauses here until the user extends the limit; aborting ends the task.
+        enforce_spend_limits(config, total_cost, &ui_tx).await?;
+
         // ── Call the model ────────────────────────────────────────────────────
         // ThinkParser splits <think>…</think> blocks from normal text so the TUI
         // can render reasoning separately. It owns all buffering state — no Arc/Mutex needed.
         let tx_clone = ui_tx.clone();
         let parser = std::sync::Arc::new(std::sync::Mutex::new(ThinkParser::new()));
         let parser_c = parser.clone();
-        let response = client
-            .chat(system_prompt, &messages, &tools, move |chunk| {
+        let response = chat_with_failover(
+            client,
+            config,
+            &mut active_client,
+            system_prompt,
+            &messages,
+            &tools,
+            move |chunk| {
                 let (normal, thinking) = parser_c.lock().unwrap().push(chunk);
                 if !normal.is_empty() { let _ = tx_clone.send(UiEvent::Chunk(normal)); }
                 if !thinking.is_empty() { let _ = tx_clone.send(UiEvent::ThinkingChunk(thinking)); }
-            })
-            .await?;
+            },
+            &ui_tx,
+        )
+        .await?;
         // Drain any remainder held back by the lookahead buffer.
         {
             let p = std::mem::replace(&mut *parser.lock().unwrap(), ThinkParser::new());
@@ -281,6 +338,13 @@ pub async fn run_tui(
 
         total_input_tokens += response.input_tokens;
         total_output_tokens += response.output_tokens;
+        total_cache_read += response.cache_read_tokens;
+        total_cache_write += response.cache_write_tokens;
+        // Cost with the client that actually answered — fallbacks price differently.
+        let (_, responder) = client_at(client, config, active_client);
+        let turn_cost = responder.cost_of(&response);
+        total_cost = add_cost(total_cost, turn_cost);
+        config.spend.lock().unwrap().add(response.input_tokens + response.output_tokens, turn_cost);
 
         // Always send token stats so the TUI/telemetry can track usage live.
         // If the agent crashes or is cancelled, partial stats are already recorded.
@@ -290,6 +354,9 @@ pub async fn run_tui(
                 _output: response.output_tokens,
                 total_input: total_input_tokens,
                 total_output: total_output_tokens,
+                total_cache_read,
+                total_cache_write,
+                total_cost,
                 tool_calls: tool_call_count,
             });
         }
@@ -394,13 +461,16 @@ pub async fn run_tui(
             _output: 0,
             total_input: total_input_tokens,
             total_output: total_output_tokens,
+            total_cache_read,
+            total_cache_write,
+            total_cost,
             tool_calls: tool_call_count,
         });
     }
 
     // ── on_task_done hooks ────────────────────────────────────────────────────
     // Run after the agent loop. Output goes to TUI only — not into context.
-    for hr in hooks::run_task_done_hooks(&config.hooks, config.hooks_enabled).await {
+    for hr in hooks::run_task_done_hooks(&config.hooks, config.hooks_enabled, config.workdir.as_deref()).await {
         let _ = ui_tx.send(UiEvent::HookOutput {
             event: "on_task_done".to_string(),
             output: hr.output,
@@ -411,23 +481,21 @@ pub async fn run_tui(
     // ── Git post-task ─────────────────────────────────────────────────────────
     // Emit a diff notification and optionally auto-commit.
     if config.git_context {
-        if let Some(cwd) = std::env::current_dir().ok() {
-            if let Some(repo) = crate::git::GitRepo::open(&cwd) {
-                let ref_pt = checkpoint_hash.as_deref().unwrap_or("HEAD");
-                let pt = repo.post_task(ref_pt, task, config.auto_commit, &config.auto_commit_prefix);
-                if let Some(stat) = pt.diff_stat {
-                    let _ = ui_tx.send(UiEvent::GitChanges {
-                        stat,
-                        checkpoint_hash: checkpoint_hash.clone(),
-                        files_changed: pt.files_changed,
-                    });
-                }
-                if let Some(msg) = pt.auto_committed {
-                    let _ = ui_tx.send(UiEvent::GitAutoCommit { message: msg });
-                }
-                if let Some(err) = pt.commit_error {
-                    let _ = ui_tx.send(UiEvent::GitError(err));
-                }
+        if let Some(repo) = task_repo(config) {
+            let ref_pt = checkpoint_hash.as_deref().unwrap_or("HEAD");
+            let pt = repo.post_task(ref_pt, task, config.auto_commit, &config.auto_commit_prefix);
+            if let Some(stat) = pt.diff_stat {
+                let _ = ui_tx.send(UiEvent::GitChanges {
+                    stat,
+                    checkpoint_hash: checkpoint_hash.clone(),
+                    files_changed: pt.files_changed,
+                });
+            }
+            if let Some(msg) = pt.auto_committed {
+                let _ = ui_tx.send(UiEvent::GitAutoCommit { message: msg });
+            }
+            if let Some(err) = pt.commit_error {
+                let _ = ui_tx.send(UiEvent::GitError(err));
             }
         }
     }
@@ -443,6 +511,10 @@ pub async fn run_tui(
     let _ = ui_tx.send(UiEvent::AgentDone {
         input_tokens: total_input_tokens,
         output_tokens: total_output_tokens,
+        cache_read_tokens: total_cache_read,
+        cache_write_tokens: total_cache_write,
+        cost_usd: total_cost,
+        model: client_at(client, config, active_client).1.model.clone(),
         tool_calls: tool_call_count,
         compressed_count: history.compressed_count(),
         duration_secs: task_start.elapsed().as_secs() as u32,
@@ -453,6 +525,104 @@ pub async fn run_tui(
 }
 
 
+/// Forward client retry notices to the UI as `ApiRetry` events.
+pub(crate) fn retry_notifier(
+    ui_tx: &mpsc::UnboundedSender<UiEvent>,
+) -> impl Fn(&crate::client::retry::RetryNotice) + '_ {
+    move |n| {
+        let _ = ui_tx.send(UiEvent::ApiRetry {
+            attempt: n.attempt,
+            max_retries: n.max_retries,
+            delay_ms: n.delay.as_millis() as u64,
+            reason: n.reason.clone(),
+        });
+    }
+}
+
+/// Check spend limits before a model call. When one is reached, pause with a
+/// `SpendLimitHit` event and wait for the user: extending raises that limit and
+/// continues; aborting (or nobody answering) fails the task.
+async fn enforce_spend_limits(
+    config: &AgentConfig,
+    task_cost: Option<f64>,
+    ui_tx: &mpsc::UnboundedSender<UiEvent>,
+) -> Result<()> {
+    enforce_limits(&config.spend_limits, &config.spend, task_cost, ui_tx).await
+}
+
+/// `enforce_spend_limits` for model calls made without an `AgentConfig`
+/// (the planner).
+pub(crate) async fn enforce_limits(
+    limits: &crate::budget::SpendLimits,
+    spend: &std::sync::Mutex<crate::budget::SpendGuard>,
+    task_cost: Option<f64>,
+    ui_tx: &mpsc::UnboundedSender<UiEvent>,
+) -> Result<()> {
+    if limits.is_empty() {
+        return Ok(());
+    }
+    loop {
+        let Some(hit) = spend.lock().unwrap().check(limits, task_cost) else {
+            return Ok(());
+        };
+        let (reply_tx, reply_rx) = tokio::sync::oneshot::channel();
+        let _ = ui_tx.send(UiEvent::SpendLimitHit { hit, reply_tx });
+        match reply_rx.await {
+            Ok(SpendDecision::Extend) => spend.lock().unwrap().extend(hit.kind),
+            Ok(SpendDecision::Abort) | Err(_) => anyhow::bail!("stopped: {hit}"),
+        }
+    }
+}
+
+/// Profile name and client at `index` in `[primary, fallbacks..]`.
+fn client_at<'a>(primary: &'a Client, config: &'a AgentConfig, index: usize) -> (&'a str, &'a Client) {
+    match index {
+        0 => (config._profile_name.as_str(), primary),
+        i => (config.fallback_clients[i - 1].0.as_str(), &config.fallback_clients[i - 1].1),
+    }
+}
+
+/// Sum of two optional costs — unpriced turns don't erase priced ones.
+fn add_cost(total: Option<f64>, turn: Option<f64>) -> Option<f64> {
+    match (total, turn) {
+        (Some(a), Some(b)) => Some(a + b),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Call the active model, retrying transient failures per its policy. When a
+/// client gives up, fail over to the next of `config.fallback_clients` and
+/// resend the same request. `active` indexes `[primary, fallbacks..]` and is
+/// left pointing at the client that answered, so later turns start there.
+#[allow(clippy::too_many_arguments)]
+async fn chat_with_failover(
+    primary: &Client,
+    config: &AgentConfig,
+    active: &mut usize,
+    system: &str,
+    messages: &[Message],
+    tools: &[Tool],
+    on_text: impl Fn(&str),
+    ui_tx: &mpsc::UnboundedSender<UiEvent>,
+) -> Result<ModelResponse> {
+    let fallbacks = &config.fallback_clients;
+    loop {
+        let (name, client) = client_at(primary, config, *active);
+        match client.chat_with_retry(system, messages, tools, &on_text, retry_notifier(ui_tx)).await {
+            Ok(resp) => return Ok(resp),
+            Err(e) if *active < fallbacks.len() => {
+                let _ = ui_tx.send(UiEvent::ModelFailover {
+                    from: name.to_string(),
+                    to: fallbacks[*active].0.clone(),
+                    reason: e.to_string(),
+                });
+                *active += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
 /// Load project conventions from AGENTS.md, CLAUDE.md, or .parecode/conventions.md.
 /// Returns None if no conventions file is found.
 fn load_conventions() -> Option<String> {
@@ -472,6 +642,7 @@ fn load_conventions() -> Option<String> {
     None
 }
 
+#[derive(Clone)]
 pub struct AgentConfig {
     pub verbose: bool,
     pub dry_run: bool,
@@ -492,13 +663,35 @@ pub struct AgentConfig {
     pub git_context: bool,
     /// Project graph for PIE injection and graph intercept. None for executor plan steps.
     pub project_graph: Option<std::sync::Arc<crate::pie::ProjectGraph>>,
+    /// Replaces `project_graph` at the start of each `run_tui` turn once the
+    /// file watcher has swapped in a newer graph.
+    pub live_graph: crate::pie::LiveGraph,
     /// Project narrative for PIE injection. None for executor plan steps.
     pub project_narrative: Option<std::sync::Arc<crate::narrative::ProjectNarrative>>,
     /// Pre-computed flow paths for proactive context delivery. None for executor plan steps.
     pub flow_paths: Option<std::sync::Arc<crate::flowpaths::FlowPathIndex>>,
+    /// Embedding index behind `semantic_search` and the semantic half of
+    /// `orient` / focus files. None when `[embeddings]` isn't configured.
+    pub semantic: Option<std::sync::Arc<crate::embeddings::SemanticIndex>>,
     /// Symbols pre-selected by the user via `#` drill-down. Source code is injected
     /// before the task message so the model skips redundant reads.
     pub attached_symbols: Vec<crate::pie::AttachedSymbol>,
+    /// MCP resource URIs attached via `#`. Their content is read into the first
+    /// message, capped at 1/`RESOURCE_CONTEXT_SHARE` of the context window.
+    pub attached_resources: Vec<String>,
+    /// Clients for the profile's `fallback_profiles`, labelled by profile name.
+    /// `run_tui` fails over to these in order once the primary exhausts its retries.
+    pub fallback_clients: Vec<(String, Client)>,
+    /// Spend ceilings from the profile, checked before every model call.
+    pub spend_limits: crate::budget::SpendLimits,
+    /// Session/day usage the limits are checked against — shared across tasks.
+    pub spend: Arc<std::sync::Mutex<crate::budget::SpendGuard>>,
+    /// Allow/ask/deny rules checked before each tool call, plus the session's
+    /// "always" grants — shared across tasks.
+    pub permissions: Arc<std::sync::Mutex<crate::permissions::Policy>>,
+    /// Directory relative tool paths and hooks resolve against. None = the
+    /// process cwd; set for plan steps running in their own git worktree.
+    pub workdir: Option<std::path::PathBuf>,
 }
 
 // ── Pure prompt-assembly helpers ──────────────────────────────────────────────
@@ -643,6 +836,11 @@ async fn execute_one_tool_call(
         }
     };
 
+    let args = match config.workdir.as_deref() {
+        Some(dir) => anchor_paths(args, dir),
+        None => args,
+    };
+
     // Classify once — used by cache, mutation guard, hooks, and stale eviction.
     let kind = ToolKind::classify(&tc.name, &args);
 
@@ -678,6 +876,17 @@ async fn execute_one_tool_call(
     } else if config.dry_run {
         dispatched = false;
         format!("[dry-run: {} not executed]", tc.name)
+    } else if let Err(refusal) = crate::permissions::authorize(
+        &config.permissions,
+        config.workdir.as_deref().unwrap_or(std::path::Path::new(".")),
+        &tc.name,
+        &args,
+        ui_tx,
+    )
+    .await
+    {
+        dispatched = false;
+        refusal
     } else {
         // ── Cache read (before dispatch) ──────────────────────────────────────
         if let ToolKind::Read { ref path, is_symbols: false, .. } = kind {
@@ -719,35 +928,7 @@ async fn execute_one_tool_call(
             args_summary: format_args_summary(&args),
         });
         let raw = dispatch_tool(&tc.name, &args, &kind, cache, ui_tx, &config.mcp, config).await;
-
-        // ── Post-dispatch cache maintenance ───────────────────────────────────
-        match &kind {
-            ToolKind::Read { path, has_range: false, is_symbols: false } => {
-                // Full read — cache the raw content so ranged re-reads are also free.
-                // We re-read from disk here to get the raw lines (raw is already formatted output).
-                if let Ok(content) = std::fs::read_to_string(path) {
-                    cache.store(path, content);
-                }
-            }
-            ToolKind::Read { path, has_range: true, is_symbols: false } => {
-                // Ranged read miss — prime the cache with the full file so future
-                // reads of any window (including different ranges) are free.
-                if let Ok(content) = std::fs::read_to_string(path) {
-                    let lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
-                    cache.store_lines(path, lines);
-                }
-            }
-            ToolKind::Mutate { path } => {
-                cache.invalidate(path);
-            }
-            ToolKind::Other { is_bash: true } => {
-                if let Some(cmd) = args["command"].as_str() {
-                    cache.invalidate_if_mentioned(cmd);
-                }
-            }
-            _ => {}
-        }
-
+        maintain_cache(&kind, &args, cache);
         raw
     };
 
@@ -765,19 +946,7 @@ async fn execute_one_tool_call(
     // Appended into the tool result so the model sees compile/lint errors
     // immediately and can self-correct without an extra round-trip.
     if let ToolKind::Mutate { .. } = kind {
-        for hr in hooks::run_edit_hooks(&config.hooks, config.hooks_enabled).await {
-            let hook_line = if hr.exit_code == 0 && hr.output.trim().is_empty() {
-                format!("\n\n⚙ `{}` ✓", hr.cmd)
-            } else {
-                format!("\n\n⚙ `{}` (exit {}):\n{}", hr.cmd, hr.exit_code, hr.output)
-            };
-            result_content.push_str(&hook_line);
-            let _ = ui_tx.send(UiEvent::HookOutput {
-                event: "on_edit".to_string(),
-                output: hr.output,
-                exit_code: hr.exit_code,
-            });
-        }
+        result_content.push_str(&edit_hook_report(config, ui_tx).await);
     }
 
     // ── Post-mutation stale eviction ──────────────────────────────────────────
@@ -790,17 +959,81 @@ async fn execute_one_tool_call(
     }
 
     // Compute had_error after hooks have been appended — this is the authoritative signal
-    // for the early-exit logic. Catches: tool errors, build failures, hook failures (exit != 0).
-    let had_error = result_content.contains("[Tool error")
-        || result_content.contains("[Not executed")
-        || result_content.contains("✗ build check failed")
-        || result_content.contains("⚠ FILE WRITTEN BUT BUILD BROKEN")
-        || result_content.contains("⚠ skipped dependent edit")
-        || result_content.contains("[Loop detected")
-        || result_content.contains("[dry-run")
-        || (result_content.contains("(exit ") && {
+    // for the early-exit logic.
+    let had_error = is_error_result(&result_content);
+
+    (Some(ContentPart::ToolResult {
+        tool_use_id: tc.id.clone(),
+        content: result_content,
+    }), dispatched, had_error)
+}
+
+/// Post-dispatch cache maintenance: keep what a read saw, drop what a
+/// mutation or bash command may have changed.
+pub(crate) fn maintain_cache(kind: &ToolKind, args: &Value, cache: &mut FileCache) {
+    match kind {
+        ToolKind::Read { path, has_range: false, is_symbols: false } => {
+            // Full read — cache the raw content so ranged re-reads are also free.
+            // We re-read from disk here to get the raw lines (raw is already formatted output).
+            if let Ok(content) = std::fs::read_to_string(path) {
+                cache.store(path, content);
+            }
+        }
+        ToolKind::Read { path, has_range: true, is_symbols: false } => {
+            // Ranged read miss — prime the cache with the full file so future
+            // reads of any window (including different ranges) are free.
+            if let Ok(content) = std::fs::read_to_string(path) {
+                let lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+                cache.store_lines(path, lines);
+            }
+        }
+        ToolKind::Mutate { path } => {
+            cache.invalidate(path);
+        }
+        ToolKind::Other { is_bash: true } => {
+            if let Some(cmd) = args["command"].as_str() {
+                cache.invalidate_if_mentioned(cmd);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Run the on_edit hooks, reporting each to the UI. Returns the text to
+/// append to the edit's tool result, so the model sees compile/lint errors
+/// immediately.
+pub(crate) async fn edit_hook_report(config: &AgentConfig, ui_tx: &mpsc::UnboundedSender<UiEvent>) -> String {
+    let mut report = String::new();
+    for hr in hooks::run_edit_hooks(&config.hooks, config.hooks_enabled, config.workdir.as_deref()).await {
+        let hook_line = if hr.exit_code == 0 && hr.output.trim().is_empty() {
+            format!("\n\n⚙ `{}` ✓", hr.cmd)
+        } else {
+            format!("\n\n⚙ `{}` (exit {}):\n{}", hr.cmd, hr.exit_code, hr.output)
+        };
+        report.push_str(&hook_line);
+        let _ = ui_tx.send(UiEvent::HookOutput {
+            event: "on_edit".to_string(),
+            output: hr.output,
+            exit_code: hr.exit_code,
+        });
+    }
+    report
+}
+
+/// Whether a tool result (hook output included) reports a failure. Catches:
+/// tool errors, build failures, skipped/looping/dry-run calls, and hooks
+/// that exited non-zero.
+pub(crate) fn is_error_result(content: &str) -> bool {
+    content.contains("[Tool error")
+        || content.contains("[Not executed")
+        || content.contains("✗ build check failed")
+        || content.contains("⚠ FILE WRITTEN BUT BUILD BROKEN")
+        || content.contains("⚠ skipped dependent edit")
+        || content.contains("[Loop detected")
+        || content.contains("[dry-run")
+        || (content.contains("(exit ") && {
             // Hook exited non-zero: pattern is `⚙ `cmd` (exit N):` where N != 0
-            result_content.lines().any(|l| {
+            content.lines().any(|l| {
                 if let Some(rest) = l.strip_prefix("⚙ ") {
                     if let Some(idx) = rest.find("(exit ") {
                         let after = &rest[idx + 6..];
@@ -810,12 +1043,7 @@ async fn execute_one_tool_call(
                 }
                 false
             })
-        });
-
-    (Some(ContentPart::ToolResult {
-        tool_use_id: tc.id.clone(),
-        content: result_content,
-    }), dispatched, had_error)
+        })
 }
 
 /// Per-turn context compression — runs after each tool round-trip, before the
@@ -876,7 +1104,10 @@ fn strip_cot_from_last_assistant(messages: &mut Vec<Message>) {
 /// because the model explicitly requested that window and likely still needs it.
 fn should_protect_tool_result(tool_name: &str, content: &str) -> bool {
     // Graph navigation results — tiny, always valid, never need re-fetching
-    if tool_name == "find_symbol" || tool_name == "trace_calls" || tool_name == "check_wiring" || tool_name == "orient" {
+    if tool_name == "find_symbol" || tool_name == "trace_calls" || tool_name == "check_wiring"
+        || tool_name == "find_references" || tool_name == "related_tests" || tool_name == "orient"
+        || tool_name == "semantic_search"
+    {
         return true;
     }
     // Ranged read_file results — always retain verbatim.
@@ -973,7 +1204,7 @@ fn evict_stale_content(messages: &mut [Message], edited_path: &str) {
 // Parsed once per tool call so the loop can make cache/mutation decisions
 // without re-parsing arguments or scattering tool-name strings across the code.
 
-enum ToolKind {
+pub(crate) enum ToolKind {
     /// read_file — may be served from / stored into cache.
     Read { path: String, has_range: bool, is_symbols: bool },
     /// edit_file / write_file / patch_file — mutates a file path.
@@ -983,7 +1214,7 @@ enum ToolKind {
 }
 
 impl ToolKind {
-    fn classify(name: &str, args: &Value) -> Self {
+    pub(crate) fn classify(name: &str, args: &Value) -> Self {
         match name {
             "read_file" => ToolKind::Read {
                 path: args["path"].as_str().unwrap_or("").to_string(),
@@ -1004,7 +1235,43 @@ impl ToolKind {
 // Pure routing: parse → call → return raw string.
 // Cache, history, hooks, dry-run and UI events are all handled by the caller.
 
-async fn dispatch_tool(
+/// The repo a task's checkpoint and post-task diff belong to: the step's
+/// worktree when `config.workdir` is set, else the one containing the cwd.
+fn task_repo(config: &AgentConfig) -> Option<crate::git::GitRepo> {
+    match config.workdir.as_deref() {
+        Some(dir) => crate::git::GitRepo::open(dir),
+        None => crate::git::GitRepo::open(&std::env::current_dir().ok()?),
+    }
+}
+
+/// Resolve relative `path` / `reads[].path` arguments against `dir`, so file
+/// tools act on the step's worktree rather than the process cwd.
+fn anchor_paths(mut args: Value, dir: &std::path::Path) -> Value {
+    fn anchor(v: &mut Value, dir: &std::path::Path) {
+        if let Some(p) = v.as_str().filter(|p| std::path::Path::new(p).is_relative()) {
+            *v = Value::String(dir.join(p).to_string_lossy().into_owned());
+        }
+    }
+    if let Some(path) = args.get_mut("path") {
+        anchor(path, dir);
+    }
+    if let Some(reads) = args.get_mut("reads").and_then(|r| r.as_array_mut()) {
+        for read in reads {
+            if let Some(path) = read.get_mut("path") {
+                anchor(path, dir);
+            }
+        }
+    }
+    args
+}
+
+/// `embeddings::hits` against the agent's own graph and index.
+async fn semantic_hits(config: &AgentConfig, query: &str, limit: usize, refresh: bool) -> Vec<crate::embeddings::SemanticHit> {
+    let Some(graph) = &config.project_graph else { return Vec::new() };
+    crate::embeddings::hits(config.semantic.as_deref(), graph, query, limit, refresh).await
+}
+
+pub(crate) async fn dispatch_tool(
     name: &str,
     args: &Value,
     kind: &ToolKind,
@@ -1023,20 +1290,57 @@ async fn dispatch_tool(
         //         tools::bash::execute(args).await.unwrap_or_else(|e| format!("[Tool error: {e}]"))
         //     }
         // }
+        "bash" => tools::bash::execute(args, config.workdir.as_deref())
+            .await
+            .unwrap_or_else(|e| format!("[Tool error: {e}]")),
 
         // ── find_symbol / trace_calls — in-memory graph lookups, zero disk reads
         "orient" => {
             match &config.project_graph {
-                Some(g) => tools::pie_tool::orient_execute(args, g, &mut tools::pie_tool::DeliveredRanges::new()),
+                Some(g) => {
+                    let query = args["query"].as_str().unwrap_or("");
+                    let semantic = semantic_hits(config, query, ORIENT_SEMANTIC_HITS, false).await;
+                    tools::pie_tool::orient_execute(args, g, &mut tools::pie_tool::DeliveredRanges::new(), &semantic)
+                }
                 None => "[orient: no project graph available for this session]".to_string(),
             }
         },
+        "find_symbol" => {
+            match &config.project_graph {
+                Some(g) => tools::pie_tool::execute(args, g),
+                None => "[find_symbol: no project graph available for this session]".to_string(),
+            }
+        }
+        "trace_calls" => {
+            match &config.project_graph {
+                Some(g) => tools::pie_tool::trace_calls_execute(args, g),
+                None => "[trace_calls: no project graph available for this session]".to_string(),
+            }
+        }
+        "semantic_search" => {
+            match (&config.project_graph, &config.semantic) {
+                (Some(g), Some(s)) => tools::pie_tool::semantic_search_execute(args, g, s).await,
+                _ => "[semantic_search: no embedding index — configure [embeddings] to enable it]".to_string(),
+            }
+        }
         "check_wiring" => {
             match &config.project_graph {
                 Some(g) => tools::pie_tool::check_wiring_execute(args, g),
                 None => "[check_wiring: no project graph available for this session]".to_string(),
             }
         }
+        "find_references" => {
+            match &config.project_graph {
+                Some(g) => tools::pie_tool::find_references_execute(args, g),
+                None => "[find_references: no project graph available for this session]".to_string(),
+            }
+        }
+        "related_tests" => {
+            match &config.project_graph {
+                Some(g) => tools::pie_tool::related_tests_execute(args, g),
+                None => "[related_tests: no project graph available for this session]".to_string(),
+            }
+        }
 
         
         // read_files — batched, graph-aware reads. Preferred over read_file when graph present.
@@ -1370,9 +1674,17 @@ mod tests {
             auto_commit_prefix: String::new(),
             git_context: false,
             project_graph: None,
+            live_graph: Arc::default(),
             project_narrative: None,
             flow_paths: None,
+            semantic: None,
             attached_symbols: vec![],
+            attached_resources: vec![],
+            fallback_clients: vec![],
+            spend_limits: crate::budget::SpendLimits::default(),
+            spend: Arc::new(std::sync::Mutex::new(crate::budget::SpendGuard::default())),
+            permissions: Arc::default(),
+            workdir: None,
         }
     }
 
@@ -1555,9 +1867,17 @@ mod tests {
             auto_commit_prefix: String::new(),
             git_context: false,
             project_graph: None,
+            live_graph: Arc::default(),
             project_narrative: None,
             flow_paths: None,
+            semantic: None,
             attached_symbols: vec![],
+            attached_resources: vec![],
+            fallback_clients: vec![],
+            spend_limits: crate::budget::SpendLimits::default(),
+            spend: Arc::new(std::sync::Mutex::new(crate::budget::SpendGuard::default())),
+            permissions: Arc::default(),
+            workdir: None,
         }
     }
 
@@ -1693,6 +2013,93 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_execute_one_tool_call_permission_denied() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = minimal_config_async().await;
+        config.workdir = Some(tmp.path().to_path_buf());
+        let rules = r#"
+            [[rules]]
+            tool = "write_file"
+            path = "/**"
+            action = "deny"
+        "#;
+        config.permissions = Arc::new(std::sync::Mutex::new(crate::permissions::Policy::new(toml::from_str(rules).unwrap())));
+        let outside = tmp.path().join("../outside_the_repo.txt");
+        let args = serde_json::json!({ "path": outside, "content": "x" }).to_string();
+        let tc = make_tool_call("id6", "write_file", &args);
+        let (tx, _rx) = make_channel();
+
+        let (result, dispatched, had_error) = execute_one_tool_call(
+            &tc,
+            &mut Default::default(),
+            &mut vec![],
+            &mut FileCache::default(),
+            &mut History::default(),
+            &mut LoopDetector::default(),
+            &config,
+            &tx,
+        ).await;
+        assert!(!dispatched);
+        assert!(had_error, "a refused call should signal had_error");
+        assert!(!outside.exists());
+        if let Some(ContentPart::ToolResult { content, .. }) = result {
+            assert!(content.contains("blocked by the permission policy"), "got: {content}");
+        } else {
+            panic!("expected a tool result");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_one_tool_call_resolves_paths_in_workdir() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join("only_in_worktree.txt"), "worktree line\n").unwrap();
+        let mut config = minimal_config_async().await;
+        config.workdir = Some(tmp.path().to_path_buf());
+        let tc = make_tool_call("id5", "read_file", r#"{"path":"only_in_worktree.txt"}"#);
+        let (tx, _rx) = make_channel();
+
+        let (result, dispatched, _) = execute_one_tool_call(
+            &tc,
+            &mut Default::default(),
+            &mut vec![],
+            &mut FileCache::default(),
+            &mut History::default(),
+            &mut LoopDetector::default(),
+            &config,
+            &tx,
+        ).await;
+        assert!(dispatched);
+        if let Some(ContentPart::ToolResult { content, .. }) = result {
+            assert!(content.contains("worktree line"), "got: {content}");
+        } else {
+            panic!("expected a tool result");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_one_tool_call_runs_bash_in_workdir() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = minimal_config_async().await;
+        config.workdir = Some(tmp.path().to_path_buf());
+        let tc = make_tool_call("id6", "bash", r#"{"command":"echo hi > landed.txt"}"#);
+        let (tx, _rx) = make_channel();
+
+        let (_, dispatched, _) = execute_one_tool_call(
+            &tc,
+            &mut Default::default(),
+            &mut vec![],
+            &mut FileCache::default(),
+            &mut History::default(),
+            &mut LoopDetector::default(),
+            &config,
+            &tx,
+        ).await;
+        assert!(dispatched);
+        assert!(tmp.path().join("landed.txt").exists());
+        assert!(!std::path::Path::new("landed.txt").exists());
+    }
+
     // ── should_skip_done_turn ─────────────────────────────────────────────────
     // These tests are critical: a false positive (skipping when we shouldn't)
     // breaks multi-step tasks by cutting off the agent mid-workflow.
@@ -1761,4 +2168,121 @@ mod tests {
         // Nothing happened — clearly don't skip
         assert!(!_should_skip_done_turn(false, false, false));
     }
+
+    // ── chat_with_failover ────────────────────────────────────────────────────
+
+    fn fast_retry(max_retries: u32) -> crate::client::retry::RetryPolicy {
+        crate::client::retry::RetryPolicy { max_retries, base_delay_ms: 1, max_delay_ms: 5 }
+    }
+
+    #[tokio::test]
+    async fn test_chat_with_failover_retries_then_fails_over() {
+        use crate::client::test_server::{self, MockResponse};
+        let ok = MockResponse::sse(&[
+            r#"{"choices":[{"delta":{"content":"from backup"}}]}"#,
+            "[DONE]",
+        ]);
+        let (primary_url, _p) = test_server::serve(vec![
+            MockResponse::error(503, "unavailable"),
+            MockResponse::error(429, "slow down").with_header("retry-after-ms", "2"),
+        ])
+        .await;
+        let (backup_url, _b) = test_server::serve(vec![ok]).await;
+
+        let mut primary = Client::new(primary_url, "m".to_string());
+        primary.set_retry_policy(fast_retry(1));
+        let mut backup = Client::new(backup_url, "m2".to_string());
+        backup.set_retry_policy(fast_retry(1));
+        let mut config = minimal_config_async().await;
+        config._profile_name = "main".to_string();
+        config.fallback_clients = vec![("backup".to_string(), backup)];
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut active = 0;
+        let msgs = [Message { role: "user".into(), content: "hi".into(), tool_calls: vec![] }];
+        let resp = chat_with_failover(&primary, &config, &mut active, "", &msgs, &[], |_| {}, &tx)
+            .await
+            .unwrap();
+        assert_eq!(resp.text, "from backup");
+        assert_eq!(active, 1, "later turns should stay on the fallback");
+
+        let mut events = Vec::new();
+        while let Ok(ev) = rx.try_recv() {
+            events.push(ev);
+        }
+        assert!(matches!(
+            &events[0],
+            UiEvent::ApiRetry { attempt: 1, max_retries: 1, reason, .. } if reason.contains("503")
+        ));
+        assert!(matches!(
+            &events[1],
+            UiEvent::ModelFailover { from, to, reason } if from == "main" && to == "backup" && reason.contains("429")
+        ));
+        assert_eq!(events.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_chat_with_failover_no_fallbacks_surfaces_error() {
+        use crate::client::test_server::{self, MockResponse};
+        let (url, _h) = test_server::serve(vec![MockResponse::error(400, "bad request")]).await;
+        let mut client = Client::new(url, "m".to_string());
+        client.set_retry_policy(fast_retry(3));
+        let config = minimal_config_async().await;
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut active = 0;
+        let msgs = [Message { role: "user".into(), content: "hi".into(), tool_calls: vec![] }];
+        let err = chat_with_failover(&client, &config, &mut active, "", &msgs, &[], |_| {}, &tx)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("400"), "got: {err}");
+        // 400 is not transient — no retries were attempted
+        assert!(rx.try_recv().is_err());
+    }
+
+    // ── enforce_spend_limits ──────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_spend_limit_pauses_until_extended() {
+        let mut config = minimal_config_async().await;
+        config.spend_limits.max_tokens_per_session = Some(100);
+        config.spend.lock().unwrap().add(150, None);
+        let config = Arc::new(config);
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let cfg = config.clone();
+        let task = tokio::spawn(async move { enforce_spend_limits(&cfg, None, &tx).await });
+
+        let Some(UiEvent::SpendLimitHit { hit, reply_tx }) = rx.recv().await else {
+            panic!("expected SpendLimitHit");
+        };
+        assert_eq!(hit.kind, crate::budget::LimitKind::SessionTokens);
+        assert_eq!(hit.extended, 200.0);
+        reply_tx.send(SpendDecision::Extend).unwrap();
+        task.await.unwrap().unwrap();
+
+        // Within the extended limit — no further pause
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        enforce_spend_limits(&config, None, &tx).await.unwrap();
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_spend_limit_abort_fails_task() {
+        let mut config = minimal_config_async().await;
+        config.spend_limits.max_cost_per_task = Some(0.5);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        // Under the limit, or unpriced — proceeds without asking
+        enforce_spend_limits(&config, Some(0.4), &tx).await.unwrap();
+        enforce_spend_limits(&config, None, &tx).await.unwrap();
+        assert!(rx.try_recv().is_err());
+
+        let answer = tokio::spawn(async move {
+            if let Some(UiEvent::SpendLimitHit { reply_tx, .. }) = rx.recv().await {
+                let _ = reply_tx.send(SpendDecision::Abort);
+            }
+        });
+        let err = enforce_spend_limits(&config, Some(0.75), &tx).await.unwrap_err();
+        assert!(err.to_string().contains("max_cost_per_task"), "got: {err}");
+        answer.await.unwrap();
+    }
 }
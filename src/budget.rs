@@ -249,6 +249,175 @@ impl LoopDetector {
     }
 }
 
+// ── Spend limits ──────────────────────────────────────────────────────────────
+
+/// Hard money/token ceilings from the profile. Checked before every model call;
+/// hitting one pauses the task until the user extends the limit or aborts.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SpendLimits {
+    /// USD ceiling for a single task (each plan step is its own task)
+    pub max_cost_per_task: Option<f64>,
+    /// Input + output tokens across the whole session
+    pub max_tokens_per_session: Option<u32>,
+    /// USD ceiling for the local calendar day, across all sessions
+    pub daily_spend_limit: Option<f64>,
+}
+
+impl SpendLimits {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitKind {
+    TaskCost,
+    SessionTokens,
+    DailySpend,
+}
+
+impl LimitKind {
+    /// Profile setting that controls this limit.
+    pub fn setting(&self) -> &'static str {
+        match self {
+            LimitKind::TaskCost => "max_cost_per_task",
+            LimitKind::SessionTokens => "max_tokens_per_session",
+            LimitKind::DailySpend => "daily_spend_limit",
+        }
+    }
+}
+
+/// A limit that has been reached: how much was used against the current ceiling
+/// (configured value plus any extensions granted so far).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LimitHit {
+    pub kind: LimitKind,
+    pub used: f64,
+    pub limit: f64,
+    /// Ceiling after one more extension
+    pub extended: f64,
+}
+
+impl std::fmt::Display for LimitHit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use crate::pricing::fmt_usd;
+        match self.kind {
+            LimitKind::TaskCost => write!(
+                f, "task cost {} reached {} ({})",
+                fmt_usd(self.used), fmt_usd(self.limit), self.kind.setting()
+            ),
+            LimitKind::SessionTokens => write!(
+                f, "session used {} tokens of {} ({})",
+                self.used as u64, self.limit as u64, self.kind.setting()
+            ),
+            LimitKind::DailySpend => write!(
+                f, "today's spend {} reached {} ({})",
+                fmt_usd(self.used), fmt_usd(self.limit), self.kind.setting()
+            ),
+        }
+    }
+}
+
+/// The user's answer to a paused task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpendDecision {
+    /// Raise the limit that was hit by its configured amount and continue
+    Extend,
+    Abort,
+}
+
+/// Running usage that spend limits are checked against. Shared by every task
+/// in a session (held in an `Arc<Mutex<_>>`) so plan steps and follow-up tasks
+/// see what earlier ones spent. Extensions granted by the user live here too.
+#[derive(Debug, Clone, Default)]
+pub struct SpendGuard {
+    /// Tokens used by this session so far
+    session_tokens: u64,
+    /// USD spent on `day`, seeded from telemetry.jsonl
+    spent_today: f64,
+    /// Local calendar day `spent_today` refers to ("" = not loaded)
+    day: String,
+    task_extensions: u32,
+    session_extensions: u32,
+    daily_extensions: u32,
+}
+
+impl SpendGuard {
+    /// Guard seeded with today's spend from telemetry.jsonl.
+    pub fn load() -> Self {
+        let day = today();
+        let spent_today = crate::telemetry::spent_on(&crate::telemetry::load_all(), &day);
+        Self { day, spent_today, ..Self::default() }
+    }
+
+    /// Reset per-task extensions — called at the start of every task.
+    pub fn begin_task(&mut self) {
+        self.task_extensions = 0;
+    }
+
+    /// Forget session usage and extensions (a new session was started).
+    pub fn reset_session(&mut self) {
+        self.session_tokens = 0;
+        self.session_extensions = 0;
+    }
+
+    /// Count one model call's usage towards the session and today.
+    pub fn add(&mut self, tokens: u32, cost_usd: Option<f64>) {
+        self.roll_day(&today());
+        self.session_tokens += tokens as u64;
+        self.spent_today += cost_usd.unwrap_or(0.0);
+    }
+
+    /// First limit reached given the current task's cost so far, if any.
+    pub fn check(&mut self, limits: &SpendLimits, task_cost: Option<f64>) -> Option<LimitHit> {
+        if limits.daily_spend_limit.is_some() {
+            self.roll_day(&today());
+        }
+        self.check_on(limits, task_cost)
+    }
+
+    fn check_on(&self, limits: &SpendLimits, task_cost: Option<f64>) -> Option<LimitHit> {
+        let hit = |kind, used: f64, base: f64, extensions: u32| {
+            let limit = base * (1 + extensions) as f64;
+            (used >= limit).then_some(LimitHit { kind, used, limit, extended: limit + base })
+        };
+        let task = limits.max_cost_per_task.zip(task_cost).and_then(|(max, cost)| {
+            hit(LimitKind::TaskCost, cost, max, self.task_extensions)
+        });
+        let session = limits.max_tokens_per_session.and_then(|max| {
+            hit(LimitKind::SessionTokens, self.session_tokens as f64, max as f64, self.session_extensions)
+        });
+        let daily = limits.daily_spend_limit.and_then(|max| {
+            hit(LimitKind::DailySpend, self.spent_today, max, self.daily_extensions)
+        });
+        task.or(session).or(daily)
+    }
+
+    /// Raise `kind` by one more multiple of its configured limit.
+    pub fn extend(&mut self, kind: LimitKind) {
+        match kind {
+            LimitKind::TaskCost => self.task_extensions += 1,
+            LimitKind::SessionTokens => self.session_extensions += 1,
+            LimitKind::DailySpend => self.daily_extensions += 1,
+        }
+    }
+
+    /// On a new calendar day, re-seed from telemetry and drop daily extensions.
+    fn roll_day(&mut self, today: &str) {
+        if self.day.is_empty() {
+            self.day = today.to_string();
+        } else if self.day != today {
+            self.day = today.to_string();
+            self.spent_today = crate::telemetry::spent_on(&crate::telemetry::load_all(), today);
+            self.daily_extensions = 0;
+        }
+    }
+}
+
+fn today() -> String {
+    chrono::Local::now().format("%Y-%m-%d").to_string()
+}
+
 // ── Tests ───────────────────────────────────────────────────────────────────
 
 #[cfg(test)]
@@ -479,6 +648,61 @@ mod tests {
         });
         assert!(task_present, "User task must not be dropped");
     }
-}
 
+    // ── Spend limits ─────────────────────────────────────────────────────
+
+    fn guard_on(day: &str, spent_today: f64) -> SpendGuard {
+        SpendGuard { day: day.to_string(), spent_today, ..SpendGuard::default() }
+    }
+
+    #[test]
+    fn test_spend_limits_none_never_hit() {
+        let mut g = guard_on("2026-03-14", 1_000.0);
+        g.session_tokens = 10_000_000;
+        assert_eq!(g.check_on(&SpendLimits::default(), Some(50.0)), None);
+    }
 
+    #[test]
+    fn test_spend_task_cost_limit_and_extension() {
+        let limits = SpendLimits { max_cost_per_task: Some(1.0), ..Default::default() };
+        let mut g = guard_on("2026-03-14", 0.0);
+        assert_eq!(g.check_on(&limits, None), None, "unpriced task is never capped by cost");
+        assert_eq!(g.check_on(&limits, Some(0.99)), None);
+        let hit = g.check_on(&limits, Some(1.0)).unwrap();
+        assert_eq!((hit.kind, hit.limit, hit.extended), (LimitKind::TaskCost, 1.0, 2.0));
+
+        g.extend(LimitKind::TaskCost);
+        assert_eq!(g.check_on(&limits, Some(1.5)), None);
+        assert!(g.check_on(&limits, Some(2.0)).is_some());
+
+        // Task extensions don't carry over to the next task
+        g.begin_task();
+        assert!(g.check_on(&limits, Some(1.5)).is_some());
+    }
+
+    #[test]
+    fn test_spend_session_tokens_accumulate_across_tasks() {
+        let limits = SpendLimits { max_tokens_per_session: Some(1_000), ..Default::default() };
+        let mut g = guard_on("2026-03-14", 0.0);
+        g.add(600, None);
+        assert_eq!(g.check_on(&limits, None), None);
+        g.add(500, None);
+        let hit = g.check_on(&limits, None).unwrap();
+        assert_eq!(hit.kind, LimitKind::SessionTokens);
+        assert!(hit.to_string().contains("1100 tokens of 1000"), "{hit}");
+
+        g.reset_session();
+        assert_eq!(g.check_on(&limits, None), None);
+    }
+
+    #[test]
+    fn test_spend_daily_limit_uses_seeded_total() {
+        let limits = SpendLimits { daily_spend_limit: Some(5.0), ..Default::default() };
+        let mut g = guard_on(&today(), 4.0);
+        assert_eq!(g.check_on(&limits, Some(0.5)), None);
+        g.add(100, Some(1.0));
+        assert_eq!(g.check(&limits, None).unwrap().kind, LimitKind::DailySpend);
+        g.extend(LimitKind::DailySpend);
+        assert_eq!(g.check(&limits, None), None);
+    }
+}
@@ -1,51 +1,254 @@
-/// Tree-sitter call graph extraction for Rust source files.
+/// Tree-sitter call graph extraction.
 ///
-/// Walks the concrete syntax tree of each `.rs` file and records outgoing
+/// Walks the concrete syntax tree of each source file and records outgoing
 /// call edges: for every top-level symbol, which project-internal functions
 /// does it call and on which lines?
 ///
 /// Design:
-/// - Only Rust for now. Other languages added per grammar crate.
+/// - The grammar is picked per file by extension (`Lang::from_path`): Rust,
+///   TypeScript/TSX (also used for JS), Python, Go and C/C++. Each language only
+///   differs in which node kinds are calls / constructions — the traversal
+///   and caller attribution are shared.
 /// - Filters to project-internal calls only (callee must be in `known_names`).
 /// - One representative call site per (caller, callee) pair — we care about
 ///   edge existence and a source line, not every occurrence.
-/// - Falls back gracefully: if tree-sitter init fails, extraction is skipped
-///   and the graph simply has no call edges (existing behaviour preserved).
-use std::collections::HashMap;
+/// - Receivers pin calls to one definition where they can: `Self::new()`,
+///   `ReadFile::execute()`, `read::execute()`, `self.run()`, and (Rust)
+///   `x.run()` on a local or parameter whose type is declared or constructed
+///   in the same function. The result lands in `CallEdge::target`.
+/// - Falls back gracefully: if a grammar fails to load, files in that
+///   language are skipped and simply have no call edges.
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use tree_sitter::{Node, Parser, Tree};
+
+use crate::index::{CallEdge, RefKind, Reference, Symbol, SymbolKind, base_type_name};
+
+// ── Languages ─────────────────────────────────────────────────────────────────
+
+/// A language with a tree-sitter grammar, used for call/construction
+/// extraction here and for symbol extraction in `index`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lang {
+    Rust,
+    TypeScript,
+    /// TSX grammar — also parses plain JS/JSX
+    Tsx,
+    Python,
+    Go,
+    C,
+    /// C++ grammar — also used for `.hpp` headers
+    Cpp,
+}
 
-use tree_sitter::{Node, Parser};
+impl Lang {
+    /// Pick the grammar for a file by extension. `None` = no grammar.
+    pub fn from_path(path: &str) -> Option<Self> {
+        let ext = std::path::Path::new(path).extension()?.to_str()?;
+        Some(match ext {
+            "rs" => Self::Rust,
+            "ts" | "mts" | "cts" => Self::TypeScript,
+            "tsx" | "js" | "jsx" | "mjs" | "cjs" => Self::Tsx,
+            "py" => Self::Python,
+            "go" => Self::Go,
+            "c" | "h" => Self::C,
+            "cpp" | "cc" | "cxx" | "hpp" | "hh" | "hxx" => Self::Cpp,
+            _ => return None,
+        })
+    }
 
-use crate::index::{CallEdge, Symbol};
+    pub(crate) fn grammar(self) -> tree_sitter::Language {
+        match self {
+            Self::Rust => tree_sitter_rust::LANGUAGE.into(),
+            Self::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            Self::Tsx => tree_sitter_typescript::LANGUAGE_TSX.into(),
+            Self::Python => tree_sitter_python::LANGUAGE.into(),
+            Self::Go => tree_sitter_go::LANGUAGE.into(),
+            Self::C => tree_sitter_c::LANGUAGE.into(),
+            Self::Cpp => tree_sitter_cpp::LANGUAGE.into(),
+        }
+    }
+}
 
 // ── Public API ────────────────────────────────────────────────────────────────
 
-/// Stateful extractor.  Create once, reuse across many files (parser is
-/// expensive to initialise).
+/// Stateful extractor.  Create once, reuse across many files (parsers are
+/// expensive to initialise — one is created per language on first use).
 pub struct CallExtractor {
-    parser: Parser,
+    parsers: HashMap<Lang, Parser>,
+    /// Project-wide member/module tables, from `with_resolver`
+    resolver: Arc<CallResolver>,
+    /// Last parse, reused while one file goes through several extractors
+    last_parse: Option<(Lang, String, Tree)>,
+}
+
+/// Lookup tables for pinning a call to a single definition. Built once per
+/// symbol set and shared by the per-thread extractors of a parallel pass.
+#[derive(Debug, Default)]
+pub struct CallResolver {
+    /// (owner type, member name) → keys
+    members: HashMap<(String, String), Vec<String>>,
+    /// type → traits/interfaces it implements
+    traits: HashMap<String, Vec<String>>,
+    /// (module name, function name) → keys of free functions
+    free: HashMap<(String, String), Vec<String>>,
+    /// Names of indexed classes — a Python call to one is a construction
+    classes: HashSet<String>,
+}
+
+impl CallResolver {
+    pub fn new(symbols: &[Symbol]) -> Self {
+        let mut resolver = Self::default();
+        for sym in symbols {
+            if sym.kind == SymbolKind::Class {
+                resolver.classes.insert(sym.name.clone());
+            }
+            if !sym.implements.is_empty() {
+                resolver.traits.entry(sym.name.clone()).or_default().extend(sym.implements.iter().cloned());
+            }
+            if !matches!(sym.kind, SymbolKind::Function | SymbolKind::Method | SymbolKind::Constant) {
+                continue;
+            }
+            match sym.owner() {
+                Some(owner) => resolver.members.entry((owner.to_string(), sym.name.clone())).or_default().push(sym.key()),
+                None => {
+                    // `mod tests { fn helper() }` is reachable as `tests::helper` too
+                    let inline = sym.parent.as_deref().and_then(|p| p.strip_prefix("mod "));
+                    for module in module_name(&sym.file).into_iter().chain(inline.map(str::to_string)) {
+                        resolver.free.entry((module, sym.name.clone())).or_default().push(sym.key());
+                    }
+                }
+            }
+        }
+        resolver
+    }
+
+    /// `ty::name` — the type's own member, else a member of a trait it implements
+    /// (default methods, or the trait's declaration).
+    fn member(&self, ty: &str, name: &str, file: &str) -> Option<String> {
+        let own = self.members.get(&(ty.to_string(), name.to_string()));
+        pick(own, file).or_else(|| {
+            self.traits.get(ty)?.iter().find_map(|t| {
+                pick(self.members.get(&(t.clone(), name.to_string())), file)
+            })
+        })
+    }
+
+    fn module_fn(&self, module: &str, name: &str, file: &str) -> Option<String> {
+        pick(self.free.get(&(module.to_string(), name.to_string())), file)
+    }
+
+    fn resolve(&self, call: &CallSite, caller: &Symbol, bindings: &[Binding]) -> Option<String> {
+        let file = caller.file.as_str();
+        match &call.receiver {
+            Receiver::None => self.module_fn(&module_name(file)?, &call.callee, file)
+                .filter(|key| key.starts_with(&format!("{file}::"))),
+            Receiver::SelfType => self.member(caller.owner()?, &call.callee, file),
+            Receiver::Path(path) => match path.as_str() {
+                "self" => self.module_fn(&module_name(file)?, &call.callee, file),
+                p if p.starts_with(char::is_uppercase) => self.member(p, &call.callee, file),
+                module => self.module_fn(module, &call.callee, file),
+            },
+            Receiver::Value(var) => {
+                let bound = bindings
+                    .iter()
+                    .rfind(|b| b.var == *var && (caller.line..=call.line).contains(&b.line));
+                match bound {
+                    Some(b) => self.member(&b.ty, &call.callee, file),
+                    // `utils.helper()` / `pkg.Func()` — a module, outside Rust
+                    None if Lang::from_path(file) != Some(Lang::Rust) => self.module_fn(var, &call.callee, file),
+                    None => None,
+                }
+            }
+        }
+    }
+}
+
+/// The one key in `keys`, or the one defined in `file` when there are several.
+fn pick(keys: Option<&Vec<String>>, file: &str) -> Option<String> {
+    let keys = keys?;
+    if keys.len() == 1 {
+        return keys.first().cloned();
+    }
+    let prefix = format!("{file}::");
+    let mut local = keys.iter().filter(|k| k.starts_with(&prefix));
+    match (local.next(), local.next()) {
+        (Some(key), None) => Some(key.clone()),
+        _ => None,
+    }
+}
+
+/// Name a file is imported by: `src/tools/read.rs` → `read`, `src/tools/mod.rs`
+/// → `tools`, `src/main.rs` → `crate`; Go packages are their directory.
+fn module_name(file: &str) -> Option<String> {
+    let path = std::path::Path::new(file);
+    let stem = path.file_stem()?.to_str()?;
+    let dir = path.parent().and_then(|d| d.file_name()).and_then(|d| d.to_str());
+    match (Lang::from_path(file)?, stem) {
+        (Lang::Go, _) | (_, "mod" | "__init__" | "index") => dir.map(str::to_string),
+        (Lang::Rust, "main" | "lib") => Some("crate".to_string()),
+        _ => Some(stem.to_string()),
+    }
 }
 
 impl CallExtractor {
-    /// Initialise the tree-sitter Rust parser.
+    /// Initialise the extractor, loading the Rust grammar up front. Edges
+    /// come out name-only (`CallEdge::target` = None); see `with_resolver`.
     ///
     /// Returns `Err` if the grammar ABI version is incompatible with the
     /// linked `tree-sitter` crate — in that case callers should skip call
     /// extraction rather than hard-fail.
     pub fn new() -> anyhow::Result<Self> {
+        Self::with_resolver(Arc::default())
+    }
+
+    /// Like `new`, but resolving receivers (`Type::method`, `module::func`,
+    /// trait impls) against the project's symbols. The resolver is shared, so
+    /// each worker of a parallel pass builds it only once.
+    pub fn with_resolver(resolver: Arc<CallResolver>) -> anyhow::Result<Self> {
         let mut parser = Parser::new();
-        parser.set_language(&tree_sitter_rust::language())?;
-        Ok(Self { parser })
+        parser.set_language(&Lang::Rust.grammar())?;
+        Ok(Self {
+            parsers: HashMap::from([(Lang::Rust, parser)]),
+            resolver,
+            last_parse: None,
+        })
     }
 
-    /// Extract struct/enum-variant construction edges from one Rust source file.
+    /// Parse `content` with the grammar for `lang`. `None` if the grammar
+    /// can't be loaded or parsing fails. Asking again for the same content
+    /// returns the cached tree.
+    fn parse(&mut self, lang: Lang, content: &str) -> Option<Tree> {
+        if let Some((last_lang, last_content, tree)) = &self.last_parse {
+            if *last_lang == lang && last_content == content {
+                return Some(tree.clone());
+            }
+        }
+        let parser = match self.parsers.entry(lang) {
+            std::collections::hash_map::Entry::Occupied(e) => e.into_mut(),
+            std::collections::hash_map::Entry::Vacant(e) => {
+                let mut parser = Parser::new();
+                parser.set_language(&lang.grammar()).ok()?;
+                e.insert(parser)
+            }
+        };
+        let tree = parser.parse(content.as_bytes(), None)?;
+        self.last_parse = Some((lang, content.to_string(), tree.clone()));
+        Some(tree)
+    }
+
+    /// Extract struct/enum-variant construction edges from one source file.
     ///
     /// Returns `HashMap<"file::caller", Vec<CallEdge>>` where `CallEdge.callee` is:
-    /// - `"UiEvent::TokenStats"` for scoped enum-variant constructions
-    /// - `"AppState"` for plain indexed-struct literals
+    /// - `"UiEvent::TokenStats"` for scoped enum-variant constructions (Rust)
+    /// - `"AppState"` for plain indexed-struct literals, `new AppState()` (TS),
+    ///   `AppState()` on an indexed class (Python) or `AppState{}` (Go)
     ///
     /// Used to populate `ProjectGraph::construct_edges` so orient can show
     /// which functions *create* a type, complementing call edges which show
-    /// which functions *call* other functions.
+    /// which functions *call* other functions. Python classes are known from
+    /// the resolver (`with_resolver`); `symbols` only needs this file's.
     pub fn extract_constructions(
         &mut self,
         content: &str,
@@ -54,7 +257,8 @@ impl CallExtractor {
         known_names: &HashMap<String, Vec<String>>,
     ) -> HashMap<String, Vec<CallEdge>> {
         let mut result: HashMap<String, Vec<CallEdge>> = HashMap::new();
-        let tree = match self.parser.parse(content.as_bytes(), None) {
+        let Some(lang) = Lang::from_path(file) else { return result };
+        let tree = match self.parse(lang, content) {
             Some(t) => t,
             None => return result,
         };
@@ -62,7 +266,11 @@ impl CallExtractor {
         let file_syms: Vec<&Symbol> = symbols.iter().filter(|s| s.file == file).collect();
 
         let mut raw: Vec<(usize, String)> = Vec::new();
-        collect_struct_constructions(tree.root_node(), src, known_names, &mut raw);
+        match lang {
+            Lang::Rust => collect_struct_constructions(tree.root_node(), src, known_names, &mut raw),
+            // Python has no `new` — calling an indexed class constructs it
+            _ => collect_constructions(lang, tree.root_node(), src, known_names, &self.resolver.classes, &mut raw),
+        }
 
         for (line, constructed) in raw {
             let Some(sym) = find_containing_symbol(&file_syms, line) else { continue };
@@ -72,23 +280,54 @@ impl CallExtractor {
             if sym.name == base {
                 continue;
             }
-            let key = format!("{}::{}", file, sym.name);
-            let edges = result.entry(key).or_default();
+            let edges = result.entry(sym.key()).or_default();
             // Dedup: one entry per (caller, constructed) pair — first site wins.
             if !edges.iter().any(|e| e.callee == constructed) {
-                edges.push(CallEdge { callee: constructed, call_line: line });
+                edges.push(CallEdge { callee: constructed, call_line: line, target: None });
             }
         }
         result
     }
 
+    /// Extract every use of a project name in one file: type annotations,
+    /// field reads/writes, imports, match patterns and calls.
+    ///
+    /// Types, imports, patterns and calls are kept only when the name is in
+    /// `known_names`; field accesses are always kept — fields aren't symbols,
+    /// and tracing them (`config.auto_commit`) is what this index is for.
+    pub fn extract_references(
+        &mut self,
+        content: &str,
+        file: &str,
+        known_names: &HashMap<String, Vec<String>>,
+    ) -> Vec<Reference> {
+        let Some(lang) = Lang::from_path(file) else { return Vec::new() };
+        let Some(tree) = self.parse(lang, content) else { return Vec::new() };
+        let mut refs = Vec::new();
+        collect_references(lang, tree.root_node(), content.as_bytes(), known_names, &mut refs);
+        refs.sort_by(|a, b| a.line.cmp(&b.line).then_with(|| a.name.cmp(&b.name)));
+        refs.dedup();
+        refs
+    }
+
+    /// Test cases defined in one file — see `testmap`. Empty for files the
+    /// language's test runner wouldn't collect.
+    pub fn extract_tests(&mut self, content: &str, file: &str) -> Vec<crate::testmap::TestCase> {
+        let Some(lang) = Lang::from_path(file) else { return Vec::new() };
+        if !crate::testmap::is_test_file(lang, file) {
+            return Vec::new();
+        }
+        let Some(tree) = self.parse(lang, content) else { return Vec::new() };
+        crate::testmap::collect_tests(lang, tree.root_node(), content.as_bytes(), file)
+    }
+
     /// Extract compact type signatures for all structs, enums, and traits in a file.
     /// Returns a map: symbol_name → compact definition string.
     /// Used to enrich Symbol.signature during indexing so find_symbol returns
-    /// full field/variant lists without requiring a file read.
+    /// full field/variant lists without requiring a file read. Rust only.
     pub fn extract_signatures(&mut self, content: &str) -> HashMap<String, String> {
         let mut result = HashMap::new();
-        let tree = match self.parser.parse(content.as_bytes(), None) {
+        let tree = match self.parse(Lang::Rust, content) {
             Some(t) => t,
             None => return result,
         };
@@ -97,17 +336,20 @@ impl CallExtractor {
         result
     }
 
-    /// Extract outgoing call edges from one Rust source file.
+    /// Extract outgoing call edges from one source file.
     ///
     /// # Arguments
     /// - `content`     — full UTF-8 source of the file
-    /// - `file`        — relative path used as key prefix ("src/agent.rs")
-    /// - `symbols`     — all project symbols (will be filtered to this file)
+    /// - `file`        — relative path used as key prefix ("src/agent.rs");
+    ///   its extension picks the grammar
+    /// - `symbols`     — project symbols, filtered to this file (passing only
+    ///   this file's is enough, and cheaper)
     /// - `known_names` — the `by_name` map from `ProjectGraph`; only calls
-    ///                   whose callee appears here are kept (project-internal)
+    ///   whose callee appears here are kept (project-internal)
     ///
     /// # Returns
-    /// `HashMap<String, Vec<CallEdge>>` where the key is `"file::symbol_name"`.
+    /// `HashMap<String, Vec<CallEdge>>` keyed by the caller's `Symbol::key`
+    /// (`"file::symbol_name"`, `"file::Type::method"`).
     pub fn extract_file(
         &mut self,
         content: &str,
@@ -116,8 +358,9 @@ impl CallExtractor {
         known_names: &HashMap<String, Vec<String>>,
     ) -> HashMap<String, Vec<CallEdge>> {
         let mut result: HashMap<String, Vec<CallEdge>> = HashMap::new();
+        let Some(lang) = Lang::from_path(file) else { return result };
 
-        let tree = match self.parser.parse(content.as_bytes(), None) {
+        let tree = match self.parse(lang, content) {
             Some(t) => t,
             None => return result, // parse failed — skip silently
         };
@@ -128,31 +371,37 @@ impl CallExtractor {
         let file_syms: Vec<&Symbol> = symbols.iter().filter(|s| s.file == file).collect();
 
         // Collect all raw call sites from the CST.
-        let mut raw_calls: Vec<(usize, String)> = Vec::new();
-        collect_calls(tree.root_node(), src, &mut raw_calls);
+        let mut raw_calls: Vec<CallSite> = Vec::new();
+        collect_calls(lang, tree.root_node(), src, &mut raw_calls);
+        let mut bindings: Vec<Binding> = Vec::new();
+        if lang == Lang::Rust {
+            collect_bindings(tree.root_node(), src, &mut bindings);
+        }
 
-        for (call_line, callee) in raw_calls {
+        for call in raw_calls {
             // Skip calls to external / stdlib symbols.
-            if !known_names.contains_key(&callee) {
+            if !known_names.contains_key(&call.callee) {
                 continue;
             }
 
-            // Find the top-level symbol that contains this call.
-            let Some(sym) = find_containing_symbol(&file_syms, call_line) else {
+            // Find the innermost symbol that contains this call.
+            let Some(sym) = find_containing_symbol(&file_syms, call.line) else {
                 continue;
             };
 
+            let key = sym.key();
+            let target = self.resolver.resolve(&call, sym, &bindings);
+
             // Skip trivial self-recursion (fn foo calls foo directly).
-            if sym.name == callee {
+            if target.as_ref() == Some(&key) || (target.is_none() && sym.name == call.callee) {
                 continue;
             }
 
-            let key = format!("{}::{}", file, sym.name);
             let edges = result.entry(key).or_default();
 
-            // Keep only the first call site per (caller, callee) pair.
-            if !edges.iter().any(|e| e.callee == callee) {
-                edges.push(CallEdge { callee, call_line });
+            // Keep only the first call site per (caller, callee, target).
+            if !edges.iter().any(|e| e.callee == call.callee && e.target == target) {
+                edges.push(CallEdge { callee: call.callee, call_line: call.line, target });
             }
         }
 
@@ -269,19 +518,98 @@ fn collect_struct_constructions(
     }
 }
 
-/// Walk the entire CST iteratively and collect (line, callee_name) for every
-/// call site.  Uses an explicit stack to avoid recursion-depth issues on
-/// deeply nested expressions.
-fn collect_calls(root: Node, src: &[u8], calls: &mut Vec<(usize, String)>) {
+/// Construction sites for the non-Rust grammars, recorded when the type is
+/// an indexed project symbol:
+/// - TS/JS: `new AppState(...)`
+/// - Python: `AppState(...)` where `AppState` is an indexed class
+/// - Go: `AppState{...}` / `pkg.AppState{...}` composite literals
+fn collect_constructions(
+    lang: Lang,
+    root: Node,
+    src: &[u8],
+    known_names: &HashMap<String, Vec<String>>,
+    classes: &HashSet<String>,
+    out: &mut Vec<(usize, String)>,
+) {
+    let mut stack: Vec<Node> = vec![root];
+    while let Some(node) = stack.pop() {
+        let type_node = match (lang, node.kind()) {
+            (Lang::TypeScript | Lang::Tsx, "new_expression") => node.child_by_field_name("constructor"),
+            (Lang::Python, "call") => node.child_by_field_name("function"),
+            (Lang::Go, "composite_literal") => node.child_by_field_name("type"),
+            _ => None,
+        };
+        if let Some(name) = type_node.and_then(|t| type_name(t, src)) {
+            let is_type = lang != Lang::Python || classes.contains(&name);
+            if is_type && known_names.contains_key(&name) {
+                out.push((node.start_position().row + 1, name));
+            }
+        }
+        let mut cursor = node.walk();
+        let children: Vec<Node> = node.children(&mut cursor).collect();
+        for child in children.into_iter().rev() {
+            stack.push(child);
+        }
+    }
+}
+
+/// Bare type name of a constructed type: `Foo`, `ns.Foo` / `pkg.Foo` → `Foo`,
+/// Go generics `Foo[T]` → `Foo`.
+fn type_name(node: Node, src: &[u8]) -> Option<String> {
+    let name_node = match node.kind() {
+        "identifier" | "type_identifier" => node,
+        "member_expression" => node.child_by_field_name("property")?,
+        "attribute" => node.child_by_field_name("attribute")?,
+        "qualified_type" => node.child_by_field_name("name")?,
+        "generic_type" => return node.child_by_field_name("type").and_then(|t| type_name(t, src)),
+        _ => return None,
+    };
+    name_node.utf8_text(src).ok().map(|s| s.to_string())
+}
+
+/// How a call site names its receiver.
+#[derive(Debug, Clone, PartialEq)]
+enum Receiver {
+    /// `foo()`, or a receiver expression we can't type
+    None,
+    /// `self.run()`, `Self::new()`, `this.run()`
+    SelfType,
+    /// `Type::new()` / `module::func()` — the last path segment before the name
+    Path(String),
+    /// `x.run()` — a local, a parameter, or (outside Rust) an imported module
+    Value(String),
+}
+
+#[derive(Debug)]
+struct CallSite {
+    line: usize,
+    callee: String,
+    receiver: Receiver,
+}
+
+/// A Rust local or parameter with a known type: `let x: Foo`, `let x = Foo::new()`,
+/// `let x = Foo { … }`, `fn f(x: &Foo)`.
+#[derive(Debug)]
+struct Binding {
+    line: usize,
+    var: String,
+    ty: String,
+}
+
+/// Walk the entire CST iteratively and collect every call site with its
+/// callee name and receiver.  Uses an explicit stack to avoid recursion-depth
+/// issues on deeply nested expressions.
+fn collect_calls(lang: Lang, root: Node, src: &[u8], calls: &mut Vec<CallSite>) {
     let mut stack: Vec<Node> = vec![root];
 
     while let Some(node) = stack.pop() {
         match node.kind() {
-            // Regular call: foo(), foo::bar(), obj.method() via field_expression
-            "call_expression" => {
+            // Regular call: foo(), foo::bar(), obj.method() — `call` in Python
+            "call_expression" | "call" => {
                 if let Some(func) = node.child_by_field_name("function") {
-                    if let Some(name) = callee_name(func, src) {
-                        calls.push((node.start_position().row + 1, name));
+                    if let Some(callee) = callee_name(lang, func, src) {
+                        let receiver = receiver_of(lang, func, src);
+                        calls.push(CallSite { line: node.start_position().row + 1, callee, receiver });
                     }
                 }
             }
@@ -290,8 +618,34 @@ fn collect_calls(root: Node, src: &[u8], calls: &mut Vec<(usize, String)>) {
                 if let Some(name_node) = node.child_by_field_name("name") {
                     if let Ok(name) = name_node.utf8_text(src) {
                         if !name.is_empty() {
-                            calls.push((node.start_position().row + 1, name.to_string()));
+                            calls.push(CallSite {
+                                line: node.start_position().row + 1,
+                                callee: name.to_string(),
+                                receiver: Receiver::None,
+                            });
+                        }
+                    }
+                }
+            }
+            // Macro arguments are unparsed tokens: `assert_eq!(parse(s), 2)`.
+            // An identifier directly followed by a `(…)` group is a call.
+            "token_tree" if lang == Lang::Rust => {
+                let mut cursor = node.walk();
+                let tokens: Vec<Node> = node.children(&mut cursor).collect();
+                for (i, pair) in tokens.windows(2).enumerate() {
+                    let (name, args) = (pair[0], pair[1]);
+                    if name.kind() != "identifier" || args.kind() != "token_tree" || args.child(0).is_none_or(|c| c.kind() != "(") {
+                        continue;
+                    }
+                    // `Type::new(…)` keeps its path segment
+                    let receiver = match i.checked_sub(2).map(|j| (tokens[j], tokens[j + 1])) {
+                        Some((seg, sep)) if sep.kind() == "::" && seg.kind() == "identifier" => {
+                            Receiver::Path(seg.utf8_text(src).unwrap_or("").to_string())
                         }
+                        _ => Receiver::None,
+                    };
+                    if let Ok(callee) = name.utf8_text(src) {
+                        calls.push(CallSite { line: name.start_position().row + 1, callee: callee.to_string(), receiver });
                     }
                 }
             }
@@ -309,13 +663,32 @@ fn collect_calls(root: Node, src: &[u8], calls: &mut Vec<(usize, String)>) {
 
 /// Resolve the callee name from a `function` expression node.
 ///
-/// Handles the four shapes that can appear as the `function` child of a
+/// Rust has four shapes that can appear as the `function` child of a
 /// `call_expression`:
 /// - `identifier`        — `foo()`
 /// - `scoped_identifier` — `foo::bar()` or `crate::mod::func()`
 /// - `field_expression`  — `self.method()` or `state.run()`
 /// - `generic_function`  — `collect::<Vec<_>>()` — recurse into its function
-fn callee_name(node: Node, src: &[u8]) -> Option<String> {
+///
+/// Other languages reduce member access to its last name: `obj.method()` is
+/// a `member_expression` (TS), `attribute` (Python), `selector_expression`
+/// (Go) or `field_expression` (C/C++); `ns::func()` is a C++
+/// `qualified_identifier`.
+fn callee_name(lang: Lang, node: Node, src: &[u8]) -> Option<String> {
+    if lang != Lang::Rust {
+        let name_node = match node.kind() {
+            "identifier" => node,
+            "member_expression" => node.child_by_field_name("property")?,
+            "attribute" => node.child_by_field_name("attribute")?,
+            "selector_expression" | "field_expression" => node.child_by_field_name("field")?,
+            "qualified_identifier" => return node.child_by_field_name("name").and_then(|n| callee_name(lang, n, src)),
+            "template_function" => node.child_by_field_name("name")?,
+            // `(obj.method)()` — unwrap and recurse
+            "parenthesized_expression" => return node.named_child(0).and_then(|n| callee_name(lang, n, src)),
+            _ => return None,
+        };
+        return name_node.utf8_text(src).ok().filter(|s| !s.is_empty()).map(str::to_string);
+    }
     match node.kind() {
         "identifier" => node.utf8_text(src).ok().map(|s| s.to_string()),
 
@@ -334,12 +707,266 @@ fn callee_name(node: Node, src: &[u8]) -> Option<String> {
         // `foo::<T>()` — strip the type arguments and recurse
         "generic_function" => node
             .child_by_field_name("function")
-            .and_then(|n| callee_name(n, src)),
+            .and_then(|n| callee_name(lang, n, src)),
 
         _ => None,
     }
 }
 
+/// Classify the receiver of a call's `function` expression — the part
+/// `callee_name` strips off.
+fn receiver_of(lang: Lang, node: Node, src: &[u8]) -> Receiver {
+    let text = |n: Node| n.utf8_text(src).unwrap_or("").to_string();
+    let object = match (lang, node.kind()) {
+        (Lang::Rust, "generic_function") => {
+            return node.child_by_field_name("function").map_or(Receiver::None, |f| receiver_of(lang, f, src));
+        }
+        (Lang::Rust, "scoped_identifier") => {
+            return match node.child_by_field_name("path") {
+                Some(path) if text(path) == "Self" => Receiver::SelfType,
+                Some(path) => Receiver::Path(base_type_name(&text(path))),
+                None => Receiver::None,
+            };
+        }
+        (Lang::Cpp, "qualified_identifier") => {
+            return node.child_by_field_name("scope").map_or(Receiver::None, |s| Receiver::Path(text(s)));
+        }
+        (Lang::Rust, "field_expression") => node.child_by_field_name("value"),
+        (Lang::C | Lang::Cpp, "field_expression") => node.child_by_field_name("argument"),
+        (_, "member_expression") => node.child_by_field_name("object"),
+        (_, "attribute") => node.child_by_field_name("object"),
+        (_, "selector_expression") => node.child_by_field_name("operand"),
+        _ => None,
+    };
+    let Some(object) = object else { return Receiver::None };
+    match (object.kind(), text(object).as_str()) {
+        ("self" | "this", _) => Receiver::SelfType,
+        ("identifier", "self") if lang == Lang::Python => Receiver::SelfType,
+        ("identifier", var) => Receiver::Value(var.to_string()),
+        _ => Receiver::None,
+    }
+}
+
+/// Collect typed Rust bindings: annotated or constructor-initialised `let`s
+/// and typed function parameters.
+fn collect_bindings(root: Node, src: &[u8], out: &mut Vec<Binding>) {
+    let text = |n: Node| n.utf8_text(src).unwrap_or("").to_string();
+    let mut stack: Vec<Node> = vec![root];
+    while let Some(node) = stack.pop() {
+        if matches!(node.kind(), "let_declaration" | "parameter") {
+            let var = node.child_by_field_name("pattern").filter(|p| p.kind() == "identifier");
+            let ty = match node.child_by_field_name("type") {
+                Some(ty) => Some(binding_type(&text(ty))),
+                None => node.child_by_field_name("value").and_then(|v| constructed_type(v, src)),
+            };
+            if let (Some(var), Some(ty)) = (var, ty) {
+                out.push(Binding { line: node.start_position().row + 1, var: text(var), ty });
+            }
+        }
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            stack.push(child);
+        }
+    }
+}
+
+/// Type a binding holds through references and smart pointers:
+/// `&mut Arc<Foo>` → `Foo`.
+fn binding_type(text: &str) -> String {
+    let t = text.trim_start_matches('&').trim_start_matches("mut ").trim();
+    for wrapper in ["Box<", "Arc<", "Rc<"] {
+        if let Some(inner) = t.strip_prefix(wrapper) {
+            return binding_type(inner);
+        }
+    }
+    base_type_name(t)
+}
+
+/// Type produced by an initialiser: `Foo::new(…)`, `Foo::load(…)?.await`, `Foo { … }`.
+fn constructed_type(value: Node, src: &[u8]) -> Option<String> {
+    let path = match value.kind() {
+        "try_expression" | "await_expression" => return constructed_type(value.named_child(0)?, src),
+        "struct_expression" => value.child_by_field_name("name")?,
+        "call_expression" => {
+            let func = value.child_by_field_name("function").filter(|f| f.kind() == "scoped_identifier")?;
+            func.child_by_field_name("path")?
+        }
+        _ => return None,
+    };
+    let ty = base_type_name(path.utf8_text(src).ok()?);
+    ty.starts_with(char::is_uppercase).then_some(ty)
+}
+
+// ── References ────────────────────────────────────────────────────────────────
+
+/// Walk the CST and collect name uses. Iterative, like `collect_calls`.
+fn collect_references(
+    lang: Lang,
+    root: Node,
+    src: &[u8],
+    known: &HashMap<String, Vec<String>>,
+    out: &mut Vec<Reference>,
+) {
+    let text = |n: Node| n.utf8_text(src).unwrap_or("").to_string();
+    let mut push = |name: String, node: Node, kind: RefKind, qualifier: Option<String>| {
+        out.push(Reference { name, line: node.start_position().row + 1, kind, qualifier });
+    };
+    let mut stack: Vec<Node> = vec![root];
+
+    while let Some(node) = stack.pop() {
+        let mut skip: Option<Node> = None;
+        match node.kind() {
+            // Imports — record every known name, don't look further inside
+            "use_declaration" | "import_statement" | "import_from_statement" => {
+                let mut inner: Vec<Node> = vec![node];
+                while let Some(n) = inner.pop() {
+                    if matches!(n.kind(), "identifier" | "type_identifier") && known.contains_key(&text(n)) {
+                        push(text(n), n, RefKind::Import, None);
+                    }
+                    let mut cursor = n.walk();
+                    inner.extend(n.children(&mut cursor));
+                }
+                continue;
+            }
+            "type_identifier" => {
+                if !defines_name(node) && known.contains_key(&text(node)) {
+                    push(text(node), node, RefKind::Type, None);
+                }
+            }
+            // Python annotations: `def f(x: Config) -> Graph`
+            "type" if lang == Lang::Python => {
+                for n in descendants(node).filter(|n| n.kind() == "identifier") {
+                    if known.contains_key(&text(n)) {
+                        push(text(n), n, RefKind::Type, None);
+                    }
+                }
+                continue;
+            }
+            "field_expression" | "member_expression" | "attribute" | "selector_expression" => {
+                let (object, field) = match (lang, node.kind()) {
+                    (Lang::Rust, _) => ("value", "field"),
+                    (Lang::C | Lang::Cpp, _) => ("argument", "field"),
+                    (Lang::Go, _) => ("operand", "field"),
+                    (_, "attribute") => ("object", "attribute"),
+                    _ => ("object", "property"),
+                };
+                if let Some(name) = node.child_by_field_name(field).filter(|f| f.kind().ends_with("identifier")) {
+                    let qualifier = node.child_by_field_name(object).map(text).filter(|q| q.len() <= 40 && !q.contains('\n'));
+                    match access_kind(node) {
+                        RefKind::Call if !known.contains_key(&text(name)) => {}
+                        kind => push(text(name), name, kind, qualifier),
+                    }
+                }
+            }
+            // `foo()`, `module::foo()` — member calls are covered above
+            "call_expression" | "call" => {
+                if let Some(func) = node.child_by_field_name("function") {
+                    if matches!(func.kind(), "identifier" | "scoped_identifier" | "generic_function") {
+                        if let Some(name) = callee_name(lang, func, src).filter(|n| known.contains_key(n)) {
+                            push(name, node, RefKind::Call, None);
+                        }
+                    }
+                }
+            }
+            // Rust `Profile { auto_commit: true, model }`
+            "field_initializer" | "shorthand_field_initializer" => {
+                let field = node.child_by_field_name("field").or_else(|| node.named_child(0));
+                let owner = node.parent().and_then(|l| l.parent()).and_then(|e| e.child_by_field_name("name")).map(text);
+                if let Some(field) = field.filter(|f| f.kind().ends_with("identifier")) {
+                    push(text(field), field, RefKind::FieldWrite, owner);
+                }
+            }
+            // Rust `Profile { auto_commit, .. }` in a pattern
+            "field_pattern" => {
+                let owner = node.parent().and_then(|p| p.child_by_field_name("type")).map(text);
+                if let Some(field) = node.child_by_field_name("name") {
+                    push(text(field), field, RefKind::FieldRead, owner);
+                }
+            }
+            // Rust `Mode::Normal => …`, `UiEvent::Done { .. }`, `Some(Config(..))`
+            "struct_pattern" | "tuple_struct_pattern" => {
+                if let Some(ty) = node.child_by_field_name("type") {
+                    pattern_ref(ty, &text(ty), known, &mut push);
+                    skip = Some(ty);
+                }
+            }
+            "scoped_identifier" if node.parent().is_some_and(|p| matches!(p.kind(), "match_pattern" | "or_pattern")) => {
+                pattern_ref(node, &text(node), known, &mut push);
+                continue;
+            }
+            _ => {}
+        }
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            if Some(child) != skip {
+                stack.push(child);
+            }
+        }
+    }
+}
+
+/// True for the name in a type definition (`struct Foo`, `class Foo`,
+/// `type Foo struct`) — a declaration, not a use.
+fn defines_name(node: Node) -> bool {
+    let Some(parent) = node.parent() else { return false };
+    if parent.child_by_field_name("name") != Some(node) {
+        return false;
+    }
+    match parent.kind() {
+        "struct_item" | "enum_item" | "union_item" | "type_item" | "trait_item"
+        | "class_declaration" | "abstract_class_declaration" | "interface_declaration"
+        | "type_alias_declaration" | "enum_declaration" | "type_spec" | "type_alias" => true,
+        // C/C++ `struct Foo x;` uses the type; `struct Foo { … }` defines it
+        "struct_specifier" | "union_specifier" | "class_specifier" | "enum_specifier" => {
+            parent.child_by_field_name("body").is_some()
+        }
+        _ => false,
+    }
+}
+
+/// Pattern on `A::B` records `A` (the enum); on `Foo` records `Foo`.
+fn pattern_ref(node: Node, path: &str, known: &HashMap<String, Vec<String>>, push: &mut impl FnMut(String, Node, RefKind, Option<String>)) {
+    let ty = match path.rsplit_once("::") {
+        Some((head, _)) => base_type_name(head),
+        None => path.to_string(),
+    };
+    if known.contains_key(&ty) {
+        push(ty, node, RefKind::Pattern, path.contains("::").then(|| path.to_string()));
+    }
+}
+
+/// Read, write, or call — decided by where the member expression sits.
+fn access_kind(node: Node) -> RefKind {
+    let Some(mut parent) = node.parent() else { return RefKind::FieldRead };
+    let mut child = node;
+    // Go: `a.x, b.y = …` — the left side is an expression_list
+    if parent.kind() == "expression_list" {
+        child = parent;
+        match parent.parent() {
+            Some(p) => parent = p,
+            None => return RefKind::FieldRead,
+        }
+    }
+    match parent.kind() {
+        "call_expression" | "call" if parent.child_by_field_name("function") == Some(child) => RefKind::Call,
+        "assignment_expression" | "compound_assignment_expr" | "augmented_assignment_expression"
+        | "assignment" | "augmented_assignment" | "assignment_statement"
+            if parent.child_by_field_name("left") == Some(child) => RefKind::FieldWrite,
+        "update_expression" | "inc_statement" | "dec_statement" => RefKind::FieldWrite,
+        _ => RefKind::FieldRead,
+    }
+}
+
+fn descendants(node: Node) -> impl Iterator<Item = Node> {
+    let mut stack = vec![node];
+    std::iter::from_fn(move || {
+        let n = stack.pop()?;
+        let mut cursor = n.walk();
+        stack.extend(n.children(&mut cursor));
+        Some(n)
+    })
+}
+
 // ── Helpers ───────────────────────────────────────────────────────────────────
 
 /// Find the innermost symbol in `file_syms` whose line range contains
@@ -527,7 +1154,7 @@ fn trait_sig(node: Node, src: &[u8]) -> Option<(String, String)> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::index::{SymbolKind, compute_end_lines};
+    use crate::index::{SymbolKind, Visibility};
 
     fn make_sym(name: &str, file: &str, line: usize, end_line: usize) -> Symbol {
         Symbol {
@@ -537,6 +1164,7 @@ mod tests {
             end_line,
             kind: SymbolKind::Function,
             signature: None,
+            start_byte: 0, end_byte: 0, parent: None, visibility: Visibility::Public, doc: None, implements: vec![],
         }
     }
 
@@ -555,13 +1183,10 @@ fn caller() {
 }
 fn dispatch_tool() {}
 "#;
-        let mut symbols = vec![
+        let symbols = vec![
             make_sym("caller", "src/a.rs", 2, 4),
             make_sym("dispatch_tool", "src/a.rs", 5, 5),
         ];
-        let file_lines: HashMap<String, usize> =
-            [("src/a.rs".to_string(), 6)].into_iter().collect();
-        compute_end_lines(&mut symbols, &file_lines);
 
         let known = known(&["dispatch_tool"]);
         let mut ext = CallExtractor::new().expect("tree-sitter init");
@@ -572,6 +1197,22 @@ fn dispatch_tool() {}
         assert_eq!(edges[key][0].callee, "dispatch_tool");
     }
 
+    #[test]
+    fn test_calls_inside_macro_arguments() {
+        let src = r#"
+fn caller() {
+    assert_eq!(parse("x"), Config::load(), "{}", value);
+}
+"#;
+        let symbols = vec![make_sym("caller", "src/a.rs", 2, 4)];
+        let known = known(&["parse", "load", "value"]);
+        let mut ext = CallExtractor::new().expect("tree-sitter init");
+        let edges = ext.extract_file(src, "src/a.rs", &symbols, &known);
+
+        let callees: Vec<&str> = edges["src/a.rs::caller"].iter().map(|e| e.callee.as_str()).collect();
+        assert_eq!(callees, vec!["parse", "load"], "`value` is not called");
+    }
+
     #[test]
     fn test_scoped_call() {
         let src = r#"
@@ -819,6 +1460,305 @@ fn also_default() -> AppState {
         assert!(def_edges.iter().any(|e| e.callee == "AppState"), "::default() should be tracked");
     }
 
+    // ── Other languages ───────────────────────────────────────────────────────
+
+    // ── Receiver resolution ──────────────────────────────────────────────────
+
+    /// Index `files` the way `ProjectGraph` does and return all call edges.
+    fn project_edges(files: &[(&str, &str)]) -> HashMap<String, Vec<CallEdge>> {
+        let mut symbols = Vec::new();
+        for (file, src) in files {
+            crate::index::extract_symbols(src, file, &mut symbols);
+        }
+        let mut known: HashMap<String, Vec<String>> = HashMap::new();
+        for s in &symbols {
+            known.entry(s.name.clone()).or_default().push(s.file.clone());
+        }
+        let mut ext = CallExtractor::with_resolver(Arc::new(CallResolver::new(&symbols))).expect("tree-sitter init");
+        let mut edges = HashMap::new();
+        for (file, src) in files {
+            edges.extend(ext.extract_file(src, file, &symbols, &known));
+        }
+        edges
+    }
+
+    fn target<'a>(edges: &'a HashMap<String, Vec<CallEdge>>, caller: &str, callee: &str) -> Option<&'a str> {
+        edges[caller].iter().find(|e| e.callee == callee)?.target.as_deref()
+    }
+
+    #[test]
+    fn test_resolves_module_paths_to_tool_functions() {
+        let tool = "pub fn definition() -> u32 { 1 }\npub fn execute() -> u32 { definition() }\n";
+        let edges = project_edges(&[
+            ("src/tools/read.rs", tool),
+            ("src/tools/write.rs", tool),
+            ("src/tools/mod.rs", "pub fn dispatch() {\n    read::execute();\n    super::tools::write::execute();\n}\n"),
+        ]);
+        assert_eq!(target(&edges, "src/tools/mod.rs::dispatch", "execute"), Some("src/tools/read.rs::execute"));
+        assert_eq!(edges["src/tools/mod.rs::dispatch"].len(), 2, "one edge per resolved target");
+        assert!(edges["src/tools/mod.rs::dispatch"].iter().any(|e| e.target.as_deref() == Some("src/tools/write.rs::execute")));
+        // Plain call resolves to the same file's definition
+        assert_eq!(target(&edges, "src/tools/read.rs::execute", "definition"), Some("src/tools/read.rs::definition"));
+    }
+
+    #[test]
+    fn test_resolves_self_type_and_typed_locals() {
+        let src = r#"
+pub struct Store;
+impl Store {
+    pub fn new() -> Self { Self::empty() }
+    fn empty() -> Self { Store }
+    pub fn run(&self) { self.flush(); }
+    fn flush(&self) {}
+}
+pub struct Other;
+impl Other {
+    fn flush(&self) {}
+}
+fn main() {
+    let s = Store::new();
+    s.run();
+    let o: &Other = &Other;
+    o.flush();
+}
+fn unknown(x: u32) {
+    x.run();
+}
+"#;
+        let edges = project_edges(&[("src/main.rs", src)]);
+        assert_eq!(target(&edges, "src/main.rs::Store::new", "empty"), Some("src/main.rs::Store::empty"));
+        assert_eq!(target(&edges, "src/main.rs::Store::run", "flush"), Some("src/main.rs::Store::flush"));
+        assert_eq!(target(&edges, "src/main.rs::main", "new"), Some("src/main.rs::Store::new"));
+        assert_eq!(target(&edges, "src/main.rs::main", "run"), Some("src/main.rs::Store::run"));
+        assert_eq!(target(&edges, "src/main.rs::main", "flush"), Some("src/main.rs::Other::flush"));
+        assert_eq!(target(&edges, "src/main.rs::unknown", "run"), None, "untyped receiver stays name-only");
+    }
+
+    #[test]
+    fn test_resolves_trait_default_methods() {
+        let src = r#"
+pub trait Tool {
+    fn name(&self) -> &str;
+    fn describe(&self) -> String { self.name().to_string() }
+}
+pub struct Read;
+impl Tool for Read {
+    fn name(&self) -> &str { "read" }
+}
+fn show(t: Read) {
+    t.describe();
+    t.name();
+}
+"#;
+        let edges = project_edges(&[("src/tool.rs", src)]);
+        assert_eq!(target(&edges, "src/tool.rs::show", "describe"), Some("src/tool.rs::Tool::describe"));
+        assert_eq!(target(&edges, "src/tool.rs::show", "name"), Some("src/tool.rs::Read::name"));
+        // Inside the trait, `self` is any implementor — resolves to the declaration
+        assert_eq!(target(&edges, "src/tool.rs::Tool::describe", "name"), Some("src/tool.rs::Tool::name"));
+    }
+
+    #[test]
+    fn test_resolves_python_self_and_module_calls() {
+        let edges = project_edges(&[
+            ("svc/utils.py", "def helper():\n    pass\n"),
+            ("svc/app.py", "import utils\n\nclass App:\n    def run(self):\n        self.step()\n        utils.helper()\n\n    def step(self):\n        pass\n"),
+        ]);
+        assert_eq!(target(&edges, "svc/app.py::App::run", "step"), Some("svc/app.py::App::step"));
+        assert_eq!(target(&edges, "svc/app.py::App::run", "helper"), Some("svc/utils.py::helper"));
+    }
+
+    // ── References ───────────────────────────────────────────────────────────
+
+    fn refs_of<'a>(refs: &'a [Reference], name: &str) -> Vec<(usize, &'static str, Option<&'a str>)> {
+        refs.iter()
+            .filter(|r| r.name == name)
+            .map(|r| (r.line, r.kind.label(), r.qualifier.as_deref()))
+            .collect()
+    }
+
+    #[test]
+    fn test_rust_references() {
+        let src = r#"use crate::config::{Profile, Mode};
+fn apply(config: &mut Profile, mode: Mode) -> bool {
+    config.auto_commit = true;
+    let p = Profile { auto_commit: false, model };
+    match mode {
+        Mode::Quick => config.save(),
+        Mode::Full { .. } => {}
+    }
+    let Profile { auto_commit, .. } = p;
+    helper();
+    config.auto_commit
+}
+"#;
+        let known = known(&["Profile", "Mode", "helper", "save"]);
+        let mut ext = CallExtractor::new().unwrap();
+        let refs = ext.extract_references(src, "src/a.rs", &known);
+
+        assert_eq!(
+            refs_of(&refs, "Profile"),
+            vec![(1, "import", None), (2, "type", None), (4, "type", None), (9, "pattern", None)]
+        );
+        assert_eq!(
+            refs_of(&refs, "auto_commit"),
+            vec![
+                (3, "write", Some("config")),
+                (4, "write", Some("Profile")),
+                (9, "read", Some("Profile")),
+                (11, "read", Some("config")),
+            ]
+        );
+        assert_eq!(refs_of(&refs, "model"), vec![(4, "write", Some("Profile"))], "shorthand initializer");
+        assert_eq!(
+            refs_of(&refs, "Mode"),
+            vec![(1, "import", None), (2, "type", None), (6, "pattern", Some("Mode::Quick")), (7, "pattern", Some("Mode::Full"))]
+        );
+        assert_eq!(refs_of(&refs, "save"), vec![(6, "call", Some("config"))]);
+        assert_eq!(refs_of(&refs, "helper"), vec![(10, "call", None)]);
+        assert!(refs_of(&refs, "String").is_empty(), "unknown types are not recorded");
+    }
+
+    #[test]
+    fn test_python_ts_go_references() {
+        let mut ext = CallExtractor::new().unwrap();
+        let known = known(&["Config"]);
+
+        let c = "struct Config { int retries; };\nint run(struct Config *cfg) { return cfg->retries; }\n";
+        let refs = ext.extract_references(c, "src/run.c", &known);
+        assert_eq!(refs_of(&refs, "Config"), vec![(2, "type", None)], "definition is not a use");
+        assert_eq!(refs_of(&refs, "retries"), vec![(2, "read", Some("cfg"))]);
+
+        let py = "from app.config import Config\n\ndef run(cfg: Config):\n    cfg.retries += 1\n    return cfg.retries\n";
+        let refs = ext.extract_references(py, "app/run.py", &known);
+        assert_eq!(refs_of(&refs, "Config"), vec![(1, "import", None), (3, "type", None)]);
+        assert_eq!(refs_of(&refs, "retries"), vec![(4, "write", Some("cfg")), (5, "read", Some("cfg"))]);
+
+        let ts = "import { Config } from './config';\nexport function run(cfg: Config) {\n  cfg.retries = 2;\n}\n";
+        let refs = ext.extract_references(ts, "web/run.ts", &known);
+        assert_eq!(refs_of(&refs, "Config"), vec![(1, "import", None), (2, "type", None)]);
+        assert_eq!(refs_of(&refs, "retries"), vec![(3, "write", Some("cfg"))]);
+
+        let go = "package app\n\nfunc Run(cfg *Config) int {\n\tcfg.Retries = 2\n\treturn cfg.Retries\n}\n";
+        let refs = ext.extract_references(go, "app/run.go", &known);
+        assert_eq!(refs_of(&refs, "Config"), vec![(3, "type", None)]);
+        assert_eq!(refs_of(&refs, "Retries"), vec![(4, "write", Some("cfg")), (5, "read", Some("cfg"))]);
+    }
+
+    #[test]
+    fn test_lang_from_path() {
+        assert_eq!(Lang::from_path("src/a.rs"), Some(Lang::Rust));
+        assert_eq!(Lang::from_path("web/app.ts"), Some(Lang::TypeScript));
+        assert_eq!(Lang::from_path("web/App.tsx"), Some(Lang::Tsx));
+        assert_eq!(Lang::from_path("web/util.js"), Some(Lang::Tsx));
+        assert_eq!(Lang::from_path("svc/main.py"), Some(Lang::Python));
+        assert_eq!(Lang::from_path("cmd/main.go"), Some(Lang::Go));
+        assert_eq!(Lang::from_path("include/a.h"), Some(Lang::C));
+        assert_eq!(Lang::from_path("src/engine.cpp"), Some(Lang::Cpp));
+        assert_eq!(Lang::from_path("src/Main.java"), None);
+        assert_eq!(Lang::from_path("Makefile"), None);
+    }
+
+    #[test]
+    fn test_typescript_calls_and_new() {
+        let src = r#"
+export function handler(req: Request) {
+  const store = new SessionStore();
+  return store.lookup(parseToken(req));
+}
+"#;
+        let symbols = vec![make_sym("handler", "src/api.ts", 2, 5)];
+        let known = known(&["SessionStore", "lookup", "parseToken"]);
+        let mut ext = CallExtractor::new().unwrap();
+
+        let edges = ext.extract_file(src, "src/api.ts", &symbols, &known);
+        let callees: Vec<&str> = edges["src/api.ts::handler"].iter().map(|e| e.callee.as_str()).collect();
+        assert!(callees.contains(&"lookup") && callees.contains(&"parseToken"), "got: {callees:?}");
+
+        let constructed = ext.extract_constructions(src, "src/api.ts", &symbols, &known);
+        assert_eq!(constructed["src/api.ts::handler"][0].callee, "SessionStore");
+        assert_eq!(constructed["src/api.ts::handler"][0].call_line, 3);
+    }
+
+    #[test]
+    fn test_tsx_component_calls() {
+        let src = r#"
+export function App() {
+  const items = useItems();
+  return <List items={items.map(formatItem)} />;
+}
+"#;
+        let symbols = vec![make_sym("App", "web/App.tsx", 2, 5)];
+        let known = known(&["useItems", "formatItem"]);
+        let edges = CallExtractor::new().unwrap().extract_file(src, "web/App.tsx", &symbols, &known);
+        assert_eq!(edges["web/App.tsx::App"][0].callee, "useItems");
+    }
+
+    #[test]
+    fn test_python_calls_and_class_construction() {
+        let src = r#"
+def run(argv):
+    cfg = Config(argv)
+    return cfg.load().apply(normalize(argv))
+"#;
+        let mut config = make_sym("Config", "svc/config.py", 1, 10);
+        config.kind = SymbolKind::Class;
+        let symbols = vec![make_sym("run", "svc/main.py", 2, 4), config];
+        let known = known(&["Config", "load", "apply", "normalize"]);
+        let mut ext = CallExtractor::with_resolver(Arc::new(CallResolver::new(&symbols))).unwrap();
+
+        let edges = ext.extract_file(src, "svc/main.py", &symbols, &known);
+        let callees: Vec<&str> = edges["svc/main.py::run"].iter().map(|e| e.callee.as_str()).collect();
+        for name in ["Config", "load", "apply", "normalize"] {
+            assert!(callees.contains(&name), "missing {name}: {callees:?}");
+        }
+
+        let constructed = ext.extract_constructions(src, "svc/main.py", &symbols, &known);
+        let constructed: Vec<&str> = constructed["svc/main.py::run"].iter().map(|e| e.callee.as_str()).collect();
+        assert_eq!(constructed, ["Config"], "function calls are not constructions");
+    }
+
+    #[test]
+    fn test_go_calls_and_composite_literals() {
+        let src = r#"package main
+
+func Serve(addr string) error {
+	srv := &Server{Addr: addr}
+	cfg := config.Options{}
+	return srv.Listen(validate(addr), cfg)
+}
+"#;
+        let symbols = vec![make_sym("Serve", "cmd/main.go", 3, 7)];
+        let known = known(&["Server", "Options", "Listen", "validate"]);
+        let mut ext = CallExtractor::new().unwrap();
+
+        let edges = ext.extract_file(src, "cmd/main.go", &symbols, &known);
+        let callees: Vec<&str> = edges["cmd/main.go::Serve"].iter().map(|e| e.callee.as_str()).collect();
+        assert!(callees.contains(&"Listen") && callees.contains(&"validate"), "got: {callees:?}");
+
+        let constructed = ext.extract_constructions(src, "cmd/main.go", &symbols, &known);
+        let constructed: Vec<&str> = constructed["cmd/main.go::Serve"].iter().map(|e| e.callee.as_str()).collect();
+        assert_eq!(constructed, ["Server", "Options"]);
+    }
+
+    #[test]
+    fn test_cpp_calls() {
+        let src = "void Engine::tick() {\n    update(1);\n    world->step();\n    physics::solve();\n}\n";
+        let symbols = vec![make_sym("tick", "src/engine.cpp", 1, 5)];
+        let known = known(&["update", "step", "solve"]);
+        let mut ext = CallExtractor::new().unwrap();
+        let edges = ext.extract_file(src, "src/engine.cpp", &symbols, &known);
+        let callees: Vec<&str> = edges["src/engine.cpp::tick"].iter().map(|e| e.callee.as_str()).collect();
+        assert_eq!(callees, vec!["update", "step", "solve"]);
+    }
+
+    #[test]
+    fn test_unsupported_extension_yields_nothing() {
+        let symbols = vec![make_sym("f", "src/A.java", 1, 3)];
+        let known = known(&["g"]);
+        let mut ext = CallExtractor::new().unwrap();
+        assert!(ext.extract_file("void f() { g(); }", "src/A.java", &symbols, &known).is_empty());
+    }
+
     #[test]
     fn test_format_compact_truncation() {
         let items: Vec<String> = (0..20).map(|i| format!("field_{i}: SomeLongTypeName")).collect();
@@ -0,0 +1,654 @@
+//! Native Anthropic Messages API backend.
+//!
+//! Speaks `/v1/messages` streaming directly so `tool_use` / `tool_result`
+//! blocks, thinking blocks and cache usage survive the round-trip instead of
+//! being flattened by an OpenAI-compatibility shim.
+
+use anyhow::Result;
+use futures_util::StreamExt;
+use serde_json::Value;
+use std::collections::HashMap;
+
+use super::retry::ApiError;
+use super::{Client, ContentPart, Message, MessageContent, ModelResponse, Tool, ToolCall};
+
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// Output cap sent as `max_tokens` (required by the Messages API).
+const MAX_OUTPUT_TOKENS: u32 = 8192;
+
+/// Sent as the opening user turn when history starts with an assistant message
+/// (PIE injection does this). The Messages API requires the first turn to be `user`.
+const LEADING_USER_PLACEHOLDER: &str = "(session start)";
+
+// ── Entry point ───────────────────────────────────────────────────────────────
+
+pub(super) async fn chat(
+    client: &Client,
+    system: &str,
+    messages: &[Message],
+    tools: &[Tool],
+    on_text: impl Fn(&str),
+) -> Result<ModelResponse> {
+    let (body, names) = build_request(&client.model, system, messages, tools);
+
+    let mut req = client
+        .http
+        .post(messages_url(&client.endpoint))
+        .header("Content-Type", "application/json")
+        .header("anthropic-version", ANTHROPIC_VERSION)
+        .json(&body);
+
+    if let Some(key) = &client.api_key {
+        req = req.header("x-api-key", key);
+    }
+
+    let resp = req.send().await?;
+
+    if !resp.status().is_success() {
+        return Err(ApiError::from_response(resp).await.into());
+    }
+
+    let mut state = StreamState::default();
+    let mut stream = resp.bytes_stream();
+    // Raw bytes not yet terminated by '\n' — SSE events may split anywhere,
+    // including inside a multi-byte UTF-8 sequence.
+    let mut pending: Vec<u8> = Vec::new();
+
+    while let Some(chunk) = stream.next().await {
+        pending.extend_from_slice(&chunk?);
+        while let Some(nl) = pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = pending.drain(..=nl).collect();
+            let line = String::from_utf8_lossy(&line);
+            let Some(data) = line.trim().strip_prefix("data:") else { continue };
+            let Ok(event) = serde_json::from_str::<Value>(data.trim()) else { continue };
+            state.handle_event(&event, &on_text)?;
+        }
+    }
+
+    Ok(state.finish(&names, &on_text))
+}
+
+/// Resolve the Messages endpoint from a profile endpoint.
+/// Accepts a full `/v1/messages` URL, a `/v1` base, or an OpenAI-compat
+/// `/v1/chat/completions` URL left over from older configs.
+pub fn messages_url(endpoint: &str) -> String {
+    let e = endpoint.trim_end_matches('/');
+    if e.ends_with("/messages") {
+        e.to_string()
+    } else if let Some(base) = e.strip_suffix("/chat/completions") {
+        format!("{base}/messages")
+    } else if e.ends_with("/v1") {
+        format!("{e}/messages")
+    } else {
+        format!("{e}/v1/messages")
+    }
+}
+
+// ── Request building ──────────────────────────────────────────────────────────
+
+/// The request body, and the tool names it uses for decoding the response.
+fn build_request(model: &str, system: &str, messages: &[Message], tools: &[Tool]) -> (Value, WireNames) {
+    let names = WireNames::new(tools);
+    let mut messages = build_messages(messages, &names);
+    mark_cache_breakpoint(&mut messages);
+
+    let mut body = serde_json::json!({
+        "model": model,
+        "max_tokens": MAX_OUTPUT_TOKENS,
+        "stream": true,
+        "messages": messages,
+    });
+
+    // The system prompt is identical across every turn and task in a session,
+    // so it gets its own breakpoint and is cached independently of history.
+    if !system.is_empty() {
+        body["system"] = serde_json::json!([{
+            "type": "text",
+            "text": system,
+            "cache_control": {"type": "ephemeral"},
+        }]);
+    }
+
+    if !tools.is_empty() {
+        body["tools"] = serde_json::json!(
+            tools.iter().map(|t| serde_json::json!({
+                "name": names.wire(&t.name),
+                "description": t.description,
+                "input_schema": t.parameters,
+            })).collect::<Vec<_>>()
+        );
+        body["tool_choice"] = serde_json::json!({"type": "auto"});
+    }
+
+    (body, names)
+}
+
+/// Map our OpenAI-shaped history onto Messages API turns.
+///
+/// - assistant text + `tool_calls` → `text` + `tool_use` blocks
+/// - `ContentPart::ToolResult` (role "tool" or "user") → `tool_result` blocks in a user turn
+/// - consecutive turns with the same role are merged (the API requires alternation)
+/// - empty text blocks are dropped (the API rejects them)
+fn build_messages(messages: &[Message], names: &WireNames) -> Vec<Value> {
+    let mut out: Vec<Value> = Vec::new();
+
+    for msg in messages {
+        let role = if msg.role == "assistant" { "assistant" } else { "user" };
+        let mut blocks: Vec<Value> = Vec::new();
+
+        match &msg.content {
+            MessageContent::Text(text) => push_text(&mut blocks, text),
+            MessageContent::Parts(parts) => {
+                for part in parts {
+                    match part {
+                        ContentPart::Text { text } => push_text(&mut blocks, text),
+                        ContentPart::ToolResult { tool_use_id, content } => {
+                            blocks.push(serde_json::json!({
+                                "type": "tool_result",
+                                "tool_use_id": tool_use_id,
+                                "content": content,
+                            }));
+                        }
+                    }
+                }
+            }
+        }
+
+        for tc in &msg.tool_calls {
+            let input: Value = serde_json::from_str(&tc.arguments)
+                .ok()
+                .filter(Value::is_object)
+                .unwrap_or_else(|| serde_json::json!({}));
+            blocks.push(serde_json::json!({
+                "type": "tool_use",
+                "id": tc.id,
+                "name": names.wire(&tc.name),
+                "input": input,
+            }));
+        }
+
+        if blocks.is_empty() {
+            continue;
+        }
+
+        match out.last_mut() {
+            Some(last) if last["role"] == role => {
+                if let Some(content) = last["content"].as_array_mut() {
+                    content.extend(blocks);
+                }
+            }
+            _ => out.push(serde_json::json!({ "role": role, "content": blocks })),
+        }
+    }
+
+    if out.first().is_some_and(|m| m["role"] == "assistant") {
+        out.insert(0, serde_json::json!({
+            "role": "user",
+            "content": [{"type": "text", "text": LEADING_USER_PLACEHOLDER}],
+        }));
+    }
+
+    out
+}
+
+/// Mark the final block of the conversation as a cache breakpoint.
+///
+/// Within a task the history only grows by appending, so everything sent this
+/// turn — PIE injection, prior turns, tool results — is the next turn's prefix.
+/// The API reuses the longest cached prefix ending at or before a breakpoint.
+fn mark_cache_breakpoint(messages: &mut [Value]) {
+    let last_block = messages
+        .last_mut()
+        .and_then(|m| m["content"].as_array_mut())
+        .and_then(|blocks| blocks.last_mut());
+    if let Some(block) = last_block {
+        block["cache_control"] = serde_json::json!({"type": "ephemeral"});
+    }
+}
+
+fn push_text(blocks: &mut Vec<Value>, text: &str) {
+    if !text.trim().is_empty() {
+        blocks.push(serde_json::json!({"type": "text", "text": text}));
+    }
+}
+
+/// Longest tool name the API accepts.
+const MAX_WIRE_NAME: usize = 64;
+
+/// Tool names must match `^[a-zA-Z0-9_-]{1,64}$`. MCP tools are qualified with
+/// a dot ("brave.brave_web_search"), so encode it on the way out; any other
+/// character becomes `_`.
+fn to_wire_name(name: &str) -> String {
+    let mut wire = String::new();
+    for c in name.chars() {
+        match c {
+            '.' => wire.push_str("__"),
+            c if c.is_ascii_alphanumeric() || c == '_' || c == '-' => wire.push(c),
+            _ => wire.push('_'),
+        }
+    }
+    wire.truncate(MAX_WIRE_NAME);
+    wire
+}
+
+/// The tool names of one request. Encoding isn't reversible ("a.b" and
+/// "a__b" meet), so each tool gets a distinct wire name — suffixed `_2`,
+/// `_3`… on a clash — and tool_use blocks are decoded through the map.
+#[derive(Debug, Default)]
+struct WireNames {
+    to_wire: HashMap<String, String>,
+    from_wire: HashMap<String, String>,
+}
+
+impl WireNames {
+    fn new(tools: &[Tool]) -> Self {
+        let mut names = Self::default();
+        for tool in tools.iter().filter(|t| !t.name.is_empty()) {
+            if names.to_wire.contains_key(&tool.name) {
+                continue;
+            }
+            let base = to_wire_name(&tool.name);
+            let mut wire = base.clone();
+            for n in 2.. {
+                if !names.from_wire.contains_key(&wire) {
+                    break;
+                }
+                let suffix = format!("_{n}");
+                wire = format!("{}{suffix}", &base[..base.len().min(MAX_WIRE_NAME - suffix.len())]);
+            }
+            names.to_wire.insert(tool.name.clone(), wire.clone());
+            names.from_wire.insert(wire, tool.name.clone());
+        }
+        names
+    }
+
+    /// Name to send for `name`. Tools no longer offered (older turns in the
+    /// history) fall back to the plain encoding.
+    fn wire(&self, name: &str) -> String {
+        self.to_wire.get(name).cloned().unwrap_or_else(|| to_wire_name(name))
+    }
+
+    /// Our name for a tool the model called.
+    fn original(&self, wire: String) -> String {
+        self.from_wire.get(&wire).cloned().unwrap_or(wire)
+    }
+}
+
+// ── Stream accumulation ───────────────────────────────────────────────────────
+
+enum Block {
+    Text,
+    Thinking,
+    ToolUse { id: String, name: String, json: String },
+    Other,
+}
+
+#[derive(Default)]
+struct StreamState {
+    /// Content blocks by stream index.
+    blocks: Vec<Block>,
+    text: String,
+    input_tokens: u32,
+    output_tokens: u32,
+    cache_read_tokens: u32,
+    cache_write_tokens: u32,
+    /// True between a thinking block's start and stop — mirrors the
+    /// `<think>` wrapping the OpenAI path does for `reasoning_content`.
+    thinking_open: bool,
+}
+
+impl StreamState {
+    fn handle_event(&mut self, event: &Value, on_text: &impl Fn(&str)) -> Result<()> {
+        match event["type"].as_str().unwrap_or("") {
+            "message_start" => self.record_usage(&event["message"]["usage"]),
+            "message_delta" => self.record_usage(&event["usage"]),
+            "content_block_start" => {
+                let idx = event["index"].as_u64().unwrap_or(0) as usize;
+                let cb = &event["content_block"];
+                let block = match cb["type"].as_str().unwrap_or("") {
+                    "text" => {
+                        // Initial text is normally empty but may carry content.
+                        if let Some(t) = cb["text"].as_str().filter(|t| !t.is_empty()) {
+                            on_text(t);
+                            self.text.push_str(t);
+                        }
+                        Block::Text
+                    }
+                    "thinking" => {
+                        on_text("<think>");
+                        self.thinking_open = true;
+                        Block::Thinking
+                    }
+                    "tool_use" => Block::ToolUse {
+                        id: cb["id"].as_str().unwrap_or("").to_string(),
+                        name: cb["name"].as_str().unwrap_or("").to_string(),
+                        json: String::new(),
+                    },
+                    _ => Block::Other,
+                };
+                while self.blocks.len() <= idx {
+                    self.blocks.push(Block::Other);
+                }
+                self.blocks[idx] = block;
+            }
+            "content_block_delta" => {
+                let idx = event["index"].as_u64().unwrap_or(0) as usize;
+                let delta = &event["delta"];
+                match (self.blocks.get_mut(idx), delta["type"].as_str().unwrap_or("")) {
+                    (Some(Block::Text), "text_delta") => {
+                        let t = delta["text"].as_str().unwrap_or("");
+                        on_text(t);
+                        self.text.push_str(t);
+                    }
+                    (Some(Block::Thinking), "thinking_delta") => {
+                        on_text(delta["thinking"].as_str().unwrap_or(""));
+                    }
+                    (Some(Block::ToolUse { json, .. }), "input_json_delta") => {
+                        json.push_str(delta["partial_json"].as_str().unwrap_or(""));
+                    }
+                    _ => {}
+                }
+            }
+            "content_block_stop" => {
+                let idx = event["index"].as_u64().unwrap_or(0) as usize;
+                if matches!(self.blocks.get(idx), Some(Block::Thinking)) && self.thinking_open {
+                    on_text("</think>");
+                    self.thinking_open = false;
+                }
+            }
+            "error" => {
+                // Mid-stream errors carry no HTTP status; map the error type to the
+                // status Anthropic documents for it so retry classification applies.
+                let err = &event["error"];
+                let kind = err["type"].as_str().unwrap_or("error");
+                let status = match kind {
+                    "rate_limit_error" => 429,
+                    "api_error" => 500,
+                    "overloaded_error" => 529,
+                    _ => 400,
+                };
+                return Err(ApiError {
+                    status,
+                    retry_after: None,
+                    body: format!("{kind}: {}", err["message"].as_str().unwrap_or("stream error")),
+                }
+                .into());
+            }
+            // ping, message_stop
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Usage arrives in `message_start` (input side) and `message_delta`
+    /// (cumulative output). Fields absent from an event leave prior values intact.
+    fn record_usage(&mut self, usage: &Value) {
+        let get = |k: &str| usage[k].as_u64().map(|n| n as u32);
+        let uncached = get("input_tokens");
+        if let Some(n) = get("cache_read_input_tokens") {
+            self.cache_read_tokens = n;
+        }
+        if let Some(n) = get("cache_creation_input_tokens") {
+            self.cache_write_tokens = n;
+        }
+        if let Some(n) = uncached {
+            // Anthropic's input_tokens excludes cached tokens — fold them back in
+            // so input_tokens means "total prompt size" across providers.
+            self.input_tokens = n + self.cache_read_tokens + self.cache_write_tokens;
+        }
+        if let Some(n) = get("output_tokens") {
+            self.output_tokens = n;
+        }
+    }
+
+    fn finish(mut self, names: &WireNames, on_text: &impl Fn(&str)) -> ModelResponse {
+        if self.thinking_open {
+            on_text("</think>");
+        }
+        let tool_calls = std::mem::take(&mut self.blocks)
+            .into_iter()
+            .filter_map(|b| match b {
+                Block::ToolUse { id, name, json } if !name.is_empty() => Some(ToolCall {
+                    id,
+                    name: names.original(name),
+                    arguments: if json.trim().is_empty() { "{}".to_string() } else { json },
+                }),
+                _ => None,
+            })
+            .collect();
+        ModelResponse {
+            text: self.text,
+            tool_calls,
+            input_tokens: self.input_tokens,
+            output_tokens: self.output_tokens,
+            cache_read_tokens: self.cache_read_tokens,
+            cache_write_tokens: self.cache_write_tokens,
+        }
+    }
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::Provider;
+    use crate::client::test_server::{self, MockResponse};
+
+    fn user(text: &str) -> Message {
+        Message { role: "user".to_string(), content: MessageContent::from(text), tool_calls: vec![] }
+    }
+
+    #[test]
+    fn test_messages_url() {
+        assert_eq!(messages_url("https://api.anthropic.com/v1/messages"), "https://api.anthropic.com/v1/messages");
+        assert_eq!(messages_url("https://api.anthropic.com/v1/chat/completions"), "https://api.anthropic.com/v1/messages");
+        assert_eq!(messages_url("https://api.anthropic.com/v1/"), "https://api.anthropic.com/v1/messages");
+        assert_eq!(messages_url("http://127.0.0.1:9000"), "http://127.0.0.1:9000/v1/messages");
+    }
+
+    #[test]
+    fn test_build_messages_tool_round_trip() {
+        let messages = vec![
+            user("fix it"),
+            Message {
+                role: "assistant".to_string(),
+                content: MessageContent::from(""),
+                tool_calls: vec![ToolCall {
+                    id: "toolu_1".to_string(),
+                    name: "brave.search".to_string(),
+                    arguments: r#"{"q":"x"}"#.to_string(),
+                }],
+            },
+            Message {
+                role: "tool".to_string(),
+                content: MessageContent::Parts(vec![ContentPart::ToolResult {
+                    tool_use_id: "toolu_1".to_string(),
+                    content: "result".to_string(),
+                }]),
+                tool_calls: vec![],
+            },
+            user("now continue"),
+        ];
+        let out = build_messages(&messages, &WireNames::default());
+        assert_eq!(out.len(), 3, "tool result + follow-up user text merge into one turn");
+        assert_eq!(out[1]["role"], "assistant");
+        // Empty CoT text is dropped; only the tool_use block remains
+        assert_eq!(out[1]["content"].as_array().unwrap().len(), 1);
+        assert_eq!(out[1]["content"][0]["type"], "tool_use");
+        assert_eq!(out[1]["content"][0]["name"], "brave__search");
+        assert_eq!(out[1]["content"][0]["input"]["q"], "x");
+        assert_eq!(out[2]["role"], "user");
+        assert_eq!(out[2]["content"][0]["type"], "tool_result");
+        assert_eq!(out[2]["content"][0]["tool_use_id"], "toolu_1");
+        assert_eq!(out[2]["content"][1]["text"], "now continue");
+    }
+
+    #[test]
+    fn test_build_messages_leading_assistant_gets_user_turn() {
+        let messages = vec![Message {
+            role: "assistant".to_string(),
+            content: MessageContent::from(""),
+            tool_calls: vec![ToolCall {
+                id: "pie_ctx_0".to_string(),
+                name: "find_symbol".to_string(),
+                arguments: "not json".to_string(),
+            }],
+        }];
+        let out = build_messages(&messages, &WireNames::default());
+        assert_eq!(out[0]["role"], "user");
+        assert_eq!(out[1]["content"][0]["input"], serde_json::json!({}));
+    }
+
+    #[test]
+    fn test_build_request_tools() {
+        let tools = vec![Tool {
+            name: "read_file".to_string(),
+            description: "Read".to_string(),
+            parameters: serde_json::json!({"type": "object"}),
+        }];
+        let (body, _) = build_request("claude-x", "sys", &[user("hi")], &tools);
+        assert_eq!(body["system"][0]["text"], "sys");
+        assert_eq!(body["stream"], true);
+        assert_eq!(body["tools"][0]["input_schema"]["type"], "object");
+        assert_eq!(body["tool_choice"]["type"], "auto");
+        assert!(body["max_tokens"].as_u64().unwrap() > 0);
+    }
+
+    #[test]
+    fn test_wire_names_are_distinct_and_decode_back() {
+        let tool = |name: &str| Tool {
+            name: name.to_string(),
+            description: String::new(),
+            parameters: serde_json::json!({"type": "object"}),
+        };
+        let long = format!("srv.{}", "x".repeat(80));
+        let tools = vec![tool("a.b"), tool("a__b"), tool("gh/search issues"), tool(&long)];
+        let (body, names) = build_request("claude-x", "", &[], &tools);
+        let sent: Vec<&str> = body["tools"].as_array().unwrap().iter().map(|t| t["name"].as_str().unwrap()).collect();
+        assert_eq!(sent[..3], ["a__b", "a__b_2", "gh_search_issues"]);
+        assert_eq!(sent[3].len(), 64);
+        for (wire, tool) in sent.iter().zip(&tools) {
+            assert_eq!(names.original(wire.to_string()), tool.name);
+        }
+        assert_eq!(names.original("unknown".into()), "unknown");
+    }
+
+    #[test]
+    fn test_build_request_cache_breakpoints() {
+        let history = vec![
+            user("first"),
+            Message {
+                role: "assistant".to_string(),
+                content: MessageContent::from("reply"),
+                tool_calls: vec![],
+            },
+            user("second"),
+        ];
+        let (body, _) = build_request("claude-x", "sys", &history, &[]);
+        assert_eq!(body["system"][0]["cache_control"]["type"], "ephemeral");
+        let msgs = body["messages"].as_array().unwrap();
+        assert_eq!(msgs[2]["content"][0]["cache_control"]["type"], "ephemeral");
+        // Only the tail of the conversation carries a breakpoint.
+        assert!(msgs[0]["content"][0].get("cache_control").is_none());
+        assert!(msgs[1]["content"][0].get("cache_control").is_none());
+
+        let (body, _) = build_request("claude-x", "", &[], &[]);
+        assert!(body.get("system").is_none());
+        assert!(body["messages"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_record_usage_folds_cache_into_input() {
+        let mut s = StreamState::default();
+        s.record_usage(&serde_json::json!({
+            "input_tokens": 10,
+            "cache_read_input_tokens": 1000,
+            "cache_creation_input_tokens": 200,
+            "output_tokens": 1,
+        }));
+        s.record_usage(&serde_json::json!({"output_tokens": 42}));
+        assert_eq!(s.input_tokens, 1210);
+        assert_eq!(s.cache_read_tokens, 1000);
+        assert_eq!(s.cache_write_tokens, 200);
+        assert_eq!(s.output_tokens, 42);
+    }
+
+    #[tokio::test]
+    async fn test_chat_against_mock_server() {
+        let events = [
+            r#"{"type":"message_start","message":{"usage":{"input_tokens":5,"cache_read_input_tokens":100,"cache_creation_input_tokens":0,"output_tokens":1}}}"#,
+            r#"{"type":"content_block_start","index":0,"content_block":{"type":"thinking","thinking":""}}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"thinking_delta","thinking":"hmm"}}"#,
+            r#"{"type":"content_block_stop","index":0}"#,
+            r#"{"type":"content_block_start","index":1,"content_block":{"type":"text","text":""}}"#,
+            r#"{"type":"content_block_delta","index":1,"delta":{"type":"text_delta","text":"Hello"}}"#,
+            r#"{"type":"content_block_stop","index":1}"#,
+            r#"{"type":"content_block_start","index":2,"content_block":{"type":"tool_use","id":"toolu_9","name":"fs__read","input":{}}}"#,
+            r#"{"type":"content_block_delta","index":2,"delta":{"type":"input_json_delta","partial_json":"{\"path\":"}}"#,
+            r#"{"type":"content_block_delta","index":2,"delta":{"type":"input_json_delta","partial_json":"\"a.rs\"}"}}"#,
+            r#"{"type":"content_block_stop","index":2}"#,
+            r#"{"type":"message_delta","delta":{"stop_reason":"tool_use"},"usage":{"output_tokens":17}}"#,
+            r#"{"type":"message_stop"}"#,
+        ];
+        let (url, handle) = test_server::serve(vec![MockResponse::sse(&events)]).await;
+
+        let mut client = Client::new(format!("{url}/v1/messages"), "claude-test".to_string());
+        client.set_api_key("sk-ant-test".to_string());
+        assert_eq!(client.provider, Provider::Anthropic);
+
+        let tools = vec![Tool {
+            name: "fs.read".to_string(),
+            description: "Read".to_string(),
+            parameters: serde_json::json!({"type": "object"}),
+        }];
+        let streamed = std::sync::Mutex::new(String::new());
+        let resp = client
+            .chat("be brief", &[user("hi")], &tools, |t| streamed.lock().unwrap().push_str(t))
+            .await
+            .unwrap();
+
+        assert_eq!(resp.text, "Hello");
+        assert_eq!(streamed.into_inner().unwrap(), "<think>hmm</think>Hello");
+        assert_eq!(resp.tool_calls.len(), 1);
+        assert_eq!(resp.tool_calls[0].id, "toolu_9");
+        assert_eq!(resp.tool_calls[0].name, "fs.read", "wire name decoded back");
+        assert_eq!(resp.tool_calls[0].arguments, r#"{"path":"a.rs"}"#);
+        assert_eq!(resp.input_tokens, 105);
+        assert_eq!(resp.cache_read_tokens, 100);
+        assert_eq!(resp.output_tokens, 17);
+
+        let bodies = handle.await.unwrap();
+        let sent: Value = serde_json::from_str(&bodies[0]).unwrap();
+        assert_eq!(sent["model"], "claude-test");
+        assert_eq!(sent["system"][0]["text"], "be brief");
+        assert_eq!(sent["system"][0]["cache_control"]["type"], "ephemeral");
+        assert_eq!(sent["tools"][0]["name"], "fs__read");
+    }
+
+    #[tokio::test]
+    async fn test_chat_stream_error_event() {
+        let events = [
+            r#"{"type":"message_start","message":{"usage":{"input_tokens":5}}}"#,
+            r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#,
+        ];
+        let (url, _handle) = test_server::serve(vec![MockResponse::sse(&events)]).await;
+        let mut client = Client::new(url, "claude-test".to_string());
+        client.set_provider(Provider::Anthropic);
+        let err = client.chat("", &[user("hi")], &[], |_| {}).await.unwrap_err();
+        assert!(err.to_string().contains("overloaded_error"), "got: {err}");
+    }
+
+    #[tokio::test]
+    async fn test_chat_http_error_status() {
+        let resp = MockResponse::error(401, r#"{"type":"error","error":{"type":"authentication_error"}}"#)
+            .with_header("content-type", "application/json");
+        let (url, _handle) = test_server::serve(vec![resp]).await;
+        let mut client = Client::new(url, "claude-test".to_string());
+        client.set_provider(Provider::Anthropic);
+        let err = client.chat("", &[user("hi")], &[], |_| {}).await.unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.contains("401") && msg.contains("authentication_error"), "got: {msg}");
+    }
+}
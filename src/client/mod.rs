@@ -1,8 +1,46 @@
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+pub mod anthropic;
+pub mod retry;
+#[cfg(test)]
+pub(crate) mod test_server;
+
+// ── Provider ──────────────────────────────────────────────────────────────────
+
+/// Wire protocol spoken by a model endpoint.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Provider {
+    /// OpenAI-compatible `/chat/completions` streaming (Ollama, OpenAI, OpenRouter, …)
+    #[default]
+    OpenAi,
+    /// Native Anthropic Messages API (`/v1/messages`)
+    Anthropic,
+}
+
+impl Provider {
+    /// Guess the provider from an endpoint URL. Used when the profile doesn't
+    /// set `provider` explicitly.
+    pub fn detect(endpoint: &str) -> Self {
+        let e = endpoint.trim_end_matches('/');
+        if e.contains("api.anthropic.com") || e.ends_with("/messages") {
+            Provider::Anthropic
+        } else {
+            Provider::OpenAi
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Provider::OpenAi => "openai",
+            Provider::Anthropic => "anthropic",
+        }
+    }
+}
+
 // ── Wire types ────────────────────────────────────────────────────────────────
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -63,12 +101,17 @@ pub struct ToolCall {
 
 // ── Model response after streaming completes ──────────────────────────────────
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct ModelResponse {
     pub text: String,
     pub tool_calls: Vec<ToolCall>,
+    /// Total prompt tokens, including any served from or written to the cache.
     pub input_tokens: u32,
     pub output_tokens: u32,
+    /// Prompt tokens read from the provider's prompt cache (subset of `input_tokens`).
+    pub cache_read_tokens: u32,
+    /// Prompt tokens written to the provider's prompt cache (subset of `input_tokens`).
+    pub cache_write_tokens: u32,
 }
 
 // ── SSE delta types for accumulation ─────────────────────────────────────────
@@ -113,6 +156,13 @@ struct FunctionDelta {
 struct UsageStats {
     prompt_tokens: Option<u32>,
     completion_tokens: Option<u32>,
+    /// OpenAI reports automatic prefix-cache hits here.
+    prompt_tokens_details: Option<PromptTokensDetails>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PromptTokensDetails {
+    cached_tokens: Option<u32>,
 }
 
 // ── In-progress tool call accumulator ────────────────────────────────────────
@@ -126,27 +176,114 @@ struct PendingToolCall {
 
 // ── Client ────────────────────────────────────────────────────────────────────
 
+#[derive(Clone)]
 pub struct Client {
     http: reqwest::Client,
     pub endpoint: String,
     pub model: String,
     api_key: Option<String>,
+    /// Wire protocol — detected from the endpoint unless overridden by the profile.
+    pub provider: Provider,
+    /// Applied by `chat_with_retry`; bare clients don't retry.
+    pub retry: retry::RetryPolicy,
+    /// Prices for `model`, used to cost each response (None = unpriced).
+    pub pricing: Option<crate::pricing::ModelPricing>,
 }
 
 impl Client {
     pub fn new(endpoint: String, model: String) -> Self {
+        let provider = Provider::detect(&endpoint);
         Self {
             http: reqwest::Client::new(),
             endpoint,
             model,
             api_key: None,
+            provider,
+            retry: retry::RetryPolicy::none(),
+            pricing: None,
         }
     }
 
+    /// Client for the resolved profile: endpoint, model, key, provider and retry policy.
+    pub fn from_resolved(cfg: &crate::config::ResolvedConfig) -> Self {
+        let mut client = Self::new(cfg.endpoint.clone(), cfg.model.clone());
+        if let Some(key) = &cfg.api_key {
+            client.set_api_key(key.clone());
+        }
+        client.set_provider(cfg.provider);
+        client.set_retry_policy(cfg.retry);
+        client.pricing = cfg.pricing;
+        client
+    }
+
+    /// Clients for the profile's `fallback_profiles`, in order, labelled by profile name.
+    /// Fallbacks share the primary's retry policy.
+    pub fn fallbacks_for(cfg: &crate::config::ResolvedConfig) -> Vec<(String, Client)> {
+        cfg.fallbacks
+            .iter()
+            .map(|fb| {
+                let mut client = Self::new(fb.endpoint.clone(), fb.model.clone());
+                if let Some(key) = &fb.api_key {
+                    client.set_api_key(key.clone());
+                }
+                client.set_provider(fb.provider);
+                client.set_retry_policy(cfg.retry);
+                client.pricing = fb.pricing;
+                (fb.profile_name.clone(), client)
+            })
+            .collect()
+    }
+
     pub fn set_api_key(&mut self, key: String) {
         self.api_key = Some(key);
     }
 
+    pub fn set_provider(&mut self, provider: Provider) {
+        self.provider = provider;
+    }
+
+    pub fn set_retry_policy(&mut self, policy: retry::RetryPolicy) {
+        self.retry = policy;
+    }
+
+    /// USD cost of a response from this client, if its model is priced.
+    pub fn cost_of(&self, resp: &ModelResponse) -> Option<f64> {
+        self.pricing.map(|p| {
+            p.cost(resp.input_tokens, resp.output_tokens, resp.cache_read_tokens, resp.cache_write_tokens)
+        })
+    }
+
+    /// `chat`, retrying transient failures per `self.retry`.
+    /// `on_retry` is called before each backoff sleep. Text already streamed by a
+    /// failed attempt is not retracted — the retry streams its response afresh.
+    pub async fn chat_with_retry(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+        on_text: impl Fn(&str),
+        on_retry: impl Fn(&retry::RetryNotice),
+    ) -> Result<ModelResponse> {
+        let mut attempt = 0;
+        loop {
+            match self.chat(system, messages, tools, &on_text).await {
+                Ok(resp) => return Ok(resp),
+                Err(e) if attempt < self.retry.max_retries && retry::is_transient(&e) => {
+                    let delay = self.retry.delay(attempt, retry::retry_after(&e));
+                    attempt += 1;
+                    on_retry(&retry::RetryNotice {
+                        attempt,
+                        max_retries: self.retry.max_retries,
+                        delay,
+                        reason: e.to_string(),
+                    });
+                    tokio::time::sleep(delay).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
     /// Stream a chat completion. Calls `on_text` for each text chunk as it arrives.
     /// Returns the complete response once streaming finishes.
     pub async fn chat(
@@ -155,6 +292,20 @@ impl Client {
         messages: &[Message],
         tools: &[Tool],
         on_text: impl Fn(&str),
+    ) -> Result<ModelResponse> {
+        match self.provider {
+            Provider::OpenAi => self.chat_openai(system, messages, tools, on_text).await,
+            Provider::Anthropic => anthropic::chat(self, system, messages, tools, on_text).await,
+        }
+    }
+
+    /// OpenAI-compatible `/chat/completions` streaming backend.
+    async fn chat_openai(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+        on_text: impl Fn(&str),
     ) -> Result<ModelResponse> {
         let mut body = serde_json::json!({
             "model": self.model,
@@ -177,6 +328,13 @@ impl Client {
             body["tool_choice"] = serde_json::json!("auto");
         }
 
+        // OpenAI caches long prompt prefixes automatically; a stable cache key
+        // keeps requests sharing our system prompt routed to the same cache.
+        // Only sent to OpenAI itself — other compatible servers may reject it.
+        if self.endpoint.contains("api.openai.com") {
+            body["prompt_cache_key"] = serde_json::json!(prompt_cache_key(&self.model, system));
+        }
+
         let url = self.endpoint.clone();
 
         let mut req = self
@@ -192,9 +350,7 @@ impl Client {
         let resp = req.send().await?;
 
         if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("API error {}: {}", status, text));
+            return Err(retry::ApiError::from_response(resp).await.into());
         }
 
         let mut stream = resp.bytes_stream();
@@ -205,6 +361,7 @@ impl Client {
         let mut pending: Vec<PendingToolCall> = Vec::new();
         let mut input_tokens = 0u32;
         let mut output_tokens = 0u32;
+        let mut cache_read_tokens = 0u32;
         let mut leftover = String::new();
         // Track whether we're mid-reasoning-block (for models that use reasoning_content field)
         let mut reasoning_open = false;
@@ -245,6 +402,10 @@ impl Client {
                 if let Some(usage) = chunk_val.usage {
                     input_tokens = usage.prompt_tokens.unwrap_or(0);
                     output_tokens = usage.completion_tokens.unwrap_or(0);
+                    cache_read_tokens = usage
+                        .prompt_tokens_details
+                        .and_then(|d| d.cached_tokens)
+                        .unwrap_or(0);
                 }
 
                 for choice in chunk_val.choices.unwrap_or_default() {
@@ -351,12 +512,23 @@ impl Client {
             tool_calls,
             input_tokens,
             output_tokens,
+            cache_read_tokens,
+            ..Default::default()
         })
     }
 }
 
 // ── Build the messages array for the API ──────────────────────────────────────
 
+/// Cache routing key for a system prompt: identical prompts share a key.
+fn prompt_cache_key(model: &str, system: &str) -> String {
+    use std::hash::{Hash, Hasher};
+    let mut h = std::collections::hash_map::DefaultHasher::new();
+    model.hash(&mut h);
+    system.hash(&mut h);
+    format!("parecode-{:016x}", h.finish())
+}
+
 fn build_messages(system: &str, messages: &[Message]) -> Vec<Value> {
     let mut out = Vec::new();
 
@@ -438,6 +610,7 @@ mod tests {
             tool_calls: vec![],
             input_tokens: 100,
             output_tokens: 200,
+            ..Default::default()
         };
         assert_eq!(response.text, "test response");
         assert_eq!(response.input_tokens, 100);
@@ -652,12 +825,14 @@ mod tests {
             tool_calls: vec![],
             input_tokens: 100,
             output_tokens: 200,
+            ..Default::default()
         };
         let response2 = ModelResponse {
             text: "test".to_string(),
             tool_calls: vec![],
             input_tokens: 100,
             output_tokens: 200,
+            ..Default::default()
         };
         assert_eq!(response1.text, response2.text);
         assert_eq!(response1.input_tokens, response2.input_tokens);
@@ -672,6 +847,26 @@ mod tests {
         assert_eq!(client.endpoint, "https://api.example.com/v1/chat");
         assert_eq!(client.model, "gpt-4");
         assert!(client.api_key.is_none());
+        assert_eq!(client.provider, Provider::OpenAi);
+    }
+
+    #[test]
+    fn test_provider_detect() {
+        assert_eq!(Provider::detect("http://localhost:11434/v1/chat/completions"), Provider::OpenAi);
+        assert_eq!(Provider::detect("https://openrouter.ai/api/v1/chat/completions"), Provider::OpenAi);
+        assert_eq!(Provider::detect("https://api.anthropic.com/v1/chat/completions"), Provider::Anthropic);
+        assert_eq!(Provider::detect("https://api.anthropic.com/v1"), Provider::Anthropic);
+        assert_eq!(Provider::detect("http://proxy.internal/v1/messages/"), Provider::Anthropic);
+    }
+
+    #[test]
+    fn test_provider_deserialize() {
+        #[derive(Deserialize)]
+        struct Wrap { provider: Provider }
+        let w: Wrap = toml::from_str(r#"provider = "anthropic""#).unwrap();
+        assert_eq!(w.provider, Provider::Anthropic);
+        let w: Wrap = toml::from_str(r#"provider = "openai""#).unwrap();
+        assert_eq!(w.provider, Provider::OpenAi);
     }
 
     #[test]
@@ -684,4 +879,81 @@ mod tests {
         client.set_api_key("sk-test123".to_string());
         assert_eq!(client.api_key, Some("sk-test123".to_string()));
     }
+
+    #[test]
+    fn test_cost_of_uses_client_pricing() {
+        let mut client = Client::new("http://localhost".to_string(), "m".to_string());
+        let resp = ModelResponse { input_tokens: 1_000_000, output_tokens: 1_000_000, ..Default::default() };
+        assert_eq!(client.cost_of(&resp), None);
+        client.pricing = Some(crate::pricing::ModelPricing { input: 1.0, output: 5.0, cache_read: 0.1, cache_write: 1.25 });
+        assert_eq!(client.cost_of(&resp), Some(6.0));
+    }
+
+    #[test]
+    fn test_prompt_cache_key_stable_per_prompt() {
+        assert_eq!(prompt_cache_key("m", "sys"), prompt_cache_key("m", "sys"));
+        assert_ne!(prompt_cache_key("m", "sys"), prompt_cache_key("m", "other"));
+        assert_ne!(prompt_cache_key("m", "sys"), prompt_cache_key("n", "sys"));
+    }
+
+    #[tokio::test]
+    async fn test_chat_openai_reports_cached_tokens() {
+        use test_server::MockResponse;
+        let events = [
+            r#"{"choices":[{"delta":{"content":"ok"}}]}"#,
+            r#"{"choices":[],"usage":{"prompt_tokens":2000,"completion_tokens":3,"prompt_tokens_details":{"cached_tokens":1536}}}"#,
+            "[DONE]",
+        ];
+        let (url, _handle) = test_server::serve(vec![MockResponse::sse(&events)]).await;
+        let client = Client::new(format!("{url}/v1/chat/completions"), "gpt-test".to_string());
+        let msgs = [Message { role: "user".into(), content: "hi".into(), tool_calls: vec![] }];
+        let resp = client.chat("sys", &msgs, &[], |_| {}).await.unwrap();
+        assert_eq!(resp.text, "ok");
+        assert_eq!(resp.input_tokens, 2000);
+        assert_eq!(resp.cache_read_tokens, 1536);
+        assert_eq!(resp.cache_write_tokens, 0);
+    }
+
+    #[tokio::test]
+    async fn test_chat_with_retry_honours_retry_after() {
+        use test_server::MockResponse;
+        let ok = MockResponse::sse(&[r#"{"choices":[{"delta":{"content":"ok"}}]}"#, "[DONE]"]);
+        let (url, handle) = test_server::serve(vec![
+            MockResponse::error(429, "rate limited").with_header("retry-after-ms", "3"),
+            ok,
+        ])
+        .await;
+        let mut client = Client::new(url, "m".to_string());
+        client.set_retry_policy(retry::RetryPolicy { max_retries: 2, base_delay_ms: 1_000, max_delay_ms: 5_000 });
+        let notices = std::sync::Mutex::new(Vec::new());
+        let msgs = [Message { role: "user".into(), content: "hi".into(), tool_calls: vec![] }];
+        let resp = client
+            .chat_with_retry("", &msgs, &[], |_| {}, |n| notices.lock().unwrap().push(n.clone()))
+            .await
+            .unwrap();
+        assert_eq!(resp.text, "ok");
+        let notices = notices.into_inner().unwrap();
+        assert_eq!(notices.len(), 1);
+        assert_eq!(notices[0].attempt, 1);
+        assert_eq!(notices[0].delay, std::time::Duration::from_millis(3));
+        assert!(notices[0].reason.contains("429"));
+        assert_eq!(handle.await.unwrap().len(), 2, "request should be resent once");
+    }
+
+    #[tokio::test]
+    async fn test_chat_with_retry_dropped_connection_is_retried() {
+        let (url, _handle) = test_server::serve(vec![]).await;
+        // Listener dropped immediately: every attempt fails to connect.
+        let mut client = Client::new(url, "m".to_string());
+        client.set_retry_policy(retry::RetryPolicy { max_retries: 2, base_delay_ms: 1, max_delay_ms: 2 });
+        let count = std::sync::atomic::AtomicU32::new(0);
+        let msgs = [Message { role: "user".into(), content: "hi".into(), tool_calls: vec![] }];
+        let res = client
+            .chat_with_retry("", &msgs, &[], |_| {}, |_| {
+                count.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            })
+            .await;
+        assert!(res.is_err());
+        assert_eq!(count.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
 }
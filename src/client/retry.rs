@@ -0,0 +1,168 @@
+//! Retry policy for model API calls.
+//!
+//! Transient failures (429, 5xx, overload, dropped connections) are retried
+//! with exponential backoff and jitter. A server-supplied `Retry-After` always
+//! wins over the computed delay.
+
+use std::fmt;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// How many times, and how patiently, to retry a failed model call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt (0 = fail immediately).
+    pub max_retries: u32,
+    /// Delay before the first retry; doubles on each subsequent retry.
+    pub base_delay_ms: u64,
+    /// Upper bound for any single delay, including `Retry-After`.
+    pub max_delay_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self { max_retries: 3, base_delay_ms: 1_000, max_delay_ms: 30_000 }
+    }
+}
+
+impl RetryPolicy {
+    /// No retries — every failure surfaces immediately.
+    pub fn none() -> Self {
+        Self { max_retries: 0, ..Self::default() }
+    }
+
+    /// Delay before retry number `retry` (0-based).
+    /// Full-jitter exponential backoff in `[d/2, d]`, or `retry_after` if the
+    /// server sent one. Both are capped at `max_delay_ms`.
+    pub fn delay(&self, retry: u32, retry_after: Option<Duration>) -> Duration {
+        let cap = Duration::from_millis(self.max_delay_ms);
+        if let Some(ra) = retry_after {
+            return ra.min(cap);
+        }
+        let exp = self
+            .base_delay_ms
+            .saturating_mul(1u64 << retry.min(20))
+            .min(self.max_delay_ms);
+        let half = exp / 2;
+        let jitter = if half > 0 { random_u64() % (half + 1) } else { 0 };
+        Duration::from_millis(half + jitter)
+    }
+}
+
+/// A non-success HTTP status (or in-stream error event) from the model API.
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: u16,
+    /// Parsed from `retry-after-ms` / `retry-after` (seconds) when present.
+    pub retry_after: Option<Duration>,
+    pub body: String,
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "API error {}: {}", self.status, self.body)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl ApiError {
+    /// Build from a failed response, consuming its body.
+    pub async fn from_response(resp: reqwest::Response) -> Self {
+        let status = resp.status().as_u16();
+        let retry_after = parse_retry_after(resp.headers());
+        let body = resp.text().await.unwrap_or_default();
+        Self { status, retry_after, body }
+    }
+}
+
+fn parse_retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    let get = |name: &str| headers.get(name)?.to_str().ok()?.trim().parse::<f64>().ok();
+    if let Some(ms) = get("retry-after-ms") {
+        return Some(Duration::from_millis(ms.max(0.0) as u64));
+    }
+    // HTTP-date form is rare for model APIs — fall back to backoff for it.
+    get("retry-after").map(|s| Duration::from_millis((s.max(0.0) * 1000.0) as u64))
+}
+
+/// Whether `err` is worth retrying: rate limits, server errors, overload,
+/// timeouts and connections dropped mid-stream. Client errors (bad request,
+/// auth) are not — repeating them yields the same answer.
+pub fn is_transient(err: &anyhow::Error) -> bool {
+    if let Some(api) = err.downcast_ref::<ApiError>() {
+        return matches!(api.status, 408 | 409 | 425 | 429) || api.status >= 500;
+    }
+    if let Some(re) = err.downcast_ref::<reqwest::Error>() {
+        return re.is_timeout() || re.is_connect() || re.is_request() || re.is_body() || re.is_decode();
+    }
+    false
+}
+
+/// Retry-After hint carried by `err`, if any.
+pub fn retry_after(err: &anyhow::Error) -> Option<Duration> {
+    err.downcast_ref::<ApiError>().and_then(|e| e.retry_after)
+}
+
+/// Reported before each retry sleep so callers can surface it.
+#[derive(Debug, Clone)]
+pub struct RetryNotice {
+    /// 1-based retry number.
+    pub attempt: u32,
+    pub max_retries: u32,
+    pub delay: Duration,
+    pub reason: String,
+}
+
+/// Per-call randomness for jitter without pulling in a RNG crate —
+/// `RandomState` is seeded freshly from the OS for every instance.
+fn random_u64() -> u64 {
+    use std::hash::{BuildHasher, Hasher};
+    std::collections::hash_map::RandomState::new().build_hasher().finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay_exponential_with_jitter_bounds() {
+        let p = RetryPolicy { max_retries: 5, base_delay_ms: 100, max_delay_ms: 1_000 };
+        for _ in 0..20 {
+            let d0 = p.delay(0, None).as_millis();
+            assert!((50..=100).contains(&d0), "retry 0: {d0}");
+            let d2 = p.delay(2, None).as_millis();
+            assert!((200..=400).contains(&d2), "retry 2: {d2}");
+            let d9 = p.delay(9, None).as_millis();
+            assert!((500..=1_000).contains(&d9), "capped: {d9}");
+        }
+    }
+
+    #[test]
+    fn test_delay_honours_retry_after_with_cap() {
+        let p = RetryPolicy { max_retries: 3, base_delay_ms: 100, max_delay_ms: 5_000 };
+        assert_eq!(p.delay(0, Some(Duration::from_secs(2))), Duration::from_secs(2));
+        assert_eq!(p.delay(0, Some(Duration::from_secs(60))), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_is_transient_by_status() {
+        let err = |status| anyhow::Error::new(ApiError { status, retry_after: None, body: String::new() });
+        assert!(is_transient(&err(429)));
+        assert!(is_transient(&err(500)));
+        assert!(is_transient(&err(529)));
+        assert!(!is_transient(&err(400)));
+        assert!(!is_transient(&err(401)));
+        assert!(!is_transient(&anyhow::anyhow!("something else")));
+    }
+
+    #[test]
+    fn test_parse_retry_after_headers() {
+        let mut h = reqwest::header::HeaderMap::new();
+        assert_eq!(parse_retry_after(&h), None);
+        h.insert("retry-after", "3".parse().unwrap());
+        assert_eq!(parse_retry_after(&h), Some(Duration::from_secs(3)));
+        h.insert("retry-after-ms", "250".parse().unwrap());
+        assert_eq!(parse_retry_after(&h), Some(Duration::from_millis(250)));
+    }
+}
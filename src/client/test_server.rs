@@ -0,0 +1,184 @@
+//! Minimal scripted HTTP server for exercising the streaming backends in tests.
+//!
+//! Each accepted connection is answered with the next canned response in order,
+//! then closed. The raw request bodies are handed back once all responses are
+//! used so tests can assert on what the client actually sent.
+
+use std::sync::Arc;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+
+/// A canned HTTP response: status line code, extra headers, body.
+pub struct MockResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+    /// Chunks written after `body` until the sender is dropped — a held-open
+    /// event stream.
+    pub stream: Option<mpsc::UnboundedReceiver<String>>,
+}
+
+/// One received request. Header names are lower-cased.
+pub struct MockRequest {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl MockRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str())
+    }
+}
+
+impl MockResponse {
+    /// 200 with an SSE body built from `data:` payloads (one per event).
+    pub fn sse(events: &[&str]) -> Self {
+        let body: String = events.iter().map(|e| format!("data: {e}\n\n")).collect();
+        Self {
+            status: 200,
+            headers: vec![("content-type".to_string(), "text/event-stream".to_string())],
+            body,
+            stream: None,
+        }
+    }
+
+    /// 200 event stream that stays open, writing each chunk received on
+    /// `chunks` until its sender is dropped.
+    pub fn event_stream(chunks: mpsc::UnboundedReceiver<String>) -> Self {
+        Self {
+            status: 200,
+            headers: vec![("content-type".to_string(), "text/event-stream".to_string())],
+            body: String::new(),
+            stream: Some(chunks),
+        }
+    }
+
+    /// 200 with a JSON body.
+    pub fn json(body: &str) -> Self {
+        Self {
+            status: 200,
+            headers: vec![("content-type".to_string(), "application/json".to_string())],
+            body: body.to_string(),
+            stream: None,
+        }
+    }
+
+    /// Non-2xx response with a plain body.
+    pub fn error(status: u16, body: &str) -> Self {
+        Self { status, headers: vec![], body: body.to_string(), stream: None }
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+/// Bind to an ephemeral port and serve `responses` sequentially.
+/// Returns the base URL (`http://127.0.0.1:PORT`) and a handle yielding the
+/// request bodies received, one per response.
+pub async fn serve(responses: Vec<MockResponse>) -> (String, tokio::task::JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind mock server");
+    let addr = listener.local_addr().expect("mock server addr");
+    let handle = tokio::spawn(async move {
+        let mut bodies = Vec::new();
+        for resp in responses {
+            let Ok((mut sock, _)) = listener.accept().await else { break };
+            bodies.push(read_request(&mut sock).await.body);
+            write_response(&mut sock, resp).await;
+        }
+        bodies
+    });
+    (format!("http://{addr}"), handle)
+}
+
+/// Bind to an ephemeral port and answer every request with `handler(body)`
+/// until the test's runtime shuts down. Returns the base URL.
+pub async fn serve_fn(handler: impl Fn(&str) -> MockResponse + Send + Sync + 'static) -> String {
+    serve_requests(move |req| handler(&req.body)).await
+}
+
+/// Like `serve_fn`, but the handler sees method, path and headers, and each
+/// connection is served concurrently — an event stream can stay open while
+/// other requests arrive.
+pub async fn serve_requests(handler: impl Fn(&MockRequest) -> MockResponse + Send + Sync + 'static) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind mock server");
+    let addr = listener.local_addr().expect("mock server addr");
+    let handler = Arc::new(handler);
+    tokio::spawn(async move {
+        while let Ok((mut sock, _)) = listener.accept().await {
+            let handler = Arc::clone(&handler);
+            tokio::spawn(async move {
+                let req = read_request(&mut sock).await;
+                let resp = handler(&req);
+                write_response(&mut sock, resp).await;
+            });
+        }
+    });
+    format!("http://{addr}")
+}
+
+async fn write_response(sock: &mut tokio::net::TcpStream, resp: MockResponse) {
+    let reason = if resp.status < 400 { "OK" } else { "Error" };
+    let mut out = format!("HTTP/1.1 {} {reason}\r\nconnection: close\r\n", resp.status);
+    for (k, v) in &resp.headers {
+        out.push_str(&format!("{k}: {v}\r\n"));
+    }
+    match resp.stream {
+        // No length: the body runs until the connection closes
+        Some(mut chunks) => {
+            out.push_str(&format!("\r\n{}", resp.body));
+            let _ = sock.write_all(out.as_bytes()).await;
+            while let Some(chunk) = chunks.recv().await {
+                if sock.write_all(chunk.as_bytes()).await.is_err() {
+                    break;
+                }
+            }
+        }
+        None => {
+            out.push_str(&format!("content-length: {}\r\n\r\n{}", resp.body.len(), resp.body));
+            let _ = sock.write_all(out.as_bytes()).await;
+        }
+    }
+    let _ = sock.shutdown().await;
+}
+
+/// Read one HTTP request.
+async fn read_request(sock: &mut tokio::net::TcpStream) -> MockRequest {
+    let mut req = MockRequest { method: String::new(), path: String::new(), headers: Vec::new(), body: String::new() };
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let header_end = loop {
+        let Ok(n) = sock.read(&mut chunk).await else { return req };
+        if n == 0 {
+            return req;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+    };
+    let head = String::from_utf8_lossy(&buf[..header_end]).into_owned();
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap_or("").split_whitespace();
+    req.method = request_line.next().unwrap_or("").to_string();
+    req.path = request_line.next().unwrap_or("").to_string();
+    req.headers = lines
+        .filter_map(|l| l.split_once(':'))
+        .map(|(k, v)| (k.trim().to_lowercase(), v.trim().to_string()))
+        .collect();
+    let content_length: usize = req.header("content-length").and_then(|v| v.parse().ok()).unwrap_or(0);
+    while buf.len() < header_end + content_length {
+        let Ok(n) = sock.read(&mut chunk).await else { break };
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+    req.body = String::from_utf8_lossy(&buf[header_end..]).into_owned();
+    req
+}
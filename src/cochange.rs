@@ -0,0 +1,296 @@
+/// PIE — co-change coupling mined from git history.
+///
+/// `ContextWeights` learns only from parecode's own tasks; this learns from
+/// every commit. `git log --name-only` lists the files each commit touched,
+/// which are counted into:
+///   - a co-change matrix: file → partner → commits that touched both
+///   - recent churn: commits touching a file in the last `CHURN_WINDOW_DAYS`
+///
+/// Stored in `ProjectGraph::cochange` and updated incrementally from the last
+/// mined commit. When that commit is no longer in HEAD's history (rebase,
+/// branch switch) the history is mined again from scratch. Paths are relative
+/// to the project root (`git log --relative`); extra index roots have no
+/// history here.
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// Commits mined on a first pass (and at most per incremental update).
+const MAX_HISTORY: usize = 2000;
+/// Larger commits are sweeping renames or format runs — they couple
+/// everything to everything, so they are skipped.
+const MAX_COMMIT_FILES: usize = 40;
+/// Commits two files must share before they count as coupled.
+const MIN_COCHANGES: u32 = 2;
+/// Share of a file's commits that must also touch the partner.
+const MIN_CONFIDENCE: f32 = 0.3;
+const CHURN_WINDOW_DAYS: i64 = 90;
+/// Recent commit times kept per file — enough to score churn.
+const MAX_TOUCHES: usize = 32;
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct CoChange {
+    /// Newest commit mined — HEAD at the last update
+    #[serde(default)]
+    pub head: Option<String>,
+    /// Commits counted (sweeping commits excluded)
+    #[serde(default)]
+    pub commits: usize,
+    /// file → commits that touched it
+    #[serde(default)]
+    pub file_commits: HashMap<String, u32>,
+    /// file → partner → commits that touched both (stored both ways)
+    #[serde(default)]
+    pub pairs: HashMap<String, HashMap<String, u32>>,
+    /// file → commit timestamps, oldest first, capped at `MAX_TOUCHES`
+    #[serde(default)]
+    pub touches: HashMap<String, Vec<i64>>,
+}
+
+/// A file that tends to change together with another.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Coupling {
+    pub file: String,
+    /// Commits that touched both files
+    pub together: u32,
+    /// `together` / commits touching the queried file
+    pub confidence: f32,
+}
+
+impl CoChange {
+    /// Mine commits added since the last update. Returns `true` when
+    /// anything changed. Outside a git repo (or with no commits) it keeps
+    /// what it has.
+    pub fn update(&mut self, root: &Path) -> bool {
+        let Some(head) = git(root, &["rev-parse", "HEAD"]).map(|h| h.trim().to_string()) else {
+            return false;
+        };
+        if self.head.as_deref() == Some(head.as_str()) {
+            return false;
+        }
+        let limit = format!("--max-count={MAX_HISTORY}");
+        let mut args = vec![
+            "-c", "core.quotePath=false", "log", "--no-merges", "--relative", "--name-only",
+            "--reverse", "--format=%x1e%ct", limit.as_str(),
+        ];
+        let range;
+        match self.head.as_deref() {
+            Some(old) if is_ancestor(root, old) => {
+                range = format!("{old}..HEAD");
+                args.push(&range);
+            }
+            _ => {
+                // First run, or history rewritten — start over
+                *self = Self::default();
+                args.push("HEAD");
+            }
+        }
+        let Some(log) = git(root, &args) else { return false };
+        for (time, files) in parse_log(&log) {
+            self.add_commit(time, &files);
+        }
+        self.head = Some(head);
+        true
+    }
+
+    /// Count one commit's files.
+    fn add_commit(&mut self, time: i64, files: &[String]) {
+        if files.is_empty() || files.len() > MAX_COMMIT_FILES {
+            return;
+        }
+        self.commits += 1;
+        for (i, file) in files.iter().enumerate() {
+            *self.file_commits.entry(file.clone()).or_default() += 1;
+            let touches = self.touches.entry(file.clone()).or_default();
+            touches.push(time);
+            if touches.len() > MAX_TOUCHES {
+                touches.remove(0);
+            }
+            for other in &files[i + 1..] {
+                *self.pairs.entry(file.clone()).or_default().entry(other.clone()).or_default() += 1;
+                *self.pairs.entry(other.clone()).or_default().entry(file.clone()).or_default() += 1;
+            }
+        }
+    }
+
+    /// Files that usually change with `file`, strongest first.
+    pub fn coupled(&self, file: &str) -> Vec<Coupling> {
+        let Some(partners) = self.pairs.get(file) else { return Vec::new() };
+        let total = self.file_commits.get(file).copied().unwrap_or(0).max(1) as f32;
+        let mut out: Vec<Coupling> = partners
+            .iter()
+            .filter(|(_, n)| **n >= MIN_COCHANGES)
+            .map(|(f, n)| Coupling { file: f.clone(), together: *n, confidence: *n as f32 / total })
+            .filter(|c| c.confidence >= MIN_CONFIDENCE)
+            .collect();
+        out.sort_by(|a, b| {
+            b.confidence.total_cmp(&a.confidence).then(b.together.cmp(&a.together)).then(a.file.cmp(&b.file))
+        });
+        out
+    }
+
+    /// Commits touching `file` in the `CHURN_WINDOW_DAYS` before `now`.
+    pub fn churn(&self, file: &str, now: i64) -> usize {
+        let since = now - CHURN_WINDOW_DAYS * 86_400;
+        self.touches.get(file).map_or(0, |t| t.iter().filter(|&&ts| ts >= since).count())
+    }
+
+    /// The `limit` files with the most recent churn, hottest first.
+    pub fn hottest(&self, now: i64, limit: usize) -> Vec<(&str, usize)> {
+        let mut out: Vec<(&str, usize)> = self
+            .touches
+            .keys()
+            .map(|f| (f.as_str(), self.churn(f, now)))
+            .filter(|(_, n)| *n > 0)
+            .collect();
+        out.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        out.truncate(limit);
+        out
+    }
+
+    /// The `limit` most strongly coupled pairs, each listed once.
+    pub fn strongest_pairs(&self, limit: usize) -> Vec<(&str, Coupling)> {
+        let mut out: Vec<(&str, Coupling)> = self
+            .pairs
+            .keys()
+            .flat_map(|f| self.coupled(f).into_iter().map(move |c| (f.as_str(), c)))
+            .filter(|(f, c)| *f < c.file.as_str())
+            .collect();
+        out.sort_by(|a, b| {
+            b.1.together.cmp(&a.1.together).then(b.1.confidence.total_cmp(&a.1.confidence)).then(a.0.cmp(b.0))
+        });
+        out.truncate(limit);
+        out
+    }
+
+    /// Number of coupled pairs (each counted once).
+    pub fn pair_count(&self) -> usize {
+        self.pairs.keys().map(|f| self.coupled(f).len()).sum::<usize>() / 2
+    }
+}
+
+// ── Git ───────────────────────────────────────────────────────────────────────
+
+/// stdout of `git <args>` in `root`, or `None` if git failed.
+fn git(root: &Path, args: &[&str]) -> Option<String> {
+    let out = std::process::Command::new("git").args(args).current_dir(root).output().ok()?;
+    out.status.success().then(|| String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+fn is_ancestor(root: &Path, commit: &str) -> bool {
+    std::process::Command::new("git")
+        .args(["merge-base", "--is-ancestor", commit, "HEAD"])
+        .current_dir(root)
+        .output()
+        .is_ok_and(|o| o.status.success())
+}
+
+/// Split `--format=%x1e%ct --name-only` output into (commit time, files).
+fn parse_log(log: &str) -> Vec<(i64, Vec<String>)> {
+    log.split('\x1e')
+        .filter_map(|entry| {
+            let mut lines = entry.lines();
+            let time = lines.next()?.trim().parse().ok()?;
+            let files = lines.map(str::trim).filter(|l| !l.is_empty()).map(str::to_string).collect();
+            Some((time, files))
+        })
+        .collect()
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    fn run(dir: &Path, args: &[&str]) {
+        let out = std::process::Command::new("git").args(args).current_dir(dir).output().unwrap();
+        assert!(out.status.success(), "git {args:?}: {}", String::from_utf8_lossy(&out.stderr));
+    }
+
+    /// Write each file (appending a line) and commit them together.
+    pub(crate) fn commit(dir: &Path, files: &[&str]) {
+        for rel in files {
+            let path = dir.join(rel);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            let mut content = std::fs::read_to_string(&path).unwrap_or_default();
+            content.push_str("// edit\n");
+            std::fs::write(&path, content).unwrap();
+        }
+        run(dir, &["add", "-A"]);
+        run(dir, &["commit", "-q", "-m", "change"]);
+    }
+
+    pub(crate) fn init_repo(dir: &Path) {
+        run(dir, &["init", "-q"]);
+        run(dir, &["config", "user.email", "test@example.com"]);
+        run(dir, &["config", "user.name", "Test"]);
+    }
+
+    #[test]
+    fn test_update_mines_coupling_incrementally() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        init_repo(tmp.path());
+        commit(tmp.path(), &["src/parser.rs", "tests/parser_test.rs"]);
+        commit(tmp.path(), &["src/parser.rs", "tests/parser_test.rs"]);
+        commit(tmp.path(), &["src/parser.rs", "src/lexer.rs"]);
+
+        let mut cc = CoChange::default();
+        assert!(cc.update(tmp.path()));
+        assert_eq!(cc.commits, 3);
+        let coupled = cc.coupled("src/parser.rs");
+        assert_eq!(coupled.len(), 1, "lexer changed with parser only once: {coupled:?}");
+        assert_eq!(coupled[0].file, "tests/parser_test.rs");
+        assert_eq!(coupled[0].together, 2);
+        assert!((coupled[0].confidence - 2.0 / 3.0).abs() < 1e-6);
+        assert_eq!(cc.coupled("tests/parser_test.rs")[0].confidence, 1.0);
+        assert_eq!(cc.pair_count(), 1);
+
+        // Nothing new: no work
+        assert!(!cc.update(tmp.path()));
+
+        // One new commit: only that one is mined
+        commit(tmp.path(), &["src/parser.rs", "src/lexer.rs"]);
+        assert!(cc.update(tmp.path()));
+        assert_eq!(cc.commits, 4);
+        assert!(cc.coupled("src/lexer.rs").iter().any(|c| c.file == "src/parser.rs"));
+
+        let now = chrono::Utc::now().timestamp();
+        assert_eq!(cc.churn("src/parser.rs", now), 4);
+        assert_eq!(cc.churn("src/parser.rs", now + 365 * 86_400), 0);
+        assert_eq!(cc.hottest(now, 1), vec![("src/parser.rs", 4)]);
+    }
+
+    #[test]
+    fn test_rewritten_history_is_mined_again() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        init_repo(tmp.path());
+        commit(tmp.path(), &["a.rs", "b.rs"]);
+        commit(tmp.path(), &["a.rs", "b.rs"]);
+        let mut cc = CoChange::default();
+        cc.update(tmp.path());
+        assert_eq!(cc.commits, 2);
+
+        run(tmp.path(), &["reset", "-q", "--hard", "HEAD~1"]);
+        commit(tmp.path(), &["a.rs", "c.rs"]);
+        assert!(cc.update(tmp.path()));
+        assert_eq!(cc.commits, 2, "dropped commit no longer counted");
+        assert_eq!(cc.pairs["a.rs"]["b.rs"], 1);
+    }
+
+    #[test]
+    fn test_sweeping_commits_and_non_repos_ignored() {
+        let mut cc = CoChange::default();
+        let many: Vec<String> = (0..=MAX_COMMIT_FILES).map(|i| format!("f{i}.rs")).collect();
+        cc.add_commit(0, &many);
+        assert_eq!(cc.commits, 0);
+        assert!(cc.pairs.is_empty());
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        assert!(!cc.update(tmp.path()));
+        assert!(cc.head.is_none());
+    }
+}
@@ -2,20 +2,222 @@ use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 // ── MCP server config ─────────────────────────────────────────────────────────
 
-/// Configuration for a single MCP server process.
+/// Configuration for a single MCP server — a child process (`command`) or a
+/// running HTTP service (`url`).
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct McpServerConfig {
     /// Human-readable name, used as the server prefix in tool names ("brave.brave_web_search")
     pub name: String,
     /// Command + args to spawn the server (e.g. ["npx", "-y", "@modelcontextprotocol/server-brave-search"])
+    #[serde(default)]
     pub command: Vec<String>,
     /// Optional environment variables to inject (e.g. {"BRAVE_API_KEY": "..."})
     #[serde(default)]
     pub env: std::collections::HashMap<String, String>,
+    /// Endpoint of an HTTP server (e.g. "https://mcp.internal/mcp"); used instead of `command`
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Extra HTTP headers sent with every request to `url`
+    #[serde(default)]
+    pub headers: std::collections::HashMap<String, String>,
+    /// Sent as `Authorization: Bearer <token>` to `url`
+    #[serde(default)]
+    pub bearer_token: Option<String>,
+    /// Wire protocol for `url`
+    #[serde(default)]
+    pub transport: McpTransport,
+    /// Seconds to wait for a response before the request is cancelled. Default: 120.
+    #[serde(default = "default_mcp_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_mcp_timeout_secs() -> u64 {
+    120
+}
+
+/// How an MCP server at a `url` is spoken to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum McpTransport {
+    /// Streamable HTTP, falling back to legacy SSE when the server rejects the POST
+    #[default]
+    Auto,
+    /// Streamable HTTP: JSON-RPC POSTed to `url`, answered as JSON or an SSE stream
+    Http,
+    /// Legacy HTTP+SSE: a GET event stream names the endpoint messages are POSTed to
+    Sse,
+}
+
+// ── Index config ──────────────────────────────────────────────────────────────
+
+/// Which files PIE indexes and watches. `.gitignore` and `.parecodeignore`
+/// are honoured on top of these; hidden files and directories are skipped.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IndexConfig {
+    /// Globs a source file must match to be indexed (e.g. `"services/api/**"`).
+    /// Empty means every source file.
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Globs for files and directories never indexed. A pattern without `/`
+    /// matches at any depth, like a `.gitignore` line.
+    #[serde(default = "default_index_exclude")]
+    pub exclude: Vec<String>,
+    /// Extra directories indexed alongside the project (e.g. `"../shared"`),
+    /// relative to the project root. Their files appear under that prefix.
+    #[serde(default)]
+    pub roots: Vec<String>,
+}
+
+fn default_index_exclude() -> Vec<String> {
+    ["target", "node_modules", "dist", "build", "__pycache__", "venv", "coverage"]
+        .map(str::to_string)
+        .to_vec()
+}
+
+impl Default for IndexConfig {
+    fn default() -> Self {
+        Self { include: Vec::new(), exclude: default_index_exclude(), roots: Vec::new() }
+    }
+}
+
+/// Per-project `[index]` overrides, relative to the project root.
+pub const PROJECT_INDEX_CONFIG: &str = ".parecode/index.toml";
+
+impl IndexConfig {
+    /// This config with the keys set in `root/.parecode/index.toml` replaced —
+    /// the place for per-project roots and globs. A missing or malformed file
+    /// leaves it unchanged.
+    pub fn for_project(&self, root: &Path) -> Self {
+        let Ok(raw) = std::fs::read_to_string(root.join(PROJECT_INDEX_CONFIG)) else { return self.clone() };
+        let Ok(overlay) = toml::from_str::<toml::Table>(&raw) else { return self.clone() };
+        let Ok(toml::Value::Table(mut merged)) = toml::Value::try_from(self) else { return self.clone() };
+        merged.extend(overlay);
+        toml::Value::Table(merged).try_into().unwrap_or_else(|_| self.clone())
+    }
+}
+
+// ── Permissions config ────────────────────────────────────────────────────────
+
+/// What happens when the model calls a tool a rule matches.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PermissionAction {
+    #[default]
+    Allow,
+    Ask,
+    Deny,
+}
+
+/// Which calls a rule or grant covers. Every field that is set must match;
+/// an empty pattern matches every call.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct PermissionPattern {
+    /// Tool name glob, e.g. `"bash"`, `"write_file"`, `"github.*"`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+    /// Glob on the tool's `path` argument, relative to the project root. A
+    /// pattern without `/` matches at any depth; paths outside the project
+    /// are matched as absolute paths, so `"/**"` means "outside the project".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Pattern for `bash` commands; `*` matches anything, e.g. `"cargo *"`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+}
+
+/// One `[[permissions.rules]]` entry.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PermissionRule {
+    #[serde(flatten)]
+    pub pattern: PermissionPattern,
+    pub action: PermissionAction,
+}
+
+/// Per-tool permission policy (`[permissions]`). Rules are checked in order
+/// and the first match decides; calls no rule matches get `default`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct PermissionsConfig {
+    #[serde(default)]
+    pub default: PermissionAction,
+    #[serde(default)]
+    pub rules: Vec<PermissionRule>,
+    /// Calls approved with "always for this project" — they skip `ask` rules
+    /// (never `deny` ones). Saved per project in the user's config dir (see
+    /// `project_grants_path`), never read from the repository.
+    #[serde(default)]
+    pub granted: Vec<PermissionPattern>,
+    /// `ask`/`deny` rules from `.parecode/permissions.toml`. The project file
+    /// comes with the repository, so it can only make a call stricter.
+    #[serde(skip)]
+    pub project_rules: Vec<PermissionRule>,
+}
+
+/// Per-project permission rules and grants, relative to the project root.
+pub const PROJECT_PERMISSIONS_CONFIG: &str = ".parecode/permissions.toml";
+
+impl PermissionsConfig {
+    /// This config for the project at `root`: its permissions file and the
+    /// user's saved grants for it added.
+    pub fn for_project(&self, root: &Path) -> Self {
+        self.with_project_file(root).with_grants(&project_grants_path(root))
+    }
+
+    /// This config with `root/.parecode/permissions.toml` added. The file is
+    /// part of the repository, so it may only tighten: its `allow` rules are
+    /// dropped, its `default` counts only when stricter, and its other rules
+    /// are checked alongside these with the stricter answer winning. Grants in
+    /// it are ignored. A missing or malformed file leaves it unchanged.
+    pub fn with_project_file(&self, root: &Path) -> Self {
+        let Ok(raw) = std::fs::read_to_string(root.join(PROJECT_PERMISSIONS_CONFIG)) else { return self.clone() };
+        let Ok(project) = toml::from_str::<Self>(&raw) else { return self.clone() };
+        Self {
+            default: self.default.max(project.default),
+            rules: self.rules.clone(),
+            granted: self.granted.clone(),
+            project_rules: self
+                .project_rules
+                .iter()
+                .cloned()
+                .chain(project.rules.into_iter().filter(|r| r.action != PermissionAction::Allow))
+                .collect(),
+        }
+    }
+
+    /// This config with the `[[granted]]` entries of the user's grants file
+    /// added. A missing or malformed file leaves it unchanged.
+    pub fn with_grants(&self, file: &Path) -> Self {
+        let mut merged = self.clone();
+        if let Some(saved) = std::fs::read_to_string(file).ok().and_then(|raw| toml::from_str::<Self>(&raw).ok()) {
+            merged.granted.extend(saved.granted);
+        }
+        merged
+    }
+}
+
+// ── Embeddings config ─────────────────────────────────────────────────────────
+
+/// Endpoint for the optional semantic index (`[embeddings]`). Semantic search
+/// is off when the section is absent.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EmbeddingsConfig {
+    /// OpenAI-compatible embeddings URL, e.g. `http://localhost:11434/v1/embeddings`
+    pub endpoint: String,
+    /// Embedding model, e.g. `nomic-embed-text`
+    pub model: String,
+    /// Optional API key (sent as Bearer token)
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Inputs per request
+    #[serde(default = "default_embedding_batch")]
+    pub batch_size: usize,
+}
+
+fn default_embedding_batch() -> usize {
+    32
 }
 
 // ── Profile ───────────────────────────────────────────────────────────────────
@@ -26,6 +228,9 @@ pub struct Profile {
     pub endpoint: String,
     /// Model identifier
     pub model: String,
+    /// Wire protocol: "openai" or "anthropic". Detected from `endpoint` when unset.
+    #[serde(default)]
+    pub provider: Option<crate::client::Provider>,
     /// Context window size in tokens. Used for proactive budget enforcement.
     #[serde(default = "default_context_tokens")]
     pub context_tokens: u32,
@@ -39,9 +244,21 @@ pub struct Profile {
     /// MCP servers to connect for this profile
     #[serde(default)]
     pub mcp_servers: Vec<McpServerConfig>,
-    /// Optional: cost per 1M input tokens in USD, used for plan cost estimates.
-    /// Example: 0.25 for Haiku, 3.0 for Sonnet, 15.0 for Opus.
+    /// Optional: cost per 1M input tokens in USD, used for cost tracking and plan estimates.
+    /// Example: 1.0 for Haiku 4.5, 3.0 for Sonnet, 5.0 for Opus 4.6.
     pub cost_per_mtok_input: Option<f64>,
+    /// Optional: cost per 1M output tokens in USD.
+    #[serde(default)]
+    pub cost_per_mtok_output: Option<f64>,
+    /// Optional: cost per 1M prompt-cache read tokens in USD.
+    #[serde(default)]
+    pub cost_per_mtok_cache_read: Option<f64>,
+    /// Optional: cost per 1M prompt-cache write tokens in USD.
+    #[serde(default)]
+    pub cost_per_mtok_cache_write: Option<f64>,
+    /// Fill unset prices from the built-in table for well-known models. Default: true.
+    #[serde(default = "default_builtin_pricing")]
+    pub builtin_pricing: bool,
     /// Explicit hook commands per event. If all empty, auto-detection kicks in.
     #[serde(default)]
     pub hooks: crate::hooks::HookConfig,
@@ -58,6 +275,42 @@ pub struct Profile {
     /// Set to false to disable all git integration. Default: true.
     #[serde(default = "default_git_context")]
     pub git_context: bool,
+    /// Retries for transient API failures (429, 5xx, dropped streams). Default: 3.
+    #[serde(default)]
+    pub max_retries: Option<u32>,
+    /// Initial retry backoff in milliseconds; doubles per retry. Default: 1000.
+    #[serde(default)]
+    pub retry_base_delay_ms: Option<u64>,
+    /// Profiles to switch to, in order, when this one keeps failing after retries.
+    #[serde(default)]
+    pub fallback_profiles: Vec<String>,
+    /// Pause a task once its cost reaches this many USD. Requires pricing.
+    #[serde(default)]
+    pub max_cost_per_task: Option<f64>,
+    /// Pause once the session has used this many tokens (input + output).
+    #[serde(default)]
+    pub max_tokens_per_session: Option<u32>,
+    /// Pause once today's spend across all sessions reaches this many USD.
+    #[serde(default)]
+    pub daily_spend_limit: Option<f64>,
+    /// When a plan step fails verification, ask the planner for revised steps
+    /// up to this many times per plan. Default: 0 (stop at the failed step).
+    #[serde(default)]
+    pub max_replans: u32,
+}
+
+impl Profile {
+    /// Prices for `model` under this profile's `cost_per_mtok_*` settings.
+    pub fn pricing(&self, model: &str) -> Option<crate::pricing::ModelPricing> {
+        crate::pricing::resolve(
+            model,
+            self.cost_per_mtok_input,
+            self.cost_per_mtok_output,
+            self.cost_per_mtok_cache_read,
+            self.cost_per_mtok_cache_write,
+            self.builtin_pricing,
+        )
+    }
 }
 
 fn default_context_tokens() -> u32 {
@@ -72,21 +325,37 @@ fn default_git_context() -> bool {
     true
 }
 
+fn default_builtin_pricing() -> bool {
+    true
+}
+
 impl Default for Profile {
     fn default() -> Self {
         Self {
             endpoint: "http://localhost:11434/v1/chat/completions".to_string(),
             model: "qwen3:14b".to_string(),
+            provider: None,
             context_tokens: default_context_tokens(),
             api_key: None,
             planner_model: None,
             mcp_servers: Vec::new(),
             cost_per_mtok_input: None,
+            cost_per_mtok_output: None,
+            cost_per_mtok_cache_read: None,
+            cost_per_mtok_cache_write: None,
+            builtin_pricing: default_builtin_pricing(),
             hooks: crate::hooks::HookConfig::default(),
             hooks_disabled: false,
             auto_commit: false,
             auto_commit_prefix: default_auto_commit_prefix(),
             git_context: default_git_context(),
+            max_retries: None,
+            retry_base_delay_ms: None,
+            fallback_profiles: Vec::new(),
+            max_cost_per_task: None,
+            max_tokens_per_session: None,
+            daily_spend_limit: None,
+            max_replans: 0,
         }
     }
 }
@@ -111,6 +380,18 @@ pub struct ConfigFile {
     /// None means no hooks are active.
     #[serde(default)]
     pub active_hooks: Option<String>,
+
+    /// `[index]` — include/exclude globs for the project graph.
+    #[serde(default)]
+    pub index: IndexConfig,
+
+    /// `[embeddings]` — endpoint for semantic search. Off when absent.
+    #[serde(default)]
+    pub embeddings: Option<EmbeddingsConfig>,
+
+    /// `[permissions]` — allow/ask/deny rules checked before each tool call.
+    #[serde(default)]
+    pub permissions: PermissionsConfig,
 }
 
 fn default_profile_name() -> String {
@@ -175,6 +456,8 @@ impl ConfigFile {
 pub struct ResolvedConfig {
     pub endpoint: String,
     pub model: String,
+    /// Wire protocol for `endpoint` (explicit profile setting, else detected)
+    pub provider: crate::client::Provider,
     pub context_tokens: u32,
     pub api_key: Option<String>,
     /// Profile name that was resolved (for display)
@@ -183,8 +466,10 @@ pub struct ResolvedConfig {
     pub planner_model: Option<String>,
     /// MCP servers configured for this profile
     pub mcp_servers: Vec<McpServerConfig>,
-    /// Optional cost per 1M input tokens in USD (for plan estimates)
-    pub cost_per_mtok_input: Option<f64>,
+    /// Per-token prices for `model` (None = unknown model, usage recorded unpriced)
+    pub pricing: Option<crate::pricing::ModelPricing>,
+    /// Per-token prices for `planner_model` (same as `pricing` when unset)
+    pub planner_pricing: Option<crate::pricing::ModelPricing>,
     /// Hook commands from profile config (may be empty — auto-detect handles that)
     pub hooks: crate::hooks::HookConfig,
     /// When true, all hooks are suppressed including auto-detected ones
@@ -201,6 +486,33 @@ pub struct ResolvedConfig {
     pub active_hooks: Option<String>,
     /// The resolved HookConfig for the active named hook (empty if none active)
     pub active_hook_config: crate::hooks::HookConfig,
+    /// Retry policy for transient model API failures
+    pub retry: crate::client::retry::RetryPolicy,
+    /// Fallback profiles from `fallback_profiles`, in order (unknown names skipped)
+    pub fallbacks: Vec<FallbackTarget>,
+    /// Per-task, per-session and per-day spend ceilings
+    pub spend_limits: crate::budget::SpendLimits,
+    /// Replan attempts allowed per plan after a failed step (0 = off)
+    pub max_replans: u32,
+    /// Include/exclude globs and extra roots for the project graph (from
+    /// `[index]`, overlaid with the project's `.parecode/index.toml`)
+    pub index: IndexConfig,
+    /// Semantic search endpoint (from `[embeddings]`)
+    pub embeddings: Option<EmbeddingsConfig>,
+    /// Tool permission rules (from `[permissions]`, overlaid with the
+    /// project's `.parecode/permissions.toml`)
+    pub permissions: PermissionsConfig,
+}
+
+/// Connection details of a fallback profile — just enough to build a client.
+#[derive(Debug, Clone)]
+pub struct FallbackTarget {
+    pub profile_name: String,
+    pub endpoint: String,
+    pub model: String,
+    pub provider: crate::client::Provider,
+    pub api_key: Option<String>,
+    pub pricing: Option<crate::pricing::ModelPricing>,
 }
 
 impl ResolvedConfig {
@@ -225,13 +537,51 @@ impl ResolvedConfig {
         let mut hook_names: Vec<String> = file.hooks.keys().cloned().collect();
         hook_names.sort();
 
+        let endpoint = endpoint_override
+            .map(str::to_string)
+            .unwrap_or_else(|| base.endpoint.clone());
+        let provider = base.provider
+            .unwrap_or_else(|| crate::client::Provider::detect(&endpoint));
+
+        let default_retry = crate::client::retry::RetryPolicy::default();
+        let retry = crate::client::retry::RetryPolicy {
+            max_retries: base.max_retries.unwrap_or(default_retry.max_retries),
+            base_delay_ms: base.retry_base_delay_ms.unwrap_or(default_retry.base_delay_ms),
+            ..default_retry
+        };
+
+        let fallbacks = base.fallback_profiles
+            .iter()
+            .filter(|name| **name != profile_name)
+            .filter_map(|name| {
+                let p = file.profiles.get(name)?;
+                Some(FallbackTarget {
+                    profile_name: name.clone(),
+                    endpoint: p.endpoint.clone(),
+                    model: p.model.clone(),
+                    provider: p.provider
+                        .unwrap_or_else(|| crate::client::Provider::detect(&p.endpoint)),
+                    api_key: p.api_key.clone(),
+                    pricing: p.pricing(&p.model),
+                })
+            })
+            .collect();
+
+        let model = model_override
+            .map(str::to_string)
+            .unwrap_or_else(|| base.model.clone());
+        let pricing = base.pricing(&model);
+        // The profile's `cost_per_mtok_*` rates are for `model`; a different
+        // planner model is priced from the built-in table alone.
+        let planner_pricing = match &base.planner_model {
+            Some(pm) if *pm != model => crate::pricing::resolve(pm, None, None, None, None, base.builtin_pricing),
+            _ => pricing,
+        };
+
         Self {
-            endpoint: endpoint_override
-                .map(str::to_string)
-                .unwrap_or(base.endpoint),
-            model: model_override
-                .map(str::to_string)
-                .unwrap_or(base.model),
+            endpoint,
+            provider,
+            model,
             context_tokens: base.context_tokens,
             api_key: api_key_override
                 .map(str::to_string)
@@ -239,7 +589,8 @@ impl ResolvedConfig {
             profile_name,
             planner_model: base.planner_model,
             mcp_servers: base.mcp_servers,
-            cost_per_mtok_input: base.cost_per_mtok_input,
+            pricing,
+            planner_pricing,
             hooks: base.hooks,
             hooks_disabled: base.hooks_disabled,
             auto_commit: base.auto_commit,
@@ -251,6 +602,17 @@ impl ResolvedConfig {
                 .unwrap_or_default(),
             available_hooks: hook_names,
             active_hooks: file.active_hooks.clone(),
+            retry,
+            spend_limits: crate::budget::SpendLimits {
+                max_cost_per_task: base.max_cost_per_task,
+                max_tokens_per_session: base.max_tokens_per_session,
+                daily_spend_limit: base.daily_spend_limit,
+            },
+            fallbacks,
+            max_replans: base.max_replans,
+            index: file.index.for_project(Path::new(".")),
+            embeddings: file.embeddings.clone(),
+            permissions: file.permissions.for_project(Path::new(".")),
         }
     }
 }
@@ -264,6 +626,16 @@ pub fn config_path() -> PathBuf {
         .join("config.toml")
 }
 
+/// The user's "always for this project" grants for `root`. Kept in the
+/// config dir rather than the repository, so a cloned repo can't grant
+/// itself anything; named after the canonical project path.
+pub fn project_grants_path(root: &Path) -> PathBuf {
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let name = root.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let hash = crate::embeddings::fnv1a(root.to_string_lossy().as_bytes());
+    config_path().with_file_name("grants").join(format!("{name}-{hash:016x}.toml"))
+}
+
 fn dirs_config_dir() -> Option<PathBuf> {
     // XDG_CONFIG_HOME or ~/.config on Linux/macOS, %APPDATA% on Windows
     std::env::var("XDG_CONFIG_HOME")
@@ -297,18 +669,23 @@ context_tokens = 32768
 # context_tokens = 32768
 
 # ── Anthropic Claude ─────────────────────────────────────────────────────────
+# Uses the native Messages API (detected from the endpoint; or set provider = "anthropic").
 # [profiles.claude]
-# endpoint             = "https://api.anthropic.com/v1/chat/completions"
+# endpoint             = "https://api.anthropic.com/v1/messages"
 # model                = "claude-sonnet-4-6"
 # context_tokens       = 200000
 # api_key              = "sk-ant-..."
-# cost_per_mtok_input  = 3.0   # USD per 1M input tokens — enables cost estimates in /plan
+# cost_per_mtok_input  = 3.0   # USD per 1M input tokens (known Claude/GPT models are priced built-in)
+# cost_per_mtok_output = 15.0  # also: cost_per_mtok_cache_read / cost_per_mtok_cache_write
+# max_cost_per_task    = 2.0   # pause for confirmation past these; also max_tokens_per_session
+# daily_spend_limit    = 20.0
+# max_replans          = 2     # on a failed plan step, ask the planner for revised steps
 
 # ── Anthropic Claude — Opus planner + Haiku executor ─────────────────────────
 # Uses Opus for planning (high reasoning, low token count) and Haiku for
 # executing each step (fast, cheap). Best cost/quality ratio for large tasks.
 # [profiles.claude-split]
-# endpoint       = "https://api.anthropic.com/v1/chat/completions"
+# endpoint       = "https://api.anthropic.com/v1/messages"
 # model          = "claude-haiku-4-5-20251001"
 # planner_model  = "claude-opus-4-6"
 # context_tokens = 200000
@@ -333,6 +710,21 @@ context_tokens = 32768
 # auto_commit = false          # auto-commit all changes after each successful task
 # auto_commit_prefix = "parecode: "
 
+# ── Project graph indexing (optional, global) ────────────────────────────────
+# .gitignore and .parecodeignore are always honoured; these globs narrow further.
+# [index]
+# include = ["services/api/**", "libs/**"]   # empty = every source file
+# exclude = ["target", "node_modules", "dist", "build", "__pycache__", "venv", "coverage", "**/generated/**"]
+# Per-project overrides (e.g. roots = ["../shared"]) go in .parecode/index.toml.
+
+# ── Semantic search (optional, global) ───────────────────────────────────────
+# Embeds symbol bodies and docs so orient and semantic_search find code by meaning.
+# Any OpenAI-compatible /embeddings endpoint works; Ollama keeps it local.
+# [embeddings]
+# endpoint = "http://localhost:11434/v1/embeddings"
+# model    = "nomic-embed-text"
+# batch_size = 32
+
 # ── MCP servers (optional, per-profile) ──────────────────────────────────────
 # Add MCP servers to any profile to give the model extra tools.
 # Tools appear as "<server_name>.<tool_name>" (e.g. "brave.brave_web_search").
@@ -388,6 +780,7 @@ mod tests {
         let profile = Profile::default();
         assert_eq!(profile.endpoint, "http://localhost:11434/v1/chat/completions");
         assert_eq!(profile.model, "qwen3:14b");
+        assert_eq!(profile.provider, None);
         assert_eq!(profile.context_tokens, 32_768);
         assert_eq!(profile.api_key, None);
         assert_eq!(profile.planner_model, None);
@@ -457,11 +850,12 @@ mod tests {
         let resolved = ResolvedConfig::resolve(&file, None, None, None, None);
         assert_eq!(resolved.endpoint, "http://example.com");
         assert_eq!(resolved.model, "model1");
+        assert_eq!(resolved.provider, crate::client::Provider::OpenAi);
         assert_eq!(resolved.context_tokens, 1000);
         assert_eq!(resolved.api_key, Some("key1".to_string()));
         assert_eq!(resolved.profile_name, "local");
         assert_eq!(resolved.planner_model, Some("planner1".to_string()));
-        assert_eq!(resolved.cost_per_mtok_input, Some(0.5));
+        assert_eq!(resolved.pricing.map(|p| p.input), Some(0.5));
         assert_eq!(resolved.hooks, crate::hooks::HookConfig::default());
         assert_eq!(resolved.hooks_disabled, false);
         assert_eq!(resolved.auto_commit, true);
@@ -520,6 +914,168 @@ mod tests {
         assert_eq!(profile.git_context, true);
     }
 
+    #[test]
+    fn test_resolved_provider_explicit_and_detected() {
+        let mut file = ConfigFile::default();
+        file.profiles.insert("claude".to_string(), Profile {
+            endpoint: "https://api.anthropic.com/v1/messages".to_string(),
+            ..Default::default()
+        });
+        file.profiles.insert("proxy".to_string(), Profile {
+            endpoint: "http://gateway.internal/llm".to_string(),
+            provider: Some(crate::client::Provider::Anthropic),
+            ..Default::default()
+        });
+        let r = ResolvedConfig::resolve(&file, Some("claude"), None, None, None);
+        assert_eq!(r.provider, crate::client::Provider::Anthropic);
+        let r = ResolvedConfig::resolve(&file, Some("proxy"), None, None, None);
+        assert_eq!(r.provider, crate::client::Provider::Anthropic);
+        // CLI endpoint override is re-detected when the profile doesn't pin a provider
+        let r = ResolvedConfig::resolve(&file, Some("claude"), Some("http://localhost:11434/v1/chat/completions"), None, None);
+        assert_eq!(r.provider, crate::client::Provider::OpenAi);
+    }
+
+    #[test]
+    fn test_resolved_retry_and_fallbacks() {
+        let toml_str = r#"
+            default_profile = "main"
+            [profiles.main]
+            endpoint = "https://api.anthropic.com/v1/messages"
+            model = "claude-sonnet"
+            api_key = "sk-main"
+            max_retries = 5
+            fallback_profiles = ["missing", "main", "local"]
+            [profiles.local]
+            endpoint = "http://localhost:11434/v1/chat/completions"
+            model = "qwen3:14b"
+        "#;
+        let file: ConfigFile = toml::from_str(toml_str).unwrap();
+        let r = ResolvedConfig::resolve(&file, None, None, None, None);
+        assert_eq!(r.retry.max_retries, 5);
+        assert_eq!(r.retry.base_delay_ms, 1_000);
+        // Unknown names and the profile itself are skipped
+        assert_eq!(r.fallbacks.len(), 1);
+        let fb = &r.fallbacks[0];
+        assert_eq!(fb.profile_name, "local");
+        assert_eq!(fb.model, "qwen3:14b");
+        assert_eq!(fb.provider, crate::client::Provider::OpenAi);
+        assert!(fb.api_key.is_none());
+
+        let r = ResolvedConfig::resolve(&file, Some("local"), None, None, None);
+        assert_eq!(r.retry, crate::client::retry::RetryPolicy::default());
+        assert!(r.fallbacks.is_empty());
+    }
+
+    #[test]
+    fn test_resolved_spend_limits() {
+        let toml_str = r#"
+            default_profile = "main"
+            [profiles.main]
+            endpoint = "http://localhost:11434/v1/chat/completions"
+            model = "qwen3:14b"
+            max_cost_per_task = 1.5
+            max_tokens_per_session = 2000000
+            daily_spend_limit = 20
+            [profiles.open]
+            endpoint = "http://localhost:11434/v1/chat/completions"
+            model = "qwen3:14b"
+        "#;
+        let file: ConfigFile = toml::from_str(toml_str).unwrap();
+        let r = ResolvedConfig::resolve(&file, None, None, None, None);
+        assert_eq!(r.spend_limits.max_cost_per_task, Some(1.5));
+        assert_eq!(r.spend_limits.max_tokens_per_session, Some(2_000_000));
+        assert_eq!(r.spend_limits.daily_spend_limit, Some(20.0));
+        let r = ResolvedConfig::resolve(&file, Some("open"), None, None, None);
+        assert!(r.spend_limits.is_empty());
+    }
+
+    #[test]
+    fn test_resolved_max_replans() {
+        let toml_str = r#"
+            default_profile = "main"
+            [profiles.main]
+            endpoint = "http://localhost:11434/v1/chat/completions"
+            model = "qwen3:14b"
+            max_replans = 2
+        "#;
+        let file: ConfigFile = toml::from_str(toml_str).unwrap();
+        assert_eq!(ResolvedConfig::resolve(&file, None, None, None, None).max_replans, 2);
+        assert_eq!(Profile::default().max_replans, 0, "replanning is off unless configured");
+    }
+
+    #[test]
+    fn test_resolved_planner_pricing_ignores_executor_rates() {
+        let toml_str = r#"
+            default_profile = "local"
+            [profiles.local]
+            endpoint = "http://localhost:11434/v1/chat/completions"
+            model = "qwen3:14b"
+            planner_model = "claude-sonnet-4-5"
+            cost_per_mtok_input = 0.0
+        "#;
+        let file: ConfigFile = toml::from_str(toml_str).unwrap();
+        let r = ResolvedConfig::resolve(&file, None, None, None, None);
+        assert_eq!(r.pricing.map(|p| p.input), Some(0.0));
+        assert_eq!(r.planner_pricing, crate::pricing::builtin("claude-sonnet-4-5"));
+        assert!(r.planner_pricing.unwrap().input > 0.0);
+
+        // The same model plans and executes: the profile's rates apply to both
+        let r = ResolvedConfig::resolve(&file, None, None, Some("claude-sonnet-4-5"), None);
+        assert_eq!(r.planner_pricing, r.pricing);
+    }
+
+    #[test]
+    fn test_index_config() {
+        let toml_str = r#"
+            default_profile = "main"
+            [index]
+            include = ["services/api/**"]
+            [profiles.main]
+            endpoint = "http://localhost:11434/v1/chat/completions"
+            model = "qwen3:14b"
+        "#;
+        let file: ConfigFile = toml::from_str(toml_str).unwrap();
+        let r = ResolvedConfig::resolve(&file, None, None, None, None);
+        assert_eq!(r.index.include, vec!["services/api/**"]);
+        assert!(r.index.exclude.contains(&"node_modules".to_string()), "default excludes kept");
+        assert_eq!(ConfigFile::default().index, IndexConfig::default());
+    }
+
+    #[test]
+    fn test_embeddings_config() {
+        let toml_str = r#"
+            default_profile = "main"
+            [embeddings]
+            endpoint = "http://localhost:11434/v1/embeddings"
+            model = "nomic-embed-text"
+            [profiles.main]
+            endpoint = "http://localhost:11434/v1/chat/completions"
+            model = "qwen3:14b"
+        "#;
+        let file: ConfigFile = toml::from_str(toml_str).unwrap();
+        let embeddings = ResolvedConfig::resolve(&file, None, None, None, None).embeddings.unwrap();
+        assert_eq!(embeddings.model, "nomic-embed-text");
+        assert_eq!(embeddings.batch_size, 32);
+        assert_eq!(ConfigFile::default().embeddings, None, "off unless configured");
+    }
+
+    #[test]
+    fn test_project_index_config_overrides_keys() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let global = IndexConfig { include: vec!["src/**".into()], ..IndexConfig::default() };
+        assert_eq!(global.for_project(tmp.path()), global, "no project file");
+
+        fs::create_dir_all(tmp.path().join(".parecode")).unwrap();
+        fs::write(tmp.path().join(PROJECT_INDEX_CONFIG), "roots = [\"../shared\"]\n").unwrap();
+        let merged = global.for_project(tmp.path());
+        assert_eq!(merged.roots, vec!["../shared"]);
+        assert_eq!(merged.include, global.include, "keys not set in the project file are kept");
+        assert_eq!(merged.exclude, global.exclude);
+
+        fs::write(tmp.path().join(PROJECT_INDEX_CONFIG), "roots = 3\n").unwrap();
+        assert_eq!(global.for_project(tmp.path()), global, "malformed file is ignored");
+    }
+
     #[test]
     fn test_mcp_server_config_serialization() {
         let toml_str = r#"
@@ -532,6 +1088,22 @@ mod tests {
         assert_eq!(config.name, "brave");
         assert_eq!(config.command, vec!["npx", "-y", "@modelcontextprotocol/server-brave-search"]);
         assert_eq!(config.env.get("BRAVE_API_KEY"), Some(&"BSA...".to_string()));
+        assert_eq!(config.url, None);
+        assert_eq!(config.transport, McpTransport::Auto);
+        assert_eq!(config.timeout_secs, 120);
+
+        let remote: McpServerConfig = toml::from_str(r#"
+            name = "search"
+            url = "https://mcp.internal/mcp"
+            bearer_token = "tok"
+            transport = "sse"
+            [headers]
+            X-Team = "tools"
+        "#).unwrap();
+        assert!(remote.command.is_empty());
+        assert_eq!(remote.url.as_deref(), Some("https://mcp.internal/mcp"));
+        assert_eq!(remote.headers.get("X-Team").map(String::as_str), Some("tools"));
+        assert_eq!(remote.transport, McpTransport::Sse);
     }
 
     // ── Constants ────────────────────────────────────────────────────────────
@@ -550,6 +1122,16 @@ mod tests {
         assert!(path.ends_with("parecode/config.toml"));
     }
 
+    #[test]
+    fn test_project_grants_path_is_outside_the_project() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = project_grants_path(tmp.path());
+        assert!(path.starts_with(config_path().parent().unwrap()));
+        assert!(!path.starts_with(tmp.path()));
+        assert_eq!(path, project_grants_path(&tmp.path().join(".")), "keyed by the canonical root");
+        assert_ne!(path, project_grants_path(Path::new("/")));
+    }
+
     #[test]
     fn test_dirs_config_dir_env() {
         // This is a weak test but ensures the function doesn't panic
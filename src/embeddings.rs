@@ -0,0 +1,477 @@
+/// Semantic search — an optional embedding index over symbol bodies and doc
+/// comments, so `orient`, task focus files and `semantic_search` can find code
+/// by what it does ("where do we throttle retries") rather than by its name.
+///
+/// # Embeddings
+/// Vectors come from any OpenAI-compatible `/embeddings` endpoint configured
+/// under `[embeddings]` — Ollama's `/v1/embeddings` works fully locally. One
+/// document per function, method, type and trait: kind, qualified name, file,
+/// doc comment and source, capped at `MAX_DOC_CHARS`.
+///
+/// # Storage
+/// Persisted to `.parecode/embeddings.bin` (MessagePack, like the graph) next
+/// to `project.graph`. Refreshed incrementally: files whose mtime moved are
+/// hashed, and only those whose content hash changed are re-embedded. Vectors
+/// are stored unit-length so similarity is a dot product.
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::config::EmbeddingsConfig;
+use crate::index::{Symbol, SymbolKind};
+use crate::pie::ProjectGraph;
+
+// ── Constants ─────────────────────────────────────────────────────────────────
+
+const EMBEDDINGS_PATH: &str = ".parecode/embeddings.bin";
+
+/// Source characters per document — enough for the head of a long function
+/// while keeping requests small for local models.
+const MAX_DOC_CHARS: usize = 1500;
+
+/// Hits below this cosine similarity are noise for every model tried.
+const MIN_SIMILARITY: f32 = 0.2;
+
+/// Documents embedded before the index is saved again during a large build,
+/// so an interrupted first run keeps most of its work.
+const SAVE_EVERY: usize = 512;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+// ── Public types ──────────────────────────────────────────────────────────────
+
+/// A symbol ranked by similarity to a query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SemanticHit {
+    /// Graph key (`Symbol::key`)
+    pub key: String,
+    pub file: String,
+    pub line: usize,
+    /// Cosine similarity, `MIN_SIMILARITY..=1.0`
+    pub score: f32,
+}
+
+/// The embedding index for one project plus the endpoint that feeds it.
+/// Shared between tasks; `refresh` and `search` take `&self`.
+pub struct SemanticIndex {
+    root: PathBuf,
+    embedder: Embedder,
+    index: RwLock<EmbeddingIndex>,
+    /// Held for the duration of a refresh — a second caller skips instead
+    /// of embedding the same files twice.
+    refreshing: tokio::sync::Mutex<()>,
+}
+
+impl SemanticIndex {
+    /// Load the index under `root`, discarding it if it was built with a
+    /// different model. No network access until `refresh`/`search`.
+    pub fn new(config: &EmbeddingsConfig, root: &Path) -> Self {
+        let index = std::fs::read(root.join(EMBEDDINGS_PATH))
+            .ok()
+            .and_then(|bytes| rmp_serde::from_slice::<EmbeddingIndex>(&bytes).ok())
+            .filter(|i| i.model == config.model)
+            .unwrap_or_else(|| EmbeddingIndex { model: config.model.clone(), ..Default::default() });
+        Self {
+            root: root.to_path_buf(),
+            embedder: Embedder::new(config),
+            index: RwLock::new(index),
+            refreshing: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Number of embedded symbols.
+    pub fn len(&self) -> usize {
+        self.index.read().unwrap().entries.len()
+    }
+
+    /// Bring the index in line with `graph`: drop files that left it and
+    /// re-embed the ones whose content changed. Returns the number of files
+    /// re-embedded — 0 as well when another refresh is already running.
+    pub async fn refresh(&self, graph: &ProjectGraph) -> Result<usize> {
+        let Ok(_guard) = self.refreshing.try_lock() else { return Ok(0) };
+
+        let mut stale: Vec<StaleFile> = Vec::new();
+        {
+            let mut guard = self.index.write().unwrap();
+            let index = &mut *guard;
+            index.files.retain(|f, _| graph.file_lines.contains_key(f));
+            let live: HashSet<&String> = index.files.keys().collect();
+            let before = index.entries.len();
+            index.entries.retain(|e| live.contains(&e.file));
+            let mut dropped = index.entries.len() != before;
+
+            let mut by_file: HashMap<&str, Vec<&Symbol>> = HashMap::new();
+            for sym in graph.symbols.iter().filter(|s| is_embedded(&s.kind)) {
+                by_file.entry(sym.file.as_str()).or_default().push(sym);
+            }
+            let mut files: Vec<&String> = graph.file_lines.keys().collect();
+            files.sort();
+            for file in files {
+                let path = self.root.join(file);
+                let mtime = mtime_nanos(&path);
+                let known = index.files.get(file).cloned();
+                if known.as_ref().is_some_and(|k| k.mtime == mtime) {
+                    continue;
+                }
+                let Ok(content) = std::fs::read_to_string(&path) else { continue };
+                let stamp = FileStamp { mtime, hash: fnv1a(content.as_bytes()) };
+                if known.is_some_and(|k| k.hash == stamp.hash) {
+                    // Touched, not changed
+                    index.files.insert(file.clone(), stamp);
+                    dropped = true;
+                    continue;
+                }
+                let syms = by_file.get(file.as_str()).map(Vec::as_slice).unwrap_or_default();
+                let docs = syms.iter().map(|s| document(s, &content)).collect();
+                let keys = syms.iter().map(|s| (s.key(), s.line)).collect();
+                stale.push(StaleFile { file: file.clone(), stamp, docs, keys });
+            }
+            if stale.is_empty() {
+                if dropped {
+                    index.save(&self.root);
+                }
+                return Ok(0);
+            }
+        }
+
+        let refreshed = stale.len();
+        let mut pending = 0;
+        for StaleFile { file, stamp, docs, keys } in stale {
+            let mut vectors = Vec::with_capacity(docs.len());
+            for batch in docs.chunks(self.embedder.batch_size) {
+                vectors.extend(self.embedder.embed(batch).await?);
+            }
+            let mut index = self.index.write().unwrap();
+            index.entries.retain(|e| e.file != file);
+            for ((key, line), vector) in keys.into_iter().zip(vectors) {
+                index.entries.push(Entry { key, file: file.clone(), line, vector });
+            }
+            index.files.insert(file, stamp);
+            pending += docs.len();
+            if pending >= SAVE_EVERY {
+                index.save(&self.root);
+                pending = 0;
+            }
+        }
+        self.index.read().unwrap().save(&self.root);
+        Ok(refreshed)
+    }
+
+    /// The `limit` symbols closest in meaning to `query`, best first.
+    pub async fn search(&self, query: &str, limit: usize) -> Result<Vec<SemanticHit>> {
+        if query.trim().is_empty() || self.len() == 0 {
+            return Ok(Vec::new());
+        }
+        let query_vec = self.embedder.embed(&[query.to_string()]).await?.pop().unwrap_or_default();
+        Ok(self.index.read().unwrap().rank(&query_vec, limit))
+    }
+}
+
+/// Symbols closest in meaning to `query`; empty without an index. With
+/// `refresh`, files edited since the last call are re-embedded first. Endpoint
+/// errors just mean no hits — keyword matching still applies.
+pub async fn hits(
+    semantic: Option<&SemanticIndex>,
+    graph: &ProjectGraph,
+    query: &str,
+    limit: usize,
+    refresh: bool,
+) -> Vec<SemanticHit> {
+    let Some(semantic) = semantic else { return Vec::new() };
+    if refresh {
+        let _ = semantic.refresh(graph).await;
+    }
+    semantic.search(query, limit).await.unwrap_or_default()
+}
+
+// ── Index ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct EmbeddingIndex {
+    /// Model the vectors came from — a different model invalidates them all
+    model: String,
+    /// file → state it was embedded at
+    files: HashMap<String, FileStamp>,
+    entries: Vec<Entry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct FileStamp {
+    /// Modification time in nanoseconds — cheap pre-check before hashing
+    mtime: u64,
+    /// FNV-1a of the content
+    hash: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    key: String,
+    file: String,
+    line: usize,
+    /// Unit length
+    vector: Vec<f32>,
+}
+
+/// A file `refresh` has to re-embed, read while the index lock was held.
+struct StaleFile {
+    file: String,
+    stamp: FileStamp,
+    /// One document per embedded symbol
+    docs: Vec<String>,
+    /// Key and line of the symbol behind each document
+    keys: Vec<(String, usize)>,
+}
+
+impl EmbeddingIndex {
+    fn rank(&self, query: &[f32], limit: usize) -> Vec<SemanticHit> {
+        let mut hits: Vec<SemanticHit> = self
+            .entries
+            .iter()
+            .filter(|e| e.vector.len() == query.len())
+            .map(|e| SemanticHit {
+                key: e.key.clone(),
+                file: e.file.clone(),
+                line: e.line,
+                score: e.vector.iter().zip(query).map(|(a, b)| a * b).sum(),
+            })
+            .filter(|h| h.score >= MIN_SIMILARITY)
+            .collect();
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.key.cmp(&b.key)));
+        hits.truncate(limit);
+        hits
+    }
+
+    /// Write via a temp file so a concurrent reader never sees half an index.
+    fn save(&self, root: &Path) {
+        static SEQ: AtomicUsize = AtomicUsize::new(0);
+        let path = root.join(EMBEDDINGS_PATH);
+        let _ = std::fs::create_dir_all(path.parent().unwrap_or(root));
+        let Ok(bytes) = rmp_serde::to_vec_named(self) else { return };
+        let tmp = path.with_extension(format!("tmp{}.{}", std::process::id(), SEQ.fetch_add(1, Ordering::Relaxed)));
+        if std::fs::write(&tmp, bytes).is_ok() && std::fs::rename(&tmp, &path).is_err() {
+            let _ = std::fs::remove_file(&tmp);
+        }
+    }
+}
+
+// ── Endpoint ──────────────────────────────────────────────────────────────────
+
+/// Client for an OpenAI-compatible `/embeddings` endpoint.
+struct Embedder {
+    http: reqwest::Client,
+    endpoint: String,
+    model: String,
+    api_key: Option<String>,
+    batch_size: usize,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    #[serde(default)]
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+impl Embedder {
+    fn new(config: &EmbeddingsConfig) -> Self {
+        Self {
+            http: reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build().unwrap_or_default(),
+            endpoint: config.endpoint.clone(),
+            model: config.model.clone(),
+            api_key: config.api_key.clone(),
+            batch_size: config.batch_size.max(1),
+        }
+    }
+
+    /// One unit-length vector per input, in input order.
+    async fn embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>> {
+        let body = serde_json::json!({ "model": self.model, "input": inputs });
+        let mut req = self.http.post(&self.endpoint).json(&body);
+        if let Some(key) = &self.api_key {
+            req = req.bearer_auth(key);
+        }
+        let resp = req.send().await.with_context(|| format!("embeddings request to {} failed", self.endpoint))?;
+        let status = resp.status();
+        if !status.is_success() {
+            let text: String = resp.text().await.unwrap_or_default().chars().take(200).collect();
+            bail!("embeddings endpoint returned {status}: {text}");
+        }
+        let mut parsed: EmbeddingResponse = resp.json().await.context("malformed embeddings response")?;
+        if parsed.data.len() != inputs.len() {
+            bail!("embeddings endpoint returned {} vectors for {} inputs", parsed.data.len(), inputs.len());
+        }
+        parsed.data.sort_by_key(|d| d.index);
+        Ok(parsed.data.into_iter().map(|d| normalize(d.embedding)).collect())
+    }
+}
+
+// ── Helpers ───────────────────────────────────────────────────────────────────
+
+fn is_embedded(kind: &SymbolKind) -> bool {
+    matches!(
+        kind,
+        SymbolKind::Function | SymbolKind::Method | SymbolKind::Struct | SymbolKind::Enum | SymbolKind::Trait | SymbolKind::Class
+    )
+}
+
+/// Text embedded for `sym`: what it is, where it lives, its doc and source.
+fn document(sym: &Symbol, content: &str) -> String {
+    let mut doc = format!("{} {} in {}\n", sym.kind.label(), sym.qualified_name(), sym.file);
+    if let Some(d) = &sym.doc {
+        doc.push_str(d);
+        doc.push('\n');
+    }
+    let body = content.get(sym.start_byte..sym.end_byte).unwrap_or("");
+    doc.extend(body.chars().take(MAX_DOC_CHARS));
+    doc
+}
+
+fn normalize(mut v: Vec<f32>) -> Vec<f32> {
+    let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        v.iter_mut().for_each(|x| *x /= norm);
+    }
+    v
+}
+
+fn mtime_nanos(path: &Path) -> u64 {
+    std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+/// FNV-1a — stable across builds, unlike `DefaultHasher`.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |h, b| (h ^ u64::from(*b)).wrapping_mul(0x100_0000_01b3))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::client::test_server::{self, MockResponse};
+    use crate::index::IndexScope;
+
+    /// Stub embedder: a bag of words hashed into 64 buckets, so texts that
+    /// share words are close. Counts requests and inputs.
+    pub(crate) async fn stub_endpoint() -> (EmbeddingsConfig, std::sync::Arc<AtomicUsize>) {
+        let inputs = std::sync::Arc::new(AtomicUsize::new(0));
+        let counter = inputs.clone();
+        let url = test_server::serve_fn(move |body| {
+            let req: serde_json::Value = serde_json::from_str(body).unwrap_or_default();
+            let texts: Vec<&str> = req["input"].as_array().into_iter().flatten().filter_map(|v| v.as_str()).collect();
+            counter.fetch_add(texts.len(), Ordering::SeqCst);
+            let data: Vec<serde_json::Value> = texts
+                .iter()
+                .enumerate()
+                .map(|(index, text)| serde_json::json!({ "index": index, "embedding": bag_of_words(text) }))
+                .collect();
+            MockResponse::json(&serde_json::json!({ "data": data }).to_string())
+        })
+        .await;
+        let config = EmbeddingsConfig {
+            endpoint: format!("{url}/v1/embeddings"),
+            model: "stub".to_string(),
+            api_key: None,
+            batch_size: 2,
+        };
+        (config, inputs)
+    }
+
+    fn bag_of_words(text: &str) -> Vec<f32> {
+        let mut v = vec![0.0; 64];
+        for word in text.split(|c: char| !c.is_alphanumeric()).filter(|w| w.len() >= 3) {
+            v[(fnv1a(word.to_lowercase().as_bytes()) % 64) as usize] += 1.0;
+        }
+        v
+    }
+
+    fn write(dir: &Path, rel: &str, content: &str) {
+        let path = dir.join(rel);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_semantic_search_finds_code_by_meaning() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        write(
+            tmp.path(),
+            "src/client.rs",
+            "/// Throttle retries so a failing endpoint is not hammered.\n\
+             pub fn backoff_delay(attempt: u32) -> u64 { 100 * 2u64.pow(attempt) }\n\n\
+             pub fn parse_body(raw: &str) -> String { raw.trim().to_string() }\n",
+        );
+        write(tmp.path(), "src/ui.rs", "/// Draw the status bar.\npub fn render_status() {}\n");
+        let graph = ProjectGraph::build_fresh(tmp.path(), &IndexScope::default());
+
+        let (config, inputs) = stub_endpoint().await;
+        let semantic = SemanticIndex::new(&config, tmp.path());
+        assert_eq!(semantic.refresh(&graph).await.unwrap(), 2);
+        assert_eq!(semantic.len(), 3);
+        assert_eq!(inputs.load(Ordering::SeqCst), 3);
+
+        let hits = semantic.search("where do we throttle retries", 5).await.unwrap();
+        assert_eq!(hits[0].key, "src/client.rs::backoff_delay");
+        assert_eq!(hits[0].line, 2);
+        assert!(hits.iter().all(|h| h.score >= MIN_SIMILARITY));
+        assert_eq!(inputs.load(Ordering::SeqCst), 4, "the query is embedded too");
+
+        // Reloaded from disk: nothing to embed
+        let semantic = SemanticIndex::new(&config, tmp.path());
+        assert_eq!(semantic.len(), 3);
+        assert_eq!(semantic.refresh(&graph).await.unwrap(), 0);
+        assert_eq!(inputs.load(Ordering::SeqCst), 4);
+
+        // Touched without a change: rehashed, not re-embedded
+        let ui = tmp.path().join("src/ui.rs");
+        filetime::set_file_mtime(&ui, filetime::FileTime::from_unix_time(1_000_000, 0)).unwrap();
+        assert_eq!(semantic.refresh(&graph).await.unwrap(), 0);
+        assert_eq!(inputs.load(Ordering::SeqCst), 4);
+
+        // Edited: only that file is re-embedded
+        write(tmp.path(), "src/ui.rs", "/// Draw the status bar and spinner.\npub fn render_status() {}\n");
+        assert_eq!(semantic.refresh(&graph).await.unwrap(), 1);
+        assert_eq!(inputs.load(Ordering::SeqCst), 5);
+
+        // A different model starts over
+        let other = EmbeddingsConfig { model: "other".to_string(), ..config };
+        assert_eq!(SemanticIndex::new(&other, tmp.path()).len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_endpoint_errors_surface() {
+        let (url, _h) = test_server::serve(vec![MockResponse::error(500, "model not found")]).await;
+        let config = EmbeddingsConfig { endpoint: url, model: "m".into(), api_key: None, batch_size: 8 };
+        let err = Embedder::new(&config).embed(&["x".to_string()]).await.unwrap_err().to_string();
+        assert!(err.contains("500") && err.contains("model not found"), "{err}");
+    }
+
+    #[test]
+    fn test_rank_orders_and_filters() {
+        let entry = |key: &str, vector: Vec<f32>| Entry { key: key.into(), file: "a.rs".into(), line: 1, vector: normalize(vector) };
+        let index = EmbeddingIndex {
+            model: "m".into(),
+            files: HashMap::new(),
+            entries: vec![entry("near", vec![1.0, 0.1]), entry("far", vec![0.0, 1.0]), entry("mid", vec![1.0, 1.0])],
+        };
+        let hits = index.rank(&normalize(vec![1.0, 0.0]), 5);
+        let keys: Vec<&str> = hits.iter().map(|h| h.key.as_str()).collect();
+        assert_eq!(keys, vec!["near", "mid"], "orthogonal vector filtered out");
+        assert_eq!(index.rank(&[1.0, 0.0, 0.0], 5), vec![], "dimension mismatch ignored");
+    }
+}
@@ -31,8 +31,10 @@ use crate::pie::ProjectGraph;
 /// (e.g. formatters, validators, loggers) and are not followed into paths.
 const UTILITY_THRESHOLD: usize = 6;
 
-/// Callees defined in this many or more files are trait-method dispatch and
-/// are not followed (avoids exploding into every tool implementation, etc.).
+/// Unresolved callees defined in this many or more files are trait-method
+/// dispatch and are not followed (avoids exploding into every tool
+/// implementation, etc.). Edges whose receiver pinned a target are always
+/// followed.
 const MAX_AMBIGUITY: usize = 4;
 
 /// Maximum BFS steps per path — prevents runaway traversal on dense graphs.
@@ -297,7 +299,7 @@ fn find_entry_points(
         }
 
         let incoming = caller_counts.get(&sym.name).copied().unwrap_or(0);
-        let key = format!("{}::{}", sym.file, sym.name);
+        let key = sym.key();
         let outgoing = graph.call_edges.get(&key).map(|v| v.len()).unwrap_or(0);
 
         let is_primary = incoming == 0;
@@ -341,8 +343,8 @@ fn trace_bfs(
             let callee = &edge.callee;
 
             // Skip high-ambiguity callees (trait method dispatch)
-            let defs = graph.by_name.get(callee).map(|v| v.as_slice()).unwrap_or(&[]);
-            if defs.len() > MAX_AMBIGUITY {
+            let defs = graph.by_name.get(callee).map(|v| v.len()).unwrap_or(0);
+            if edge.target.is_none() && defs > MAX_AMBIGUITY {
                 continue;
             }
 
@@ -353,8 +355,7 @@ fn trace_bfs(
             }
 
             // Resolve callee to one or more symbol keys
-            for file in defs {
-                let key = format!("{}::{}", file, callee);
+            for key in graph.callee_keys(edge) {
                 if visited.contains(&key) {
                     continue;
                 }
@@ -505,9 +506,7 @@ pub fn build_path_context(
 
     for (depth, key) in path.all_keys().enumerate() {
         // Find the symbol — key format is "file::symbol_name"
-        let Some(sym) = graph.symbols.iter().find(|s| {
-            format!("{}::{}", s.file, s.name) == key
-        }) else {
+        let Some(sym) = graph.symbol_by_key(key) else {
             continue;
         };
 
@@ -557,7 +556,7 @@ fn append_sig(out: &mut String, sym: &crate::index::Symbol) {
 mod tests {
     use super::*;
     use std::collections::HashMap;
-    use crate::index::{CallEdge, Symbol, SymbolKind};
+    use crate::index::{CallEdge, Symbol, SymbolKind, Visibility};
     use crate::pie::{Cluster, ProjectGraph};
 
     fn fn_sym(name: &str, file: &str, line: usize) -> Symbol {
@@ -568,6 +567,7 @@ mod tests {
             end_line: line + 10,
             kind: SymbolKind::Function,
             signature: None,
+            start_byte: 0, end_byte: 0, parent: None, visibility: Visibility::Public, doc: None, implements: vec![],
         }
     }
 
@@ -590,9 +590,13 @@ mod tests {
             symbols,
             by_name,
             clusters: Vec::new(),
+            packages: Vec::new(),
             last_indexed: 0,
             call_edges,
             construct_edges: HashMap::new(),
+            references: HashMap::new(),
+            tests: HashMap::new(),
+            cochange: Default::default(),
         }
     }
 
@@ -616,11 +620,11 @@ mod tests {
         let mut call_edges = HashMap::new();
         call_edges.insert(
             "src/main.rs::main".to_string(),
-            vec![CallEdge { callee: "run_tui".to_string(), call_line: 5 }],
+            vec![CallEdge { callee: "run_tui".to_string(), call_line: 5, target: None }],
         );
         call_edges.insert(
             "src/agent.rs::run_tui".to_string(),
-            vec![CallEdge { callee: "dispatch_tool".to_string(), call_line: 10 }],
+            vec![CallEdge { callee: "dispatch_tool".to_string(), call_line: 10, target: None }],
         );
         let graph = make_graph(symbols, call_edges);
         let index = FlowPathIndex::build(&graph);
@@ -647,7 +651,7 @@ mod tests {
         let mut call_edges = HashMap::new();
         call_edges.insert(
             "src/agent.rs::test_something".to_string(),
-            vec![CallEdge { callee: "real_fn".to_string(), call_line: 5 }],
+            vec![CallEdge { callee: "real_fn".to_string(), call_line: 5, target: None }],
         );
         let graph = make_graph(symbols, call_edges);
         let index = FlowPathIndex::build(&graph);
@@ -678,13 +682,13 @@ mod tests {
         let mut call_edges = HashMap::new();
         call_edges.insert(
             "src/a.rs::entry".to_string(),
-            vec![CallEdge { callee: "helper".to_string(), call_line: 5 }],
+            vec![CallEdge { callee: "helper".to_string(), call_line: 5, target: None }],
         );
         // Make helper have 6+ callers so it hits UTILITY_THRESHOLD
         for (i, caller) in ["caller2","caller3","caller4","caller5","caller6","caller7"].iter().enumerate() {
             call_edges.insert(
                 format!("src/{}::{}", (b'c' + i as u8) as char, caller),
-                vec![CallEdge { callee: "helper".to_string(), call_line: 5 }],
+                vec![CallEdge { callee: "helper".to_string(), call_line: 5, target: None }],
             );
         }
 
@@ -702,6 +706,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_resolved_edge_bypasses_ambiguity() {
+        // `execute` is defined in six tool modules; only a resolved edge is followed
+        let mut symbols = vec![fn_sym("dispatch", "src/main.rs", 1), fn_sym("guess", "src/main.rs", 20)];
+        for tool in ["read", "write", "edit", "bash", "search", "list"] {
+            symbols.push(fn_sym("execute", &format!("src/tools/{tool}.rs"), 1));
+        }
+        let mut call_edges = HashMap::new();
+        call_edges.insert(
+            "src/main.rs::dispatch".to_string(),
+            vec![CallEdge {
+                callee: "execute".to_string(),
+                call_line: 5,
+                target: Some("src/tools/read.rs::execute".to_string()),
+            }],
+        );
+        call_edges.insert(
+            "src/main.rs::guess".to_string(),
+            vec![CallEdge { callee: "execute".to_string(), call_line: 25, target: None }],
+        );
+        let graph = make_graph(symbols, call_edges);
+
+        let counts = HashMap::new();
+        assert_eq!(trace_bfs("src/main.rs::dispatch", &graph, &counts), vec!["src/tools/read.rs::execute"]);
+        assert!(trace_bfs("src/main.rs::guess", &graph, &counts).is_empty());
+    }
+
     #[test]
     fn test_cycle_prevention() {
         // a -> b -> a (cycle)
@@ -712,11 +743,11 @@ mod tests {
         let mut call_edges = HashMap::new();
         call_edges.insert(
             "src/a.rs::a".to_string(),
-            vec![CallEdge { callee: "b".to_string(), call_line: 2 }],
+            vec![CallEdge { callee: "b".to_string(), call_line: 2, target: None }],
         );
         call_edges.insert(
             "src/b.rs::b".to_string(),
-            vec![CallEdge { callee: "a".to_string(), call_line: 2 }],
+            vec![CallEdge { callee: "a".to_string(), call_line: 2, target: None }],
         );
         let graph = make_graph(symbols, call_edges);
         // Should not hang or stack overflow
@@ -183,6 +183,52 @@ impl GitRepo {
         Ok(checkpoints)
     }
 
+    /// Check out `commit` into a new detached worktree at `path`.
+    /// Plan steps that run in parallel each get one, so their edits can't collide.
+    pub fn add_worktree(&self, path: &Path, commit: &str) -> Result<()> {
+        let path = path.to_string_lossy();
+        self.run_git(&["worktree", "add", "--detach", &path, commit])?;
+        Ok(())
+    }
+
+    /// Remove a worktree created by `add_worktree`, discarding anything left in it.
+    pub fn remove_worktree(&self, path: &Path) -> Result<()> {
+        let path = path.to_string_lossy();
+        self.run_git(&["worktree", "remove", "--force", &path])?;
+        Ok(())
+    }
+
+    /// Stage everything and return the binary-safe patch from `base` to the
+    /// working tree — what a worktree step changed, ready for `apply_patch`.
+    pub fn staged_patch(&self, base: &str) -> Result<String> {
+        self.run_git(&["add", "-A"])?;
+        self.run_git(&["diff", "--cached", "--binary", base])
+    }
+
+    /// Apply a patch from `staged_patch` to the working tree. Fails without
+    /// touching anything if it doesn't apply cleanly.
+    pub fn apply_patch(&self, patch: &str) -> Result<()> {
+        use std::io::Write;
+        let mut child = std::process::Command::new("git")
+            .args(["apply", "--whitespace=nowarn", "-"])
+            .current_dir(&self.root)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .map_err(|e| anyhow!("failed to run git: {e}"))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(patch.as_bytes())?;
+        }
+        let output = child.wait_with_output()?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            Err(anyhow!("git apply: {}", stderr.trim()))
+        }
+    }
+
     /// Run a git command in the repo root. Returns stdout on success, Err(stderr) on failure.
     fn run_git(&self, args: &[&str]) -> Result<String> {
         let output = std::process::Command::new("git")
@@ -597,6 +643,34 @@ mod tests {
         assert!(checkpoints[19].message.contains("5"));
     }
 
+    // ── worktrees ───────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_worktree_patch_round_trip() {
+        let (_dir, repo) = setup_git_repo();
+        let base = repo.checkpoint("test").unwrap();
+        let wt_dir = tempdir().unwrap();
+        let wt_path = wt_dir.path().join("step");
+        repo.add_worktree(&wt_path, &base).unwrap();
+        assert_eq!(fs::read_to_string(wt_path.join("file1.txt")).unwrap(), "content1");
+
+        fs::write(wt_path.join("file1.txt"), "changed in worktree").unwrap();
+        fs::write(wt_path.join("added.txt"), "new").unwrap();
+        let wt = GitRepo::open(&wt_path).unwrap();
+        let patch = wt.staged_patch(&base).unwrap();
+        // Main tree is untouched until the patch is applied
+        assert_eq!(fs::read_to_string(repo.root.join("file1.txt")).unwrap(), "content1");
+
+        repo.apply_patch(&patch).unwrap();
+        assert_eq!(fs::read_to_string(repo.root.join("file1.txt")).unwrap(), "changed in worktree");
+        assert_eq!(fs::read_to_string(repo.root.join("added.txt")).unwrap(), "new");
+        // Applying again conflicts
+        assert!(repo.apply_patch(&patch).is_err());
+
+        repo.remove_worktree(&wt_path).unwrap();
+        assert!(!wt_path.exists());
+    }
+
     // ── post_task ───────────────────────────────────────────────────────────────
 
     #[test]
@@ -4,6 +4,7 @@
 /// model sees compile/lint errors and can self-correct immediately.
 /// `on_task_done` hooks run after the agent loop and are shown in the TUI only.
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 use tokio::time::{Duration, timeout};
 use tokio::process::Command;
 
@@ -195,7 +196,18 @@ const HOOK_MAX_LINES: usize = 100;
 /// Run a single hook command via `sh -c`. Merges stdout + stderr.
 /// Caps output at `HOOK_MAX_LINES` lines to avoid bloating context.
 pub async fn run_hook(cmd: &str) -> HookResult {
-    let fut = Command::new("sh").arg("-c").arg(cmd).output();
+    run_hook_in(cmd, None).await
+}
+
+/// `run_hook` in `dir` instead of the process cwd (plan steps running in a
+/// git worktree).
+pub async fn run_hook_in(cmd: &str, dir: Option<&Path>) -> HookResult {
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(cmd);
+    if let Some(dir) = dir {
+        command.current_dir(dir);
+    }
+    let fut = command.output();
 
     let output = match timeout(Duration::from_secs(HOOK_TIMEOUT_SECS), fut).await {
         Ok(Ok(o)) => o,
@@ -244,14 +256,15 @@ pub async fn run_hook(cmd: &str) -> HookResult {
 ///
 /// Returns an empty vec if `hooks_enabled` is false or `on_edit` is empty.
 /// The caller appends hook output to the tool result string and emits UI events
-/// — keeping this function free of any UI dependency.
-pub async fn run_edit_hooks(hooks: &HookConfig, hooks_enabled: bool) -> Vec<HookResult> {
+/// — keeping this function free of any UI dependency. Hooks run in `dir`
+/// when given, else the process cwd.
+pub async fn run_edit_hooks(hooks: &HookConfig, hooks_enabled: bool, dir: Option<&Path>) -> Vec<HookResult> {
     if !hooks_enabled || hooks.on_edit.is_empty() {
         return vec![];
     }
     let mut results = Vec::with_capacity(hooks.on_edit.len());
     for cmd in &hooks.on_edit {
-        results.push(run_hook(cmd).await);
+        results.push(run_hook_in(cmd, dir).await);
     }
     results
 }
@@ -260,13 +273,14 @@ pub async fn run_edit_hooks(hooks: &HookConfig, hooks_enabled: bool) -> Vec<Hook
 ///
 /// Returns an empty vec if `hooks_enabled` is false or `on_task_done` is empty.
 /// Callers map the results to UI events — keeping this function free of any UI dependency.
-pub async fn run_task_done_hooks(hooks: &HookConfig, hooks_enabled: bool) -> Vec<HookResult> {
+/// Hooks run in `dir` when given, else the process cwd.
+pub async fn run_task_done_hooks(hooks: &HookConfig, hooks_enabled: bool, dir: Option<&Path>) -> Vec<HookResult> {
     if !hooks_enabled || hooks.on_task_done.is_empty() {
         return vec![];
     }
     let mut results = Vec::with_capacity(hooks.on_task_done.len());
     for cmd in &hooks.on_task_done {
-        results.push(run_hook(cmd).await);
+        results.push(run_hook_in(cmd, dir).await);
     }
     results
 }
@@ -542,6 +556,15 @@ mod tests {
     // and config module, which is complex for unit tests. Integration tests
     // would be more appropriate for testing that function.
 
+    #[tokio::test]
+    async fn test_run_hook_in_uses_dir() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join("marker.txt"), "here").unwrap();
+        let result = run_hook_in("cat marker.txt", Some(tmp.path())).await;
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(result.output.trim(), "here");
+    }
+
     // ── run_task_done_hooks ──────────────────────────────────────────────────────
 
     #[tokio::test]
@@ -550,14 +573,14 @@ mod tests {
             on_task_done: vec!["echo should_not_run".to_string()],
             ..Default::default()
         };
-        let results = run_task_done_hooks(&hooks, false).await;
+        let results = run_task_done_hooks(&hooks, false, None).await;
         assert!(results.is_empty(), "hooks_enabled=false should return empty");
     }
 
     #[tokio::test]
     async fn test_run_task_done_hooks_empty_list() {
         let hooks = HookConfig::default();
-        let results = run_task_done_hooks(&hooks, true).await;
+        let results = run_task_done_hooks(&hooks, true, None).await;
         assert!(results.is_empty(), "empty on_task_done should return empty");
     }
 
@@ -570,7 +593,7 @@ mod tests {
             ],
             ..Default::default()
         };
-        let results = run_task_done_hooks(&hooks, true).await;
+        let results = run_task_done_hooks(&hooks, true, None).await;
         assert_eq!(results.len(), 2);
         assert!(results[0].output.contains("first"));
         assert!(results[1].output.contains("second"));
@@ -584,7 +607,7 @@ mod tests {
             on_task_done: vec!["exit 5".to_string()],
             ..Default::default()
         };
-        let results = run_task_done_hooks(&hooks, true).await;
+        let results = run_task_done_hooks(&hooks, true, None).await;
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].exit_code, 5);
     }
@@ -597,14 +620,14 @@ mod tests {
             on_edit: vec!["echo should_not_run".to_string()],
             ..Default::default()
         };
-        let results = run_edit_hooks(&hooks, false).await;
+        let results = run_edit_hooks(&hooks, false, None).await;
         assert!(results.is_empty(), "hooks_enabled=false should return empty");
     }
 
     #[tokio::test]
     async fn test_run_edit_hooks_empty_list() {
         let hooks = HookConfig::default();
-        let results = run_edit_hooks(&hooks, true).await;
+        let results = run_edit_hooks(&hooks, true, None).await;
         assert!(results.is_empty(), "empty on_edit should return empty");
     }
 
@@ -617,7 +640,7 @@ mod tests {
             ],
             ..Default::default()
         };
-        let results = run_edit_hooks(&hooks, true).await;
+        let results = run_edit_hooks(&hooks, true, None).await;
         assert_eq!(results.len(), 2);
         assert!(results[0].output.contains("alpha"));
         assert!(results[1].output.contains("beta"));
@@ -631,7 +654,7 @@ mod tests {
             on_edit: vec!["exit 3".to_string()],
             ..Default::default()
         };
-        let results = run_edit_hooks(&hooks, true).await;
+        let results = run_edit_hooks(&hooks, true, None).await;
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].exit_code, 3);
     }
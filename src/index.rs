@@ -5,13 +5,23 @@
 /// those names to real file paths.
 ///
 /// Design constraints:
-/// - Zero model calls — pure regex/text scan
-/// - Fast enough to run on every `/plan` invocation (< 100ms for typical projects)
+/// - Zero model calls — symbols come from tree-sitter syntax trees, so spans,
+///   containers and doc comments are exact rather than guessed from indentation
+/// - Fast enough to run on every `/plan` invocation (< 100ms for typical projects);
+///   files are parsed in parallel so monorepos of tens of thousands of files scale
 /// - Language-agnostic: covers Rust, TypeScript/JS, Python, Go, C/C++
 /// - Output is compact text suitable for injection into a model prompt
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use ignore::WalkBuilder;
+use rayon::prelude::*;
+use tree_sitter::{Node, Parser};
+
+use crate::callgraph::Lang;
+use crate::config::IndexConfig;
+
 /// A single outgoing call edge from a symbol to a callee by name.
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct CallEdge {
@@ -20,6 +30,55 @@ pub struct CallEdge {
     /// Line number (1-indexed) in the source file where this call occurs.
     /// When a callee is called multiple times, this is the first occurrence.
     pub call_line: usize,
+    /// Key (`Symbol::key`) of the definition this call lands on, when the
+    /// receiver pinned it down: `Self::`, `Type::`, `module::`, a typed local.
+    /// None = resolve by name, which is ambiguous for `new`, `execute`, ….
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+}
+
+/// How a name is used at a reference site.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum RefKind {
+    /// Type position: annotation, generic argument, `impl X`, `: X`
+    Type,
+    /// `x.field` read, or a field named in a destructuring pattern
+    FieldRead,
+    /// `x.field = …`, `x.field += …`, or `Struct { field: … }`
+    FieldWrite,
+    /// `use` / `import` of the name
+    Import,
+    /// `match` arm / destructuring on a type or enum variant
+    Pattern,
+    /// Called as a function or method
+    Call,
+}
+
+impl RefKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            RefKind::Type => "type",
+            RefKind::FieldRead => "read",
+            RefKind::FieldWrite => "write",
+            RefKind::Import => "import",
+            RefKind::Pattern => "pattern",
+            RefKind::Call => "call",
+        }
+    }
+}
+
+/// One use of a name in a file (the file is the key it's stored under).
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Reference {
+    /// The referenced name: type, field, variant's enum, or function
+    pub name: String,
+    /// 1-indexed line
+    pub line: usize,
+    pub kind: RefKind,
+    /// What the name hangs off: `config` in `config.auto_commit`, `Profile`
+    /// in `Profile { auto_commit }`, `Mode::Normal` in a match arm.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub qualifier: Option<String>,
 }
 
 /// A single indexed symbol.
@@ -28,18 +87,66 @@ pub struct Symbol {
     pub name: String,
     pub file: String,
     pub line: usize,
-    /// Last line of this symbol's body (inclusive). Computed post-sort as the
-    /// line before the next symbol in the same file, or the file's total line count.
+    /// Last line of this symbol's definition (inclusive, 1-indexed).
     #[serde(default)]
     pub end_line: usize,
     pub kind: SymbolKind,
     /// Compact interface summary extracted at index time:
     /// - fn: "(param: Type, ...) -> RetType" (signature, truncated at 120 chars)
-    /// - struct: "field: Type, field: Type, ..." (pub fields, truncated at 120 chars)
-    /// - enum: "Variant, Variant, ..." (variant names, truncated)
+    /// - struct: "field: Type, field: Type, ..." (Rust pub fields, truncated at 120 chars)
+    /// - enum: "Variant, Variant, ..." (Rust variant names, truncated)
     /// None for impl blocks, traits, constants, and other kinds.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub signature: Option<String>,
+    /// Byte range of the definition in its file, `start_byte..end_byte`.
+    /// Leading doc comments and attributes are not included.
+    #[serde(default)]
+    pub start_byte: usize,
+    #[serde(default)]
+    pub end_byte: usize,
+    /// Enclosing container: "impl Tool for ReadFile", "impl AppState",
+    /// "trait Tool", "mod tests", "class UserService", "namespace ns".
+    /// None for top-level definitions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    #[serde(default)]
+    pub visibility: Visibility,
+    /// First paragraph of the doc comment (Python: docstring), on one line.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc: Option<String>,
+    /// Traits/interfaces this definition implements: the trait of an
+    /// `impl Trait for Type` block, a TS class's `implements` list.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub implements: Vec<String>,
+}
+
+impl Symbol {
+    /// Type or class a member belongs to: `ReadFile` for a method in
+    /// `impl Tool for ReadFile`, `Tool` inside `trait Tool`. None for free
+    /// functions and for items that are only inside a `mod`/namespace.
+    pub fn owner(&self) -> Option<&str> {
+        let parent = self.parent.as_deref()?;
+        let (keyword, rest) = parent.split_once(' ')?;
+        match keyword {
+            "impl" => Some(rest.rsplit(" for ").next().unwrap_or(rest)),
+            "trait" | "class" | "struct" | "union" | "type" => Some(rest),
+            _ => None,
+        }
+    }
+
+    /// `Type::method` for members, the bare name otherwise.
+    pub fn qualified_name(&self) -> String {
+        match self.owner() {
+            Some(owner) => format!("{owner}::{}", self.name),
+            None => self.name.clone(),
+        }
+    }
+
+    /// Graph key: `"src/tools/read.rs::execute"`, `"src/agent.rs::AgentConfig::new"`.
+    /// Keys `call_edges` / `construct_edges` and `CallEdge::target`.
+    pub fn key(&self) -> String {
+        format!("{}::{}", self.file, self.qualified_name())
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -55,6 +162,18 @@ pub enum SymbolKind {
     Other,
 }
 
+/// How far a symbol is visible outside its container, normalised across languages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum Visibility {
+    /// `pub`, `export`, capitalised Go names, public members, non-`static` C
+    #[default]
+    Public,
+    /// `pub(crate)` / `pub(super)`, `protected` members
+    Restricted,
+    /// No Rust modifier, unexported TS, `_name` in Python, lower-case Go, `static` C
+    Private,
+}
+
 impl SymbolKind {
     pub(crate) fn label(&self) -> &'static str {
         match self {
@@ -82,41 +201,27 @@ pub struct SymbolIndex {
     pub file_lines: HashMap<String, usize>,
 }
 
-impl SymbolIndex {
-    /// Build an index by walking the project from `root`.
-    /// Ignores noise directories (target, node_modules, etc.).
-    /// Caps at `max_files` to keep runtime bounded.
-    pub fn build(root: &Path, max_files: usize) -> Self {
-        const IGNORED: &[&str] = &[
-            "target", "node_modules", ".git", ".next", "dist", "build",
-            "__pycache__", ".venv", "venv", ".cache", "coverage",
-        ];
-        const EXTENSIONS: &[&str] = &[
-            "rs", "ts", "tsx", "js", "jsx", "py", "go", "c", "cpp", "h", "hpp",
-        ];
+/// File extensions the indexer reads.
+pub(crate) const SOURCE_EXTENSIONS: &[&str] = &[
+    "rs", "ts", "tsx", "js", "jsx", "py", "go", "c", "cpp", "h", "hpp",
+];
 
-        let mut index = SymbolIndex::default();
-        let mut files: Vec<PathBuf> = Vec::new();
-        collect_files(root, IGNORED, EXTENSIONS, &mut files, max_files);
+/// Per-directory ignore file for PIE, in `.gitignore` syntax.
+pub(crate) const IGNORE_FILE: &str = ".parecodeignore";
 
-        for path in &files {
-            let Ok(content) = std::fs::read_to_string(path) else { continue };
-            let rel = path
-                .strip_prefix(root)
-                .unwrap_or(path)
-                .to_string_lossy()
-                .to_string();
-            let line_count = content.lines().count();
-            index.file_lines.insert(rel.clone(), line_count);
-            extract_symbols(&content, &rel, &mut index.symbols);
+impl SymbolIndex {
+    /// Build an index of every file `scope` covers under `root`, parsing
+    /// files in parallel across all cores.
+    pub fn build(root: &Path, scope: &IndexScope) -> Self {
+        let mut index = SymbolIndex::default();
+        for (rel, line_count, symbols) in extract_files(root, &scope.files(root)) {
+            index.file_lines.insert(rel, line_count);
+            index.symbols.extend(symbols);
         }
 
         // Sort by file, then line
         index.symbols.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)));
 
-        // Compute end lines: next symbol's start - 1, or file's total line count
-        compute_end_lines(&mut index.symbols, &index.file_lines);
-
         // Build name → files map
         for sym in &index.symbols {
             index
@@ -136,148 +241,797 @@ impl SymbolIndex {
 
 }
 
+/// Read and extract symbols from `files` (relative to `root`) in parallel.
+/// Returns (file, line count, symbols) per readable file, in input order.
+pub(crate) fn extract_files(root: &Path, files: &[String]) -> Vec<(String, usize, Vec<Symbol>)> {
+    files
+        .par_iter()
+        .filter_map(|rel| {
+            let content = std::fs::read_to_string(root.join(rel)).ok()?;
+            let mut symbols = Vec::new();
+            extract_symbols(&content, rel, &mut symbols);
+            Some((rel.clone(), content.lines().count(), symbols))
+        })
+        .collect()
+}
+
 // ── File collection ────────────────────────────────────────────────────────────
 
-fn collect_files(
-    dir: &Path,
-    ignored: &[&str],
-    extensions: &[&str],
-    out: &mut Vec<PathBuf>,
-    max: usize,
-) {
-    if out.len() >= max {
-        return;
+/// Which files the project graph covers: source extensions, minus hidden
+/// paths, `.gitignore` / `.parecodeignore` rules (nested ones included) and
+/// the `[index]` exclude globs, narrowed to the include globs when set.
+/// Extra `[index] roots` are walked too; their files keep the root as a path
+/// prefix (`../shared/src/lib.rs`) and globs match below that prefix.
+#[derive(Debug, Clone)]
+pub struct IndexScope {
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+    /// Extra roots relative to the project root, without trailing `/`
+    roots: Vec<String>,
+}
+
+impl Default for IndexScope {
+    fn default() -> Self {
+        Self::new(&IndexConfig::default())
     }
-    let Ok(entries) = std::fs::read_dir(dir) else { return };
-    let mut entries: Vec<_> = entries.filter_map(|e| e.ok()).collect();
-    entries.sort_by_key(|e| e.file_name());
+}
 
-    for entry in entries {
-        if out.len() >= max {
-            break;
+impl IndexScope {
+    /// Compile the configured globs. Invalid patterns are skipped.
+    pub fn new(config: &IndexConfig) -> Self {
+        let mut roots: Vec<String> = config
+            .roots
+            .iter()
+            .map(|r| r.trim().trim_end_matches('/').to_string())
+            .filter(|r| !r.is_empty() && r != ".")
+            .collect();
+        roots.dedup();
+        Self {
+            include: (!config.include.is_empty()).then(|| glob_set(&config.include)),
+            exclude: glob_set(&config.exclude),
+            roots,
         }
-        let name = entry.file_name();
-        let name_str = name.to_string_lossy();
-        if name_str.starts_with('.') {
-            continue;
+    }
+
+    /// The project root followed by each extra root, as (path prefix, directory).
+    /// The project root's prefix is `""`.
+    pub fn roots(&self, root: &Path) -> Vec<(String, PathBuf)> {
+        std::iter::once((String::new(), root.to_path_buf()))
+            .chain(self.roots.iter().map(|r| (r.clone(), root.join(r))))
+            .collect()
+    }
+
+    /// Every indexable file under `root` and the extra roots as a sorted
+    /// relative path.
+    pub fn files(&self, root: &Path) -> Vec<String> {
+        let mut out: Vec<String> = Vec::new();
+        for (prefix, base) in self.roots(root) {
+            out.extend(
+                self.walk(&base, &base)
+                    .filter(|e| e.file_type().is_some_and(|t| t.is_file()))
+                    .filter_map(|e| relative_path(&base, e.path()))
+                    .map(|rel| if prefix.is_empty() { rel } else { format!("{prefix}/{rel}") })
+                    .filter(|rel| self.accepts(rel)),
+            );
+        }
+        out.sort();
+        out.dedup();
+        out
+    }
+
+    /// `start` and every directory below it that the walk enters.
+    pub(crate) fn dirs(&self, root: &Path, start: &Path) -> Vec<PathBuf> {
+        // The deepest root containing `start` decides what is excluded
+        let base = self
+            .roots(root)
+            .into_iter()
+            .map(|(_, base)| base)
+            .filter(|base| start.starts_with(base))
+            .max_by_key(|base| base.components().count())
+            .unwrap_or_else(|| root.to_path_buf());
+        self.walk(&base, start)
+            .filter(|e| e.file_type().is_some_and(|t| t.is_dir()))
+            .map(|e| e.into_path())
+            .collect()
+    }
+
+    /// Whether the relative file path `rel` is in scope by extension and
+    /// globs. Ignore files are only consulted by `files`/`dirs`.
+    pub fn accepts(&self, rel: &str) -> bool {
+        let ext = Path::new(rel).extension().and_then(|e| e.to_str());
+        ext.is_some_and(|e| SOURCE_EXTENSIONS.contains(&e))
+            && self.accepts_dir(rel)
+            && self.include.as_ref().is_none_or(|g| g.is_match(self.within_root(rel)))
+    }
+
+    /// True when no prefix of `rel` is hidden or excluded.
+    pub fn accepts_dir(&self, rel: &str) -> bool {
+        let mut prefix = PathBuf::new();
+        for part in Path::new(self.within_root(rel)).components() {
+            let std::path::Component::Normal(name) = part else { return false };
+            if name.to_string_lossy().starts_with('.') {
+                return false;
+            }
+            prefix.push(name);
+            if self.exclude.is_match(&prefix) {
+                return false;
+            }
         }
-        if ignored.contains(&name_str.as_ref()) {
+        true
+    }
+
+    /// `rel` with its extra-root prefix removed, so globs and the hidden-path
+    /// check see the same path they would in that repo.
+    fn within_root<'a>(&self, rel: &'a str) -> &'a str {
+        self.roots
+            .iter()
+            .filter_map(|r| rel.strip_prefix(r.as_str()))
+            .find_map(|rest| if rest.is_empty() { Some(rest) } else { rest.strip_prefix('/') })
+            .unwrap_or(rel)
+    }
+
+    fn walk(&self, root: &Path, start: &Path) -> impl Iterator<Item = ignore::DirEntry> {
+        let root = root.to_path_buf();
+        let exclude = self.exclude.clone();
+        WalkBuilder::new(start)
+            .hidden(true)
+            .require_git(false)
+            .add_custom_ignore_filename(IGNORE_FILE)
+            .filter_entry(move |e| {
+                e.path().strip_prefix(&root).is_ok_and(|rel| rel.as_os_str().is_empty() || !exclude.is_match(rel))
+            })
+            .build()
+            .filter_map(|e| e.ok())
+    }
+}
+
+/// Compile patterns into one set. A pattern without `/` matches at any depth,
+/// as in `.gitignore`; a trailing `/` is dropped.
+fn glob_set(patterns: &[String]) -> GlobSet {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let pattern = pattern.trim().trim_end_matches('/');
+        if pattern.is_empty() {
             continue;
         }
-        let path = entry.path();
-        if path.is_dir() {
-            collect_files(&path, ignored, extensions, out, max);
-        } else if let Some(ext) = path.extension() {
-            if extensions.contains(&ext.to_string_lossy().as_ref()) {
-                out.push(path);
-            }
+        let pattern = if pattern.contains('/') { pattern.to_string() } else { format!("**/{pattern}") };
+        if let Ok(glob) = GlobBuilder::new(&pattern).literal_separator(true).build() {
+            builder.add(glob);
         }
     }
+    builder.build().unwrap_or_else(|_| GlobSet::empty())
+}
+
+fn relative_path(root: &Path, path: &Path) -> Option<String> {
+    Some(path.strip_prefix(root).ok()?.to_string_lossy().to_string())
 }
 
 // ── Symbol extraction ──────────────────────────────────────────────────────────
 
+/// Parse `content` with the grammar for `file`'s extension and append every
+/// definition to `out`.
+///
+/// Walks module-level items and the bodies of containers — Rust `mod`/`impl`/
+/// `trait`, classes, TS namespaces, C++ namespaces — so nested modules, impls
+/// inside `mod tests` and class members are all found regardless of
+/// indentation. Function bodies are never entered: local helpers and closures
+/// stay out of the index. Files whose grammar fails to parse yield nothing.
 pub(crate) fn extract_symbols(content: &str, file: &str, out: &mut Vec<Symbol>) {
-    let ext = Path::new(file)
-        .extension()
-        .map(|e| e.to_string_lossy().to_string())
-        .unwrap_or_default();
-
-    let lines: Vec<&str> = content.lines().collect();
-
-    // Track whether we're inside a raw string literal (`r#"..."#` or `r"..."`).
-    // Lines inside raw strings can start at column 0 and look like top-level symbols
-    // (common in test fixtures that embed source code), causing false index entries.
-    // We use a simple heuristic: count unmatched r#" openers vs "#" closers.
-    let mut raw_depth: u32 = 0;
-
-    for (i, line) in lines.iter().enumerate() {
-        // Update raw-string depth before the top-level check so we don't
-        // accidentally index the first line of a raw string.
-        // r#" opens, "# closes. Handle same-line open+close (no depth change).
-        let opens = line.matches("r#\"").count() as u32;
-        let closes = line.matches("\"#").count() as u32;
-        if raw_depth == 0 {
-            // Not currently inside a raw string — check if this line opens one
-            // and doesn't immediately close it on the same line.
-            if opens > closes {
-                raw_depth += opens - closes;
+    let Some(lang) = Lang::from_path(file) else { return };
+    let mut parser = Parser::new();
+    if parser.set_language(&lang.grammar()).is_err() {
+        return;
+    }
+    let Some(tree) = parser.parse(content, None) else { return };
+    let mut walker = Walker {
+        lang,
+        file,
+        src: content.as_bytes(),
+        lines: content.lines().collect(),
+        out,
+    };
+    walker.items(tree.root_node(), &Scope::default());
+}
+
+/// The container the walker is currently inside.
+#[derive(Debug, Clone, Default)]
+struct Scope {
+    /// Becomes `Symbol::parent` for everything defined here
+    label: Option<String>,
+    /// Class/struct body — functions here are members
+    in_class: bool,
+    /// Rust trait or trait impl — items carry no modifier but are as visible as the trait
+    in_trait: bool,
+    /// C++ member access before the first `public:`/`private:` label
+    access: Visibility,
+}
+
+impl Scope {
+    fn container(label: String) -> Self {
+        Self { label: Some(label), ..Self::default() }
+    }
+
+    fn class(label: String, access: Visibility) -> Self {
+        Self { label: Some(label), in_class: true, in_trait: false, access }
+    }
+}
+
+struct Walker<'a> {
+    lang: Lang,
+    file: &'a str,
+    src: &'a [u8],
+    lines: Vec<&'a str>,
+    out: &'a mut Vec<Symbol>,
+}
+
+impl Walker<'_> {
+    fn text(&self, node: Node) -> &str {
+        node.utf8_text(self.src).unwrap_or("")
+    }
+
+    /// Visit the direct children of a container node.
+    fn items(&mut self, container: Node, scope: &Scope) {
+        let mut access = scope.access;
+        let mut cursor = container.walk();
+        let children: Vec<Node> = container.named_children(&mut cursor).collect();
+        for node in children {
+            match self.lang {
+                Lang::Rust => self.rust_item(node, scope),
+                Lang::TypeScript | Lang::Tsx if scope.in_class => self.ts_member(node, scope),
+                Lang::TypeScript | Lang::Tsx => self.ts_item(node, scope),
+                Lang::Python => self.python_item(node, scope),
+                Lang::Go => self.go_item(node),
+                Lang::C | Lang::Cpp => {
+                    if node.kind() == "access_specifier" {
+                        access = match self.text(node) {
+                            "public" => Visibility::Public,
+                            "protected" => Visibility::Restricted,
+                            _ => Visibility::Private,
+                        };
+                        continue;
+                    }
+                    self.c_item(node, &Scope { access, ..scope.clone() });
+                }
+            }
+        }
+    }
+
+    /// A symbol spanning `node`, public and undocumented until the caller says otherwise.
+    fn symbol(&self, node: Node, name: &str, kind: SymbolKind, scope: &Scope) -> Symbol {
+        Symbol {
+            name: name.to_string(),
+            file: self.file.to_string(),
+            line: node.start_position().row + 1,
+            end_line: last_row(node) + 1,
+            kind,
+            signature: None,
+            start_byte: node.start_byte(),
+            end_byte: node.end_byte(),
+            parent: scope.label.clone(),
+            visibility: Visibility::Public,
+            doc: None,
+            implements: Vec::new(),
+        }
+    }
+
+    // ── Rust ──────────────────────────────────────────────────────────────────
+
+    fn rust_item(&mut self, node: Node, scope: &Scope) {
+        let kind = match node.kind() {
+            "function_item" | "function_signature_item" => SymbolKind::Function,
+            "struct_item" | "union_item" => SymbolKind::Struct,
+            "enum_item" => SymbolKind::Enum,
+            "trait_item" => SymbolKind::Trait,
+            "impl_item" => SymbolKind::Impl,
+            "const_item" | "static_item" => SymbolKind::Constant,
+            "type_item" | "macro_definition" => SymbolKind::Other,
+            "mod_item" => {
+                if let (Some(name), Some(body)) = (node.child_by_field_name("name"), node.child_by_field_name("body")) {
+                    let inner = Scope::container(format!("mod {}", self.text(name)));
+                    self.items(body, &inner);
+                }
+                return;
             }
-            // If opens == closes the raw string opened and closed on this line — fine.
+            _ => return,
+        };
+        // `impl Trait for Type` is indexed under the type's bare name
+        let name_node = if kind == SymbolKind::Impl { "type" } else { "name" };
+        let Some(name_node) = node.child_by_field_name(name_node) else { return };
+        let name = if kind == SymbolKind::Impl {
+            base_type_name(self.text(name_node))
         } else {
-            // Inside a raw string — look for the closing "#
-            if closes >= raw_depth {
-                raw_depth = 0;
-            } else {
-                raw_depth -= closes;
+            self.text(name_node).to_string()
+        };
+
+        let mut sym = self.symbol(node, &name, kind.clone(), scope);
+        if let Some(t) = node.child_by_field_name("trait").filter(|_| kind == SymbolKind::Impl) {
+            sym.implements.push(base_type_name(self.text(t)));
+        }
+        sym.visibility = match child_of_kind(node, "visibility_modifier").map(|v| self.text(v)) {
+            Some("pub") => Visibility::Public,
+            Some(_) => Visibility::Restricted,
+            None if scope.in_trait || kind == SymbolKind::Impl => Visibility::Public,
+            None => Visibility::Private,
+        };
+        sym.doc = leading_doc(self.lang, node, self.src);
+        sym.signature = match kind {
+            SymbolKind::Function => fn_signature(self.lang, node, self.src),
+            SymbolKind::Struct => extract_struct_fields(&self.lines, node.start_position().row),
+            SymbolKind::Enum => extract_enum_variants(&self.lines, node.start_position().row),
+            _ => None,
+        };
+        self.out.push(sym);
+
+        let Some(body) = node.child_by_field_name("body") else { return };
+        match kind {
+            SymbolKind::Trait => {
+                let inner = Scope { in_trait: true, ..Scope::container(format!("trait {name}")) };
+                self.items(body, &inner);
+            }
+            SymbolKind::Impl => {
+                let label = match node.child_by_field_name("trait") {
+                    Some(t) => format!("impl {} for {name}", collapse_ws(self.text(t))),
+                    None => format!("impl {name}"),
+                };
+                let in_trait = node.child_by_field_name("trait").is_some();
+                self.items(body, &Scope { in_trait, ..Scope::container(label) });
             }
-            continue; // skip symbol extraction while inside raw string
-        }
-
-        // Only index top-level symbols — lines that start at column 0 (no indentation).
-        // Indented lines are inside function/impl bodies and must not create false
-        // symbol boundaries (which would truncate end_line for the enclosing symbol).
-        let first = line.chars().next();
-        let is_top_level = matches!(first, Some(c) if !c.is_whitespace());
-        if !is_top_level {
-            // Capture impl methods: exactly 4-space indented `fn` lines in Rust.
-            // Methods inside `impl` blocks are always at one indent level and are
-            // critical for orient / symbol-picker / check_wiring to find (e.g. apply_event,
-            // new, push). We only go one level deep — 8+ spaces are closures or nested fns.
-            if ext == "rs" {
-                let indent = line.len() - line.trim_start_matches(' ').len();
-                if indent == 4 {
-                    let trimmed = line.trim();
-                    if let Some(mut sym) = extract_symbol_from_line(trimmed, &ext, i + 1, file) {
-                        if matches!(sym.kind, SymbolKind::Function) {
-                            sym.signature = extract_signature(&sym.kind, &ext, &lines, i);
-                            out.push(sym);
-                        }
+            _ => {}
+        }
+    }
+
+    // ── TypeScript / JavaScript ────────────────────────────────────────────────
+
+    /// Module-level statement. Exported declarations are public and take their
+    /// span and doc comment from the `export` statement wrapping them.
+    fn ts_item(&mut self, node: Node, scope: &Scope) {
+        match node.kind() {
+            "export_statement" => {
+                let decl = node
+                    .child_by_field_name("declaration")
+                    .or_else(|| node.child_by_field_name("value"));
+                if let Some(decl) = decl {
+                    self.ts_decl(decl, node, Visibility::Public, scope);
+                }
+            }
+            // `namespace N { … }` parses as an expression statement
+            "expression_statement" => {
+                if let Some(inner) = node.named_child(0).filter(|n| n.kind() == "internal_module") {
+                    self.ts_decl(inner, node, Visibility::Private, scope);
+                }
+            }
+            _ => self.ts_decl(node, node, Visibility::Private, scope),
+        }
+    }
+
+    fn ts_decl(&mut self, decl: Node, outer: Node, visibility: Visibility, scope: &Scope) {
+        let kind = match decl.kind() {
+            "function_declaration" | "generator_function_declaration"
+            | "function_expression" | "arrow_function" => SymbolKind::Function,
+            "class_declaration" | "abstract_class_declaration" | "class" => SymbolKind::Class,
+            "interface_declaration" => SymbolKind::Struct, // treat as struct-like
+            "enum_declaration" => SymbolKind::Enum,
+            "type_alias_declaration" => SymbolKind::Other,
+            "internal_module" | "module" => {
+                if let (Some(name), Some(body)) = (decl.child_by_field_name("name"), decl.child_by_field_name("body")) {
+                    let inner = Scope::container(format!("namespace {}", self.text(name)));
+                    self.items(body, &inner);
+                }
+                return;
+            }
+            // `const handler = async (req) => { … }` — only function-valued bindings
+            "lexical_declaration" | "variable_declaration" => {
+                let mut cursor = decl.walk();
+                let declarators: Vec<Node> = decl
+                    .named_children(&mut cursor)
+                    .filter(|d| d.kind() == "variable_declarator")
+                    .collect();
+                let single = declarators.len() == 1;
+                for declarator in declarators {
+                    let Some(value) = declarator.child_by_field_name("value") else { continue };
+                    if !is_ts_function(value) {
+                        continue;
                     }
+                    let Some(name) = declarator.child_by_field_name("name") else { continue };
+                    let span = if single { outer } else { declarator };
+                    let mut sym = self.symbol(span, self.text(name), SymbolKind::Function, scope);
+                    sym.visibility = visibility;
+                    sym.doc = leading_doc(self.lang, outer, self.src);
+                    sym.signature = fn_signature(self.lang, value, self.src);
+                    self.out.push(sym);
                 }
+                return;
             }
-            continue;
+            _ => return,
+        };
+        // `export default function () {}` has no name of its own
+        let name = decl
+            .child_by_field_name("name")
+            .map(|n| self.text(n).to_string())
+            .unwrap_or_else(|| "default".to_string());
+
+        let mut sym = self.symbol(outer, &name, kind.clone(), scope);
+        sym.visibility = visibility;
+        sym.doc = leading_doc(self.lang, outer, self.src);
+        if kind == SymbolKind::Function {
+            sym.signature = fn_signature(self.lang, decl, self.src);
+        }
+        // class Foo extends Base implements Service, Disposable
+        let heritage = child_of_kind(decl, "class_heritage");
+        if let Some(implements) = heritage.and_then(|h| child_of_kind(h, "implements_clause")) {
+            let mut cursor = implements.walk();
+            for ty in implements.named_children(&mut cursor) {
+                sym.implements.push(base_type_name(self.text(ty)));
+            }
+        }
+        self.out.push(sym);
+
+        if kind == SymbolKind::Class {
+            if let Some(body) = decl.child_by_field_name("body") {
+                self.items(body, &Scope::class(format!("class {name}"), Visibility::Public));
+            }
+        }
+    }
+
+    /// Class member: methods, and fields holding arrow functions.
+    fn ts_member(&mut self, node: Node, scope: &Scope) {
+        let function = match node.kind() {
+            "method_definition" | "abstract_method_signature" | "method_signature" => node,
+            "public_field_definition" | "field_definition" => {
+                match node.child_by_field_name("value").filter(|v| is_ts_function(*v)) {
+                    Some(value) => value,
+                    None => return,
+                }
+            }
+            _ => return,
+        };
+        let Some(name) = node.child_by_field_name("name").or_else(|| node.child_by_field_name("property")) else {
+            return;
+        };
+        let name = self.text(name).to_string();
+        let mut sym = self.symbol(node, &name, SymbolKind::Method, scope);
+        sym.visibility = match child_of_kind(node, "accessibility_modifier").map(|m| self.text(m)) {
+            Some("private") => Visibility::Private,
+            Some("protected") => Visibility::Restricted,
+            _ if name.starts_with('#') => Visibility::Private,
+            _ => Visibility::Public,
+        };
+        sym.doc = leading_doc(self.lang, node, self.src);
+        sym.signature = fn_signature(self.lang, function, self.src);
+        self.out.push(sym);
+    }
+
+    // ── Python ────────────────────────────────────────────────────────────────
+
+    fn python_item(&mut self, node: Node, scope: &Scope) {
+        // Decorators are not part of the definition's span
+        let node = match node.kind() {
+            "decorated_definition" => match node.child_by_field_name("definition") {
+                Some(def) => def,
+                None => return,
+            },
+            _ => node,
+        };
+        let kind = match node.kind() {
+            "function_definition" if scope.in_class => SymbolKind::Method,
+            "function_definition" => SymbolKind::Function,
+            "class_definition" => SymbolKind::Class,
+            _ => return,
+        };
+        let Some(name) = node.child_by_field_name("name") else { return };
+        let name = self.text(name).to_string();
+        let mut sym = self.symbol(node, &name, kind.clone(), scope);
+        // `_helper` is private by convention; dunders like `__init__` are not
+        let private = name.starts_with('_') && !(name.starts_with("__") && name.ends_with("__"));
+        sym.visibility = if private { Visibility::Private } else { Visibility::Public };
+        sym.doc = python_docstring(node, self.src);
+        if kind != SymbolKind::Class {
+            sym.signature = fn_signature(self.lang, node, self.src);
         }
-        let trimmed = line.trim();
-        if let Some(mut sym) = extract_symbol_from_line(trimmed, &ext, i + 1, file) {
-            sym.signature = extract_signature(&sym.kind, &ext, &lines, i);
-            out.push(sym);
+        self.out.push(sym);
+
+        if kind == SymbolKind::Class {
+            if let Some(body) = node.child_by_field_name("body") {
+                self.items(body, &Scope::class(format!("class {name}"), Visibility::Public));
+            }
         }
     }
+
+    // ── Go ────────────────────────────────────────────────────────────────────
+
+    fn go_item(&mut self, node: Node) {
+        match node.kind() {
+            "function_declaration" => self.go_symbol(node, node, SymbolKind::Function, None),
+            "method_declaration" => {
+                // func (s *Server) Handle(…) — parent is the receiver's type
+                let receiver = node
+                    .child_by_field_name("receiver")
+                    .and_then(|r| r.named_child(0))
+                    .and_then(|p| p.child_by_field_name("type"))
+                    .map(|t| {
+                        let t = self.text(t).trim_start_matches('*');
+                        format!("type {}", t.split('[').next().unwrap_or(t))
+                    });
+                self.go_symbol(node, node, SymbolKind::Method, receiver);
+            }
+            "type_declaration" | "const_declaration" => {
+                let mut cursor = node.walk();
+                let specs: Vec<Node> = node.named_children(&mut cursor).collect();
+                let single = specs.len() == 1;
+                for spec in specs {
+                    let kind = match (spec.kind(), spec.child_by_field_name("type").map(|t| t.kind())) {
+                        ("type_spec", Some("struct_type")) => SymbolKind::Struct,
+                        ("type_spec", Some("interface_type")) => SymbolKind::Trait,
+                        ("type_spec" | "type_alias", _) => SymbolKind::Other,
+                        ("const_spec", _) => SymbolKind::Constant,
+                        _ => continue,
+                    };
+                    // A lone `type Foo struct{}` spans its `type` keyword and doc comment
+                    let span = if single { node } else { spec };
+                    let mut cursor = spec.walk();
+                    let names: Vec<Node> = spec.children_by_field_name("name", &mut cursor).collect();
+                    for name in names {
+                        let mut sym = self.symbol(span, self.text(name), kind.clone(), &Scope::default());
+                        sym.visibility = go_visibility(&sym.name);
+                        sym.doc = leading_doc(self.lang, span, self.src);
+                        self.out.push(sym);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn go_symbol(&mut self, node: Node, span: Node, kind: SymbolKind, parent: Option<String>) {
+        let Some(name) = node.child_by_field_name("name") else { return };
+        let scope = Scope { label: parent, ..Scope::default() };
+        let mut sym = self.symbol(span, self.text(name), kind, &scope);
+        sym.visibility = go_visibility(&sym.name);
+        sym.doc = leading_doc(self.lang, span, self.src);
+        sym.signature = fn_signature(self.lang, node, self.src);
+        self.out.push(sym);
+    }
+
+    // ── C/C++ ─────────────────────────────────────────────────────────────────
+
+    fn c_item(&mut self, node: Node, scope: &Scope) {
+        match node.kind() {
+            // Transparent wrappers: include guards, templates, `extern "C" { … }`
+            "preproc_ifdef" | "preproc_if" | "preproc_else" | "preproc_elif" | "template_declaration" => {
+                self.items(node, scope);
+            }
+            "linkage_specification" => {
+                if let Some(body) = node.child_by_field_name("body") {
+                    self.items(body, scope);
+                }
+            }
+            "namespace_definition" => {
+                if let Some(body) = node.child_by_field_name("body") {
+                    let name = node.child_by_field_name("name").map(|n| self.text(n)).unwrap_or("");
+                    let inner = Scope::container(format!("namespace {name}").trim_end().to_string());
+                    self.items(body, &inner);
+                }
+            }
+            // Definitions, prototypes, and member declarations in class bodies
+            "function_definition" | "declaration" | "field_declaration" => {
+                let Some(declarator) = c_function_declarator(node) else { return };
+                let Some(name) = declarator.child_by_field_name("declarator") else { return };
+                let name = c_base_name(self.text(name));
+                let kind = if scope.in_class { SymbolKind::Method } else { SymbolKind::Function };
+                let mut sym = self.symbol(node, &name, kind, scope);
+                sym.visibility = if scope.in_class {
+                    scope.access
+                } else if child_of_kind(node, "storage_class_specifier").is_some_and(|s| self.text(s) == "static") {
+                    Visibility::Private
+                } else {
+                    Visibility::Public
+                };
+                sym.doc = leading_doc(self.lang, node, self.src);
+                sym.signature = fn_signature(self.lang, node, self.src);
+                self.out.push(sym);
+            }
+            "struct_specifier" | "union_specifier" | "class_specifier" | "enum_specifier" => {
+                let (Some(name), Some(body)) = (node.child_by_field_name("name"), node.child_by_field_name("body")) else {
+                    return;
+                };
+                let (kind, keyword, access) = match node.kind() {
+                    "class_specifier" => (SymbolKind::Class, "class", Visibility::Private),
+                    "enum_specifier" => (SymbolKind::Enum, "enum", Visibility::Public),
+                    "union_specifier" => (SymbolKind::Struct, "union", Visibility::Public),
+                    _ => (SymbolKind::Struct, "struct", Visibility::Public),
+                };
+                let name = self.text(name).to_string();
+                let mut sym = self.symbol(node, &name, kind.clone(), scope);
+                sym.visibility = if scope.in_class { scope.access } else { Visibility::Public };
+                sym.doc = leading_doc(self.lang, node, self.src);
+                self.out.push(sym);
+                if kind != SymbolKind::Enum {
+                    self.items(body, &Scope::class(format!("{keyword} {name}"), access));
+                }
+            }
+            // typedef struct { … } Name;
+            "type_definition" => {
+                let Some(name) = node.child_by_field_name("declarator") else { return };
+                let kind = match node.child_by_field_name("type").map(|t| t.kind()) {
+                    Some("struct_specifier" | "union_specifier" | "class_specifier") => SymbolKind::Struct,
+                    Some("enum_specifier") => SymbolKind::Enum,
+                    _ => SymbolKind::Other,
+                };
+                let mut sym = self.symbol(node, self.text(name), kind, scope);
+                sym.doc = leading_doc(self.lang, node, self.src);
+                self.out.push(sym);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Last line a node covers, 0-indexed. Nodes that swallow their trailing
+/// newline end at column 0 of the following line — that line isn't theirs.
+fn last_row(node: Node) -> usize {
+    let end = node.end_position();
+    if end.column == 0 && end.row > node.start_position().row { end.row - 1 } else { end.row }
+}
+
+fn child_of_kind<'t>(node: Node<'t>, kind: &str) -> Option<Node<'t>> {
+    let mut cursor = node.walk();
+    node.children(&mut cursor).find(|c| c.kind() == kind)
+}
+
+/// Bare type name: `fmt::Display` / `Foo<T>` / `&'a mut crate::Foo` / `ns.Service`
+/// → `Display` / `Foo` / `Foo` / `Service`.
+pub(crate) fn base_type_name(text: &str) -> String {
+    let text = text.split('<').next().unwrap_or(text).trim_start_matches('&');
+    // skip lifetimes and `mut` / `dyn` / `impl`
+    let text = text
+        .split_whitespace()
+        .find(|w| !w.starts_with('\'') && !matches!(*w, "mut" | "dyn" | "impl"))
+        .unwrap_or("");
+    let text = text.rsplit("::").next().unwrap_or(text);
+    text.rsplit('.').next().unwrap_or(text).to_string()
+}
+
+fn is_ts_function(value: Node) -> bool {
+    matches!(value.kind(), "arrow_function" | "function_expression" | "function" | "generator_function")
+}
+
+/// Go exports exactly the names that start with an upper-case letter.
+fn go_visibility(name: &str) -> Visibility {
+    if name.starts_with(|c: char| c.is_uppercase()) { Visibility::Public } else { Visibility::Private }
 }
 
-/// Extract a compact interface summary for a symbol using lookahead from its definition line.
-fn extract_signature(kind: &SymbolKind, ext: &str, lines: &[&str], start: usize) -> Option<String> {
-    if ext != "rs" {
-        return None; // Only Rust for now
+/// The `function_declarator` of a C/C++ definition or prototype, looking
+/// through pointer and reference declarators (`int *make(void)`).
+fn c_function_declarator(node: Node) -> Option<Node> {
+    let mut declarator = node.child_by_field_name("declarator")?;
+    loop {
+        match declarator.kind() {
+            "function_declarator" => return Some(declarator),
+            "pointer_declarator" | "reference_declarator" | "parenthesized_declarator" => {
+                let inner = declarator
+                    .child_by_field_name("declarator")
+                    .or_else(|| declarator.named_child(0))?;
+                declarator = inner;
+            }
+            _ => return None,
+        }
     }
-    match kind {
-        SymbolKind::Function => extract_fn_signature(lines, start),
-        SymbolKind::Struct   => extract_struct_fields(lines, start),
-        SymbolKind::Enum     => extract_enum_variants(lines, start),
-        _ => None,
+}
+
+/// `Engine::tick` / `max<int>` → `tick` / `max`
+fn c_base_name(text: &str) -> String {
+    let text = text.split('<').next().unwrap_or(text);
+    text.rsplit("::").next().unwrap_or(text).to_string()
+}
+
+/// Compact `(params) -> Ret` for a function node (TS keeps its own `(params): Ret`,
+/// Go `(params) Ret`), truncated at 120 chars.
+fn fn_signature(lang: Lang, node: Node, src: &[u8]) -> Option<String> {
+    let params = match lang {
+        Lang::C | Lang::Cpp => c_function_declarator(node)?.child_by_field_name("parameters")?,
+        // `x => x` has a bare `parameter`
+        _ => node
+            .child_by_field_name("parameters")
+            .or_else(|| node.child_by_field_name("parameter"))?,
+    };
+    let mut sig = collapse_ws(params.utf8_text(src).ok()?);
+    let ret = match lang {
+        Lang::Go => node.child_by_field_name("result"),
+        Lang::C | Lang::Cpp => node.child_by_field_name("type"),
+        _ => node.child_by_field_name("return_type"),
+    };
+    if let Some(ret) = ret.and_then(|r| r.utf8_text(src).ok()) {
+        let ret = collapse_ws(ret);
+        match lang {
+            // the type annotation carries its own `:`
+            Lang::TypeScript | Lang::Tsx => sig.push_str(&ret),
+            Lang::Go => sig = format!("{sig} {ret}"),
+            _ => sig = format!("{sig} -> {ret}"),
+        }
     }
+    Some(truncate_at_word(&sig, 120))
 }
 
-/// Extract `(params) -> RetType` from a fn definition (may span multiple lines).
-fn extract_fn_signature(lines: &[&str], start: usize) -> Option<String> {
-    // Collect lines from start until we hit the opening `{` or a `;`
-    let mut sig = String::new();
-    for line in lines.iter().skip(start) {
-        let t = line.trim();
-        sig.push(' ');
-        sig.push_str(t);
-        if t.ends_with('{') || t.ends_with(';') {
+/// Single-line form of a multi-line parameter list: `( a: T, b: U, )` → `(a: T, b: U)`
+fn collapse_ws(s: &str) -> String {
+    s.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace("( ", "(")
+        .replace(" )", ")")
+        .replace(",)", ")")
+}
+
+/// Doc comment directly above `node`: the run of comments ending on the line
+/// before it, stepping over attributes in between. Rust only counts `///` and
+/// `/** */`; other languages take any comment (JSDoc, godoc, Doxygen).
+fn leading_doc(lang: Lang, node: Node, src: &[u8]) -> Option<String> {
+    let mut above: Vec<&str> = Vec::new();
+    let mut next_row = node.start_position().row;
+    let mut prev = node.prev_sibling();
+    while let Some(p) = prev {
+        let is_comment = matches!(p.kind(), "comment" | "line_comment" | "block_comment");
+        if !(is_comment || p.kind() == "attribute_item") || last_row(p) + 1 < next_row {
             break;
         }
-        if sig.len() > 300 { break; }
+        // A trailing comment belongs to the code on its own line
+        if p.prev_sibling().is_some_and(|pp| last_row(pp) == p.start_position().row) {
+            break;
+        }
+        if is_comment {
+            let text = p.utf8_text(src).ok()?;
+            if lang == Lang::Rust && !is_rust_doc(text) {
+                break;
+            }
+            above.push(text);
+        }
+        next_row = p.start_position().row;
+        prev = p.prev_sibling();
     }
-    // Extract the part between the first `(` and the `{`
-    let paren_start = sig.find('(')?;
-    let brace = sig.rfind(" {")?;
-    let inner = sig[paren_start..brace].trim().to_string();
-    // Trim to 120 chars at a clean boundary
-    Some(truncate_at_word(&inner, 120))
+    above.reverse();
+    let lines: Vec<&str> = above.iter().flat_map(|c| c.lines()).map(strip_comment_marker).collect();
+    first_paragraph(&lines)
+}
+
+fn is_rust_doc(comment: &str) -> bool {
+    (comment.starts_with("///") && !comment.starts_with("////"))
+        || (comment.starts_with("/**") && !comment.starts_with("/***") && comment != "/**/")
+}
+
+fn strip_comment_marker(line: &str) -> &str {
+    let t = line.trim();
+    let t = ["///", "//", "/**", "/*"]
+        .iter()
+        .find_map(|m| t.strip_prefix(m))
+        .unwrap_or(t);
+    let t = t.strip_suffix("*/").unwrap_or(t).trim();
+    t.strip_prefix('*').unwrap_or(t).trim()
+}
+
+/// Python docstring: the string literal opening a def/class body.
+fn python_docstring(node: Node, src: &[u8]) -> Option<String> {
+    let first = node.child_by_field_name("body")?.named_child(0)?;
+    let string = Some(first)
+        .filter(|s| s.kind() == "expression_statement")?
+        .named_child(0)
+        .filter(|s| s.kind() == "string")?;
+    let content = child_of_kind(string, "string_content")?;
+    let lines: Vec<&str> = content.utf8_text(src).ok()?.lines().map(str::trim).collect();
+    first_paragraph(&lines)
+}
+
+/// Doc text up to the first blank line, joined into one line and capped at 300 chars.
+fn first_paragraph(lines: &[&str]) -> Option<String> {
+    let para: Vec<&str> = lines
+        .iter()
+        .skip_while(|l| l.is_empty())
+        .take_while(|l| !l.is_empty())
+        .copied()
+        .collect();
+    if para.is_empty() {
+        return None;
+    }
+    Some(truncate_at_word(&para.join(" "), 300))
 }
 
 /// Extract pub fields from a struct body: `field: Type, field: Type, ...`
@@ -338,292 +1092,83 @@ fn extract_enum_variants(lines: &[&str], start: usize) -> Option<String> {
 /// Truncate a string at a word boundary to fit within `max` chars.
 fn truncate_at_word(s: &str, max: usize) -> String {
     if s.len() <= max { return s.to_string(); }
+    // Doc comments are full of `—` and `→`; never cut inside a character
+    let max = (0..=max).rev().find(|&i| s.is_char_boundary(i)).unwrap_or(0);
     let cut = s[..max].rfind(|c: char| c == ',' || c == ' ').unwrap_or(max);
     format!("{}…", &s[..cut])
 }
 
-fn extract_symbol_from_line(line: &str, ext: &str, line_no: usize, file: &str) -> Option<Symbol> {
-    let (kind, name) = match ext {
-        "rs" => extract_rust(line)?,
-        "ts" | "tsx" | "js" | "jsx" => extract_ts(line)?,
-        "py" => extract_python(line)?,
-        "go" => extract_go(line)?,
-        "c" | "cpp" | "h" | "hpp" => extract_c(line)?,
-        _ => return None,
-    };
-
-    Some(Symbol {
-        name,
-        file: file.to_string(),
-        line: line_no,
-        end_line: line_no, // placeholder — overwritten by compute_end_lines after sort
-        kind,
-        signature: None,  // filled by extract_symbols after kind is known
-    })
-}
-
-// ── Rust ──────────────────────────────────────────────────────────────────────
-
-fn extract_rust(line: &str) -> Option<(SymbolKind, String)> {
-    // pub fn / fn / pub async fn / async fn
-    if let Some(rest) = strip_rust_fn(line) {
-        let name = ident_at_start(rest)?;
-        return Some((SymbolKind::Function, name));
-    }
-    // pub struct / struct
-    if let Some(rest) = strip_prefix_variants(line, &["pub struct ", "pub(crate) struct ", "struct "]) {
-        let name = ident_at_start(rest)?;
-        return Some((SymbolKind::Struct, name));
-    }
-    // pub enum / enum
-    if let Some(rest) = strip_prefix_variants(line, &["pub enum ", "pub(crate) enum ", "enum "]) {
-        let name = ident_at_start(rest)?;
-        return Some((SymbolKind::Enum, name));
-    }
-    // pub trait / trait
-    if let Some(rest) = strip_prefix_variants(line, &["pub trait ", "pub(crate) trait ", "trait "]) {
-        let name = ident_at_start(rest)?;
-        return Some((SymbolKind::Trait, name));
-    }
-    // impl Type / impl<T> Type / impl Trait for Type
-    if let Some(rest) = line.strip_prefix("impl") {
-        let rest = rest.trim_start();
-        // Skip generic params: impl<T> Foo -> skip to after >
-        let rest = if rest.starts_with('<') {
-            match rest.find('>') {
-                Some(i) => rest[i + 1..].trim(),
-                None => return None,
-            }
-        } else {
-            rest
-        };
-        // "Trait for Type" → take last word; plain "Type" → take first word
-        let name = if rest.contains(" for ") {
-            rest.split(" for ").nth(1).and_then(|s| ident_at_start(s))?
-        } else {
-            ident_at_start(rest)?
-        };
-        return Some((SymbolKind::Impl, name));
-    }
-    // pub const / const
-    if let Some(rest) = strip_prefix_variants(line, &["pub const ", "const "]) {
-        let name = ident_at_start(rest)?;
-        return Some((SymbolKind::Constant, name));
-    }
-    None
-}
-
-fn strip_rust_fn(line: &str) -> Option<&str> {
-    let prefixes = [
-        "pub async fn ", "pub(crate) async fn ", "async fn ",
-        "pub fn ", "pub(crate) fn ", "fn ",
-    ];
-    strip_prefix_variants(line, &prefixes)
-}
-
-// ── TypeScript / JavaScript ────────────────────────────────────────────────────
-
-fn extract_ts(line: &str) -> Option<(SymbolKind, String)> {
-    // export async function / async function / function
-    if let Some(rest) = strip_prefix_variants(line, &[
-        "export async function ", "export function ",
-        "async function ", "function ",
-    ]) {
-        let name = ident_at_start(rest)?;
-        return Some((SymbolKind::Function, name));
-    }
-    // export default function
-    if let Some(rest) = line.strip_prefix("export default function ") {
-        let name = ident_at_start(rest).unwrap_or_else(|| "default".to_string());
-        return Some((SymbolKind::Function, name));
-    }
-    // export class / class
-    if let Some(rest) = strip_prefix_variants(line, &["export class ", "export abstract class ", "class "]) {
-        let name = ident_at_start(rest)?;
-        return Some((SymbolKind::Class, name));
-    }
-    // export interface / interface
-    if let Some(rest) = strip_prefix_variants(line, &["export interface ", "interface "]) {
-        let name = ident_at_start(rest)?;
-        return Some((SymbolKind::Struct, name)); // treat as struct-like
-    }
-    // export type
-    if let Some(rest) = strip_prefix_variants(line, &["export type "]) {
-        let name = ident_at_start(rest)?;
-        return Some((SymbolKind::Other, name));
-    }
-    // const/let/var arrow functions: `export const foo = (`  or `const foo = async (`
-    if let Some(rest) = strip_prefix_variants(line, &[
-        "export const ", "export let ", "const ", "let ",
-    ]) {
-        let name = ident_at_start(rest)?;
-        // Only capture if it looks like a function (has => or = async)
-        if line.contains("=>") || line.contains("= async") || line.contains("= function") {
-            return Some((SymbolKind::Function, name));
-        }
-    }
-    None
-}
-
-// ── Python ─────────────────────────────────────────────────────────────────────
-
-fn extract_python(line: &str) -> Option<(SymbolKind, String)> {
-    if let Some(rest) = strip_prefix_variants(line, &["async def ", "def "]) {
-        let name = ident_at_start(rest)?;
-        return Some((SymbolKind::Function, name));
-    }
-    if let Some(rest) = line.strip_prefix("class ") {
-        let name = ident_at_start(rest)?;
-        return Some((SymbolKind::Class, name));
-    }
-    None
-}
-
-// ── Go ────────────────────────────────────────────────────────────────────────
-
-fn extract_go(line: &str) -> Option<(SymbolKind, String)> {
-    if let Some(rest) = line.strip_prefix("func ") {
-        // func (r *Receiver) Method(...) — extract method name
-        if rest.starts_with('(') {
-            // Skip the receiver, get to the method name
-            let after_paren = rest.find(')')?.checked_add(2)?;
-            let name = ident_at_start(rest.get(after_paren..)?)?;
-            return Some((SymbolKind::Method, name));
-        }
-        let name = ident_at_start(rest)?;
-        return Some((SymbolKind::Function, name));
-    }
-    if let Some(rest) = strip_prefix_variants(line, &["type "]) {
-        let name = ident_at_start(rest)?;
-        return Some((SymbolKind::Struct, name));
-    }
-    None
-}
-
-// ── C/C++ ────────────────────────────────────────────────────────────────────
-
-fn extract_c(line: &str) -> Option<(SymbolKind, String)> {
-    if let Some(rest) = strip_prefix_variants(line, &["struct ", "typedef struct "]) {
-        let name = ident_at_start(rest)?;
-        return Some((SymbolKind::Struct, name));
-    }
-    // Simple heuristic: line ends with `)` or `) {` and has an identifier before `(`
-    // This catches `int foo(` style definitions without full C parsing
-    if line.contains('(') && !line.starts_with("//") && !line.starts_with(" ") {
-        if let Some(paren_pos) = line.find('(') {
-            let before = line[..paren_pos].trim();
-            let name = before.split_whitespace().last()?.to_string();
-            if is_ident(&name) {
-                return Some((SymbolKind::Function, name));
-            }
-        }
-    }
-    None
-}
-
-// ── Helpers ───────────────────────────────────────────────────────────────────
-
-fn strip_prefix_variants<'a>(s: &'a str, prefixes: &[&str]) -> Option<&'a str> {
-    for prefix in prefixes {
-        if let Some(rest) = s.strip_prefix(prefix) {
-            return Some(rest);
-        }
-    }
-    None
-}
-
-/// Extract an identifier at the start of a string (stops at whitespace, `(`, `<`, `:`, `{`)
-fn ident_at_start(s: &str) -> Option<String> {
-    let s = s.trim();
-    let end = s
-        .find(|c: char| !c.is_alphanumeric() && c != '_')
-        .unwrap_or(s.len());
-    if end == 0 {
-        return None;
-    }
-    let name = &s[..end];
-    if is_ident(name) { Some(name.to_string()) } else { None }
-}
-
 fn is_ident(s: &str) -> bool {
     !s.is_empty()
         && s.chars().next().map(|c| c.is_alphabetic() || c == '_').unwrap_or(false)
         && s.chars().all(|c| c.is_alphanumeric() || c == '_')
 }
 
-/// Fill `end_line` for every symbol. Must be called after symbols are sorted by file+line.
-/// End = next symbol's start line - 1 within the same file, or the file's total line count.
-pub fn compute_end_lines(symbols: &mut Vec<Symbol>, file_lines: &HashMap<String, usize>) {
-    let n = symbols.len();
-    for i in 0..n {
-        let file_total = *file_lines.get(&symbols[i].file).unwrap_or(&symbols[i].line);
-        let end = if i + 1 < n && symbols[i + 1].file == symbols[i].file {
-            symbols[i + 1].line.saturating_sub(1)
-        } else {
-            file_total
-        };
-        symbols[i].end_line = end.max(symbols[i].line);
-    }
-}
-
 // ── Tests ─────────────────────────────────────────────────────────────────────
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn symbols(src: &str, file: &str) -> Vec<Symbol> {
+        let mut out = Vec::new();
+        extract_symbols(src, file, &mut out);
+        out
+    }
+
+    fn find<'a>(syms: &'a [Symbol], name: &str) -> &'a Symbol {
+        syms.iter().find(|s| s.name == name).unwrap_or_else(|| panic!("{name} not indexed: {syms:#?}"))
+    }
+
     #[test]
     fn test_rust_symbols() {
         let cases = vec![
-            ("pub fn validate_token(", SymbolKind::Function, "validate_token"),
-            ("pub async fn handle_request(", SymbolKind::Function, "handle_request"),
-            ("fn internal(", SymbolKind::Function, "internal"),
-            ("pub struct AuthError {", SymbolKind::Struct, "AuthError"),
-            ("pub enum Status {", SymbolKind::Enum, "Status"),
-            ("pub trait Authenticate {", SymbolKind::Trait, "Authenticate"),
-            ("impl AuthService {", SymbolKind::Impl, "AuthService"),
-            ("pub const MAX_RETRIES:", SymbolKind::Constant, "MAX_RETRIES"),
+            ("pub fn validate_token() {}", SymbolKind::Function, "validate_token"),
+            ("pub async fn handle_request() {}", SymbolKind::Function, "handle_request"),
+            ("fn internal() {}", SymbolKind::Function, "internal"),
+            ("pub struct AuthError {}", SymbolKind::Struct, "AuthError"),
+            ("pub enum Status {}", SymbolKind::Enum, "Status"),
+            ("pub trait Authenticate {}", SymbolKind::Trait, "Authenticate"),
+            ("impl AuthService {}", SymbolKind::Impl, "AuthService"),
+            ("pub const MAX_RETRIES: u32 = 3;", SymbolKind::Constant, "MAX_RETRIES"),
         ];
         for (line, expected_kind, expected_name) in cases {
-            let result = extract_rust(line);
-            assert!(result.is_some(), "Failed to extract from: {line}");
-            let (kind, name) = result.unwrap();
-            assert_eq!(kind, expected_kind, "Wrong kind for: {line}");
-            assert_eq!(name, expected_name, "Wrong name for: {line}");
+            let result = symbols(line, "src/lib.rs");
+            assert_eq!(result.len(), 1, "Failed to extract from: {line}");
+            assert_eq!(result[0].kind, expected_kind, "Wrong kind for: {line}");
+            assert_eq!(result[0].name, expected_name, "Wrong name for: {line}");
         }
     }
 
     #[test]
     fn test_ts_symbols() {
         let cases = vec![
-            ("export function processUser(", SymbolKind::Function, "processUser"),
-            ("export async function fetchData(", SymbolKind::Function, "fetchData"),
-            ("export class UserService {", SymbolKind::Class, "UserService"),
-            ("export interface UserProfile {", SymbolKind::Struct, "UserProfile"),
+            ("export function processUser() {}", SymbolKind::Function, "processUser"),
+            ("export async function fetchData() {}", SymbolKind::Function, "fetchData"),
+            ("export class UserService {}", SymbolKind::Class, "UserService"),
+            ("export interface UserProfile {}", SymbolKind::Struct, "UserProfile"),
+            ("export const handler = async (req) => req;", SymbolKind::Function, "handler"),
         ];
         for (line, expected_kind, expected_name) in cases {
-            let result = extract_ts(line);
-            assert!(result.is_some(), "Failed to extract from: {line}");
-            let (kind, name) = result.unwrap();
-            assert_eq!(kind, expected_kind, "Wrong kind for: {line}");
-            assert_eq!(name, expected_name, "Wrong name for: {line}");
+            let result = symbols(line, "web/app.ts");
+            assert_eq!(result.len(), 1, "Failed to extract from: {line}");
+            assert_eq!(result[0].kind, expected_kind, "Wrong kind for: {line}");
+            assert_eq!(result[0].name, expected_name, "Wrong name for: {line}");
         }
     }
 
     #[test]
     fn test_python_symbols() {
-        assert_eq!(
-            extract_python("def process_request("),
-            Some((SymbolKind::Function, "process_request".to_string()))
-        );
-        assert_eq!(
-            extract_python("async def fetch_data("),
-            Some((SymbolKind::Function, "fetch_data".to_string()))
-        );
-        assert_eq!(
-            extract_python("class UserService:"),
-            Some((SymbolKind::Class, "UserService".to_string()))
+        let syms = symbols(
+            "def process_request():\n    pass\nasync def fetch_data():\n    pass\nclass UserService:\n    pass\n",
+            "svc/app.py",
         );
+        let got: Vec<(SymbolKind, &str)> = syms.iter().map(|s| (s.kind.clone(), s.name.as_str())).collect();
+        assert_eq!(got, vec![
+            (SymbolKind::Function, "process_request"),
+            (SymbolKind::Function, "fetch_data"),
+            (SymbolKind::Class, "UserService"),
+        ]);
     }
 
     #[test]
@@ -666,11 +1211,298 @@ fn outer() {
     }
 
     #[test]
-    fn test_ident_at_start() {
-        assert_eq!(ident_at_start("foo(bar)"), Some("foo".to_string()));
-        assert_eq!(ident_at_start("MyStruct {"), Some("MyStruct".to_string()));
-        assert_eq!(ident_at_start("  leading"), Some("leading".to_string()));
-        assert_eq!(ident_at_start("(not_ident"), None);
-        assert_eq!(ident_at_start(""), None);
+    fn test_rust_spans_parents_and_docs() {
+        let src = "\
+/// Reads a file.
+///
+/// Longer explanation.
+#[derive(Debug)]
+pub struct ReadFile;
+
+impl Tool for ReadFile {
+\tfn execute(&self, args: &Value) -> Result<String> {
+\t\tOk(String::new())
+\t}
+}
+
+pub mod outer {
+    pub(crate) mod inner {
+        pub fn deep() {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    struct Fixture;
+    impl Fixture {
+        fn build() -> Self { Fixture }
+    }
+}
+";
+        let syms = symbols(src, "src/tools/read.rs");
+
+        let read = find(&syms, "ReadFile");
+        assert_eq!(read.kind, SymbolKind::Struct);
+        assert_eq!((read.line, read.end_line), (5, 5));
+        assert_eq!(&src[read.start_byte..read.end_byte], "pub struct ReadFile;");
+        assert_eq!(read.doc.as_deref(), Some("Reads a file."), "doc above the attribute");
+        assert_eq!(read.visibility, Visibility::Public);
+
+        let execute = find(&syms, "execute");
+        assert_eq!(execute.parent.as_deref(), Some("impl Tool for ReadFile"), "tab-indented method");
+        assert_eq!((execute.line, execute.end_line), (8, 10));
+        assert_eq!(execute.visibility, Visibility::Public, "trait impl methods are as visible as the trait");
+        assert_eq!(execute.signature.as_deref(), Some("(&self, args: &Value) -> Result<String>"));
+
+        let deep = find(&syms, "deep");
+        assert_eq!(deep.parent.as_deref(), Some("mod inner"));
+        assert_eq!(deep.line, 15);
+
+        let build = find(&syms, "build");
+        assert_eq!(build.parent.as_deref(), Some("impl Fixture"), "impl inside mod tests");
+        assert_eq!(build.visibility, Visibility::Private);
+        assert_eq!(find(&syms, "Fixture").parent.as_deref(), Some("mod tests"));
+    }
+
+    #[test]
+    fn test_owner_keys_and_implements() {
+        let src = "pub trait Tool { fn run(&self); }\nstruct Read;\nimpl<'a> crate::tools::Tool for &'a Read { fn run(&self) {} }\nmod tests { fn helper() {} }\n";
+        let syms = symbols(src, "src/tools/read.rs");
+        let imp = syms.iter().find(|s| s.kind == SymbolKind::Impl).unwrap();
+        assert_eq!((imp.name.as_str(), imp.implements.as_slice()), ("Read", &["Tool".to_string()][..]));
+
+        let runs: Vec<String> = syms.iter().filter(|s| s.name == "run").map(Symbol::key).collect();
+        assert_eq!(runs, vec!["src/tools/read.rs::Tool::run", "src/tools/read.rs::Read::run"]);
+        let helper = find(&syms, "helper");
+        assert_eq!((helper.owner(), helper.qualified_name()), (None, "helper".to_string()), "modules are not owners");
+
+        let ts = symbols("class A extends B implements C, D<E> { go() {} }\n", "web/a.ts");
+        assert_eq!(find(&ts, "A").implements, vec!["C", "D"]);
+        assert_eq!(find(&ts, "go").key(), "web/a.ts::A::go");
+    }
+
+    #[test]
+    fn test_rust_end_line_is_the_closing_brace() {
+        // The old scanner stretched `first` down to the line before `second`
+        let src = "fn first() {\n    work();\n}\n\n// trailing notes\n\nfn second() {}\n";
+        let syms = symbols(src, "src/a.rs");
+        assert_eq!(find(&syms, "first").end_line, 3);
+        assert_eq!(find(&syms, "second").doc, None, "plain comments are not docs");
+    }
+
+    #[test]
+    fn test_ts_class_members() {
+        let src = "\
+/** Manages users. */
+export class UserService {
+  private cache = new Map();
+  /** Load one user. */
+  async load(id: string): Promise<User> { return this.cache.get(id); }
+  protected reset() {}
+  #secret() {}
+  onChange = (e: Event) => {};
+}
+function helper() {}
+";
+        let syms = symbols(src, "web/users.ts");
+        let service = find(&syms, "UserService");
+        assert_eq!(service.doc.as_deref(), Some("Manages users."));
+        assert_eq!(service.visibility, Visibility::Public);
+        assert_eq!((service.line, service.end_line), (2, 9));
+
+        let load = find(&syms, "load");
+        assert_eq!(load.kind, SymbolKind::Method);
+        assert_eq!(load.parent.as_deref(), Some("class UserService"));
+        assert_eq!(load.doc.as_deref(), Some("Load one user."));
+        assert_eq!(load.signature.as_deref(), Some("(id: string): Promise<User>"));
+        assert_eq!(find(&syms, "reset").visibility, Visibility::Restricted);
+        assert_eq!(find(&syms, "#secret").visibility, Visibility::Private);
+        assert_eq!(find(&syms, "onChange").kind, SymbolKind::Method);
+        assert!(!syms.iter().any(|s| s.name == "cache"), "plain fields are not indexed");
+        assert_eq!(find(&syms, "helper").visibility, Visibility::Private, "not exported");
+    }
+
+    #[test]
+    fn test_python_class_methods_and_docstrings() {
+        let src = "\
+class UserService:
+    \"\"\"Loads users.
+
+    Details.
+    \"\"\"
+
+    def __init__(self, db):
+        self.db = db
+
+    @cached
+    def load(self, user_id: int) -> User:
+        '''Fetch one user.'''
+        return self.db.get(user_id)
+
+    def _evict(self):
+        pass
+";
+        let syms = symbols(src, "svc/users.py");
+        let service = find(&syms, "UserService");
+        assert_eq!(service.doc.as_deref(), Some("Loads users."));
+        assert_eq!(service.end_line, 16);
+
+        let load = find(&syms, "load");
+        assert_eq!(load.kind, SymbolKind::Method);
+        assert_eq!(load.parent.as_deref(), Some("class UserService"));
+        assert_eq!(load.line, 11, "span starts at `def`, not the decorator");
+        assert_eq!(load.doc.as_deref(), Some("Fetch one user."));
+        assert_eq!(load.signature.as_deref(), Some("(self, user_id: int) -> User"));
+        assert_eq!(find(&syms, "__init__").visibility, Visibility::Public);
+        assert_eq!(find(&syms, "_evict").visibility, Visibility::Private);
+    }
+
+    #[test]
+    fn test_go_symbols() {
+        let src = "\
+package api
+
+// Server handles requests.
+type Server struct {
+\tdb *DB
+}
+
+type Store interface {
+\tGet(id string) string
+}
+
+func (s *Server) Handle(w Writer) error {
+\treturn nil
+}
+
+func newServer() *Server { return nil }
+";
+        let syms = symbols(src, "api/server.go");
+        let server = find(&syms, "Server");
+        assert_eq!(server.kind, SymbolKind::Struct);
+        assert_eq!(server.doc.as_deref(), Some("Server handles requests."));
+        assert_eq!(find(&syms, "Store").kind, SymbolKind::Trait);
+
+        let handle = find(&syms, "Handle");
+        assert_eq!(handle.kind, SymbolKind::Method);
+        assert_eq!(handle.parent.as_deref(), Some("type Server"));
+        assert_eq!(handle.signature.as_deref(), Some("(w Writer) error"));
+        assert_eq!(find(&syms, "newServer").visibility, Visibility::Private);
+    }
+
+    #[test]
+    fn test_c_and_cpp_symbols() {
+        let header = "\
+#ifndef UTIL_H
+#define UTIL_H
+/** Parse a config file. */
+int parse_config(const char *path);
+typedef struct {
+    int width;
+} Size;
+static int clamp(int v) { return v; }
+#endif
+";
+        let syms = symbols(header, "include/util.h");
+        let parse = find(&syms, "parse_config");
+        assert_eq!(parse.kind, SymbolKind::Function);
+        assert_eq!(parse.doc.as_deref(), Some("Parse a config file."));
+        assert_eq!(parse.signature.as_deref(), Some("(const char *path) -> int"));
+        assert_eq!(find(&syms, "Size").kind, SymbolKind::Struct);
+        assert_eq!(find(&syms, "clamp").visibility, Visibility::Private);
+
+        let src = "\
+namespace engine {
+class World {
+public:
+    void step(float dt);
+private:
+    void integrate() {}
+};
+}
+";
+        let syms = symbols(src, "src/world.cpp");
+        let world = find(&syms, "World");
+        assert_eq!(world.kind, SymbolKind::Class);
+        assert_eq!(world.parent.as_deref(), Some("namespace engine"));
+        let step = find(&syms, "step");
+        assert_eq!((step.kind.clone(), step.parent.as_deref()), (SymbolKind::Method, Some("class World")));
+        assert_eq!(step.visibility, Visibility::Public);
+        assert_eq!(find(&syms, "integrate").visibility, Visibility::Private);
+    }
+
+    #[test]
+    fn test_index_scope_honours_ignore_files_and_globs() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let root = tmp.path();
+        for (rel, content) in [
+            ("src/main.rs", ""),
+            ("src/gen/schema.rs", ""),
+            ("legacy/old.py", ""),
+            ("web/app.ts", ""),
+            ("web/api.gen.ts", ""),
+            ("target/debug/build.rs", ""),
+            (".hidden/x.rs", ""),
+            ("README.md", ""),
+            (".gitignore", "src/gen/\n"),
+            (".parecodeignore", "legacy/\n"),
+            ("web/.gitignore", "*.gen.ts\n"),
+        ] {
+            let path = root.join(rel);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+
+        // No `.git` here — ignore files apply anyway
+        assert_eq!(IndexScope::default().files(root), vec!["src/main.rs", "web/app.ts"]);
+
+        let config = IndexConfig { include: vec!["web/**".into()], exclude: vec![], ..IndexConfig::default() };
+        assert_eq!(IndexScope::new(&config).files(root), vec!["web/app.ts"]);
+        let config = IndexConfig { include: vec![], exclude: vec!["*.ts".into(), "src/".into()], ..IndexConfig::default() };
+        assert_eq!(IndexScope::new(&config).files(root), vec!["target/debug/build.rs"], "replaces the defaults");
+
+        let scope = IndexScope::default();
+        assert!(scope.accepts("a/b/c.go"));
+        assert!(!scope.accepts("a/node_modules/c.js"), "default excludes match at any depth");
+        assert!(!scope.accepts("a/.venv/c.py"));
+        assert!(!scope.accepts("a/notes.txt"));
+        assert!(!scope.accepts_dir("build"));
+        let dirs = scope.dirs(root, root);
+        assert!(dirs.contains(&root.join("web")) && !dirs.contains(&root.join("target")));
+    }
+
+    #[test]
+    fn test_index_scope_extra_roots() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        for rel in ["app/src/main.rs", "app/target/x.rs", "shared/src/lib.rs", "shared/target/y.rs", "shared/.cache/z.rs"] {
+            let path = tmp.path().join(rel);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+        let root = tmp.path().join("app");
+        let config = IndexConfig { roots: vec!["../shared/".into()], ..IndexConfig::default() };
+        let scope = IndexScope::new(&config);
+        assert_eq!(scope.files(&root), vec!["../shared/src/lib.rs", "src/main.rs"]);
+        assert!(scope.accepts("../shared/src/lib.rs"));
+        assert!(!scope.accepts("../shared/target/y.rs"), "excludes apply inside the extra root");
+        assert!(!scope.accepts("../other/src/lib.rs"), "paths outside every root are rejected");
+        let dirs = scope.dirs(&root, &root.join("../shared"));
+        assert!(dirs.contains(&root.join("../shared/src")) && !dirs.contains(&root.join("../shared/target")));
+
+        let config = IndexConfig { include: vec!["src/**".into()], roots: vec!["../shared".into()], ..IndexConfig::default() };
+        assert_eq!(IndexScope::new(&config).files(&root).len(), 2, "include globs match below each root");
+    }
+
+    #[test]
+    fn test_unknown_extension_yields_nothing() {
+        assert!(symbols("public class A {}", "src/A.java").is_empty());
+    }
+
+    #[test]
+    fn test_truncate_at_word_respects_char_boundaries() {
+        let s = "Runs the agent — with a long tail of words";
+        let cut = truncate_at_word(s, 16);
+        assert!(cut.ends_with('…'), "{cut}");
+        assert!(cut.len() <= 16 + '…'.len_utf8());
     }
 }
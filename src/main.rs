@@ -2,26 +2,35 @@ mod agent;
 mod budget;
 mod cache;
 mod callgraph;
+mod cochange;
 mod flowpaths;
 mod client;
 mod config;
 mod context_weights;
+mod embeddings;
 mod git;
 mod history;
 mod hooks;
 mod index;
 mod init;
 mod mcp;
+mod mcp_server;
 mod narrative;
+mod output;
+mod permissions;
 mod pie;
 mod plan;
+mod pricing;
 mod sessions;
 mod setup;
 mod task_memory;
+mod testmap;
 mod telemetry;
 mod tools;
 mod tui;
 mod ui;
+mod watcher;
+mod workspace;
 
 use anyhow::Result;
 use clap::{CommandFactory, Parser};
@@ -32,29 +41,33 @@ use config::{ConfigFile, ResolvedConfig};
     name = "parecode",
     about = "A hyper-efficient coding agent for local and cloud LLMs",
     long_about = None,
+    args_conflicts_with_subcommands = true,
 )]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Task to run directly (omit to enter interactive TUI mode)
     task: Option<String>,
 
     /// Profile to use from config file
-    #[arg(short, long, env = "PARECODE_PROFILE")]
+    #[arg(short, long, global = true, env = "PARECODE_PROFILE")]
     profile: Option<String>,
 
     /// Override endpoint URL
-    #[arg(long, env = "PARECODE_ENDPOINT")]
+    #[arg(long, global = true, env = "PARECODE_ENDPOINT")]
     endpoint: Option<String>,
 
     /// Override model name
-    #[arg(short, long, env = "PARECODE_MODEL")]
+    #[arg(short, long, global = true, env = "PARECODE_MODEL")]
     model: Option<String>,
 
     /// Override API key
-    #[arg(long, env = "PARECODE_API_KEY")]
+    #[arg(long, global = true, env = "PARECODE_API_KEY")]
     api_key: Option<String>,
 
     /// Show tool calls without executing them
-    #[arg(long)]
+    #[arg(long, global = true)]
     dry_run: bool,
 
     /// Quick mode — single API call, no multi-turn loop, minimal context
@@ -62,9 +75,13 @@ struct Args {
     quick: bool,
 
     /// Show extra token / compression detail
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     verbose: bool,
 
+    /// Output format for a direct task or plan command: human text, one JSON document, or NDJSON records
+    #[arg(long, global = true, value_enum, default_value_t = output::OutputFormat::Text)]
+    output: output::OutputFormat,
+
     /// Show timestamps on messages
     #[arg(long)]
     timestamps: bool,
@@ -86,6 +103,38 @@ struct Args {
     update: bool,
 }
 
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Generate a plan for a task and save it as JSON for review
+    Plan {
+        /// What the plan should accomplish
+        task: String,
+
+        /// Where to write the plan (default: .parecode/plans/<timestamp>-plan.json)
+        #[arg(long, value_name = "FILE")]
+        out: Option<std::path::PathBuf>,
+    },
+    /// Execute a saved plan, writing step status back to the file
+    RunPlan {
+        /// Plan JSON written by `parecode plan` or the TUI
+        file: std::path::PathBuf,
+
+        /// Step to start from (1-based); earlier steps are left as they are
+        #[arg(long, default_value_t = 1, value_name = "N")]
+        from: usize,
+
+        /// Approve pending steps instead of refusing to run them
+        #[arg(long)]
+        auto_approve: bool,
+    },
+    /// Serve parecode's tools to other MCP clients over stdio
+    McpServe {
+        /// Project to serve (default: the current directory)
+        #[arg(long, value_name = "DIR")]
+        root: Option<std::path::PathBuf>,
+    },
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
@@ -111,9 +160,11 @@ async fn main() -> Result<()> {
     // ── First-run wizard ──────────────────────────────────────────────────────
     // If no config file exists and no CLI/env overrides fully configure us,
     // run the interactive setup wizard before loading config.
+    // (Not for mcp-serve: stdin/stdout belong to the MCP client.)
     if !config::config_path().exists()
         && args.endpoint.is_none()
         && args.model.is_none()
+        && !matches!(args.command, Some(Command::McpServe { .. }))
     {
         match setup::run_setup_wizard().await {
             Ok(true) => {
@@ -150,12 +201,35 @@ async fn main() -> Result<()> {
         args.api_key.as_deref(),
     );
 
+    // ── MCP server ────────────────────────────────────────────────────────────
+    if let Some(Command::McpServe { root }) = args.command {
+        return mcp_server::run(root, &resolved).await;
+    }
+
+    // ── Headless plan commands ────────────────────────────────────────────────
+    if let Some(command) = args.command {
+        let status = match command {
+            Command::Plan { task, out } => run_plan_generate(task, out, resolved, args.output).await,
+            Command::RunPlan { file, from, auto_approve } => {
+                run_plan_file(file, from, auto_approve, resolved, args.verbose, args.dry_run, args.output).await
+            }
+            Command::McpServe { .. } => unreachable!("handled above"),
+        };
+        if status != output::RunStatus::Success {
+            std::process::exit(status.exit_code());
+        }
+        return Ok(());
+    }
+
     // ── Single-shot mode (non-TUI) ────────────────────────────────────────────
     if let Some(task) = args.task {
-        if args.quick {
-            run_single_shot_quick(task, resolved, args.verbose).await?;
+        let status = if args.quick {
+            run_single_shot_quick(task, resolved, args.verbose, args.output).await
         } else {
-            run_single_shot(task, file, resolved, args.verbose, args.dry_run).await?;
+            run_single_shot(task, file, resolved, args.verbose, args.dry_run, args.output).await
+        };
+        if status != output::RunStatus::Success {
+            std::process::exit(status.exit_code());
         }
         return Ok(());
     }
@@ -175,19 +249,20 @@ async fn run_single_shot(
     resolved: ResolvedConfig,
     verbose: bool,
     dry_run: bool,
-) -> Result<()> {
+    format: output::OutputFormat,
+) -> output::RunStatus {
     use tokio::sync::mpsc;
 
-    println!();
-    println!("  ▲ parecode  {}  ·  {}", resolved.profile_name, resolved.model);
-    println!();
-    println!("  task: {task}");
-    println!();
-
-    let mut client = client::Client::new(resolved.endpoint.clone(), resolved.model.clone());
-    if let Some(key) = &resolved.api_key {
-        client.set_api_key(key.clone());
+    let text = format == output::OutputFormat::Text;
+    if text {
+        println!();
+        println!("  ▲ parecode  {}  ·  {}", resolved.profile_name, resolved.model);
+        println!();
+        println!("  task: {task}");
+        println!();
     }
+
+    let client = client::Client::from_resolved(&resolved);
     let mcp = mcp::McpClient::new(&resolved.mcp_servers).await;
     let hook_config = if !resolved.active_hook_config.is_empty() {
         resolved.active_hook_config.clone()
@@ -198,10 +273,10 @@ async fn run_single_shot(
     // Build or load PIE graph — always, warm or cold.
     let project_graph = {
         let graph_path = std::path::Path::new(".parecode/project.graph");
-        if !graph_path.exists() {
+        if !graph_path.exists() && text {
             println!("  ◈ indexing project…");
         }
-        let (graph, _) = pie::ProjectGraph::load_or_build(std::path::Path::new("."), 500);
+        let (graph, _) = pie::ProjectGraph::load_or_build(std::path::Path::new("."), &index::IndexScope::new(&resolved.index));
         std::sync::Arc::new(graph)
     };
 
@@ -222,7 +297,16 @@ async fn run_single_shot(
         project_narrative: None,
         flow_paths: crate::flowpaths::FlowPathIndex::load(std::path::Path::new("."))
             .map(std::sync::Arc::new),
+        semantic: resolved.embeddings.as_ref()
+            .map(|e| std::sync::Arc::new(embeddings::SemanticIndex::new(e, std::path::Path::new(".")))),
         attached_symbols: vec![],
+        attached_resources: vec![],
+        fallback_clients: client::Client::fallbacks_for(&resolved),
+        spend_limits: resolved.spend_limits,
+        spend: std::sync::Arc::new(std::sync::Mutex::new(budget::SpendGuard::load())),
+        permissions: std::sync::Arc::new(std::sync::Mutex::new(permissions::Policy::new(resolved.permissions.clone()))),
+        live_graph: std::sync::Arc::default(),
+        workdir: None,
     };
 
     let (tx, mut rx) = mpsc::unbounded_channel::<tui::UiEvent>();
@@ -234,15 +318,36 @@ async fn run_single_shot(
         ).await
     });
 
-    // Print events to stdout, tracking accumulated token usage
+    report_events(&mut rx, agent_handle, format).await
+}
+
+/// Print or record events until the agent finishes, then close the output and
+/// return the run's status (which becomes the exit code).
+async fn report_events(
+    rx: &mut tokio::sync::mpsc::UnboundedReceiver<tui::UiEvent>,
+    agent_handle: tokio::task::JoinHandle<Result<()>>,
+    format: output::OutputFormat,
+) -> output::RunStatus {
+    let mut run = output::HeadlessRun::new(format);
+    // Accumulated token usage, shown if the run fails part-way
     let mut accum_input: u32 = 0;
     let mut accum_output: u32 = 0;
     while let Some(ev) = rx.recv().await {
-        print_event_plain(&ev, &mut accum_input, &mut accum_output);
+        if format == output::OutputFormat::Text {
+            print_event_plain(&ev, &mut accum_input, &mut accum_output);
+        }
+        run.observe(&ev);
     }
 
-    agent_handle.await??;
-    Ok(())
+    let result = agent_handle.await.map_err(anyhow::Error::from).and_then(|r| r);
+    if let (output::OutputFormat::Text, Err(e)) = (format, &result) {
+        if accum_input > 0 || accum_output > 0 {
+            eprintln!("\n  ✗ {e:#}  (partial: in {accum_input} out {accum_output})");
+        } else {
+            eprintln!("\n  ✗ {e:#}");
+        }
+    }
+    run.finish(&result)
 }
 
 fn print_event_plain(ev: &tui::UiEvent, accum_input: &mut u32, accum_output: &mut u32) {
@@ -272,13 +377,33 @@ fn print_event_plain(ev: &tui::UiEvent, accum_input: &mut u32, accum_output: &mu
         UiEvent::ToolBudgetHit { limit } => {
             println!("\n  ■ tool call limit ({limit}) reached");
         }
-        UiEvent::AgentDone { input_tokens, output_tokens, tool_calls, compressed_count, .. } => {
+        UiEvent::ApiRetry { attempt, max_retries, delay_ms, reason } => {
+            let reason = reason.lines().next().unwrap_or("");
+            println!("  ↻ retry {attempt}/{max_retries} in {:.1}s — {reason}", *delay_ms as f64 / 1000.0);
+        }
+        UiEvent::ModelFailover { from, to, reason } => {
+            let reason = reason.lines().next().unwrap_or("");
+            println!("  ⇄ failing over {from} → {to} — {reason}");
+        }
+        UiEvent::AgentDone { input_tokens, output_tokens, cache_read_tokens, cost_usd, tool_calls, compressed_count, .. } => {
             let compressed = if *compressed_count > 0 {
                 format!("  {compressed_count} outputs truncated")
             } else {
                 String::new()
             };
-            println!("\n\n  ✓ in {input_tokens} out {output_tokens}  tools {tool_calls}{compressed}");
+            let cached = if *cache_read_tokens > 0 {
+                format!(" ({cache_read_tokens} cached)")
+            } else {
+                String::new()
+            };
+            let cost = cost_usd
+                .map(|c| format!("  {}", crate::pricing::fmt_usd(c)))
+                .unwrap_or_default();
+            println!("\n\n  ✓ in {input_tokens}{cached} out {output_tokens}  tools {tool_calls}{compressed}{cost}");
+        }
+        // No one to confirm an extension in plain mode — dropping the reply aborts the task
+        UiEvent::SpendLimitHit { hit, .. } => {
+            println!("\n  ⏸ {hit}");
         }
         UiEvent::AgentError(e) => {
             if *accum_input > 0 || *accum_output > 0 {
@@ -298,49 +423,247 @@ fn print_event_plain(ev: &tui::UiEvent, accum_input: &mut u32, accum_output: &mu
                 println!("  ⚙ {event} {mark}: {}", output.lines().next().unwrap_or(""));
             }
         }
-        // Plan, Git, and AskUser lifecycle events only occur in TUI mode — ignore here
-        UiEvent::PlanReady(_)
-        | UiEvent::PlanGenerateFailed(_)
-        | UiEvent::PlanStepStart { .. }
-        | UiEvent::PlanStepDone { .. }
-        | UiEvent::PlanComplete { .. }
-        | UiEvent::PlanFailed { .. }
-        | UiEvent::GitChanges { .. }
-        | UiEvent::GitAutoCommit { .. }
-        | UiEvent::GitError(_)
-        | UiEvent::AskUser { .. }
-        | UiEvent::IndexReady { .. } => {}
+        // ── Plan lifecycle (`parecode plan` / `run-plan`) ─────────────────────
+        UiEvent::PlanReady(plan) => {
+            println!("\n  plan: {} step{}", plan.steps.len(), if plan.steps.len() == 1 { "" } else { "s" });
+            for (i, step) in plan.steps.iter().enumerate() {
+                println!("  {:>3}. {}", i + 1, step.description);
+                if !step.files.is_empty() {
+                    println!("       {}", step.files.join(", "));
+                }
+            }
+        }
+        UiEvent::PlanGenerateFailed(e) => {
+            println!("\n  ✗ plan generation failed: {e}");
+        }
+        UiEvent::PlanStepStart { index, total, desc } => {
+            println!("\n  ▶ step {}/{total}: {desc}", index + 1);
+        }
+        UiEvent::PlanStepDone { index, passed, error } => {
+            if *passed {
+                println!("\n  ✓ step {} complete", index + 1);
+            } else {
+                println!("\n  ✗ step {} failed: {}", index + 1, error.as_deref().unwrap_or("unknown error"));
+            }
+        }
+        UiEvent::PlanComplete { total } => {
+            println!("\n  ✓ plan complete — {total} step{} executed", if *total == 1 { "" } else { "s" });
+        }
+        UiEvent::PlanFailed { step, .. } => {
+            println!("  plan stopped at step {} — fix the issue, then run-plan --from {}", step + 1, step + 1);
+        }
+        UiEvent::PlanReplanned { step, plan } => {
+            println!("\n  ⟳ replanned from step {} — now {} steps", step + 1, plan.steps.len());
+        }
+        UiEvent::GitChanges { stat, files_changed, .. } => {
+            println!("  ± {files_changed} file{} changed", if *files_changed == 1 { "" } else { "s" });
+            for line in stat.lines() {
+                println!("    {line}");
+            }
+        }
+        UiEvent::GitAutoCommit { message } => {
+            println!("  ✓ committed: {message}");
+        }
+        UiEvent::GitError(e) => {
+            println!("  ⚠ git: {e}");
+        }
+        // Index and prompt events only occur in TUI mode; questions can only be
+        // answered there (dropping one refuses a tool waiting for permission)
+        UiEvent::AskUser { .. }
+        | UiEvent::IndexReady { .. }
+        | UiEvent::GraphUpdated { .. }
+        | UiEvent::McpPromptReady { .. } => {}
         UiEvent::SystemMsg(msg) => {
             println!("  {msg}");
         }
     }
 }
 
+// ── Headless plans: `parecode plan` / `parecode run-plan` ─────────────────────
+
+/// Generate a plan with the planner model and save it for review.
+/// The written path is reported as a system message.
+async fn run_plan_generate(
+    task: String,
+    out: Option<std::path::PathBuf>,
+    resolved: ResolvedConfig,
+    format: output::OutputFormat,
+) -> output::RunStatus {
+    use tokio::sync::mpsc;
+
+    // Use planner_model if configured, otherwise fall back to the regular model
+    let plan_model = resolved.planner_model.clone().unwrap_or_else(|| resolved.model.clone());
+    let text = format == output::OutputFormat::Text;
+    if text {
+        println!();
+        println!("  ▲ parecode plan  {}  ·  {plan_model}", resolved.profile_name);
+        println!();
+        println!("  task: {task}");
+        println!();
+    }
+
+    let mut client = client::Client::from_resolved(&resolved);
+    client.model = plan_model.clone();
+    client.pricing = resolved.planner_pricing;
+    let profile = resolved.profile_name.clone();
+    let project = std::env::current_dir()
+        .ok()
+        .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+        .unwrap_or_else(|| "project".to_string());
+
+    let root = std::path::Path::new(".");
+    if !root.join(".parecode/project.graph").exists() && text {
+        println!("  ◈ indexing project…");
+    }
+    let (graph, _) = pie::ProjectGraph::load_or_build(root, &index::IndexScope::new(&resolved.index));
+    let semantic = resolved.embeddings.as_ref().map(|e| embeddings::SemanticIndex::new(e, root));
+
+    let spend_limits = resolved.spend_limits;
+    let (tx, mut rx) = mpsc::unbounded_channel::<tui::UiEvent>();
+    let plan_handle = tokio::spawn(async move {
+        let spend = std::sync::Mutex::new(budget::SpendGuard::load());
+        let narrative = narrative::ProjectNarrative::load_or_generate_ref(&graph, &client, root).await;
+        let flow_paths = flowpaths::FlowPathIndex::load(root);
+        let tx_chunk = tx.clone();
+        let on_chunk = move |chunk: &str| {
+            let _ = tx_chunk.send(tui::UiEvent::ThinkingChunk(chunk.to_string()));
+        };
+        let context = plan::PlanContext {
+            narrative: Some(&narrative),
+            flow_paths: flow_paths.as_ref(),
+            semantic: semantic.as_ref(),
+        };
+        let (generated, metrics) = plan::generate_plan(
+            &task, &client, &project, &[], &[], &graph, context, &spend_limits, &spend, tx.clone(), on_chunk,
+        )
+        .await?;
+        // Planning spend is recorded as its own task so plan costs show up in stats.
+        telemetry::append_record(&plan::metrics_record(&metrics, &task, "", &project, &plan_model, &profile));
+
+        let path = match out {
+            Some(path) => {
+                plan::save_plan_to(&generated, &path)?;
+                path
+            }
+            None => plan::save_plan(&generated)?,
+        };
+        plan::write_plan_to_disk(&generated);
+        let _ = tx.send(tui::UiEvent::PlanReady(generated));
+        let _ = tx.send(tui::UiEvent::SystemMsg(format!(
+            "plan saved to {} — approve its steps, then: parecode run-plan {}",
+            path.display(),
+            path.display()
+        )));
+        Ok(())
+    });
+
+    report_events(&mut rx, plan_handle, format).await
+}
+
+/// Execute a saved plan file step by step. Step and plan status are written
+/// back to `file` as the run progresses, so a failed run can be resumed
+/// with `--from`.
+async fn run_plan_file(
+    file: std::path::PathBuf,
+    from: usize,
+    auto_approve: bool,
+    resolved: ResolvedConfig,
+    verbose: bool,
+    dry_run: bool,
+    format: output::OutputFormat,
+) -> output::RunStatus {
+    use tokio::sync::mpsc;
+
+    if format == output::OutputFormat::Text {
+        println!();
+        println!("  ▲ parecode run-plan  {}  ·  {}", resolved.profile_name, resolved.model);
+        println!();
+    }
+
+    let client = client::Client::from_resolved(&resolved);
+    let hook_config = if !resolved.active_hook_config.is_empty() {
+        resolved.active_hook_config.clone()
+    } else {
+        resolved.hooks.clone()
+    };
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<tui::UiEvent>();
+    let plan_handle = tokio::spawn(async move {
+        let mut active_plan = plan::load_plan(&file)?;
+        let Some(start) = from.checked_sub(1) else {
+            anyhow::bail!("--from is 1-based — the first step is 1");
+        };
+        plan::prepare_run(&mut active_plan, start, auto_approve)?;
+        let _ = tx.send(tui::UiEvent::SystemMsg(format!(
+            "plan: {} — {} steps, starting at step {from}",
+            active_plan.task,
+            active_plan.steps.len()
+        )));
+
+        let config = agent::AgentConfig {
+            verbose,
+            dry_run,
+            context_tokens: resolved.context_tokens,
+            _profile_name: resolved.profile_name.clone(),
+            _model: resolved.model.clone(),
+            _show_timestamps: false,
+            mcp: mcp::McpClient::new(&resolved.mcp_servers).await,
+            hooks: std::sync::Arc::new(hook_config),
+            hooks_enabled: !resolved.hooks_disabled,
+            auto_commit: resolved.auto_commit,
+            auto_commit_prefix: resolved.auto_commit_prefix.clone(),
+            git_context: resolved.git_context,
+            project_graph: None, // executor steps have pre-digested instructions — no graph needed
+            project_narrative: None,
+            flow_paths: None,
+            semantic: None,
+            attached_symbols: vec![],
+            attached_resources: vec![],
+            fallback_clients: client::Client::fallbacks_for(&resolved),
+            spend_limits: resolved.spend_limits,
+            spend: std::sync::Arc::new(std::sync::Mutex::new(budget::SpendGuard::load())),
+            permissions: std::sync::Arc::new(std::sync::Mutex::new(permissions::Policy::new(resolved.permissions.clone()))),
+            live_graph: std::sync::Arc::default(),
+            workdir: None,
+        };
+
+        // Nothing cancels a headless run between steps — Ctrl+C ends the process
+        let cancelled = std::sync::atomic::AtomicBool::new(false);
+        plan::run_plan(&mut active_plan, start, &client, &config, tx, &cancelled, |p| {
+            let _ = plan::save_plan_to(p, &file);
+        })
+        .await
+    });
+
+    report_events(&mut rx, plan_handle, format).await
+}
+
 // ── Quick single-shot (plain stdout, no TUI, no loop) ─────────────────────────
 
 async fn run_single_shot_quick(
     task: String,
     resolved: ResolvedConfig,
     verbose: bool,
-) -> Result<()> {
+    format: output::OutputFormat,
+) -> output::RunStatus {
     use tokio::sync::mpsc;
 
-    println!();
-    println!("  ⚡ parecode quick  {}  ·  {}", resolved.profile_name, resolved.model);
-    println!();
-
-    let mut client = client::Client::new(resolved.endpoint.clone(), resolved.model.clone());
-    if let Some(key) = &resolved.api_key {
-        client.set_api_key(key.clone());
+    let text = format == output::OutputFormat::Text;
+    if text {
+        println!();
+        println!("  ⚡ parecode quick  {}  ·  {}", resolved.profile_name, resolved.model);
+        println!();
     }
+
+    let client = client::Client::from_resolved(&resolved);
     let mcp = mcp::McpClient::new(&resolved.mcp_servers).await;
 
     let project_graph = {
         let graph_path = std::path::Path::new(".parecode/project.graph");
-        if !graph_path.exists() {
+        if !graph_path.exists() && text {
             println!("  ◈ indexing project…");
         }
-        let (graph, _) = pie::ProjectGraph::load_or_build(std::path::Path::new("."), 500);
+        let (graph, _) = pie::ProjectGraph::load_or_build(std::path::Path::new("."), &index::IndexScope::new(&resolved.index));
         std::sync::Arc::new(graph)
     };
 
@@ -360,7 +683,16 @@ async fn run_single_shot_quick(
         project_graph: Some(project_graph),
         project_narrative: None,
         flow_paths: None, // quick/headless mode: no path preloading
+        semantic: resolved.embeddings.as_ref()
+            .map(|e| std::sync::Arc::new(embeddings::SemanticIndex::new(e, std::path::Path::new(".")))),
         attached_symbols: vec![],
+        attached_resources: vec![],
+        fallback_clients: vec![], // quick mode: single call, retries only
+        spend_limits: resolved.spend_limits,
+        spend: std::sync::Arc::new(std::sync::Mutex::new(budget::SpendGuard::load())),
+        permissions: std::sync::Arc::new(std::sync::Mutex::new(permissions::Policy::new(resolved.permissions.clone()))),
+        live_graph: std::sync::Arc::default(),
+        workdir: None,
     };
 
     let (tx, mut rx) = mpsc::unbounded_channel::<tui::UiEvent>();
@@ -368,13 +700,7 @@ async fn run_single_shot_quick(
         agent::run_quick(&task, &client, &config, tx).await
     });
 
-    let mut accum_input: u32 = 0;
-    let mut accum_output: u32 = 0;
-    while let Some(ev) = rx.recv().await {
-        print_event_plain(&ev, &mut accum_input, &mut accum_output);
-    }
-    agent_handle.await??;
-    Ok(())
+    report_events(&mut rx, agent_handle, format).await
 }
 
 // ── Profiles listing (non-TUI) ────────────────────────────────────────────────
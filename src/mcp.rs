@@ -1,18 +1,42 @@
 /// MCP (Model Context Protocol) client.
 ///
-/// Spawns MCP server processes, performs the JSON-RPC 2.0 handshake,
-/// discovers tools, and dispatches calls. Each server runs as a child
-/// process communicating over stdin/stdout.
+/// Connects to MCP servers, performs the JSON-RPC 2.0 handshake,
+/// discovers tools, resources and prompts, and dispatches calls. A server is either a child
+/// process speaking over stdin/stdout, or an HTTP service reached by
+/// Streamable HTTP or the legacy HTTP+SSE transport.
+///
+/// Servers are independent of each other. Requests to one server are
+/// multiplexed by id, time out after `timeout_secs` (the server is sent
+/// `notifications/cancelled`, as it is when the caller gives up), and a
+/// dropped connection is restarted with backoff. `list_changed`
+/// notifications re-list the server's tools, resources and prompts.
 use anyhow::{anyhow, Context, Result};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::process::Stdio;
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+use std::time::{Duration, Instant};
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::process::{Child, ChildStdin, ChildStdout};
-use tokio::sync::Mutex;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::config::{McpServerConfig, McpTransport};
+
+/// Pages followed for one `*/list` request.
+const MAX_LIST_PAGES: usize = 20;
+/// Restart attempts after a connection drops before the server is given up on.
+const MAX_RESTARTS: u32 = 5;
+/// Wait before the first restart attempt; doubles with each further one.
+const RESTART_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_RESTART_BACKOFF: Duration = Duration::from_secs(30);
+/// A connection that stayed up this long starts the next restart from scratch.
+const STABLE_UPTIME: Duration = Duration::from_secs(60);
+/// Newest protocol revision we speak, requested over Streamable HTTP.
+const PROTOCOL_VERSION: &str = "2025-06-18";
+/// Requested over stdio and the legacy HTTP+SSE transport.
+const LEGACY_PROTOCOL_VERSION: &str = "2024-11-05";
 
 // ── JSON-RPC types ─────────────────────────────────────────────────────────────
 
@@ -27,7 +51,6 @@ struct Request {
 
 #[derive(Debug, Deserialize)]
 struct Response {
-    #[allow(dead_code)]
     id: Option<Value>,
     result: Option<Value>,
     error: Option<RpcError>,
@@ -54,74 +77,536 @@ pub struct McpTool {
     pub _server_name: String,
 }
 
-// ── Per-server state ───────────────────────────────────────────────────────────
+// ── MCP resources and prompts (resources/list, prompts/list) ──────────────────
 
-struct ServerConn {
-    name: String,
-    stdin: ChildStdin,
-    stdout: BufReader<ChildStdout>,
-    #[allow(dead_code)]
-    child: Child,
-    tools: Vec<McpTool>,
+/// Readable context a server exposes — a document, a record, a log.
+#[derive(Debug, Clone)]
+pub struct McpResource {
+    pub uri: String,
+    pub name: String,
+    /// Which server serves this resource
+    pub server: String,
+}
+
+/// A prompt template a server exposes, run as `/<server>.<name>`.
+#[derive(Debug, Clone)]
+pub struct McpPrompt {
+    /// "<server_name>.<prompt_name>", like tool names
+    pub qualified_name: String,
+    pub description: String,
+    pub arguments: Vec<McpPromptArgument>,
+}
+
+#[derive(Debug, Clone)]
+pub struct McpPromptArgument {
+    pub name: String,
+    pub required: bool,
+}
+
+impl McpPrompt {
+    /// Map the text after the slash command to arguments: `key=value` pairs
+    /// (values may be double-quoted), or — without any `key=` — the whole
+    /// text as the first argument.
+    pub fn parse_arguments(&self, text: &str) -> Result<HashMap<String, String>> {
+        let text = text.trim();
+        let is_pair = |token: &str| {
+            token.split_once('=').is_some_and(|(k, _)| self.arguments.iter().any(|a| a.name == k))
+        };
+        let mut args = HashMap::new();
+        if text.split_whitespace().any(is_pair) {
+            let mut rest = text;
+            while let Some((key, after)) = rest.trim_start().split_once('=') {
+                let (value, next) = match after.strip_prefix('"') {
+                    Some(quoted) => quoted.split_once('"').unwrap_or((quoted, "")),
+                    None => after.split_once(char::is_whitespace).unwrap_or((after, "")),
+                };
+                args.insert(key.trim().to_string(), value.to_string());
+                rest = next;
+            }
+        } else if let (Some(first), false) = (self.arguments.first(), text.is_empty()) {
+            args.insert(first.name.clone(), text.to_string());
+        }
+        if let Some(missing) = self.arguments.iter().find(|a| a.required && !args.contains_key(&a.name)) {
+            return Err(anyhow!("missing argument '{}' — usage: {}", missing.name, self.usage()));
+        }
+        Ok(args)
+    }
+
+    /// `/server.prompt name=<name> [lang=<lang>]`
+    pub fn usage(&self) -> String {
+        let mut out = format!("/{}", self.qualified_name);
+        for a in &self.arguments {
+            let arg = format!("{}=<{}>", a.name, a.name);
+            out.push(' ');
+            out.push_str(&if a.required { arg } else { format!("[{arg}]") });
+        }
+        out
+    }
+}
+
+// ── Server status (public) ─────────────────────────────────────────────────────
+
+/// Where a configured server stands, for the Config tab.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum McpServerStatus {
+    Connecting,
+    Ready,
+    /// The connection dropped; restart `attempt` of `MAX_RESTARTS` is pending
+    Restarting { attempt: u32, error: String },
+    /// Gave up after repeated failures
+    Failed(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct McpServerInfo {
+    pub name: String,
+    pub status: McpServerStatus,
+    pub tools: usize,
+    pub resources: usize,
+    pub prompts: usize,
+    /// Successful restarts since startup
+    pub restarts: u32,
+}
+
+// ── Connection ─────────────────────────────────────────────────────────────────
+
+/// What a connection reports to its server's supervisor.
+enum LinkEvent {
+    /// The server announced a changed tool, resource or prompt list
+    ListChanged { generation: u64 },
+    /// Nothing more can arrive; requests still waiting have failed
+    Closed { generation: u64, reason: String },
+}
+
+/// One live connection to a server. Responses are matched to requests by
+/// id, so any number of requests can be in flight at once.
+struct Link {
+    server: String,
+    /// Which of the server's connections this is; events from replaced ones are ignored
+    generation: u64,
+    transport: Transport,
+    timeout: Duration,
     next_id: AtomicU64,
+    pending: Mutex<HashMap<u64, oneshot::Sender<Result<Value>>>>,
+    closed: AtomicBool,
+    events: mpsc::UnboundedSender<LinkEvent>,
+    /// Task reading the server's standing stream (stdio, legacy SSE, the HTTP GET stream)
+    reader: OnceLock<tokio::task::AbortHandle>,
+}
+
+impl Drop for Link {
+    fn drop(&mut self) {
+        if let Some(reader) = self.reader.get() {
+            reader.abort();
+        }
+    }
 }
 
-impl ServerConn {
-    async fn send_request(&mut self, method: &str, params: Option<Value>) -> Result<Value> {
+impl Link {
+    fn open(
+        server: &str,
+        generation: u64,
+        transport: Transport,
+        incoming: Option<Incoming>,
+        timeout: Duration,
+        events: mpsc::UnboundedSender<LinkEvent>,
+    ) -> Arc<Self> {
+        let link = Arc::new(Self {
+            server: server.to_string(),
+            generation,
+            transport,
+            timeout,
+            next_id: AtomicU64::new(1),
+            pending: Mutex::new(HashMap::new()),
+            closed: AtomicBool::new(false),
+            events,
+            reader: OnceLock::new(),
+        });
+        if let Some(incoming) = incoming {
+            link.listen(incoming);
+        }
+        link
+    }
+
+    /// Read `incoming` in the background, dispatching each message.
+    fn listen(self: &Arc<Self>, mut incoming: Incoming) {
+        // Weak: the reader must not keep a replaced connection (and its child) alive
+        let weak = Arc::downgrade(self);
+        let task = tokio::spawn(async move {
+            let reason = loop {
+                match incoming.next().await {
+                    Ok(Some(message)) => match weak.upgrade() {
+                        Some(link) => link.dispatch(&message),
+                        None => return,
+                    },
+                    Ok(None) => break "the server closed the connection".to_string(),
+                    Err(e) => break format!("read failed: {e}"),
+                }
+            };
+            if !incoming.ends_connection() {
+                return;
+            }
+            if let Some(link) = weak.upgrade() {
+                link.close(reason);
+            }
+        });
+        let _ = self.reader.set(task.abort_handle());
+    }
+
+    fn closed_error(&self) -> anyhow::Error {
+        anyhow!("MCP server '{}' closed the connection", self.server)
+    }
+
+    /// Mark the connection dead, failing every waiting request, and tell the supervisor.
+    fn close(&self, reason: String) {
+        if self.closed.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        // Dropping the senders wakes each waiter with an error
+        self.pending.lock().unwrap().clear();
+        let _ = self.events.send(LinkEvent::Closed { generation: self.generation, reason });
+    }
+
+    async fn request(self: &Arc<Self>, method: &str, params: Option<Value>) -> Result<Value> {
         let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(id, tx);
+        let mut in_flight = InFlight { link: self, id, settled: false, reason: "cancelled by the client" };
+        if self.closed.load(Ordering::SeqCst) {
+            in_flight.settled = true;
+            return Err(self.closed_error());
+        }
+
         let req = Request {
             jsonrpc: "2.0",
             id,
             method: method.to_string(),
             params,
         };
-        let mut line = serde_json::to_string(&req)?;
-        line.push('\n');
-        self.stdin
-            .write_all(line.as_bytes())
-            .await
-            .context("MCP stdin write failed")?;
-        self.stdin.flush().await?;
+        let exchange = async {
+            self.send(&serde_json::to_string(&req)?).await?;
+            rx.await.map_err(|_| self.closed_error())?
+        };
+        match tokio::time::timeout(self.timeout, exchange).await {
+            Ok(result) => {
+                in_flight.settled = true;
+                result
+            }
+            Err(_) => {
+                in_flight.reason = "timed out";
+                Err(anyhow!(
+                    "MCP server '{}' did not answer {method} within {}s",
+                    self.server,
+                    self.timeout.as_secs()
+                ))
+            }
+        }
+    }
 
-        // Read lines until we get the response for our id (skip notifications)
-        let mut buf = String::new();
-        loop {
-            buf.clear();
-            let n = self.stdout.read_line(&mut buf).await?;
-            if n == 0 {
-                return Err(anyhow!("MCP server '{}' closed stdout", self.name));
+    /// Send a notification — no id, no response expected.
+    async fn notify(self: &Arc<Self>, method: &str) -> Result<()> {
+        let notif = json!({ "jsonrpc": "2.0", "method": method });
+        self.send(&notif.to_string()).await
+    }
+
+    /// Every item of a paginated `*/list` result, following `nextCursor`.
+    async fn list_all(self: &Arc<Self>, method: &str, key: &str) -> Result<Vec<Value>> {
+        let mut items = Vec::new();
+        let mut cursor: Option<String> = None;
+        for _ in 0..MAX_LIST_PAGES {
+            let params = cursor.as_ref().map(|c| json!({ "cursor": c }));
+            let result = self.request(method, params).await?;
+            items.extend(result.get(key).and_then(|v| v.as_array()).cloned().unwrap_or_default());
+            match result.get("nextCursor").and_then(|c| c.as_str()) {
+                Some(next) => cursor = Some(next.to_string()),
+                None => break,
             }
-            let trimmed = buf.trim();
-            if trimmed.is_empty() {
-                continue;
+        }
+        Ok(items)
+    }
+
+    async fn send(self: &Arc<Self>, message: &str) -> Result<()> {
+        match self.transport.send(message).await {
+            Ok(replies) => {
+                for reply in replies {
+                    self.dispatch(&reply);
+                }
+                Ok(())
             }
-            let resp: Response = match serde_json::from_str(trimmed) {
-                Ok(r) => r,
-                Err(_) => continue, // skip malformed / notification lines
-            };
-            // Match our request id
-            let resp_id = resp.id.as_ref().and_then(|v| v.as_u64()).unwrap_or(u64::MAX);
-            if resp_id != id {
-                continue; // different id — keep reading
+            Err(e) => {
+                if let Some(lost) = e.downcast_ref::<ConnectionLost>() {
+                    self.close(lost.0.clone());
+                }
+                Err(e)
             }
-            if let Some(err) = resp.error {
-                return Err(anyhow!("MCP error from '{}': {}", self.name, err.message));
+        }
+    }
+
+    /// Send without waiting: replies to the server and cancellations, which
+    /// are issued from sync code (`dispatch`, `Drop`).
+    fn send_detached(self: &Arc<Self>, message: Value) {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else { return };
+        let link = Arc::clone(self);
+        runtime.spawn(async move {
+            let _ = link.transport.send(&message.to_string()).await;
+        });
+    }
+
+    /// Route one incoming message (or batch): a response to its waiting
+    /// request, a notification to the supervisor, a server request to a reply.
+    fn dispatch(self: &Arc<Self>, message: &str) {
+        let Ok(value) = serde_json::from_str::<Value>(message.trim()) else {
+            return; // skip malformed lines
+        };
+        let messages = match value {
+            Value::Array(batch) => batch,
+            single => vec![single],
+        };
+        for message in messages {
+            let method = message.get("method").and_then(|m| m.as_str()).map(str::to_string);
+            match (method, message.get("id").cloned()) {
+                (Some(method), None) => {
+                    if method.ends_with("/list_changed") {
+                        let _ = self.events.send(LinkEvent::ListChanged { generation: self.generation });
+                    }
+                }
+                // The server asks us something: answer pings, decline the rest
+                (Some(method), Some(id)) => self.send_detached(match method.as_str() {
+                    "ping" => json!({ "jsonrpc": "2.0", "id": id, "result": {} }),
+                    _ => json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": -32601, "message": format!("Method not found: {method}") }
+                    }),
+                }),
+                (None, Some(_)) => {
+                    let Ok(resp) = serde_json::from_value::<Response>(message) else { continue };
+                    let Some(id) = resp.id.as_ref().and_then(|v| v.as_u64()) else { continue };
+                    let Some(waiter) = self.pending.lock().unwrap().remove(&id) else { continue };
+                    let result = match resp.error {
+                        Some(err) => Err(anyhow!("MCP error from '{}': {}", self.server, err.message)),
+                        None => resp.result.ok_or_else(|| anyhow!("MCP response had no result")),
+                    };
+                    let _ = waiter.send(result);
+                }
+                (None, None) => {}
             }
-            return resp.result.ok_or_else(|| anyhow!("MCP response had no result"));
         }
     }
+}
 
-    async fn call_tool(&mut self, tool_name: &str, arguments: Value) -> Result<String> {
-        let result = self
-            .send_request(
-                "tools/call",
-                Some(json!({ "name": tool_name, "arguments": arguments })),
-            )
-            .await?;
+/// Cleans up after a request however its future ends. Unless it settled,
+/// the server is told to stop working on it: the request timed out, or the
+/// caller was dropped (the user cancelled the task).
+struct InFlight<'a> {
+    link: &'a Arc<Link>,
+    id: u64,
+    settled: bool,
+    reason: &'static str,
+}
 
-        // MCP tools/call result: { content: [ {type: "text", text: "..."} ] }
-        extract_text_content(&result)
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.link.pending.lock().unwrap().remove(&self.id);
+        if !self.settled && !self.link.closed.load(Ordering::SeqCst) {
+            self.link.send_detached(json!({
+                "jsonrpc": "2.0",
+                "method": "notifications/cancelled",
+                "params": { "requestId": self.id, "reason": self.reason }
+            }));
+        }
+    }
+}
+
+// ── Per-server state ───────────────────────────────────────────────────────────
+
+/// One configured server: its current connection, what it offers, and how
+/// it is doing. `supervise` replaces the connection when it drops and
+/// re-lists the offers when they change.
+struct Server {
+    cfg: McpServerConfig,
+    events: mpsc::UnboundedSender<LinkEvent>,
+    next_generation: AtomicU64,
+    link: Mutex<Option<Arc<Link>>>,
+    state: Mutex<ServerState>,
+}
+
+struct ServerState {
+    status: McpServerStatus,
+    offers: Offers,
+    /// Bumped whenever `offers` is replaced
+    revision: u64,
+    restarts: u32,
+    /// Restart attempts since the last stable connection, for backoff
+    attempts: u32,
+    connected_at: Option<Instant>,
+}
+
+/// What a server offers, as of its last listing.
+#[derive(Default)]
+struct Offers {
+    capabilities: Value,
+    tools: Vec<McpTool>,
+    resources: Vec<McpResource>,
+    prompts: Vec<McpPrompt>,
+}
+
+impl Server {
+    fn start(cfg: McpServerConfig) -> Arc<Self> {
+        let (events, events_rx) = mpsc::unbounded_channel();
+        let server = Arc::new(Self {
+            cfg,
+            events,
+            next_generation: AtomicU64::new(1),
+            link: Mutex::new(None),
+            state: Mutex::new(ServerState {
+                status: McpServerStatus::Connecting,
+                offers: Offers::default(),
+                revision: 0,
+                restarts: 0,
+                attempts: 0,
+                connected_at: None,
+            }),
+        });
+        tokio::spawn(supervise(Arc::downgrade(&server), events_rx));
+        server
+    }
+
+    /// The live connection, or why there is none.
+    fn link(&self) -> Result<Arc<Link>> {
+        if let Some(link) = self.link.lock().unwrap().as_ref() {
+            return Ok(Arc::clone(link));
+        }
+        let name = &self.cfg.name;
+        Err(match &self.state.lock().unwrap().status {
+            McpServerStatus::Restarting { attempt, error } => {
+                anyhow!("MCP server '{name}' is restarting (attempt {attempt}/{MAX_RESTARTS}) after: {error}")
+            }
+            McpServerStatus::Failed(error) => anyhow!("MCP server '{name}' is unavailable: {error}"),
+            _ => anyhow!("MCP server '{name}' is not connected"),
+        })
+    }
+
+    fn is_current(&self, generation: u64) -> bool {
+        self.link.lock().unwrap().as_ref().is_some_and(|l| l.generation == generation)
+    }
+
+    async fn request(&self, method: &str, params: Option<Value>) -> Result<Value> {
+        self.link()?.request(method, params).await
+    }
+
+    fn set_offers(&self, offers: Offers) {
+        let mut state = self.state.lock().unwrap();
+        state.offers = offers;
+        state.revision += 1;
+    }
+
+    /// Open a connection, run the handshake and make it the current one.
+    async fn connect(&self) -> Result<()> {
+        let generation = self.next_generation.fetch_add(1, Ordering::Relaxed);
+        let (link, offers) = open_and_init(&self.cfg, generation, self.events.clone()).await?;
+        self.set_offers(offers);
+        {
+            let mut state = self.state.lock().unwrap();
+            state.status = McpServerStatus::Ready;
+            state.connected_at = Some(Instant::now());
+        }
+        *self.link.lock().unwrap() = Some(link);
+        Ok(())
+    }
+
+    /// Re-list tools, resources and prompts; on failure the old lists stay.
+    async fn refresh(&self) {
+        let Ok(link) = self.link() else { return };
+        let capabilities = self.state.lock().unwrap().offers.capabilities.clone();
+        if let Ok(offers) = list_offers(&link, capabilities).await {
+            self.set_offers(offers);
+        }
     }
+
+    /// Reconnect after the connection dropped, backing off between attempts.
+    async fn restart(&self, mut error: String) {
+        *self.link.lock().unwrap() = None;
+        loop {
+            let attempt = {
+                let mut state = self.state.lock().unwrap();
+                if state.connected_at.take().is_some_and(|at| at.elapsed() >= STABLE_UPTIME) {
+                    state.attempts = 0;
+                }
+                state.attempts += 1;
+                if state.attempts > MAX_RESTARTS {
+                    state.status = McpServerStatus::Failed(error);
+                    return;
+                }
+                state.status = McpServerStatus::Restarting { attempt: state.attempts, error: error.clone() };
+                state.attempts
+            };
+            tokio::time::sleep(restart_delay(attempt)).await;
+            match self.connect().await {
+                Ok(()) => {
+                    self.state.lock().unwrap().restarts += 1;
+                    return;
+                }
+                Err(e) => error = format!("{e:#}"),
+            }
+        }
+    }
+}
+
+fn restart_delay(attempt: u32) -> Duration {
+    RESTART_BACKOFF.saturating_mul(1 << attempt.saturating_sub(1).min(16)).min(MAX_RESTART_BACKOFF)
+}
+
+/// Act on a server's connection events until its client is dropped.
+async fn supervise(server: Weak<Server>, mut events: mpsc::UnboundedReceiver<LinkEvent>) {
+    while let Some(event) = events.recv().await {
+        let Some(server) = server.upgrade() else { return };
+        match event {
+            LinkEvent::ListChanged { generation } if server.is_current(generation) => server.refresh().await,
+            LinkEvent::Closed { generation, reason } if server.is_current(generation) => server.restart(reason).await,
+            _ => {}
+        }
+    }
+}
+
+/// Text of a `resources/read` result. Binary contents are noted, not inlined.
+fn resource_text(result: &Value) -> String {
+    let contents = result.get("contents").and_then(|c| c.as_array()).cloned().unwrap_or_default();
+    let parts: Vec<String> = contents
+        .iter()
+        .map(|c| match (c.get("text").and_then(|t| t.as_str()), c.get("blob").and_then(|b| b.as_str())) {
+            (Some(text), _) => text.to_string(),
+            (None, Some(blob)) => {
+                let mime = c.get("mimeType").and_then(|m| m.as_str()).unwrap_or("binary");
+                format!("[{mime} content, {} bytes base64 — not displayed]", blob.len())
+            }
+            (None, None) => String::new(),
+        })
+        .filter(|p| !p.is_empty())
+        .collect();
+    parts.join("\n")
+}
+
+/// Text of a `prompts/get` result: each message's text (or embedded
+/// resource text), assistant turns labelled.
+fn prompt_text(result: &Value) -> String {
+    let messages = result.get("messages").and_then(|m| m.as_array()).cloned().unwrap_or_default();
+    let parts: Vec<String> = messages
+        .iter()
+        .filter_map(|m| {
+            let content = m.get("content")?;
+            let text = match content.get("type").and_then(|t| t.as_str()) {
+                Some("text") => content.get("text")?.as_str()?.to_string(),
+                Some("resource") => content.get("resource")?.get("text")?.as_str()?.to_string(),
+                _ => return None,
+            };
+            Some(match m.get("role").and_then(|r| r.as_str()) {
+                Some("assistant") => format!("[assistant]\n{text}"),
+                _ => text,
+            })
+        })
+        .collect();
+    parts.join("\n\n")
 }
 
 fn extract_text_content(result: &Value) -> Result<String> {
@@ -155,34 +640,48 @@ fn extract_text_content(result: &Value) -> Result<String> {
 // ── McpClient (public) ────────────────────────────────────────────────────────
 
 pub struct McpClient {
-    servers: Mutex<HashMap<String, ServerConn>>,
+    servers: Vec<Arc<Server>>,
 }
 
 impl McpClient {
-    /// Spawn all configured MCP servers, perform initialization, discover tools.
-    /// Silently skips servers that fail to start (logs to stderr).
-    pub async fn new(server_configs: &[crate::config::McpServerConfig]) -> Arc<Self> {
-        let mut servers = HashMap::new();
-        for cfg in server_configs {
-            match spawn_and_init(cfg).await {
-                Ok(conn) => {
-                    eprintln!("[mcp] connected: {} ({} tools)", cfg.name, conn.tools.len());
-                    servers.insert(cfg.name.clone(), conn);
+    /// Start all configured MCP servers, perform initialization, discover tools.
+    /// Servers connect concurrently; one that fails to start is logged to
+    /// stderr and retried in the background.
+    pub async fn new(server_configs: &[McpServerConfig]) -> Arc<Self> {
+        let servers: Vec<Arc<Server>> = server_configs.iter().cloned().map(Server::start).collect();
+        let results = futures_util::future::join_all(servers.iter().map(|s| s.connect())).await;
+        for (server, result) in servers.iter().zip(results) {
+            match result {
+                Ok(()) => {
+                    let state = server.state.lock().unwrap();
+                    eprintln!(
+                        "[mcp] connected: {} ({} tools, {} resources, {} prompts)",
+                        server.cfg.name,
+                        state.offers.tools.len(),
+                        state.offers.resources.len(),
+                        state.offers.prompts.len()
+                    );
                 }
                 Err(e) => {
-                    eprintln!("[mcp] failed to start '{}': {e}", cfg.name);
+                    eprintln!("[mcp] failed to start '{}': {e}", server.cfg.name);
+                    let server = Arc::clone(server);
+                    tokio::spawn(async move { server.restart(format!("{e:#}")).await });
                 }
             }
         }
-        Arc::new(Self {
-            servers: Mutex::new(servers),
-        })
+        Arc::new(Self { servers })
+    }
+
+    fn server(&self, name: &str) -> Result<&Arc<Server>> {
+        self.servers
+            .iter()
+            .find(|s| s.cfg.name == name)
+            .ok_or_else(|| anyhow!("No MCP server named '{name}'"))
     }
 
     /// All tools discovered across all running servers.
     pub async fn all_tools(&self) -> Vec<McpTool> {
-        let servers = self.servers.lock().await;
-        servers.values().flat_map(|s| s.tools.clone()).collect()
+        self.servers.iter().flat_map(|s| s.state.lock().unwrap().offers.tools.clone()).collect()
     }
 
     /// Call a tool by its qualified name ("server.tool_name").
@@ -190,21 +689,492 @@ impl McpClient {
         let (server_name, tool_name) = qualified_name
             .split_once('.')
             .ok_or_else(|| anyhow!("Invalid MCP tool name: {qualified_name}"))?;
-        let mut servers = self.servers.lock().await;
-        let conn = servers
-            .get_mut(server_name)
-            .ok_or_else(|| anyhow!("No MCP server named '{server_name}'"))?;
-        conn.call_tool(tool_name, arguments).await
+        let result = self
+            .server(server_name)?
+            .request("tools/call", Some(json!({ "name": tool_name, "arguments": arguments })))
+            .await?;
+
+        // MCP tools/call result: { content: [ {type: "text", text: "..."} ] }
+        extract_text_content(&result)
+    }
+
+    /// All resources discovered across all running servers.
+    pub async fn all_resources(&self) -> Vec<McpResource> {
+        let mut out: Vec<McpResource> =
+            self.servers.iter().flat_map(|s| s.state.lock().unwrap().offers.resources.clone()).collect();
+        out.sort_by(|a, b| a.uri.cmp(&b.uri));
+        out
+    }
+
+    /// All prompts discovered across all running servers.
+    pub async fn all_prompts(&self) -> Vec<McpPrompt> {
+        let mut out: Vec<McpPrompt> =
+            self.servers.iter().flat_map(|s| s.state.lock().unwrap().offers.prompts.clone()).collect();
+        out.sort_by(|a, b| a.qualified_name.cmp(&b.qualified_name));
+        out
+    }
+
+    /// Read a listed resource as text.
+    pub async fn read_resource(&self, uri: &str) -> Result<String> {
+        let server = self
+            .servers
+            .iter()
+            .find(|s| s.state.lock().unwrap().offers.resources.iter().any(|r| r.uri == uri))
+            .ok_or_else(|| anyhow!("No MCP server lists resource '{uri}'"))?;
+        let result = server.request("resources/read", Some(json!({ "uri": uri }))).await?;
+        Ok(resource_text(&result))
+    }
+
+    /// Render a prompt by its qualified name ("server.prompt_name").
+    pub async fn get_prompt(&self, qualified_name: &str, arguments: HashMap<String, String>) -> Result<String> {
+        let (server_name, prompt_name) = qualified_name
+            .split_once('.')
+            .ok_or_else(|| anyhow!("Invalid MCP prompt name: {qualified_name}"))?;
+        let result = self
+            .server(server_name)?
+            .request("prompts/get", Some(json!({ "name": prompt_name, "arguments": arguments })))
+            .await?;
+        Ok(prompt_text(&result))
+    }
+
+    /// Every configured server and how it is doing, in config order.
+    pub fn statuses(&self) -> Vec<McpServerInfo> {
+        self.servers
+            .iter()
+            .map(|s| {
+                let state = s.state.lock().unwrap();
+                McpServerInfo {
+                    name: s.cfg.name.clone(),
+                    status: state.status.clone(),
+                    tools: state.offers.tools.len(),
+                    resources: state.offers.resources.len(),
+                    prompts: state.offers.prompts.len(),
+                    restarts: state.restarts,
+                }
+            })
+            .collect()
+    }
+
+    /// Changes whenever any server's tools, resources or prompts are
+    /// re-listed, so callers know to rebuild what they derived from them.
+    pub fn revision(&self) -> u64 {
+        self.servers.iter().map(|s| s.state.lock().unwrap().revision).sum()
+    }
+
+    /// Attached resources as a context block for the first user message.
+    /// Each is read in order and charged against `max_tokens` (estimated
+    /// with `budget::estimate_tokens`, like file reads); the one that
+    /// crosses the budget is cut at a line boundary and the rest omitted.
+    pub async fn resource_preload(&self, uris: &[String], max_tokens: usize) -> String {
+        let mut out = String::new();
+        let mut left = max_tokens;
+        for uri in uris {
+            let text = match self.read_resource(uri).await {
+                Ok(text) => text,
+                Err(e) => {
+                    out.push_str(&format!("[resource {uri} — unavailable: {e}]\n\n"));
+                    continue;
+                }
+            };
+            let total = crate::budget::estimate_tokens(&text);
+            if total <= left {
+                out.push_str(&format!("[resource {uri}]\n\n{text}\n\n"));
+                left -= total;
+                continue;
+            }
+            // Keep whole lines within the remaining chars (≈ 4 per token)
+            let max_chars = left.saturating_sub(10) * 4;
+            let mut kept = String::new();
+            for line in text.lines() {
+                if kept.chars().count() + line.chars().count() + 1 > max_chars {
+                    break;
+                }
+                kept.push_str(line);
+                kept.push('\n');
+            }
+            if kept.is_empty() {
+                out.push_str(&format!("[resource {uri} — omitted: ~{total} tokens, context budget spent]\n\n"));
+            } else {
+                let shown = crate::budget::estimate_tokens(&kept);
+                out.push_str(&format!("[resource {uri} — first ~{shown} of ~{total} tokens]\n\n{kept}\n"));
+            }
+            left = 0;
+        }
+        out
+    }
+}
+
+// ── Transports ────────────────────────────────────────────────────────────────
+
+/// Sends serialized JSON-RPC messages to one server. Id matching and error
+/// handling stay in `Link`.
+enum Transport {
+    Stdio {
+        stdin: tokio::sync::Mutex<ChildStdin>,
+        /// Killed when the connection is dropped
+        #[allow(dead_code)]
+        child: Child,
+    },
+    Http(HttpTransport),
+    Sse(SseTransport),
+}
+
+impl Transport {
+    /// Send one message. Replies carried in an HTTP response body are
+    /// returned; everything else arrives on the connection's `Incoming`.
+    async fn send(&self, message: &str) -> Result<Vec<String>> {
+        match self {
+            Transport::Stdio { stdin, .. } => {
+                let mut stdin = stdin.lock().await;
+                let line = format!("{message}\n");
+                stdin
+                    .write_all(line.as_bytes())
+                    .await
+                    .map_err(|e| ConnectionLost(format!("MCP stdin write failed: {e}")))?;
+                stdin.flush().await.map_err(|e| ConnectionLost(format!("MCP stdin write failed: {e}")))?;
+                Ok(Vec::new())
+            }
+            Transport::Http(http) => http.post(message).await,
+            Transport::Sse(sse) => sse.post(message).await.map(|()| Vec::new()),
+        }
+    }
+}
+
+/// A server's standing stream of messages, read by the connection's reader task.
+enum Incoming {
+    Stdio(BufReader<ChildStdout>),
+    Sse(SseStream),
+    /// Streamable HTTP's GET stream, for messages outside any request
+    Http(SseStream),
+}
+
+impl Incoming {
+    /// The next message from the server, or `None` once nothing more can arrive.
+    async fn next(&mut self) -> Result<Option<String>> {
+        match self {
+            Incoming::Stdio(stdout) => {
+                let mut buf = String::new();
+                let n = stdout.read_line(&mut buf).await?;
+                Ok((n > 0).then_some(buf))
+            }
+            Incoming::Sse(events) | Incoming::Http(events) => loop {
+                match events.next().await? {
+                    Some(event) if event.event == "message" => return Ok(Some(event.data)),
+                    Some(_) => continue,
+                    None => return Ok(None),
+                }
+            },
+        }
+    }
+
+    /// Whether the stream ending means the connection is gone. The HTTP GET
+    /// stream is optional: the server may end it while POSTs carry on.
+    fn ends_connection(&self) -> bool {
+        !matches!(self, Incoming::Http(_))
+    }
+}
+
+/// The connection is gone — the process exited, the network failed or the
+/// HTTP session expired — and the server needs restarting.
+#[derive(Debug)]
+struct ConnectionLost(String);
+
+impl std::fmt::Display for ConnectionLost {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ConnectionLost {}
+
+/// A non-2xx answer from an HTTP server. Kept as a type so a rejected
+/// Streamable HTTP POST can fall back to legacy SSE.
+#[derive(Debug)]
+struct HttpStatusError {
+    status: reqwest::StatusCode,
+    body: String,
+}
+
+impl std::fmt::Display for HttpStatusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "HTTP {}: {}", self.status, self.body.trim())
+    }
+}
+
+impl std::error::Error for HttpStatusError {}
+
+async fn check_status(resp: reqwest::Response) -> Result<reqwest::Response> {
+    let status = resp.status();
+    if status.is_success() {
+        return Ok(resp);
+    }
+    let body = resp.text().await.unwrap_or_default();
+    Err(HttpStatusError { status, body }.into())
+}
+
+/// Configured headers plus bearer auth, sent with every request.
+fn http_headers(cfg: &McpServerConfig) -> Result<reqwest::header::HeaderMap> {
+    use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
+    let mut headers = HeaderMap::new();
+    for (k, v) in &cfg.headers {
+        let name = HeaderName::from_bytes(k.as_bytes()).with_context(|| format!("Invalid header name '{k}'"))?;
+        let value = HeaderValue::from_str(v).with_context(|| format!("Invalid value for header '{k}'"))?;
+        headers.insert(name, value);
+    }
+    if let Some(token) = &cfg.bearer_token {
+        let value = HeaderValue::from_str(&format!("Bearer {token}")).context("Invalid bearer token")?;
+        headers.insert(AUTHORIZATION, value);
+    }
+    Ok(headers)
+}
+
+/// Streamable HTTP: every message is POSTed to one endpoint. The reply is
+/// JSON or an SSE stream carrying the response. POSTs run concurrently.
+struct HttpTransport {
+    http: reqwest::Client,
+    url: String,
+    headers: reqwest::header::HeaderMap,
+    /// `Mcp-Session-Id` assigned by the server at initialization
+    session: Mutex<Option<String>>,
+    /// Protocol revision agreed at initialization, sent as `MCP-Protocol-Version`
+    protocol: Mutex<Option<String>>,
+}
+
+impl HttpTransport {
+    fn new(url: &str, headers: reqwest::header::HeaderMap) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            url: url.to_string(),
+            headers,
+            session: Mutex::new(None),
+            protocol: Mutex::new(None),
+        }
+    }
+
+    /// Add the session and protocol headers, once initialization has set them.
+    fn with_session(&self, mut req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        if let Some(session) = self.session.lock().unwrap().as_deref() {
+            req = req.header("Mcp-Session-Id", session);
+        }
+        if let Some(protocol) = self.protocol.lock().unwrap().as_deref() {
+            req = req.header("MCP-Protocol-Version", protocol);
+        }
+        req
+    }
+
+    /// Open the GET stream the server sends notifications on, or `None`
+    /// when it doesn't offer one (405 or any other failure).
+    async fn event_stream(&self) -> Option<SseStream> {
+        let req = self.http.get(&self.url).headers(self.headers.clone()).header("Accept", "text/event-stream");
+        let resp = self.with_session(req).send().await.ok()?;
+        (resp.status().is_success() && is_event_stream(&resp)).then(|| SseStream::new(resp))
+    }
+
+    async fn post(&self, message: &str) -> Result<Vec<String>> {
+        let session = self.session.lock().unwrap().clone();
+        let req = self
+            .http
+            .post(&self.url)
+            .headers(self.headers.clone())
+            .header("Accept", "application/json, text/event-stream")
+            .header("Content-Type", "application/json")
+            .body(message.to_string());
+        let resp = self.with_session(req).send().await.map_err(|e| ConnectionLost(format!("MCP request failed: {e}")))?;
+        // The server forgot the session: only a new one helps
+        if session.is_some() && resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(ConnectionLost("MCP session expired".to_string()).into());
+        }
+        let resp = check_status(resp).await?;
+        if let Some(session) = resp.headers().get("mcp-session-id").and_then(|v| v.to_str().ok()) {
+            *self.session.lock().unwrap() = Some(session.to_string());
+        }
+        if is_event_stream(&resp) {
+            // Read until the response arrives; the server may keep the stream open
+            let mut replies = Vec::new();
+            let mut events = SseStream::new(resp);
+            while let Some(event) = events.next().await? {
+                if event.event != "message" {
+                    continue;
+                }
+                let is_response = serde_json::from_str::<Value>(&event.data)
+                    .is_ok_and(|v| v.get("id").is_some() && v.get("method").is_none());
+                replies.push(event.data);
+                if is_response {
+                    return Ok(replies);
+                }
+            }
+            let is_request = serde_json::from_str::<Value>(message)
+                .is_ok_and(|v| v.get("id").is_some() && v.get("method").is_some());
+            if is_request {
+                return Err(anyhow!("MCP reply stream ended before the response"));
+            }
+            return Ok(replies);
+        }
+        // 202 Accepted for notifications: no body
+        let body = resp.text().await?;
+        Ok(match serde_json::from_str::<Value>(&body) {
+            Ok(Value::Array(batch)) => batch.iter().map(Value::to_string).collect(),
+            _ if body.trim().is_empty() => Vec::new(),
+            _ => vec![body],
+        })
+    }
+}
+
+fn is_event_stream(resp: &reqwest::Response) -> bool {
+    resp.headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.starts_with("text/event-stream"))
+}
+
+/// Legacy HTTP+SSE (protocol 2024-11-05): a GET opens an event stream whose
+/// first `endpoint` event names the URL messages are POSTed to; responses
+/// arrive on the stream as `message` events.
+struct SseTransport {
+    http: reqwest::Client,
+    endpoint: reqwest::Url,
+    headers: reqwest::header::HeaderMap,
+}
+
+impl SseTransport {
+    /// Open the event stream; it is returned for the connection's reader.
+    async fn connect(url: &str, headers: reqwest::header::HeaderMap) -> Result<(Self, SseStream)> {
+        let http = reqwest::Client::new();
+        let base = reqwest::Url::parse(url).with_context(|| format!("Invalid MCP url '{url}'"))?;
+        let resp = http
+            .get(base.clone())
+            .headers(headers.clone())
+            .header("Accept", "text/event-stream")
+            .send()
+            .await?;
+        let mut events = SseStream::new(check_status(resp).await?);
+        loop {
+            match events.next().await? {
+                Some(event) if event.event == "endpoint" => {
+                    let endpoint = base.join(event.data.trim()).context("Invalid SSE endpoint")?;
+                    return Ok((Self { http, endpoint, headers }, events));
+                }
+                Some(_) => continue,
+                None => return Err(anyhow!("SSE stream ended before an endpoint event")),
+            }
+        }
+    }
+
+    async fn post(&self, message: &str) -> Result<()> {
+        let resp = self
+            .http
+            .post(self.endpoint.clone())
+            .headers(self.headers.clone())
+            .header("Content-Type", "application/json")
+            .body(message.to_string())
+            .send()
+            .await
+            .map_err(|e| ConnectionLost(format!("MCP request failed: {e}")))?;
+        check_status(resp).await?;
+        Ok(())
+    }
+}
+
+/// One server-sent event. `event` defaults to "message".
+struct SseEvent {
+    event: String,
+    data: String,
+}
+
+/// Incremental `text/event-stream` parser over a response body.
+struct SseStream {
+    resp: reqwest::Response,
+    /// Bytes not yet terminated by '\n' — chunks split anywhere
+    pending: Vec<u8>,
+}
+
+impl SseStream {
+    fn new(resp: reqwest::Response) -> Self {
+        Self { resp, pending: Vec::new() }
+    }
+
+    async fn next(&mut self) -> Result<Option<SseEvent>> {
+        let mut event = String::new();
+        let mut data: Vec<String> = Vec::new();
+        loop {
+            while let Some(nl) = self.pending.iter().position(|&b| b == b'\n') {
+                let raw: Vec<u8> = self.pending.drain(..=nl).collect();
+                let line = String::from_utf8_lossy(&raw);
+                let line = line.trim_end_matches(['\r', '\n']);
+                if line.is_empty() {
+                    // Blank line dispatches the event
+                    if !data.is_empty() {
+                        let event = if event.is_empty() { "message".to_string() } else { event };
+                        return Ok(Some(SseEvent { event, data: data.join("\n") }));
+                    }
+                    event.clear();
+                    continue;
+                }
+                if line.starts_with(':') {
+                    continue; // comment / keep-alive
+                }
+                let (field, value) = line.split_once(':').unwrap_or((line, ""));
+                let value = value.strip_prefix(' ').unwrap_or(value);
+                match field {
+                    "event" => event = value.to_string(),
+                    "data" => data.push(value.to_string()),
+                    _ => {}
+                }
+            }
+            match self.resp.chunk().await? {
+                Some(bytes) => self.pending.extend_from_slice(&bytes),
+                None => return Ok(None),
+            }
+        }
     }
 }
 
 // ── Server spawn + initialization ─────────────────────────────────────────────
 
-async fn spawn_and_init(cfg: &crate::config::McpServerConfig) -> Result<ServerConn> {
+/// Open a connection for `cfg` and run the handshake over it.
+async fn open_and_init(
+    cfg: &McpServerConfig,
+    generation: u64,
+    events: mpsc::UnboundedSender<LinkEvent>,
+) -> Result<(Arc<Link>, Offers)> {
+    let timeout = Duration::from_secs(cfg.timeout_secs);
+    let open = |transport: Transport, incoming: Option<Incoming>| {
+        Link::open(&cfg.name, generation, transport, incoming, timeout, events.clone())
+    };
+    let Some(url) = &cfg.url else {
+        let (transport, stdout) = spawn_stdio(cfg)?;
+        let link = open(transport, Some(Incoming::Stdio(stdout)));
+        let offers = initialize(&link).await?;
+        return Ok((link, offers));
+    };
+    let headers = http_headers(cfg)?;
+    let link = match cfg.transport {
+        McpTransport::Sse => {
+            let (sse, stream) = SseTransport::connect(url, headers.clone()).await?;
+            open(Transport::Sse(sse), Some(Incoming::Sse(stream)))
+        }
+        McpTransport::Http | McpTransport::Auto => open(Transport::Http(HttpTransport::new(url, headers.clone())), None),
+    };
+    match initialize(&link).await {
+        Ok(offers) => Ok((link, offers)),
+        // Servers predating Streamable HTTP answer the POST with 4xx
+        Err(e) if cfg.transport == McpTransport::Auto
+            && e.downcast_ref::<HttpStatusError>().is_some_and(|s| s.status.is_client_error()) =>
+        {
+            let (sse, stream) = SseTransport::connect(url, headers).await?;
+            let link = open(Transport::Sse(sse), Some(Incoming::Sse(stream)));
+            let offers = initialize(&link).await?;
+            Ok((link, offers))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+fn spawn_stdio(cfg: &McpServerConfig) -> Result<(Transport, BufReader<ChildStdout>)> {
     use tokio::process::Command;
 
     if cfg.command.is_empty() {
-        return Err(anyhow!("Empty command for MCP server '{}'", cfg.name));
+        return Err(anyhow!("MCP server '{}' needs a command or a url", cfg.name));
     }
 
     let mut cmd = Command::new(&cfg.command[0]);
@@ -217,53 +1187,113 @@ async fn spawn_and_init(cfg: &crate::config::McpServerConfig) -> Result<ServerCo
 
     cmd.stdin(Stdio::piped())
         .stdout(Stdio::piped())
-        .stderr(Stdio::inherit()); // pass server stderr to our stderr for debugging
+        .stderr(Stdio::inherit()) // pass server stderr to our stderr for debugging
+        .kill_on_drop(true);
 
     let mut child = cmd.spawn().context(format!(
         "Failed to spawn MCP server '{}': {:?}",
         cfg.name, cfg.command
     ))?;
 
-    let stdin = child.stdin.take().unwrap();
+    let stdin = tokio::sync::Mutex::new(child.stdin.take().unwrap());
     let stdout = BufReader::new(child.stdout.take().unwrap());
+    Ok((Transport::Stdio { stdin, child }, stdout))
+}
 
-    let mut conn = ServerConn {
-        name: cfg.name.clone(),
-        stdin,
-        stdout,
-        child,
-        tools: Vec::new(),
-        next_id: AtomicU64::new(1),
+/// Handshake and tool, resource and prompt discovery, over any transport.
+async fn initialize(link: &Arc<Link>) -> Result<Offers> {
+    let http = match &link.transport {
+        Transport::Http(http) => Some(http),
+        _ => None,
     };
-
     // MCP initialization handshake
-    let _init_result = conn
-        .send_request(
+    let init_result = link
+        .request(
             "initialize",
             Some(json!({
-                "protocolVersion": "2024-11-05",
+                "protocolVersion": if http.is_some() { PROTOCOL_VERSION } else { LEGACY_PROTOCOL_VERSION },
                 "capabilities": { "tools": {} },
                 "clientInfo": { "name": "parecode", "version": "0.1.0" }
             })),
         )
         .await
         .context("MCP initialize failed")?;
+    if let Some(http) = http {
+        let agreed = init_result.get("protocolVersion").and_then(|v| v.as_str()).unwrap_or(PROTOCOL_VERSION);
+        *http.protocol.lock().unwrap() = Some(agreed.to_string());
+    }
 
     // Notify server that init is complete
-    // (initialized is a notification — no id, no response expected)
-    let notif = "{\"jsonrpc\":\"2.0\",\"method\":\"notifications/initialized\"}\n";
-    conn.stdin.write_all(notif.as_bytes()).await?;
-    conn.stdin.flush().await?;
-
-    // Discover tools
-    let tools_result = conn
-        .send_request("tools/list", None)
+    link.notify("notifications/initialized").await?;
+
+    // Over Streamable HTTP, notifications outside a request come on a GET stream
+    if let Some(http) = http
+        && let Some(stream) = http.event_stream().await
+    {
+        link.listen(Incoming::Http(stream));
+    }
+
+    let capabilities = init_result.get("capabilities").cloned().unwrap_or_default();
+    list_offers(link, capabilities).await
+}
+
+/// Tools, plus resources and prompts when `capabilities` advertises them.
+/// A failed resource or prompt listing leaves that list empty.
+async fn list_offers(link: &Arc<Link>, capabilities: Value) -> Result<Offers> {
+    let tools_result = link
+        .request("tools/list", None)
         .await
         .context("MCP tools/list failed")?;
 
-    conn.tools = parse_tools(&cfg.name, &tools_result);
+    let mut offers = Offers { tools: parse_tools(&link.server, &tools_result), ..Offers::default() };
+    if capabilities.get("resources").is_some() {
+        let items = link.list_all("resources/list", "resources").await.unwrap_or_default();
+        offers.resources = parse_resources(&link.server, &items);
+    }
+    if capabilities.get("prompts").is_some() {
+        let items = link.list_all("prompts/list", "prompts").await.unwrap_or_default();
+        offers.prompts = parse_prompts(&link.server, &items);
+    }
+    offers.capabilities = capabilities;
+    Ok(offers)
+}
 
-    Ok(conn)
+fn parse_resources(server_name: &str, items: &[Value]) -> Vec<McpResource> {
+    let text = |v: &Value, key: &str| v.get(key).and_then(|d| d.as_str()).unwrap_or("").to_string();
+    items
+        .iter()
+        .filter_map(|r| {
+            let uri = r.get("uri")?.as_str()?.to_string();
+            Some(McpResource { name: text(r, "name"), server: server_name.to_string(), uri })
+        })
+        .collect()
+}
+
+fn parse_prompts(server_name: &str, items: &[Value]) -> Vec<McpPrompt> {
+    let text = |v: &Value, key: &str| v.get(key).and_then(|d| d.as_str()).unwrap_or("").to_string();
+    items
+        .iter()
+        .filter_map(|p| {
+            let name = p.get("name")?.as_str()?;
+            let arguments = p
+                .get("arguments")
+                .and_then(|a| a.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|a| {
+                    Some(McpPromptArgument {
+                        name: a.get("name")?.as_str()?.to_string(),
+                        required: a.get("required").and_then(|r| r.as_bool()).unwrap_or(false),
+                    })
+                })
+                .collect();
+            Some(McpPrompt {
+                qualified_name: format!("{server_name}.{name}"),
+                description: text(p, "description"),
+                arguments,
+            })
+        })
+        .collect()
 }
 
 fn parse_tools(server_name: &str, result: &Value) -> Vec<McpTool> {
@@ -297,3 +1327,344 @@ fn parse_tools(server_name: &str, result: &Value) -> Vec<McpTool> {
         })
         .collect()
 }
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::test_server::{self, MockRequest, MockResponse};
+    use std::sync::Mutex as StdMutex;
+    use tokio::sync::mpsc;
+
+    /// A small MCP server: an `echo` tool, two resources listed over two
+    /// pages, and a `review` prompt.
+    fn reply(message: &Value) -> Option<Value> {
+        let id = message.get("id")?.clone();
+        let params = &message["params"];
+        let result = match message["method"].as_str()? {
+            "initialize" => json!({
+                "protocolVersion": "2024-11-05",
+                "capabilities": { "tools": {}, "resources": {}, "prompts": {} }
+            }),
+            "tools/list" => json!({ "tools": [{ "name": "echo", "description": "Echo text" }] }),
+            "resources/list" if params["cursor"] == "2" => {
+                json!({ "resources": [{ "uri": "docs://api", "name": "API guide" }] })
+            }
+            "resources/list" => json!({ "resources": [{ "uri": "db://schema", "name": "Schema" }], "nextCursor": "2" }),
+            "resources/read" => {
+                let text = if params["uri"] == "docs://api" { "line one\n".repeat(200) } else { "CREATE TABLE t;".to_string() };
+                json!({ "contents": [{ "uri": params["uri"], "text": text }, { "uri": params["uri"], "blob": "AAAA", "mimeType": "image/png" }] })
+            }
+            "prompts/list" => json!({ "prompts": [{
+                "name": "review",
+                "description": "Review a change",
+                "arguments": [{ "name": "focus", "required": true }, { "name": "tone" }]
+            }] }),
+            "prompts/get" => json!({ "messages": [
+                { "role": "user", "content": { "type": "text", "text": format!("Review {}", params["arguments"]["focus"].as_str()?) } },
+                { "role": "assistant", "content": { "type": "text", "text": "Which files?" } }
+            ] }),
+            "tools/call" if message["params"]["name"] == "echo" => {
+                json!({ "content": [{ "type": "text", "text": message["params"]["arguments"]["text"] }] })
+            }
+            _ => return Some(json!({ "jsonrpc": "2.0", "id": id, "error": { "code": -32601, "message": "unknown tool" } })),
+        };
+        Some(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+    }
+
+    fn remote(url: &str, transport: McpTransport) -> McpServerConfig {
+        McpServerConfig {
+            name: "remote".into(),
+            command: vec![],
+            env: HashMap::new(),
+            url: Some(url.to_string()),
+            headers: HashMap::from([("X-Team".to_string(), "tools".to_string())]),
+            bearer_token: Some("tok".into()),
+            transport,
+            timeout_secs: 5,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_streamable_http_session_and_sse_replies() {
+        let seen: Arc<StdMutex<Vec<String>>> = Arc::default();
+        let log = Arc::clone(&seen);
+        let url = test_server::serve_requests(move |req: &MockRequest| {
+            assert_eq!(req.header("authorization"), Some("Bearer tok"));
+            assert_eq!(req.header("x-team"), Some("tools"));
+            // No GET stream on offer
+            if req.method == "GET" {
+                return MockResponse::error(405, "method not allowed");
+            }
+            let message: Value = serde_json::from_str(&req.body).unwrap();
+            let method = message["method"].as_str().unwrap_or("").to_string();
+            log.lock().unwrap().push(format!("{method} session={}", req.header("mcp-session-id").unwrap_or("-")));
+            match (method.as_str(), reply(&message)) {
+                ("initialize", Some(r)) => MockResponse::json(&r.to_string()).with_header("mcp-session-id", "s1"),
+                // Answered as an event stream, after an unrelated notification
+                (_, Some(r)) => MockResponse::sse(&[r#"{"jsonrpc":"2.0","method":"notifications/progress"}"#, &r.to_string()]),
+                (_, None) => MockResponse { status: 202, headers: vec![], body: String::new(), stream: None },
+            }
+        })
+        .await;
+
+        let client = McpClient::new(&[remote(&format!("{url}/mcp"), McpTransport::Auto)]).await;
+        let tools = client.all_tools().await;
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].qualified_name, "remote.echo");
+        let out = client.call("remote.echo", json!({ "text": "hi" })).await.unwrap();
+        assert_eq!(out, "hi");
+        assert_eq!(
+            seen.lock().unwrap()[..3],
+            ["initialize session=-", "notifications/initialized session=s1", "tools/list session=s1"]
+        );
+        assert_eq!(seen.lock().unwrap().last().map(String::as_str), Some("tools/call session=s1"));
+    }
+
+    #[tokio::test]
+    async fn test_streamable_http_version_and_event_stream() {
+        let stream: Arc<StdMutex<Option<mpsc::UnboundedSender<String>>>> = Arc::default();
+        let versions: Arc<StdMutex<Vec<String>>> = Arc::default();
+        let more_tools = Arc::new(AtomicBool::new(false));
+        let (slot, log, more) = (Arc::clone(&stream), Arc::clone(&versions), Arc::clone(&more_tools));
+        let url = test_server::serve_requests(move |req: &MockRequest| {
+            let message: Value = serde_json::from_str(&req.body).unwrap_or_default();
+            let method = message["method"].as_str().unwrap_or(&req.method).to_string();
+            log.lock().unwrap().push(format!("{method} {}", req.header("mcp-protocol-version").unwrap_or("-")));
+            if req.method == "GET" {
+                let (tx, rx) = mpsc::unbounded_channel();
+                *slot.lock().unwrap() = Some(tx);
+                return MockResponse::event_stream(rx);
+            }
+            let reply = match method.as_str() {
+                "initialize" => Some(json!({
+                    "jsonrpc": "2.0",
+                    "id": message["id"],
+                    "result": { "protocolVersion": message["params"]["protocolVersion"], "capabilities": { "tools": {} } }
+                })),
+                "tools/list" if more.load(Ordering::SeqCst) => Some(json!({
+                    "jsonrpc": "2.0",
+                    "id": message["id"],
+                    "result": { "tools": [{ "name": "echo" }, { "name": "shout" }] }
+                })),
+                _ => reply(&message),
+            };
+            match reply {
+                Some(r) => MockResponse::json(&r.to_string()).with_header("mcp-session-id", "s1"),
+                None => MockResponse { status: 202, headers: vec![], body: String::new(), stream: None },
+            }
+        })
+        .await;
+
+        let client = McpClient::new(&[remote(&url, McpTransport::Http)]).await;
+        assert_eq!(client.statuses()[0].tools, 1);
+        // The newest revision is requested, then named on every later request
+        assert_eq!(
+            versions.lock().unwrap()[..3],
+            ["initialize -", "notifications/initialized 2025-06-18", "GET 2025-06-18"]
+        );
+
+        // A change announced on the GET stream re-lists the tools
+        more_tools.store(true, Ordering::SeqCst);
+        let announce = r#"{"jsonrpc":"2.0","method":"notifications/tools/list_changed"}"#;
+        stream.lock().unwrap().as_ref().expect("GET stream not opened").send(format!("data: {announce}\n\n")).unwrap();
+        assert!(eventually(|| client.statuses()[0].tools == 2).await, "tools not re-listed");
+        assert_eq!(versions.lock().unwrap().last().map(String::as_str), Some("tools/list 2025-06-18"));
+
+        // The server may end that stream; the session carries on
+        drop(stream.lock().unwrap().take());
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(client.statuses()[0].status, McpServerStatus::Ready);
+        assert_eq!(client.call("remote.echo", json!({ "text": "still up" })).await.unwrap(), "still up");
+    }
+
+    #[tokio::test]
+    async fn test_resources_and_prompts() {
+        let url = test_server::serve_fn(|body| match serde_json::from_str(body).ok().as_ref().map(reply) {
+            Some(Some(r)) => MockResponse::json(&r.to_string()),
+            Some(None) => MockResponse { status: 202, headers: vec![], body: String::new(), stream: None },
+            // The GET for an event stream
+            None => MockResponse::error(405, "method not allowed"),
+        })
+        .await;
+        let client = McpClient::new(&[remote(&url, McpTransport::Http)]).await;
+
+        let resources = client.all_resources().await;
+        let uris: Vec<&str> = resources.iter().map(|r| r.uri.as_str()).collect();
+        assert_eq!(uris, vec!["db://schema", "docs://api"], "both pages listed");
+        assert_eq!(resources[1].name, "API guide");
+        assert_eq!(
+            client.read_resource("db://schema").await.unwrap(),
+            "CREATE TABLE t;\n[image/png content, 4 bytes base64 — not displayed]"
+        );
+        assert!(client.read_resource("docs://missing").await.is_err());
+
+        // The first resource fits; the second is cut to the remaining budget
+        let preload = client.resource_preload(&["db://schema".into(), "docs://api".into()], 120).await;
+        assert!(preload.starts_with("[resource db://schema]\n\nCREATE TABLE t;"), "{preload}");
+        assert!(preload.contains("[resource docs://api — first ~"), "{preload}");
+        let shown = preload.split("docs://api").nth(1).unwrap();
+        assert!(shown.matches("line one").count() < 200, "{preload}");
+
+        let prompts = client.all_prompts().await;
+        assert_eq!(prompts.len(), 1);
+        let review = &prompts[0];
+        assert_eq!(review.qualified_name, "remote.review");
+        assert_eq!(review.usage(), "/remote.review focus=<focus> [tone=<tone>]");
+        let args = review.parse_arguments(" the parser ").unwrap();
+        assert_eq!(args["focus"], "the parser");
+        let args = review.parse_arguments(r#"tone=terse focus="error paths""#).unwrap();
+        assert_eq!((args["focus"].as_str(), args["tone"].as_str()), ("error paths", "terse"));
+        assert!(review.parse_arguments("tone=terse").unwrap_err().to_string().contains("missing argument 'focus'"));
+
+        let text = client.get_prompt("remote.review", args).await.unwrap();
+        assert_eq!(text, "Review error paths\n\n[assistant]\nWhich files?");
+    }
+
+    #[tokio::test]
+    async fn test_legacy_sse_fallback() {
+        let stream: Arc<StdMutex<Option<mpsc::UnboundedSender<String>>>> = Arc::default();
+        let url = test_server::serve_requests(move |req: &MockRequest| match (req.method.as_str(), req.path.as_str()) {
+            // Not a Streamable HTTP server
+            ("POST", "/sse") => MockResponse::error(405, "method not allowed"),
+            ("GET", "/sse") => {
+                let (tx, rx) = mpsc::unbounded_channel();
+                tx.send("event: endpoint\ndata: /messages?session=7\n\n".to_string()).unwrap();
+                *stream.lock().unwrap() = Some(tx);
+                MockResponse::event_stream(rx)
+            }
+            ("POST", "/messages?session=7") => {
+                let message: Value = serde_json::from_str(&req.body).unwrap();
+                if let (Some(r), Some(tx)) = (reply(&message), stream.lock().unwrap().as_ref()) {
+                    tx.send(format!(": keep-alive\n\nevent: message\ndata: {r}\n\n")).unwrap();
+                }
+                MockResponse { status: 202, headers: vec![], body: String::new(), stream: None }
+            }
+            _ => MockResponse::error(404, "not found"),
+        })
+        .await;
+
+        let client = McpClient::new(&[remote(&format!("{url}/sse"), McpTransport::Auto)]).await;
+        assert_eq!(client.all_tools().await.len(), 1);
+        let out = client.call("remote.echo", json!({ "text": "over sse" })).await.unwrap();
+        assert_eq!(out, "over sse");
+        let err = client.call("remote.missing", json!({})).await.unwrap_err();
+        assert!(err.to_string().contains("unknown tool"), "{err}");
+
+        // Forcing Streamable HTTP keeps the 405
+        let forced = McpClient::new(&[remote(&format!("{url}/sse"), McpTransport::Http)]).await;
+        assert!(forced.all_tools().await.is_empty());
+    }
+
+    /// Poll `check` for up to five seconds.
+    async fn eventually(mut check: impl FnMut() -> bool) -> bool {
+        for _ in 0..100 {
+            if check() {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        false
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_calls_timeout_and_cancel() {
+        let cancelled: Arc<StdMutex<Vec<Value>>> = Arc::default();
+        let hung: Arc<StdMutex<Vec<mpsc::UnboundedSender<String>>>> = Arc::default();
+        let (log, hold) = (Arc::clone(&cancelled), Arc::clone(&hung));
+        let url = test_server::serve_requests(move |req: &MockRequest| {
+            if req.method == "GET" {
+                return MockResponse::error(405, "method not allowed");
+            }
+            let message: Value = serde_json::from_str(&req.body).unwrap();
+            if message["method"] == "notifications/cancelled" {
+                log.lock().unwrap().push(message["params"].clone());
+            }
+            if message["params"]["name"] == "slow" {
+                // Never answered
+                let (tx, rx) = mpsc::unbounded_channel();
+                hold.lock().unwrap().push(tx);
+                return MockResponse::event_stream(rx);
+            }
+            match reply(&message) {
+                Some(r) => MockResponse::json(&r.to_string()),
+                None => MockResponse { status: 202, headers: vec![], body: String::new(), stream: None },
+            }
+        })
+        .await;
+        let cfg = McpServerConfig { timeout_secs: 1, ..remote(&url, McpTransport::Http) };
+        let client = McpClient::new(&[cfg]).await;
+
+        let slow = tokio::spawn({
+            let client = Arc::clone(&client);
+            async move { client.call("remote.slow", json!({})).await }
+        });
+        // The slow call doesn't hold up another one to the same server
+        let quick = tokio::time::timeout(Duration::from_millis(500), client.call("remote.echo", json!({ "text": "quick" })));
+        assert_eq!(quick.await.expect("echo waited on the slow call").unwrap(), "quick");
+
+        let err = slow.await.unwrap().unwrap_err();
+        assert!(err.to_string().contains("did not answer tools/call within 1s"), "{err}");
+        // A caller giving up (the task was cancelled) cancels too
+        let dropped = tokio::time::timeout(Duration::from_millis(100), client.call("remote.slow", json!({}))).await;
+        assert!(dropped.is_err());
+
+        assert!(eventually(|| cancelled.lock().unwrap().len() == 2).await);
+        let reasons: Vec<Value> = cancelled.lock().unwrap().iter().map(|p| p["reason"].clone()).collect();
+        assert_eq!(reasons, vec![json!("timed out"), json!("cancelled by the client")]);
+        assert!(cancelled.lock().unwrap().iter().all(|p| p["requestId"].is_u64()));
+        assert_eq!(client.statuses()[0].status, McpServerStatus::Ready);
+    }
+
+    #[tokio::test]
+    async fn test_list_changed_refresh_and_restart() {
+        let stream: Arc<StdMutex<Option<mpsc::UnboundedSender<String>>>> = Arc::default();
+        let more_tools = Arc::new(AtomicBool::new(false));
+        let (slot, more) = (Arc::clone(&stream), Arc::clone(&more_tools));
+        let url = test_server::serve_requests(move |req: &MockRequest| match req.method.as_str() {
+            "GET" => {
+                let (tx, rx) = mpsc::unbounded_channel();
+                tx.send("event: endpoint\ndata: /messages\n\n".to_string()).unwrap();
+                *slot.lock().unwrap() = Some(tx);
+                MockResponse::event_stream(rx)
+            }
+            _ => {
+                let message: Value = serde_json::from_str(&req.body).unwrap();
+                let reply = match reply(&message) {
+                    Some(_) if message["method"] == "tools/list" && more.load(Ordering::SeqCst) => Some(json!({
+                        "jsonrpc": "2.0",
+                        "id": message["id"],
+                        "result": { "tools": [{ "name": "echo" }, { "name": "shout" }] }
+                    })),
+                    reply => reply,
+                };
+                if let (Some(r), Some(tx)) = (reply, slot.lock().unwrap().as_ref()) {
+                    tx.send(format!("data: {r}\n\n")).unwrap();
+                }
+                MockResponse { status: 202, headers: vec![], body: String::new(), stream: None }
+            }
+        })
+        .await;
+        let client = McpClient::new(&[remote(&url, McpTransport::Sse)]).await;
+        assert_eq!(client.statuses()[0].tools, 1);
+        let revision = client.revision();
+
+        // The server announces a new tool
+        more_tools.store(true, Ordering::SeqCst);
+        let announce = r#"{"jsonrpc":"2.0","method":"notifications/tools/list_changed"}"#;
+        stream.lock().unwrap().as_ref().unwrap().send(format!("data: {announce}\n\n")).unwrap();
+        assert!(eventually(|| client.statuses()[0].tools == 2).await, "tools not re-listed");
+        assert!(client.revision() > revision);
+        assert!(client.all_tools().await.iter().any(|t| t.qualified_name == "remote.shout"));
+
+        // The server drops its stream: reconnected after a backoff
+        drop(stream.lock().unwrap().take());
+        assert!(eventually(|| matches!(client.statuses()[0].status, McpServerStatus::Restarting { .. })).await);
+        assert!(client.call("remote.echo", json!({ "text": "x" })).await.unwrap_err().to_string().contains("restarting"));
+        assert!(eventually(|| client.statuses()[0].restarts == 1).await, "{:?}", client.statuses());
+        assert_eq!(client.statuses()[0].status, McpServerStatus::Ready);
+        let out = client.call("remote.echo", json!({ "text": "back" })).await.unwrap();
+        assert_eq!(out, "back");
+    }
+}
@@ -0,0 +1,303 @@
+/// `parecode mcp-serve` — parecode's own tools served over MCP (stdio).
+///
+/// Editors and other agents get the PIE tools (`orient`, `check_wiring`,
+/// `trace_calls`, `read_files`, …) and the hash-anchored edit tools from
+/// `tools::all_definitions()`. `ask_user` needs the TUI and is left out;
+/// `semantic_search` is served when `[embeddings]` is configured.
+///
+/// Calls go through `agent::dispatch_tool` against the loaded `ProjectGraph`
+/// and a session `FileCache`, with the agent's cache maintenance and
+/// `on_edit` hooks. An edit reindexes the file before the next call sees the
+/// graph; the file watcher picks up changes made by anyone else.
+///
+/// One JSON-RPC message per line. Calls run one at a time, in arrival order;
+/// `notifications/cancelled` aborts a call that is queued or running.
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc;
+
+use crate::agent::{self, AgentConfig, ToolKind};
+use crate::cache::FileCache;
+use crate::config::ResolvedConfig;
+use crate::index::IndexScope;
+use crate::pie::ProjectGraph;
+use crate::tools;
+use crate::tui::UiEvent;
+
+/// Protocol revisions we can speak; a client asking for another gets the newest.
+const PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// Tools that only make sense with a user at the keyboard.
+const UNSERVED_TOOLS: &[&str] = &[tools::TOOL_ASK_USER];
+
+/// Serve the project at `root` (default: the current directory) on
+/// stdin/stdout until stdin closes. Log lines go to stderr.
+pub async fn run(root: Option<PathBuf>, resolved: &ResolvedConfig) -> Result<()> {
+    if let Some(root) = root {
+        std::env::set_current_dir(&root)?;
+    }
+    let session = Session::new(resolved).await;
+    session.watch(IndexScope::new(&resolved.index));
+    eprintln!("[mcp-serve] serving {} tools", session.tools().len());
+    serve(session, tokio::io::BufReader::new(tokio::io::stdin()), tokio::io::stdout()).await
+}
+
+// ── Session ───────────────────────────────────────────────────────────────────
+
+/// What the served tools run against. Everything mutable sits behind one
+/// lock, which is what serializes calls.
+pub struct Session {
+    root: PathBuf,
+    scope: IndexScope,
+    /// `semantic_search` is served (`[embeddings]` is configured)
+    has_semantic: bool,
+    state: tokio::sync::Mutex<State>,
+}
+
+struct State {
+    config: AgentConfig,
+    cache: FileCache,
+}
+
+impl Session {
+    /// Load (or build) the graph of the current directory and set up the
+    /// agent config the tools are dispatched with.
+    pub async fn new(resolved: &ResolvedConfig) -> Arc<Self> {
+        let root = std::env::current_dir().unwrap_or_else(|_| ".".into());
+        let scope = IndexScope::new(&resolved.index);
+        let graph = {
+            let scope = scope.clone();
+            tokio::task::spawn_blocking(move || ProjectGraph::load_or_build(Path::new("."), &scope).0)
+                .await
+                .unwrap_or_default()
+        };
+        let hook_config = if !resolved.active_hook_config.is_empty() {
+            resolved.active_hook_config.clone()
+        } else {
+            resolved.hooks.clone()
+        };
+        let config = AgentConfig {
+            verbose: false,
+            dry_run: false,
+            context_tokens: resolved.context_tokens,
+            _profile_name: resolved.profile_name.clone(),
+            _model: resolved.model.clone(),
+            _show_timestamps: false,
+            // Tools are parecode's own; the profile's MCP servers are not proxied
+            mcp: crate::mcp::McpClient::new(&[]).await,
+            hooks: Arc::new(hook_config),
+            hooks_enabled: !resolved.hooks_disabled,
+            auto_commit: false,
+            auto_commit_prefix: String::new(),
+            git_context: false,
+            project_graph: Some(Arc::new(graph)),
+            live_graph: Arc::default(),
+            project_narrative: None,
+            flow_paths: crate::flowpaths::FlowPathIndex::load(Path::new(".")).map(Arc::new),
+            semantic: resolved
+                .embeddings
+                .as_ref()
+                .map(|e| Arc::new(crate::embeddings::SemanticIndex::new(e, Path::new(".")))),
+            attached_symbols: vec![],
+            attached_resources: vec![],
+            fallback_clients: vec![],
+            spend_limits: resolved.spend_limits,
+            spend: Arc::new(std::sync::Mutex::new(crate::budget::SpendGuard::default())),
+            // The client decides what its agent may run
+            permissions: Arc::default(),
+            workdir: None,
+        };
+        let has_semantic = config.semantic.is_some();
+        let state = tokio::sync::Mutex::new(State { config, cache: FileCache::default() });
+        Arc::new(Self { root, scope, has_semantic, state })
+    }
+
+    /// Keep the graph current while files change under us (same watcher as the TUI).
+    pub fn watch(self: &Arc<Self>, scope: IndexScope) {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        crate::watcher::spawn(self.root.clone(), scope, tx);
+        let session = Arc::clone(self);
+        tokio::spawn(async move {
+            while let Some(event) = rx.recv().await {
+                if let UiEvent::GraphUpdated { graph, .. } = event {
+                    let mut state = session.state.lock().await;
+                    state.config.project_graph = Some(Arc::new(graph));
+                    state.config.flow_paths = crate::flowpaths::FlowPathIndex::load(&session.root).map(Arc::new);
+                }
+            }
+        });
+    }
+
+    /// Whether a tool of `tools::all_definitions()` is offered to clients.
+    fn serves(&self, name: &str) -> bool {
+        !UNSERVED_TOOLS.contains(&name) && (self.has_semantic || name != tools::TOOL_SEMANTIC_SEARCH)
+    }
+
+    /// The served tools as MCP `tools/list` entries.
+    pub fn tools(&self) -> Vec<Value> {
+        tools::all_definitions()
+            .into_iter()
+            .filter(|t| self.serves(&t.name))
+            .map(|t| json!({ "name": t.name, "description": t.description, "inputSchema": t.parameters }))
+            .collect()
+    }
+
+    /// Run one tool. Returns its output and whether it reports an error.
+    pub async fn call_tool(&self, name: &str, args: Value) -> (String, bool) {
+        if !self.serves(name) || !tools::all_definitions().iter().any(|t| t.name == name) {
+            return (format!("[Tool error: unknown tool '{name}']"), true);
+        }
+        let mut state = self.state.lock().await;
+        let State { config, cache } = &mut *state;
+        let kind = ToolKind::classify(name, &args);
+
+        // Re-reads come from the cache while the file is unchanged on disk
+        if let Some(hit) = cached_read(&kind, &args, cache) {
+            return (hit, false);
+        }
+
+        // Hook and tool UI events have no audience here
+        let (ui_tx, _ui_rx) = mpsc::unbounded_channel();
+        let mut output = agent::dispatch_tool(name, &args, &kind, cache, &ui_tx, &config.mcp, config).await;
+        agent::maintain_cache(&kind, &args, cache);
+        if let ToolKind::Mutate { path } = &kind {
+            output.push_str(&agent::edit_hook_report(config, &ui_tx).await);
+            if !output.contains("[Tool error") {
+                self.reindex(config, path);
+            }
+        }
+        let is_error = agent::is_error_result(&output);
+        (output, is_error)
+    }
+
+    /// Apply an edit to the graph now, so the next call's `orient` or
+    /// `check_wiring` sees it without waiting for the watcher.
+    fn reindex(&self, config: &mut AgentConfig, path: &str) {
+        let rel = Path::new(path).strip_prefix(&self.root).unwrap_or(Path::new(path));
+        let rel = rel.to_string_lossy().trim_start_matches("./").to_string();
+        let Some(current) = &config.project_graph else { return };
+        if !self.scope.accepts(&rel) {
+            return;
+        }
+        let mut graph = ProjectGraph::clone(current);
+        if graph.apply_file_changes(&[rel], &self.root, &self.scope) {
+            graph.save(&self.root);
+            config.project_graph = Some(Arc::new(graph));
+        }
+    }
+}
+
+/// A `read_file` answered from the cache, as the agent would serve it.
+fn cached_read(kind: &ToolKind, args: &Value, cache: &FileCache) -> Option<String> {
+    let ToolKind::Read { path, has_range, is_symbols: false } = kind else { return None };
+    if !has_range {
+        return cache.check(path).map(|hit| hit.into_message());
+    }
+    let range = args["line_range"].as_array()?;
+    let start = range.first().and_then(|v| v.as_u64()).map(|n| (n as usize).saturating_sub(1)).unwrap_or(0);
+    let end = range.get(1).and_then(|v| v.as_u64()).map(|n| n as usize).unwrap_or(usize::MAX);
+    cache.check_range(path, start, end).map(|hit| hit.into_message())
+}
+
+// ── JSON-RPC loop ─────────────────────────────────────────────────────────────
+
+/// Answer requests from `input` on `output` until `input` ends, then wait
+/// for calls still running.
+pub async fn serve<R, W>(session: Arc<Session>, input: R, mut output: W) -> Result<()>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    let (out_tx, mut out_rx) = mpsc::unbounded_channel::<Value>();
+    let writer = tokio::spawn(async move {
+        while let Some(message) = out_rx.recv().await {
+            let line = format!("{message}\n");
+            if output.write_all(line.as_bytes()).await.is_err() || output.flush().await.is_err() {
+                break;
+            }
+        }
+    });
+
+    // Calls by request id, so a cancellation can abort them
+    let running: Arc<Mutex<HashMap<String, tokio::task::AbortHandle>>> = Arc::default();
+    let mut lines = input.lines();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let message: Value = match serde_json::from_str(&line) {
+            Ok(m) => m,
+            Err(e) => {
+                let _ = out_tx.send(error_response(Value::Null, PARSE_ERROR, &format!("Parse error: {e}")));
+                continue;
+            }
+        };
+        let method = message["method"].as_str().unwrap_or("");
+        let Some(id) = message.get("id").cloned() else {
+            // Notifications: only cancellation needs anything from us
+            if method == "notifications/cancelled" {
+                let key = message["params"]["requestId"].to_string();
+                if let Some(call) = running.lock().unwrap().remove(&key) {
+                    call.abort();
+                }
+            }
+            continue;
+        };
+        let reply = match method {
+            "initialize" => response(id, initialize_result(&message["params"])),
+            "ping" => response(id, json!({})),
+            "tools/list" => response(id, json!({ "tools": session.tools() })),
+            "tools/call" => {
+                let name = message["params"]["name"].as_str().unwrap_or("").to_string();
+                let args = match &message["params"]["arguments"] {
+                    Value::Null => json!({}),
+                    args => args.clone(),
+                };
+                let key = id.to_string();
+                // Held across the spawn so the call can't finish before it is registered
+                let mut calls = running.lock().unwrap();
+                let (session, out_tx, running) = (Arc::clone(&session), out_tx.clone(), Arc::clone(&running));
+                let call = tokio::spawn(async move {
+                    let (text, is_error) = session.call_tool(&name, args).await;
+                    running.lock().unwrap().remove(&id.to_string());
+                    let result = json!({ "content": [{ "type": "text", "text": text }], "isError": is_error });
+                    let _ = out_tx.send(response(id, result));
+                });
+                calls.insert(key, call.abort_handle());
+                continue;
+            }
+            _ => error_response(id, METHOD_NOT_FOUND, &format!("Method not found: {method}")),
+        };
+        let _ = out_tx.send(reply);
+    }
+
+    // Input closed: let running calls answer, then stop writing
+    drop(out_tx);
+    let _ = writer.await;
+    Ok(())
+}
+
+fn initialize_result(params: &Value) -> Value {
+    let requested = params["protocolVersion"].as_str().unwrap_or("");
+    let version = PROTOCOL_VERSIONS.iter().find(|v| **v == requested).unwrap_or(&PROTOCOL_VERSIONS[0]);
+    json!({
+        "protocolVersion": version,
+        "capabilities": { "tools": {} },
+        "serverInfo": { "name": "parecode", "version": env!("CARGO_PKG_VERSION") }
+    })
+}
+
+fn response(id: Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
@@ -481,7 +481,7 @@ mod tests {
         write(tmp.path(), "src/auth/token.rs", "pub fn validate() {}\n");
         write(tmp.path(), "src/server.rs", "pub fn start() {}\n");
         write(tmp.path(), "src/utils/fmt.rs", "pub fn format_date() {}\n");
-        let (graph, _) = crate::pie::ProjectGraph::load_or_build(tmp.path(), 100);
+        let (graph, _) = crate::pie::ProjectGraph::load_or_build(tmp.path(), &crate::index::IndexScope::default());
         graph
     }
 
@@ -600,7 +600,7 @@ mod tests {
 
         // Minimal graph with one file
         write(tmp.path(), "src/main.rs", "pub fn main() {}\n");
-        let (graph, _) = crate::pie::ProjectGraph::load_or_build(tmp.path(), 100);
+        let (graph, _) = crate::pie::ProjectGraph::load_or_build(tmp.path(), &crate::index::IndexScope::default());
 
         // Client with invalid endpoint — will fail the model call
         let client = crate::client::Client::new(
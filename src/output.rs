@@ -0,0 +1,365 @@
+/// Headless output — how `parecode "task"`, `parecode plan` and
+/// `parecode run-plan` report to scripts and CI.
+///
+/// `--output text` (default) prints human-readable progress. `json` prints one
+/// document at the end (`{"events": [...], "result": {...}}`); `ndjson` streams
+/// one record per line as events happen and ends with the `result` record.
+/// Every record has a `type` field (`tool_call`, `hook_output`, `agent_done`, …).
+/// Streamed text and thinking chunks are coalesced into one record per
+/// contiguous run.
+///
+/// The process exit code is derived from the run, see `RunStatus`.
+use serde_json::{Value, json};
+
+use crate::tui::UiEvent;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+    Ndjson,
+}
+
+/// Final status of a headless run. Maps 1:1 onto the process exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunStatus {
+    /// Task completed (exit 0)
+    Success,
+    /// Anything else went wrong — config, spend limit abort, internal error (exit 1)
+    Error,
+    /// The agent stopped at the tool-call limit before finishing (exit 2)
+    ToolBudget,
+    /// The model API failed after retries and fallbacks (exit 3)
+    ApiError,
+    /// The task completed but a task-done or plan-step-done hook exited non-zero (exit 4)
+    HookFailed,
+    /// A plan step ran but failed its verification (exit 5)
+    StepFailed,
+}
+
+impl RunStatus {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            RunStatus::Success => 0,
+            RunStatus::Error => 1,
+            RunStatus::ToolBudget => 2,
+            RunStatus::ApiError => 3,
+            RunStatus::HookFailed => 4,
+            RunStatus::StepFailed => 5,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RunStatus::Success => "success",
+            RunStatus::Error => "error",
+            RunStatus::ToolBudget => "tool_budget",
+            RunStatus::ApiError => "api_error",
+            RunStatus::HookFailed => "hook_failed",
+            RunStatus::StepFailed => "step_failed",
+        }
+    }
+}
+
+/// Whether `err` came from the model API (HTTP status or transport failure).
+pub fn is_api_error(err: &anyhow::Error) -> bool {
+    err.chain().any(|e| {
+        e.downcast_ref::<crate::client::retry::ApiError>().is_some()
+            || e.downcast_ref::<reqwest::Error>().is_some()
+    })
+}
+
+/// Structured record for one event. Chunks are handled by `HeadlessRun`.
+pub fn event_record(ev: &UiEvent) -> Value {
+    match ev {
+        UiEvent::Chunk(text) => json!({ "type": "text", "text": text }),
+        UiEvent::ThinkingChunk(text) => json!({ "type": "thinking", "text": text }),
+        UiEvent::ToolCall { name, args_summary } => {
+            json!({ "type": "tool_call", "name": name, "args": args_summary })
+        }
+        UiEvent::ToolResult { summary } => json!({ "type": "tool_result", "summary": summary }),
+        UiEvent::CacheHit { path, lines } => json!({ "type": "cache_hit", "path": path, "lines": lines }),
+        UiEvent::LoopWarning { tool_name } => json!({ "type": "loop_warning", "tool": tool_name }),
+        UiEvent::BudgetWarning => json!({ "type": "context_compressed" }),
+        UiEvent::ToolBudgetHit { limit } => json!({ "type": "tool_budget_hit", "limit": limit }),
+        UiEvent::AgentDone {
+            input_tokens, output_tokens, cache_read_tokens, cache_write_tokens,
+            cost_usd, model, tool_calls, compressed_count, duration_secs, cwd,
+        } => json!({
+            "type": "agent_done",
+            "input_tokens": input_tokens,
+            "output_tokens": output_tokens,
+            "cache_read_tokens": cache_read_tokens,
+            "cache_write_tokens": cache_write_tokens,
+            "cost_usd": cost_usd,
+            "model": model,
+            "tool_calls": tool_calls,
+            "compressed_count": compressed_count,
+            "duration_secs": duration_secs,
+            "cwd": cwd,
+        }),
+        UiEvent::AgentError(e) => json!({ "type": "agent_error", "error": e }),
+        UiEvent::ApiRetry { attempt, max_retries, delay_ms, reason } => json!({
+            "type": "api_retry",
+            "attempt": attempt,
+            "max_retries": max_retries,
+            "delay_ms": delay_ms,
+            "reason": reason,
+        }),
+        UiEvent::ModelFailover { from, to, reason } => {
+            json!({ "type": "model_failover", "from": from, "to": to, "reason": reason })
+        }
+        UiEvent::TokenStats {
+            total_input, total_output, total_cache_read, total_cache_write, total_cost, tool_calls, ..
+        } => json!({
+            "type": "token_stats",
+            "total_input": total_input,
+            "total_output": total_output,
+            "total_cache_read": total_cache_read,
+            "total_cache_write": total_cache_write,
+            "total_cost": total_cost,
+            "tool_calls": tool_calls,
+        }),
+        UiEvent::ContextUpdate { used, total, compressed } => {
+            json!({ "type": "context_update", "used": used, "total": total, "compressed": compressed })
+        }
+        UiEvent::PlanReady(plan) => json!({ "type": "plan_ready", "plan": plan }),
+        UiEvent::PlanGenerateFailed(e) => json!({ "type": "plan_generate_failed", "error": e }),
+        UiEvent::PlanStepStart { index, total, desc } => {
+            json!({ "type": "plan_step_start", "index": index, "total": total, "description": desc })
+        }
+        UiEvent::PlanStepDone { index, passed, error } => {
+            json!({ "type": "plan_step_done", "index": index, "passed": passed, "error": error })
+        }
+        UiEvent::PlanComplete { total } => json!({ "type": "plan_complete", "total": total }),
+        UiEvent::PlanFailed { step, error } => json!({ "type": "plan_failed", "step": step, "error": error }),
+        UiEvent::PlanReplanned { step, plan } => json!({ "type": "plan_replanned", "step": step, "plan": plan }),
+        UiEvent::HookOutput { event, output, exit_code } => {
+            json!({ "type": "hook_output", "event": event, "output": output, "exit_code": exit_code })
+        }
+        UiEvent::GitChanges { stat, checkpoint_hash, files_changed } => json!({
+            "type": "git_changes",
+            "stat": stat,
+            "checkpoint": checkpoint_hash,
+            "files_changed": files_changed,
+        }),
+        UiEvent::GitAutoCommit { message } => json!({ "type": "git_auto_commit", "message": message }),
+        UiEvent::GitError(e) => json!({ "type": "git_error", "error": e }),
+        UiEvent::SystemMsg(msg) => json!({ "type": "system", "message": msg }),
+        UiEvent::McpPromptReady { name, text } => json!({ "type": "mcp_prompt", "name": name, "text": text }),
+        UiEvent::AskUser { question, choices, .. } => {
+            json!({ "type": "ask_user", "question": question, "choices": choices })
+        }
+        UiEvent::SpendLimitHit { hit, .. } => json!({
+            "type": "spend_limit_hit",
+            "limit": hit.kind.setting(),
+            "used": hit.used,
+            "max": hit.limit,
+        }),
+        UiEvent::IndexReady { .. } => json!({ "type": "index_ready" }),
+        UiEvent::GraphUpdated { files, .. } => json!({ "type": "graph_updated", "files": files }),
+    }
+}
+
+/// Collects the outcome of a headless run and writes structured records.
+pub struct HeadlessRun {
+    format: OutputFormat,
+    /// Coalesced run of text or thinking chunks not yet emitted: (type, text)
+    pending: Option<(&'static str, String)>,
+    /// Records held back for the final document (`json` only)
+    events: Vec<Value>,
+    tool_budget_hit: bool,
+    /// An `on_task_done` / `on_plan_step_done` hook exited non-zero. `on_edit`
+    /// failures don't count — the model sees them and usually fixes the edit.
+    hook_failed: bool,
+    done: Option<Value>,
+}
+
+impl HeadlessRun {
+    pub fn new(format: OutputFormat) -> Self {
+        Self { format, pending: None, events: Vec::new(), tool_budget_hit: false, hook_failed: false, done: None }
+    }
+
+    /// Track an event and, for structured formats, record it.
+    pub fn observe(&mut self, ev: &UiEvent) {
+        match ev {
+            UiEvent::ToolBudgetHit { .. } => self.tool_budget_hit = true,
+            UiEvent::HookOutput { event, exit_code, .. }
+                if *exit_code != 0 && matches!(event.as_str(), "on_task_done" | "on_plan_step_done") =>
+            {
+                self.hook_failed = true
+            }
+            UiEvent::AgentDone { .. } => self.done = Some(event_record(ev)),
+            _ => {}
+        }
+        if self.format == OutputFormat::Text {
+            return;
+        }
+        let chunk = match ev {
+            UiEvent::Chunk(t) => Some(("text", t)),
+            UiEvent::ThinkingChunk(t) => Some(("thinking", t)),
+            _ => None,
+        };
+        match (chunk, &mut self.pending) {
+            (Some((kind, t)), Some((pending_kind, buf))) if *pending_kind == kind => buf.push_str(t),
+            (Some((kind, t)), _) => {
+                self.flush_pending();
+                self.pending = Some((kind, t.clone()));
+            }
+            (None, _) => {
+                self.flush_pending();
+                self.emit(event_record(ev));
+            }
+        }
+    }
+
+    /// Final status given how the agent task ended.
+    pub fn status(&self, result: &anyhow::Result<()>) -> RunStatus {
+        match result {
+            Err(e) if is_api_error(e) => RunStatus::ApiError,
+            Err(e) if e.chain().any(|c| c.is::<crate::plan::VerificationFailed>()) => RunStatus::StepFailed,
+            Err(_) => RunStatus::Error,
+            Ok(()) if self.tool_budget_hit => RunStatus::ToolBudget,
+            Ok(()) if self.hook_failed => RunStatus::HookFailed,
+            Ok(()) => RunStatus::Success,
+        }
+    }
+
+    /// The closing `result` record: status, exit code, error and final stats.
+    pub fn result_record(&self, result: &anyhow::Result<()>) -> Value {
+        let status = self.status(result);
+        let mut record = json!({
+            "type": "result",
+            "status": status.as_str(),
+            "exit_code": status.exit_code(),
+            "error": result.as_ref().err().map(|e| format!("{e:#}")),
+        });
+        if let (Some(done), Some(obj)) = (&self.done, record.as_object_mut()) {
+            for (k, v) in done.as_object().into_iter().flatten().filter(|(k, _)| *k != "type") {
+                obj.insert(k.clone(), v.clone());
+            }
+        }
+        record
+    }
+
+    /// Write the closing output for structured formats and return the status.
+    pub fn finish(mut self, result: &anyhow::Result<()>) -> RunStatus {
+        self.flush_pending();
+        let status = self.status(result);
+        let record = self.result_record(result);
+        match self.format {
+            OutputFormat::Text => {}
+            OutputFormat::Ndjson => write_line(&record.to_string()),
+            OutputFormat::Json => {
+                let doc = json!({ "events": std::mem::take(&mut self.events), "result": record });
+                write_line(&serde_json::to_string_pretty(&doc).unwrap_or_default());
+            }
+        }
+        status
+    }
+
+    fn flush_pending(&mut self) {
+        if let Some((kind, text)) = self.pending.take() {
+            self.emit(json!({ "type": kind, "text": text }));
+        }
+    }
+
+    fn emit(&mut self, record: Value) {
+        match self.format {
+            OutputFormat::Ndjson => write_line(&record.to_string()),
+            OutputFormat::Json => self.events.push(record),
+            OutputFormat::Text => {}
+        }
+    }
+}
+
+/// Write one line to stdout and flush. A closed pipe (`| head`) is not an error.
+fn write_line(line: &str) {
+    use std::io::Write;
+    let mut out = std::io::stdout().lock();
+    let _ = writeln!(out, "{line}");
+    let _ = out.flush();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn done() -> UiEvent {
+        UiEvent::AgentDone {
+            input_tokens: 100,
+            output_tokens: 20,
+            cache_read_tokens: 0,
+            cache_write_tokens: 0,
+            cost_usd: Some(0.01),
+            model: "m".to_string(),
+            tool_calls: 1,
+            compressed_count: 0,
+            duration_secs: 2,
+            cwd: "proj".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_chunks_are_coalesced_per_run() {
+        let mut run = HeadlessRun::new(OutputFormat::Json);
+        run.observe(&UiEvent::Chunk("Hel".into()));
+        run.observe(&UiEvent::Chunk("lo".into()));
+        run.observe(&UiEvent::ThinkingChunk("hmm".into()));
+        run.observe(&UiEvent::ToolCall { name: "bash".into(), args_summary: "ls".into() });
+        run.observe(&UiEvent::Chunk("done".into()));
+        run.flush_pending();
+        let types: Vec<&str> = run.events.iter().map(|e| e["type"].as_str().unwrap()).collect();
+        assert_eq!(types, ["text", "thinking", "tool_call", "text"]);
+        assert_eq!(run.events[0]["text"], "Hello");
+        assert_eq!(run.events[2]["name"], "bash");
+    }
+
+    #[test]
+    fn test_status_and_exit_codes() {
+        let ok: anyhow::Result<()> = Ok(());
+        let mut run = HeadlessRun::new(OutputFormat::Text);
+        run.observe(&done());
+        assert_eq!(run.status(&ok), RunStatus::Success);
+
+        run.observe(&UiEvent::HookOutput { event: "on_edit".into(), output: "fail".into(), exit_code: 1 });
+        assert_eq!(run.status(&ok), RunStatus::Success);
+        run.observe(&UiEvent::HookOutput { event: "on_task_done".into(), output: "fail".into(), exit_code: 1 });
+        assert_eq!(run.status(&ok).exit_code(), 4);
+        run.observe(&UiEvent::ToolBudgetHit { limit: 40 });
+        assert_eq!(run.status(&ok).exit_code(), 2);
+
+        let api: anyhow::Result<()> = Err(anyhow::Error::new(crate::client::retry::ApiError {
+            status: 529,
+            retry_after: None,
+            body: "overloaded".into(),
+        }));
+        assert_eq!(run.status(&api), RunStatus::ApiError);
+        assert_eq!(run.status(&Err(anyhow::anyhow!("stopped: limit"))).exit_code(), 1);
+
+        let verify: anyhow::Result<()> = Err(anyhow::Error::new(crate::plan::VerificationFailed {
+            step: 2,
+            reason: "pattern still present".into(),
+        }));
+        assert_eq!(run.status(&verify), RunStatus::StepFailed);
+        assert_eq!(run.status(&verify).exit_code(), 5);
+    }
+
+    #[test]
+    fn test_result_record_carries_final_stats() {
+        let mut run = HeadlessRun::new(OutputFormat::Ndjson);
+        run.done = Some(event_record(&done()));
+        let rec = run.result_record(&Ok(()));
+        assert_eq!(rec["type"], "result");
+        assert_eq!(rec["status"], "success");
+        assert_eq!(rec["exit_code"], 0);
+        assert_eq!(rec["input_tokens"], 100);
+        assert_eq!(rec["cost_usd"], 0.01);
+        assert!(rec["error"].is_null());
+
+        let rec = HeadlessRun::new(OutputFormat::Json).result_record(&Err(anyhow::anyhow!("boom")));
+        assert_eq!(rec["status"], "error");
+        assert_eq!(rec["error"], "boom");
+    }
+}
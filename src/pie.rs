@@ -1,25 +1,42 @@
 /// PIE Phase 1 — Persistent Symbol Graph.
 ///
 /// Replaces the ephemeral `SymbolIndex` (rebuilt on every `/plan`) with a
-/// `ProjectGraph` that persists to `.parecode/project.graph` (JSON).
+/// `ProjectGraph` that persists to `.parecode/project.graph` (MessagePack —
+/// JSON graphs from older versions still load).
 ///
 /// On a warm run:
-///   1. Load from disk (fast JSON parse, ~10ms)
+///   1. Load from disk (binary decode, tens of ms even for monorepos)
 ///   2. Diff file hashes via `git ls-files -s` (one subprocess) or mtime fallback
 ///   3. Reindex only changed files
-///   4. Recluster (cheap — pure directory grouping)
-///   5. Save and return
+///   4. Re-read package manifests and recluster (cheap — directory grouping
+///      within each crate / npm package / Go module, see `workspace`)
+///   5. Mine commits since the last pass into the co-change matrix (`cochange`)
+///   6. Save and return
 ///
-/// Cold run: full scan via `SymbolIndex::build()`, then persist.
-use std::collections::HashMap;
+/// Cold run: full scan via `SymbolIndex::build()`, then persist. Which files
+/// are covered is decided by `IndexScope` (ignore files + `[index]` globs);
+/// parsing and edge extraction run in parallel across cores.
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
+use std::sync::Arc;
 
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use crate::{client::{ContentPart, Message, MessageContent, ToolCall}, index::{CallEdge, Symbol, SymbolIndex, compute_end_lines, extract_symbols}};
+use crate::{callgraph::{CallExtractor, CallResolver, Lang}, cochange::CoChange, embeddings::SemanticHit, testmap::TestCase, client::{ContentPart, Message, MessageContent, ToolCall}, index::{CallEdge, IndexScope, Reference, Symbol, SymbolIndex, extract_files}, workspace::{self, Package}};
 
-const SCHEMA_VERSION: u32 = 6; // bumped: construct_edges now includes ::new()/::default() call sites
+const SCHEMA_VERSION: u32 = 11; // bumped: per-file test cases
+/// Oldest schema whose symbols are still valid — graphs from here up to
+/// `SCHEMA_VERSION` only need their edges re-extracted, not a full rebuild.
+const MIGRATABLE_SCHEMA: u32 = 8;
 const GRAPH_PATH: &str = ".parecode/project.graph";
+/// Focus files contributed by semantic search on top of keyword anchoring.
+const MAX_SEMANTIC_FOCUS_FILES: usize = 3;
+/// Focus files added because they usually change with ones already chosen.
+const MAX_COUPLED_FOCUS_FILES: usize = 2;
+/// Anchor score of a file that usually changes with an attached file —
+/// the same as one symbol-name match.
+const COUPLED_ANCHOR_SCORE: usize = 2;
 
 
 // ── Public types ──────────────────────────────────────────────────────────────
@@ -90,6 +107,10 @@ pub fn build_symbol_preload(symbols: &[AttachedSymbol]) -> String {
     out
 }
 
+/// The file watcher's latest graph, shared with running agents so a long
+/// task picks up edits made under it. None until the graph first changes.
+pub type LiveGraph = Arc<std::sync::RwLock<Option<Arc<ProjectGraph>>>>;
+
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct ProjectGraph {
     pub schema_version: u32,
@@ -101,14 +122,18 @@ pub struct ProjectGraph {
     pub symbols: Vec<Symbol>,
     /// symbol name → [files defining it]
     pub by_name: HashMap<String, Vec<String>>,
-    /// directory-based clusters
+    /// directory-based clusters, qualified by package in multi-package projects
     pub clusters: Vec<Cluster>,
+    /// Packages read from workspace manifests, with their inter-package
+    /// dependencies. Empty for projects without manifests.
+    #[serde(default)]
+    pub packages: Vec<Package>,
     /// Unix timestamp of the last index pass
     pub last_indexed: i64,
     /// Outgoing call edges extracted via tree-sitter.
     /// Key: `"src/file.rs::symbol_name"` — Value: project-internal calls made
     /// by that symbol, one entry per unique callee (first call site).
-    /// Only populated for `.rs` files; other languages have no entries yet.
+    /// Populated for every language `callgraph::Lang` has a grammar for.
     #[serde(default)]
     pub call_edges: HashMap<String, Vec<CallEdge>>,
     /// Struct/enum-variant construction edges extracted via tree-sitter.
@@ -117,13 +142,29 @@ pub struct ProjectGraph {
     /// or "AppState" (plain indexed struct literal). First construction site only.
     #[serde(default)]
     pub construct_edges: HashMap<String, Vec<CallEdge>>,
+    /// Uses of project names per file: types, field reads/writes, imports,
+    /// patterns and calls. Key: relative file path. Backs `find_references`.
+    #[serde(default)]
+    pub references: HashMap<String, Vec<Reference>>,
+    /// Test functions and `it`/`test` blocks per file, linked to the code
+    /// they exercise through the call graph (see `testmap`).
+    #[serde(default)]
+    pub tests: HashMap<String, Vec<TestCase>>,
+    /// Files that change together in git history, plus recent churn.
+    #[serde(default)]
+    pub cochange: CoChange,
 }
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Cluster {
     /// Derived from the top-level source subdirectory name:
-    /// `src/tui/mod.rs` → `"tui"`,  `src/plan.rs` → `"src"`
+    /// `src/tui/mod.rs` → `"tui"`,  `src/plan.rs` → `"src"`.
+    /// With several packages it is relative to the package and prefixed by
+    /// its name: `crates/core/src/tui/mod.rs` → `"app-core/tui"`
     pub name: String,
+    /// Package the files belong to, if any
+    #[serde(default)]
+    pub package: Option<String>,
     /// All relative file paths in this cluster
     pub files: Vec<String>,
     /// Top-3 files by symbol count — "most important" proxy
@@ -139,18 +180,19 @@ impl ProjectGraph {
     ///
     /// Returns `(graph, was_warm)`. `was_warm = true` means the graph was
     /// loaded from disk and only incrementally updated.
-    pub fn load_or_build(root: &Path, max_files: usize) -> (Self, bool) {
+    pub fn load_or_build(root: &Path, scope: &IndexScope) -> (Self, bool) {
         // Attempt warm load
         let graph_path = root.join(GRAPH_PATH);
-        if let Ok(content) = std::fs::read_to_string(&graph_path) {
-            if let Ok(mut g) = serde_json::from_str::<ProjectGraph>(&content) {
+        if let Ok(content) = std::fs::read(&graph_path) {
+            if let Some(mut g) = decode(&content) {
+                if (MIGRATABLE_SCHEMA..SCHEMA_VERSION).contains(&g.schema_version) {
+                    // Older edge extraction — keep the symbols, redo the edges
+                    g.schema_version = SCHEMA_VERSION;
+                    g.rebuild_call_edges(root);
+                }
                 if g.schema_version == SCHEMA_VERSION {
-                    // Migrate graphs persisted before end_line was added (all zeros)
-                    if g.symbols.iter().any(|s| s.end_line == 0) {
-                        compute_end_lines(&mut g.symbols, &g.file_lines);
-                    }
                     // Incremental update
-                    g.incremental_update(root, max_files);
+                    g.incremental_update(root, scope);
                     g.save(root);
                     return (g, true);
                 }
@@ -158,16 +200,74 @@ impl ProjectGraph {
         }
 
         // Cold build
-        let g = Self::build_fresh(root, max_files);
+        let g = Self::build_fresh(root, scope);
         g.save(root);
         (g, false)
     }
 
+    /// Read `.parecode/project.graph` as-is — no hash diff, no migration.
+    /// `None` when missing, unreadable, or from another schema.
+    pub fn load(root: &Path) -> Option<Self> {
+        let g = decode(&std::fs::read(root.join(GRAPH_PATH)).ok()?)?;
+        (g.schema_version == SCHEMA_VERSION).then_some(g)
+    }
+
+    /// Apply edits to specific files (as reported by the file watcher):
+    /// reindex the ones that exist, drop the ones that are gone, and refresh
+    /// their call edges. Flow paths are rebuilt only if the edges changed.
+    /// New files are only picked up if `scope` covers them.
+    /// Returns `true` when the graph was modified.
+    pub fn apply_file_changes(&mut self, paths: &[String], root: &Path, scope: &IndexScope) -> bool {
+        let (mut present, removed): (Vec<String>, Vec<String>) =
+            paths.iter().cloned().partition(|p| root.join(p).is_file());
+        if present.iter().any(|p| !self.file_lines.contains_key(p)) {
+            // Ignore files can't be checked per path — ask the walk
+            let indexable: HashSet<String> = scope.files(root).into_iter().collect();
+            present.retain(|p| self.file_lines.contains_key(p) || indexable.contains(p));
+        }
+        let removed: Vec<String> = removed.into_iter().filter(|p| self.file_lines.contains_key(p)).collect();
+        if present.is_empty() && removed.is_empty() {
+            return false;
+        }
+
+        let before = self.edge_fingerprint(paths);
+        for path in &removed {
+            self.remove_file(path);
+        }
+        self.reindex_files(&present, root);
+        for path in &present {
+            // Git blob hashes only move on `git add` — mark so the next startup re-reads it
+            self.file_hashes.insert(path.clone(), "watch".to_string());
+        }
+        self.refresh_call_edges(&present, root);
+        if self.edge_fingerprint(paths) != before {
+            crate::flowpaths::FlowPathIndex::build_and_save(self, root);
+        }
+
+        self.clusters = build_clusters(&self.symbols, &self.file_lines, &self.packages);
+        self.last_indexed = chrono::Utc::now().timestamp();
+        true
+    }
+
+    /// (caller, callee, target) for every call edge originating in `files` —
+    /// what flow paths depend on, ignoring line numbers.
+    fn edge_fingerprint(&self, files: &[String]) -> Vec<(String, String, Option<String>)> {
+        let prefixes: Vec<String> = files.iter().map(|f| format!("{f}::")).collect();
+        let mut out: Vec<_> = self
+            .call_edges
+            .iter()
+            .filter(|(k, _)| prefixes.iter().any(|p| k.starts_with(p)))
+            .flat_map(|(k, edges)| edges.iter().map(move |e| (k.clone(), e.callee.clone(), e.target.clone())))
+            .collect();
+        out.sort();
+        out
+    }
+
     /// Full rebuild from scratch using the existing `SymbolIndex` extractor.
-    pub fn build_fresh(root: &Path, max_files: usize) -> Self {
-        let idx = SymbolIndex::build(root, max_files);
+    pub fn build_fresh(root: &Path, scope: &IndexScope) -> Self {
+        let idx = SymbolIndex::build(root, scope);
 
-        let file_hashes = get_current_hashes(root);
+        let file_hashes = get_current_hashes(root, scope);
         let mut g = ProjectGraph {
             schema_version: SCHEMA_VERSION,
             file_hashes,
@@ -175,23 +275,29 @@ impl ProjectGraph {
             symbols: idx.symbols,
             by_name: idx.by_name,
             clusters: Vec::new(),
+            packages: workspace::detect(root, scope),
             last_indexed: chrono::Utc::now().timestamp(),
             call_edges: HashMap::new(),
             construct_edges: HashMap::new(),
+            references: HashMap::new(),
+            tests: HashMap::new(),
+            cochange: CoChange::default(),
         };
-        g.clusters = build_clusters(&g.symbols, &g.file_lines);
+        g.clusters = build_clusters(&g.symbols, &g.file_lines, &g.packages);
         g.rebuild_call_edges(root);
+        g.cochange.update(root);
         g
     }
 
     /// Diff stored hashes against current; reindex changed files, strip removed ones.
-    fn incremental_update(&mut self, root: &Path, _max_files: usize) {
-        let current = get_current_hashes(root);
+    fn incremental_update(&mut self, root: &Path, scope: &IndexScope) {
+        let current = get_current_hashes(root, scope);
 
-        // Files that changed or are new
+        // Files that changed or are new — or were hashed but never indexed
+        // (graphs from before the file cap was lifted)
         let mut changed: Vec<String> = Vec::new();
         for (path, hash) in &current {
-            if self.file_hashes.get(path) != Some(hash) {
+            if self.file_hashes.get(path) != Some(hash) || !self.file_lines.contains_key(path) {
                 changed.push(path.clone());
             }
         }
@@ -217,8 +323,10 @@ impl ProjectGraph {
         // Update hashes map to current state
         self.file_hashes = current;
 
-        // Recluster (cheap)
-        self.clusters = build_clusters(&self.symbols, &self.file_lines);
+        // Manifests may have changed too; recluster (cheap)
+        self.packages = workspace::detect(root, scope);
+        self.clusters = build_clusters(&self.symbols, &self.file_lines, &self.packages);
+        self.cochange.update(root);
         self.last_indexed = chrono::Utc::now().timestamp();
     }
 
@@ -238,82 +346,93 @@ impl ProjectGraph {
         let prefix = format!("{}::", path);
         self.call_edges.retain(|k, _| !k.starts_with(&prefix));
         self.construct_edges.retain(|k, _| !k.starts_with(&prefix));
+        self.references.remove(path);
+        self.tests.remove(path);
     }
 
-    /// (Re)build call edges for all Rust files from scratch, then rebuild
-    /// flow paths from the updated edge data.
+    /// (Re)build call edges for all supported source files from scratch, then
+    /// rebuild flow paths from the updated edge data.
     /// Called after a cold build; `by_name` must be fully populated first.
     fn rebuild_call_edges(&mut self, root: &Path) {
         self.call_edges.clear();
         self.construct_edges.clear();
-        let mut extractor = match crate::callgraph::CallExtractor::new() {
-            Ok(e) => e,
-            Err(_) => return, // grammar ABI mismatch — skip silently
-        };
-        let rust_files: Vec<String> = self
-            .file_lines
-            .keys()
-            .filter(|f| f.ends_with(".rs"))
-            .cloned()
-            .collect();
-        for file in &rust_files {
-            let abs = root.join(file);
-            let Ok(content) = std::fs::read_to_string(&abs) else { continue };
-            let edges = extractor.extract_file(&content, file, &self.symbols, &self.by_name);
-            self.call_edges.extend(edges);
-            let constructions = extractor.extract_constructions(&content, file, &self.symbols, &self.by_name);
-            self.construct_edges.extend(constructions);
-            // Enrich struct/enum/trait signatures from the same file parse.
-            let sigs = extractor.extract_signatures(&content);
-            for sym in self.symbols.iter_mut() {
-                if sym.file == *file && !sigs.is_empty()
-                    && matches!(sym.kind, crate::index::SymbolKind::Struct | crate::index::SymbolKind::Enum | crate::index::SymbolKind::Trait)
-                {
-                    if let Some(sig) = sigs.get(&sym.name) {
-                        sym.signature = Some(sig.clone());
-                    }
-                }
-            }
-        }
+        self.references.clear();
+        self.tests.clear();
+        let files: Vec<String> = self.file_lines.keys().cloned().collect();
+        self.extract_edges(&files, root);
         crate::flowpaths::FlowPathIndex::build_and_save(self, root);
     }
 
     /// Update call edges for a set of changed files, then rebuild flow paths.
     /// Must be called after `reindex_files` so `by_name` reflects the new symbols.
     fn update_call_edges(&mut self, changed: &[String], root: &Path) {
+        self.refresh_call_edges(changed, root);
+        crate::flowpaths::FlowPathIndex::build_and_save(self, root);
+    }
+
+    /// Re-extract call and construction edges for `changed`, leaving flow
+    /// paths untouched.
+    fn refresh_call_edges(&mut self, changed: &[String], root: &Path) {
         // Drop stale edges for changed files.
-        for file in changed {
-            let prefix = format!("{}::", file);
-            self.call_edges.retain(|k, _| !k.starts_with(&prefix));
-            self.construct_edges.retain(|k, _| !k.starts_with(&prefix));
+        let changed_set: HashSet<&str> = changed.iter().map(String::as_str).collect();
+        let stale = |key: &String| key.split("::").next().is_some_and(|f| changed_set.contains(f));
+        self.call_edges.retain(|k, _| !stale(k));
+        self.construct_edges.retain(|k, _| !stale(k));
+        self.references.retain(|file, _| !changed_set.contains(file.as_str()));
+        self.tests.retain(|file, _| !changed_set.contains(file.as_str()));
+        self.extract_edges(changed, root);
+    }
+
+    /// Parse `files` across all cores and merge their call, construction and
+    /// reference edges, test cases — plus (Rust) struct/enum/trait signatures —
+    /// into the graph.
+    fn extract_edges(&mut self, files: &[String], root: &Path) {
+        if CallExtractor::new().is_err() {
+            return; // grammar ABI mismatch — skip silently
         }
-        let mut extractor = match crate::callgraph::CallExtractor::new() {
-            Ok(e) => e,
-            Err(_) => return,
-        };
-        for file in changed {
-            if !file.ends_with(".rs") {
+        let resolver = Arc::new(CallResolver::new(&self.symbols));
+        // Symbols are sorted by file — hand each file only its own
+        let by_file: HashMap<&str, &[Symbol]> = self
+            .symbols
+            .chunk_by(|a, b| a.file == b.file)
+            .map(|chunk| (chunk[0].file.as_str(), chunk))
+            .collect();
+        let by_name = &self.by_name;
+        let extracted: Vec<FileEdges> = files
+            .par_iter()
+            .filter(|f| Lang::from_path(f).is_some())
+            .map_init(
+                || CallExtractor::with_resolver(resolver.clone()).ok(),
+                |extractor, file| {
+                    let symbols = by_file.get(file.as_str()).copied().unwrap_or_default();
+                    extract_file_edges(extractor.as_mut()?, file, root, symbols, by_name)
+                },
+            )
+            .flatten()
+            .collect();
+
+        let mut signatures: HashMap<String, HashMap<String, String>> = HashMap::new();
+        for edges in extracted {
+            self.call_edges.extend(edges.calls);
+            self.construct_edges.extend(edges.constructions);
+            if !edges.references.is_empty() {
+                self.references.insert(edges.file.clone(), edges.references);
+            }
+            if !edges.tests.is_empty() {
+                self.tests.insert(edges.file.clone(), edges.tests);
+            }
+            if !edges.signatures.is_empty() {
+                signatures.insert(edges.file, edges.signatures);
+            }
+        }
+        for sym in self.symbols.iter_mut() {
+            if !matches!(sym.kind, crate::index::SymbolKind::Struct | crate::index::SymbolKind::Enum | crate::index::SymbolKind::Trait) {
                 continue;
             }
-            let abs = root.join(file);
-            let Ok(content) = std::fs::read_to_string(&abs) else { continue };
-            let edges = extractor.extract_file(&content, file, &self.symbols, &self.by_name);
-            self.call_edges.extend(edges);
-            let constructions = extractor.extract_constructions(&content, file, &self.symbols, &self.by_name);
-            self.construct_edges.extend(constructions);
-            // Enrich struct/enum/trait signatures from the same file parse.
-            let sigs = extractor.extract_signatures(&content);
-            for sym in self.symbols.iter_mut() {
-                if sym.file == *file && !sigs.is_empty()
-                    && matches!(sym.kind, crate::index::SymbolKind::Struct | crate::index::SymbolKind::Enum | crate::index::SymbolKind::Trait)
-                {
-                    if let Some(sig) = sigs.get(&sym.name) {
-                        sym.signature = Some(sig.clone());
-                    }
-                }
+            if let Some(sig) = signatures.get(&sym.file).and_then(|sigs| sigs.get(&sym.name)) {
+                sym.signature = Some(sig.clone());
             }
         }
-        crate::flowpaths::FlowPathIndex::build_and_save(self, root);
     }
 
     /// Look up all project symbols that call `callee_name`.
@@ -331,30 +450,75 @@ impl ProjectGraph {
             .collect()
     }
 
+    /// Callers of one specific definition: edges resolved to `key`, plus
+    /// unresolved edges that merely match its name.
+    pub fn callers_of_key(&self, key: &str) -> Vec<&str> {
+        let name = key.rsplit("::").next().unwrap_or(key);
+        self.call_edges
+            .iter()
+            .filter(|(_, edges)| {
+                edges.iter().any(|e| match &e.target {
+                    Some(target) => target == key,
+                    None => e.callee == name,
+                })
+            })
+            .map(|(caller_key, _)| caller_key.as_str())
+            .collect()
+    }
+
     /// Look up all callees of a symbol identified by its key (`"file::name"`).
     pub fn callees_of(&self, caller_key: &str) -> &[CallEdge] {
         self.call_edges.get(caller_key).map(|v| v.as_slice()).unwrap_or(&[])
     }
 
-    /// Re-extract symbols for `paths`, replacing stale entries.
-    pub fn reindex_files(&mut self, paths: &[String], root: &Path) {
-        for rel_path in paths {
-            // Strip old symbols for this file
-            self.symbols.retain(|s| &s.file != rel_path);
-            for files in self.by_name.values_mut() {
-                files.retain(|f| f != rel_path);
-            }
-            self.by_name.retain(|_, files| !files.is_empty());
+    /// Keys an edge may lead to: its resolved target, else every callable
+    /// definition sharing the callee's name.
+    pub fn callee_keys(&self, edge: &CallEdge) -> Vec<String> {
+        if let Some(target) = &edge.target {
+            return vec![target.clone()];
+        }
+        self.symbols
+            .iter()
+            .filter(|s| s.name == edge.callee && s.kind != crate::index::SymbolKind::Impl)
+            .map(Symbol::key)
+            .collect()
+    }
 
-            // Re-extract from disk
-            let abs = root.join(rel_path);
-            let Ok(content) = std::fs::read_to_string(&abs) else { continue };
+    /// Symbol for a key as produced by `Symbol::key` (`"file::Type::name"`).
+    pub fn symbol_by_key(&self, key: &str) -> Option<&Symbol> {
+        let file = key.split("::").next()?;
+        self.symbols.iter().find(|s| s.file == file && s.key() == key)
+    }
 
-            let line_count = content.lines().count();
-            self.file_lines.insert(rel_path.clone(), line_count);
+    /// Every indexed use of `name`, as (file, reference), file-ordered.
+    pub fn references_to(&self, name: &str) -> Vec<(&str, &Reference)> {
+        let mut out: Vec<(&str, &Reference)> = self
+            .references
+            .iter()
+            .flat_map(|(file, refs)| refs.iter().filter(|r| r.name == name).map(move |r| (file.as_str(), r)))
+            .collect();
+        out.sort_by(|a, b| a.0.cmp(b.0).then(a.1.line.cmp(&b.1.line)));
+        out
+    }
 
-            let mut new_syms: Vec<Symbol> = Vec::new();
-            extract_symbols(&content, rel_path, &mut new_syms);
+    /// Impl/class symbols that implement the trait or interface `name`.
+    pub fn implementors_of(&self, name: &str) -> Vec<&Symbol> {
+        self.symbols.iter().filter(|s| s.implements.iter().any(|t| t == name)).collect()
+    }
+
+    /// Re-extract symbols for `paths`, replacing stale entries.
+    pub fn reindex_files(&mut self, paths: &[String], root: &Path) {
+        // Strip old symbols for these files
+        let stale: HashSet<&str> = paths.iter().map(String::as_str).collect();
+        self.symbols.retain(|s| !stale.contains(s.file.as_str()));
+        for files in self.by_name.values_mut() {
+            files.retain(|f| !stale.contains(f.as_str()));
+        }
+        self.by_name.retain(|_, files| !files.is_empty());
+
+        // Re-extract from disk
+        for (rel_path, line_count, new_syms) in extract_files(root, paths) {
+            self.file_lines.insert(rel_path, line_count);
 
             // Update by_name
             for sym in &new_syms {
@@ -367,18 +531,23 @@ impl ProjectGraph {
             self.symbols.extend(new_syms);
         }
 
-        // Re-sort by file + line, then recompute end lines
+        // Re-sort by file + line
         self.symbols
             .sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)));
-        compute_end_lines(&mut self.symbols, &self.file_lines);
     }
 
-    /// Save graph to `.parecode/project.graph` (JSON).
+    /// Save graph to `.parecode/project.graph` (MessagePack). Written to a
+    /// temp file and renamed so the watcher thread never reads half a graph.
     pub fn save(&self, root: &Path) {
         let dir = root.join(".parecode");
         let _ = std::fs::create_dir_all(&dir);
-        if let Ok(json) = serde_json::to_string(self) {
-            let _ = std::fs::write(root.join(GRAPH_PATH), json);
+        if let Ok(bytes) = rmp_serde::to_vec_named(self) {
+            static SAVES: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+            let n = SAVES.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            let tmp = dir.join(format!("project.graph.{}-{n}.tmp", std::process::id()));
+            if std::fs::write(&tmp, bytes).is_ok() {
+                let _ = std::fs::rename(&tmp, root.join(GRAPH_PATH));
+            }
         }
         append_gitignore_if_needed(root);
     }
@@ -420,6 +589,14 @@ impl ProjectGraph {
     /// Key files: src/tui/mod.rs (3100 lines), src/tui/render.rs (448 lines)
     /// fn run, struct AppState, enum Mode … (42 symbols)
     /// ```
+    ///
+    /// With more than one package, clusters are grouped under a heading per
+    /// package that names its directory and the packages it depends on:
+    /// ```text
+    /// ## Package app-web (crates/web, cargo, 12 files) → depends on app-core
+    ///
+    /// ### app-web/routes (9 files)
+    /// ```
     pub fn to_prompt_section(&self, max_clusters: usize) -> Option<String> {
         if self.clusters.is_empty() {
             // Fall back to flat symbol list if clustering produced nothing
@@ -427,6 +604,9 @@ impl ProjectGraph {
         }
 
         let total_files: usize = self.clusters.iter().map(|c| c.files.len()).sum();
+        if self.packages.len() > 1 {
+            return Some(self.package_prompt_section(max_clusters, total_files));
+        }
         let mut out = format!(
             "# Project structure — {} clusters, {} files\n",
             self.clusters.len(),
@@ -434,49 +614,7 @@ impl ProjectGraph {
         );
 
         for cluster in self.clusters.iter().take(max_clusters) {
-            out.push('\n');
-            out.push_str(&format!("## {} ({} files)\n", cluster.name, cluster.files.len()));
-
-            // Key files line
-            if !cluster.entry_files.is_empty() {
-                let key_parts: Vec<String> = cluster
-                    .entry_files
-                    .iter()
-                    .map(|f| {
-                        let lines = self.file_lines.get(f).copied().unwrap_or(0);
-                        if lines > 0 {
-                            format!("{f} ({lines} lines)")
-                        } else {
-                            f.clone()
-                        }
-                    })
-                    .collect();
-                out.push_str(&format!("Key files: {}\n", key_parts.join(", ")));
-            }
-
-            // Symbol list (capped)
-            let syms: Vec<String> = self
-                .symbols
-                .iter()
-                .filter(|s| cluster.files.contains(&s.file))
-                .take(10)
-                .map(|s| format!("{} {}", s.kind.label(), s.name))
-                .collect();
-
-            let total_syms = self
-                .symbols
-                .iter()
-                .filter(|s| cluster.files.contains(&s.file))
-                .count();
-
-            if !syms.is_empty() {
-                let ellipsis = if total_syms > 10 {
-                    format!(" … ({total_syms} symbols)")
-                } else {
-                    String::new()
-                };
-                out.push_str(&format!("{}{}\n", syms.join(", "), ellipsis));
-            }
+            self.push_cluster_section(&mut out, cluster, "##");
         }
 
         if self.clusters.len() > max_clusters {
@@ -488,6 +626,105 @@ impl ProjectGraph {
 
         Some(out)
     }
+
+    /// `to_prompt_section` for multi-package projects. Packages come in order
+    /// of their largest cluster; files outside every package come last.
+    fn package_prompt_section(&self, max_clusters: usize, total_files: usize) -> String {
+        let mut out = format!(
+            "# Project structure — {} packages, {} clusters, {} files\n",
+            self.packages.len(),
+            self.clusters.len(),
+            total_files
+        );
+
+        let mut order: Vec<Option<&str>> = Vec::new();
+        for cluster in &self.clusters {
+            if !order.contains(&cluster.package.as_deref()) {
+                order.push(cluster.package.as_deref());
+            }
+        }
+        if let Some(i) = order.iter().position(Option::is_none) {
+            let outside = order.remove(i);
+            order.push(outside);
+        }
+
+        let mut shown = 0;
+        for package in order {
+            if shown >= max_clusters {
+                break;
+            }
+            let clusters: Vec<&Cluster> = self.clusters.iter().filter(|c| c.package.as_deref() == package).collect();
+            let files: usize = clusters.iter().map(|c| c.files.len()).sum();
+            out.push('\n');
+            match package.and_then(|name| self.packages.iter().find(|p| p.name == name)) {
+                Some(p) => {
+                    let dir = if p.dir.is_empty() { "." } else { p.dir.as_str() };
+                    out.push_str(&format!("## Package {} ({dir}, {}, {files} files)", p.name, p.kind.label()));
+                    if !p.dependencies.is_empty() {
+                        out.push_str(&format!(" → depends on {}", p.dependencies.join(", ")));
+                    }
+                    out.push('\n');
+                }
+                None => out.push_str(&format!("## Outside any package ({files} files)\n")),
+            }
+            for cluster in clusters.into_iter().take(max_clusters - shown) {
+                self.push_cluster_section(&mut out, cluster, "###");
+                shown += 1;
+            }
+        }
+
+        if self.clusters.len() > shown {
+            out.push_str(&format!("\n… and {} more clusters\n", self.clusters.len() - shown));
+        }
+        out
+    }
+
+    /// Heading, key files and a capped symbol list for one cluster.
+    fn push_cluster_section(&self, out: &mut String, cluster: &Cluster, heading: &str) {
+        out.push('\n');
+        out.push_str(&format!("{heading} {} ({} files)\n", cluster.name, cluster.files.len()));
+
+        // Key files line
+        if !cluster.entry_files.is_empty() {
+            let key_parts: Vec<String> = cluster
+                .entry_files
+                .iter()
+                .map(|f| {
+                    let lines = self.file_lines.get(f).copied().unwrap_or(0);
+                    if lines > 0 {
+                        format!("{f} ({lines} lines)")
+                    } else {
+                        f.clone()
+                    }
+                })
+                .collect();
+            out.push_str(&format!("Key files: {}\n", key_parts.join(", ")));
+        }
+
+        // Symbol list (capped)
+        let syms: Vec<String> = self
+            .symbols
+            .iter()
+            .filter(|s| cluster.files.contains(&s.file))
+            .take(10)
+            .map(|s| format!("{} {}", s.kind.label(), s.name))
+            .collect();
+
+        let total_syms = self
+            .symbols
+            .iter()
+            .filter(|s| cluster.files.contains(&s.file))
+            .count();
+
+        if !syms.is_empty() {
+            let ellipsis = if total_syms > 10 {
+                format!(" … ({total_syms} symbols)")
+            } else {
+                String::new()
+            };
+            out.push_str(&format!("{}{}\n", syms.join(", "), ellipsis));
+        }
+    }
 }
 
 // ── Clustering ────────────────────────────────────────────────────────────────
@@ -495,7 +732,8 @@ impl ProjectGraph {
 /// Group files into directory-based clusters.
 /// `src/tui/mod.rs` → cluster `tui`
 /// `src/plan.rs`    → cluster `src` (flat source files)
-fn build_clusters(symbols: &[Symbol], file_lines: &HashMap<String, usize>) -> Vec<Cluster> {
+/// With several packages the same rules apply inside each package.
+fn build_clusters(symbols: &[Symbol], file_lines: &HashMap<String, usize>, packages: &[Package]) -> Vec<Cluster> {
     // Count symbols per file for entry_files selection
     let mut sym_count: HashMap<&str, usize> = HashMap::new();
     for s in symbols {
@@ -503,15 +741,19 @@ fn build_clusters(symbols: &[Symbol], file_lines: &HashMap<String, usize>) -> Ve
     }
 
     // Group files by cluster name
-    let mut groups: HashMap<String, Vec<String>> = HashMap::new();
+    let mut groups: HashMap<(String, Option<String>), Vec<String>> = HashMap::new();
     for path in file_lines.keys() {
-        let cluster_name = cluster_name_for(path);
-        groups.entry(cluster_name).or_default().push(path.clone());
+        let package = workspace::package_for(packages, path);
+        let cluster_name = match package {
+            Some(p) if packages.len() > 1 => package_cluster_name(p, path),
+            _ => cluster_name_for(path),
+        };
+        groups.entry((cluster_name, package.map(|p| p.name.clone()))).or_default().push(path.clone());
     }
 
     let mut clusters: Vec<Cluster> = groups
         .into_iter()
-        .map(|(name, mut files)| {
+        .map(|((name, package), mut files)| {
             // Sort files for determinism
             files.sort();
 
@@ -529,6 +771,7 @@ fn build_clusters(symbols: &[Symbol], file_lines: &HashMap<String, usize>) -> Ve
 
             Cluster {
                 name,
+                package,
                 files,
                 entry_files,
                 summary: None,
@@ -562,19 +805,30 @@ fn cluster_name_for(path: &str) -> String {
     }
 }
 
+/// Cluster name for `path` inside package `p`: the package name for its
+/// top-level and flat `src/` files, `"{package}/{dir}"` below that.
+fn package_cluster_name(p: &Package, path: &str) -> String {
+    let inner = if p.dir.is_empty() { path } else { &path[p.dir.len() + 1..] };
+    match cluster_name_for(inner).as_str() {
+        "src" | "root" => p.name.clone(),
+        dir => format!("{}/{dir}", p.name),
+    }
+}
+
 // ── Change detection ──────────────────────────────────────────────────────────
 
-/// Get current file hashes: git blob hashes (one subprocess) or mtime fallback.
-pub fn get_current_hashes(root: &Path) -> HashMap<String, String> {
+/// Get current hashes for the files `scope` covers: git blob hashes (one
+/// subprocess) where tracked, mtime for everything else.
+pub fn get_current_hashes(root: &Path, scope: &IndexScope) -> HashMap<String, String> {
     // Try git ls-files -s (one call regardless of repo size)
     let output = std::process::Command::new("git")
         .args(["ls-files", "-s"])
         .current_dir(root)
         .output();
 
+    let mut blobs: HashMap<String, String> = HashMap::new();
     if let Ok(out) = output {
         if out.status.success() {
-            let mut hashes = HashMap::new();
             if let Ok(text) = std::str::from_utf8(&out.stdout) {
                 for line in text.lines() {
                     // Format: "mode hash stage\tpath"
@@ -582,78 +836,84 @@ pub fn get_current_hashes(root: &Path) -> HashMap<String, String> {
                     let path = &line[tab_pos + 1..];
                     let parts: Vec<&str> = line[..tab_pos].split_whitespace().collect();
                     if parts.len() >= 2 {
-                        hashes.insert(path.to_string(), parts[1].to_string());
+                        blobs.insert(path.to_string(), parts[1].to_string());
                     }
                 }
             }
-            if !hashes.is_empty() {
-                return hashes;
-            }
         }
     }
 
-    // Fallback: mtime per file
-    mtime_hashes(root)
+    // Untracked files fall back to mtime
+    let mut hashes = mtime_hashes(root, scope);
+    for (path, hash) in hashes.iter_mut() {
+        if let Some(blob) = blobs.remove(path) {
+            *hash = blob;
+        }
+    }
+    hashes
 }
 
-/// Walk source files under `root` and return path → `"mtime:{unix_secs}"`.
-fn mtime_hashes(root: &Path) -> HashMap<String, String> {
-    const IGNORED: &[&str] = &[
-        "target", "node_modules", ".git", ".next", "dist", "build",
-        "__pycache__", ".venv", "venv", ".cache", "coverage",
-    ];
-    const EXTENSIONS: &[&str] = &[
-        "rs", "ts", "tsx", "js", "jsx", "py", "go", "c", "cpp", "h", "hpp",
-    ];
-
-    let mut out = HashMap::new();
-    collect_mtime(root, root, IGNORED, EXTENSIONS, &mut out, 2000);
-    out
+/// Files `scope` covers under `root` as path → `"mtime:{unix_secs}"`.
+pub(crate) fn mtime_hashes(root: &Path, scope: &IndexScope) -> HashMap<String, String> {
+    scope
+        .files(root)
+        .into_iter()
+        .map(|rel| {
+            let mtime = std::fs::metadata(root.join(&rel))
+                .ok()
+                .and_then(|m| m.modified().ok())
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            (rel, format!("mtime:{mtime}"))
+        })
+        .collect()
 }
 
-fn collect_mtime(
-    base: &Path,
-    dir: &Path,
-    ignored: &[&str],
-    extensions: &[&str],
-    out: &mut HashMap<String, String>,
-    max: usize,
-) {
-    if out.len() >= max {
-        return;
-    }
-    let Ok(entries) = std::fs::read_dir(dir) else { return };
-    let mut entries: Vec<_> = entries.filter_map(|e| e.ok()).collect();
-    entries.sort_by_key(|e| e.file_name());
-
-    for entry in entries {
-        if out.len() >= max {
-            break;
-        }
-        let name = entry.file_name();
-        let name_str = name.to_string_lossy();
-        if name_str.starts_with('.') || ignored.contains(&name_str.as_ref()) {
-            continue;
-        }
-        let path = entry.path();
-        if path.is_dir() {
-            collect_mtime(base, &path, ignored, extensions, out, max);
-        } else if let Some(ext) = path.extension() {
-            if extensions.contains(&ext.to_string_lossy().as_ref()) {
-                let rel = path
-                    .strip_prefix(base)
-                    .unwrap_or(&path)
-                    .to_string_lossy()
-                    .to_string();
-                let mtime = std::fs::metadata(&path)
-                    .ok()
-                    .and_then(|m| m.modified().ok())
-                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                    .map(|d| d.as_secs())
-                    .unwrap_or(0);
-                out.insert(rel, format!("mtime:{mtime}"));
-            }
-        }
+// ── Edge extraction ───────────────────────────────────────────────────────────
+
+/// Everything one parse of a file contributes to the graph's edge tables.
+struct FileEdges {
+    file: String,
+    calls: HashMap<String, Vec<CallEdge>>,
+    constructions: HashMap<String, Vec<CallEdge>>,
+    references: Vec<Reference>,
+    tests: Vec<TestCase>,
+    /// Rust struct/enum/trait name → signature
+    signatures: HashMap<String, String>,
+}
+
+/// Call, construction and reference edges and test cases for one file, given its own
+/// `symbols`. `None` if unreadable.
+fn extract_file_edges(
+    extractor: &mut CallExtractor,
+    file: &str,
+    root: &Path,
+    symbols: &[Symbol],
+    by_name: &HashMap<String, Vec<String>>,
+) -> Option<FileEdges> {
+    let content = std::fs::read_to_string(root.join(file)).ok()?;
+    let signatures = if Lang::from_path(file) == Some(Lang::Rust) {
+        extractor.extract_signatures(&content)
+    } else {
+        HashMap::new()
+    };
+    Some(FileEdges {
+        file: file.to_string(),
+        calls: extractor.extract_file(&content, file, symbols, by_name),
+        constructions: extractor.extract_constructions(&content, file, symbols, by_name),
+        references: extractor.extract_references(&content, file, by_name),
+        tests: extractor.extract_tests(&content, file),
+        signatures,
+    })
+}
+
+/// Parse a saved graph: MessagePack, or JSON as written by older versions.
+fn decode(bytes: &[u8]) -> Option<ProjectGraph> {
+    if bytes.first() == Some(&b'{') {
+        serde_json::from_slice(bytes).ok()
+    } else {
+        rmp_serde::from_slice(bytes).ok()
     }
 }
 
@@ -770,6 +1030,7 @@ pub fn build_pie_context(
     graph: &ProjectGraph,
     narrative: Option<&crate::narrative::ProjectNarrative>,
     flow_paths: Option<&crate::flowpaths::FlowPathIndex>,
+    semantic: &[SemanticHit],
 ) -> PieContext {
     // Flow path match — check before building messages so we can branch on it.
     let matched_path = flow_paths.and_then(|fp| {
@@ -782,7 +1043,7 @@ pub fn build_pie_context(
 
     let injection_messages = pie_injection_messages(graph, narrative, matched_path);
 
-    let focus_files = focus_files_for_task(task, attached, graph);
+    let focus_files = focus_files_for_task(task, attached, graph, semantic);
     let user_prefix = build_known_locations(&focus_files, graph);
 
     PieContext { injection_messages, user_prefix, path_matched, focus_files }
@@ -837,7 +1098,11 @@ fn pie_injection_messages(
 ///   - File stems (e.g. "stats" → stats_view.rs)
 ///   - Symbol names (e.g. "token" → TokenStats → src/tui/mod.rs)
 ///
-/// Returns files not already in `context_files`, sorted by score descending.
+/// Files that usually change together with a `context_files` entry in git
+/// history score too (e.g. the test file always edited with a module).
+///
+/// Returns files not already in `context_files`, sorted by score descending,
+/// ties broken by recent churn.
 /// These are merged with the user-attached files before PIE injection so the
 /// model arrives with symbol maps for files the task implicitly references.
 fn anchor_files_from_task(task: &str, context_files: &[String], graph: &ProjectGraph) -> Vec<String> {
@@ -847,12 +1112,21 @@ fn anchor_files_from_task(task: &str, context_files: &[String], graph: &ProjectG
         .map(|w| w.to_lowercase())
         .collect();
 
-    if tokens.is_empty() {
+    if tokens.is_empty() && context_files.is_empty() {
         return vec![];
     }
 
     let mut scores: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
 
+    // Co-change coupling with the attached files
+    for file in context_files {
+        for coupled in graph.cochange.coupled(file) {
+            if graph.file_lines.contains_key(&coupled.file) {
+                *scores.entry(coupled.file).or_insert(0) += COUPLED_ANCHOR_SCORE;
+            }
+        }
+    }
+
     // File stem matching — bidirectional substring
     for file in graph.file_lines.keys() {
         let stem = std::path::Path::new(file.as_str())
@@ -877,8 +1151,13 @@ fn anchor_files_from_task(task: &str, context_files: &[String], graph: &ProjectG
         }
     }
 
+    let now = chrono::Utc::now().timestamp();
     let mut ranked: Vec<(String, usize)> = scores.into_iter().collect();
-    ranked.sort_by(|a, b| b.1.cmp(&a.1));
+    ranked.sort_by(|a, b| {
+        b.1.cmp(&a.1)
+            .then_with(|| graph.cochange.churn(&b.0, now).cmp(&graph.cochange.churn(&a.0, now)))
+            .then_with(|| a.0.cmp(&b.0))
+    });
 
     ranked.into_iter()
         .map(|(f, _)| f)
@@ -976,13 +1255,42 @@ pub fn build_known_locations_reminder(focus_files: &[String], graph: &ProjectGra
     format!("Known (skip find_symbol): {}", entries.join(", "))
 }
 
-/// Return the combined focus file list for a task: attached files + keyword-anchored files.
+/// Return the combined focus file list for a task: attached files + keyword-anchored
+/// files + the files of the best `semantic` hits (ranked against the task text), which
+/// catch tasks worded differently from the code + up to 2 files that usually change
+/// together with those in git history.
 ///
 /// Exposed so callers (e.g. `plan.rs`) can build inline symbol reminders in the task
 /// message without duplicating the anchoring logic.
-pub fn focus_files_for_task(task: &str, attached: &[String], graph: &ProjectGraph) -> Vec<String> {
+pub fn focus_files_for_task(task: &str, attached: &[String], graph: &ProjectGraph, semantic: &[SemanticHit]) -> Vec<String> {
     let anchored = anchor_files_from_task(task, attached, graph);
-    attached.iter().chain(anchored.iter()).cloned().collect()
+    let mut files: Vec<String> = attached.iter().chain(anchored.iter()).cloned().collect();
+    let mut added = 0;
+    for hit in semantic {
+        if added == MAX_SEMANTIC_FOCUS_FILES {
+            break;
+        }
+        if graph.file_lines.contains_key(&hit.file) && !files.contains(&hit.file) {
+            files.push(hit.file.clone());
+            added += 1;
+        }
+    }
+    let mut coupled: Vec<crate::cochange::Coupling> = files
+        .iter()
+        .flat_map(|f| graph.cochange.coupled(f))
+        .filter(|c| graph.file_lines.contains_key(&c.file) && !files.contains(&c.file))
+        .collect();
+    coupled.sort_by(|a, b| b.confidence.total_cmp(&a.confidence).then(b.together.cmp(&a.together)));
+    let before = files.len();
+    for c in coupled {
+        if files.len() == before + MAX_COUPLED_FOCUS_FILES {
+            break;
+        }
+        if !files.contains(&c.file) {
+            files.push(c.file);
+        }
+    }
+    files
 }
 
 // ── Tests ─────────────────────────────────────────────────────────────────────
@@ -1023,7 +1331,7 @@ mod tests {
         let tmp = TempDir::new().unwrap();
         setup_multi_cluster(&tmp);
 
-        let (graph, was_warm) = ProjectGraph::load_or_build(tmp.path(), 100);
+        let (graph, was_warm) = ProjectGraph::load_or_build(tmp.path(), &IndexScope::default());
 
         assert!(!was_warm, "should be cold on first build");
         assert!(graph.clusters.len() >= 2, "expected ≥2 clusters, got {}", graph.clusters.len());
@@ -1076,7 +1384,7 @@ mod tests {
         )
         .unwrap();
 
-        let (graph, was_warm) = ProjectGraph::load_or_build(tmp.path(), 100);
+        let (graph, was_warm) = ProjectGraph::load_or_build(tmp.path(), &IndexScope::default());
 
         assert!(!was_warm, "schema mismatch should trigger cold rebuild");
         assert_eq!(
@@ -1087,6 +1395,46 @@ mod tests {
         assert!(!graph.symbols.is_empty(), "rebuilt graph should have symbols");
     }
 
+    #[test]
+    fn test_previous_schema_migrates_edges_in_place() {
+        let tmp = TempDir::new().unwrap();
+        write(tmp.path(), "svc/app.py", "def main():\n    helper()\n\ndef helper():\n    pass\n");
+        let (mut graph, _) = ProjectGraph::load_or_build(tmp.path(), &IndexScope::default());
+
+        // Persist as a pre-migration graph: old edge schema, no edges
+        graph.schema_version = MIGRATABLE_SCHEMA;
+        graph.call_edges.clear();
+        graph.save(tmp.path());
+
+        let (graph, was_warm) = ProjectGraph::load_or_build(tmp.path(), &IndexScope::default());
+        assert!(was_warm, "an older edge schema is migrated, not rebuilt");
+        assert_eq!(graph.schema_version, SCHEMA_VERSION);
+        let edge = &graph.call_edges["svc/app.py::main"][0];
+        assert_eq!(edge.target.as_deref(), Some("svc/app.py::helper"));
+    }
+
+    #[test]
+    fn test_schema_before_migratable_rebuilds_symbols() {
+        let tmp = TempDir::new().unwrap();
+        write(tmp.path(), "svc/app.py", "def main():\n    helper()\n\ndef helper():\n    pass\n");
+        let (mut graph, _) = ProjectGraph::load_or_build(tmp.path(), &IndexScope::default());
+        assert!(graph.call_edges.contains_key("svc/app.py::main"), "python edges extracted");
+
+        // Persist as a graph from the line-scanner era: no spans, no edges
+        graph.schema_version = MIGRATABLE_SCHEMA - 1;
+        graph.call_edges.clear();
+        for sym in &mut graph.symbols {
+            sym.end_byte = 0;
+        }
+        graph.save(tmp.path());
+
+        let (graph, was_warm) = ProjectGraph::load_or_build(tmp.path(), &IndexScope::default());
+        assert!(!was_warm, "symbols older than MIGRATABLE_SCHEMA are re-extracted");
+        assert_eq!(graph.schema_version, SCHEMA_VERSION);
+        assert!(graph.symbols.iter().all(|s| s.end_byte > 0));
+        assert_eq!(graph.call_edges["svc/app.py::main"][0].callee, "helper");
+    }
+
     // ── Test 3 ─────────────────────────────────────────────────────────────────
 
     #[test]
@@ -1094,7 +1442,7 @@ mod tests {
         let tmp = TempDir::new().unwrap();
         write(tmp.path(), "src/foo.rs", "pub fn alpha() {}\n");
 
-        let (mut graph, _) = ProjectGraph::load_or_build(tmp.path(), 100);
+        let (mut graph, _) = ProjectGraph::load_or_build(tmp.path(), &IndexScope::default());
 
         let names: Vec<&str> = graph.symbols.iter().map(|s| s.name.as_str()).collect();
         assert!(names.contains(&"alpha"), "alpha should be present");
@@ -1109,6 +1457,37 @@ mod tests {
         assert!(names.contains(&"beta"), "beta should now be present");
     }
 
+    #[test]
+    fn test_apply_file_changes_rebuilds_paths_only_when_edges_change() {
+        let tmp = TempDir::new().unwrap();
+        write(tmp.path(), "src/foo.rs", "pub fn alpha() { beta(); }\npub fn beta() {}\n");
+        write(tmp.path(), "src/bar.rs", "pub fn gamma() {}\n");
+        let scope = IndexScope::default();
+        let (mut graph, _) = ProjectGraph::load_or_build(tmp.path(), &scope);
+        let paths = tmp.path().join(".parecode/paths.json");
+        let foo = vec!["src/foo.rs".to_string()];
+
+        // Lines shift, edges don't — symbols move, flow paths are left alone
+        fs::remove_file(&paths).unwrap();
+        write(tmp.path(), "src/foo.rs", "\npub fn alpha() { beta(); }\npub fn beta() {}\n");
+        assert!(graph.apply_file_changes(&foo, tmp.path(), &scope));
+        assert_eq!(graph.symbols.iter().find(|s| s.name == "alpha").unwrap().line, 2);
+        assert!(!paths.exists());
+
+        // A new call changes the edges — flow paths are rebuilt
+        write(tmp.path(), "src/foo.rs", "pub fn alpha() { beta(); gamma(); }\npub fn beta() {}\n");
+        assert!(graph.apply_file_changes(&foo, tmp.path(), &scope));
+        assert_eq!(graph.call_edges["src/foo.rs::alpha"].len(), 2);
+        assert!(paths.exists());
+
+        // Deleted file is dropped; unknown missing paths are a no-op
+        fs::remove_file(tmp.path().join("src/bar.rs")).unwrap();
+        assert!(graph.apply_file_changes(&["src/bar.rs".to_string()], tmp.path(), &scope));
+        assert!(!graph.file_lines.contains_key("src/bar.rs"));
+        assert!(!graph.symbols.iter().any(|s| s.name == "gamma"));
+        assert!(!graph.apply_file_changes(&["src/nope.rs".to_string()], tmp.path(), &scope));
+    }
+
     // ── Test 4 ─────────────────────────────────────────────────────────────────
 
     #[test]
@@ -1120,7 +1499,7 @@ mod tests {
             "pub fn alpha() {}\npub fn beta() {}\n",
         );
 
-        let (mut graph, _) = ProjectGraph::load_or_build(tmp.path(), 100);
+        let (mut graph, _) = ProjectGraph::load_or_build(tmp.path(), &IndexScope::default());
 
         let names: Vec<&str> = graph.symbols.iter().map(|s| s.name.as_str()).collect();
         assert!(names.contains(&"alpha"));
@@ -1148,13 +1527,13 @@ mod tests {
         write(tmp.path(), "src/b.rs", "pub fn func_b() {}\n");
 
         // First build
-        let _ = ProjectGraph::load_or_build(tmp.path(), 100);
+        let _ = ProjectGraph::load_or_build(tmp.path(), &IndexScope::default());
 
         // Delete src/b.rs
         fs::remove_file(tmp.path().join("src/b.rs")).unwrap();
 
         // Second load — warm path, should detect removal
-        let (graph, was_warm) = ProjectGraph::load_or_build(tmp.path(), 100);
+        let (graph, was_warm) = ProjectGraph::load_or_build(tmp.path(), &IndexScope::default());
         assert!(was_warm, "second load should be warm");
 
         let files_in_symbols: Vec<&str> =
@@ -1180,7 +1559,7 @@ mod tests {
         let tmp = TempDir::new().unwrap();
         setup_multi_cluster(&tmp);
 
-        let (graph, _) = ProjectGraph::load_or_build(tmp.path(), 100);
+        let (graph, _) = ProjectGraph::load_or_build(tmp.path(), &IndexScope::default());
         let original_cluster_count = graph.clusters.len();
         let original_sym_count = graph.symbols.len();
         let original_by_name_keys: std::collections::HashSet<String> =
@@ -1188,9 +1567,9 @@ mod tests {
 
         // Read back from disk
         let graph_path = tmp.path().join(".parecode/project.graph");
-        let content = fs::read_to_string(&graph_path).expect("graph file should exist");
-        let loaded: ProjectGraph =
-            serde_json::from_str(&content).expect("graph JSON should be valid");
+        let content = fs::read(&graph_path).expect("graph file should exist");
+        assert_ne!(content.first(), Some(&b'{'), "saved as MessagePack, not JSON");
+        let loaded = ProjectGraph::load(tmp.path()).expect("graph should decode");
 
         assert_eq!(loaded.schema_version, SCHEMA_VERSION);
         assert_eq!(
@@ -1211,6 +1590,36 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_json_graph_loads_and_unindexed_files_are_picked_up() {
+        let tmp = TempDir::new().unwrap();
+        write(tmp.path(), "src/a.rs", "pub fn alpha() {}\n");
+        write(tmp.path(), "src/b.rs", "pub fn beta() {}\n");
+        let (mut graph, _) = ProjectGraph::load_or_build(tmp.path(), &IndexScope::default());
+
+        // A JSON graph from a capped build: b.rs hashed but never indexed
+        graph.remove_file("src/b.rs");
+        graph.file_hashes = get_current_hashes(tmp.path(), &IndexScope::default());
+        fs::write(tmp.path().join(GRAPH_PATH), serde_json::to_string(&graph).unwrap()).unwrap();
+
+        let (graph, was_warm) = ProjectGraph::load_or_build(tmp.path(), &IndexScope::default());
+        assert!(was_warm, "JSON graphs still load");
+        assert!(graph.symbols.iter().any(|s| s.name == "beta"));
+        assert_ne!(fs::read(tmp.path().join(GRAPH_PATH)).unwrap().first(), Some(&b'{'));
+    }
+
+    #[test]
+    fn test_no_file_cap() {
+        let tmp = TempDir::new().unwrap();
+        for i in 0..600 {
+            write(tmp.path(), &format!("src/m{i:03}.rs"), &format!("pub fn f{i}() {{ f0(); }}\n"));
+        }
+        let (graph, _) = ProjectGraph::load_or_build(tmp.path(), &IndexScope::default());
+        assert_eq!(graph.file_lines.len(), 600);
+        assert!(graph.symbols.iter().any(|s| s.name == "f599"), "the old 500-file cap cut this off");
+        assert_eq!(graph.callers_of("f0").len(), 599, "every file but f0's own");
+    }
+
     // ── Test 7 ─────────────────────────────────────────────────────────────────
 
     #[test]
@@ -1218,7 +1627,7 @@ mod tests {
         let tmp = TempDir::new().unwrap();
         setup_multi_cluster(&tmp);
 
-        let (graph, _) = ProjectGraph::load_or_build(tmp.path(), 100);
+        let (graph, _) = ProjectGraph::load_or_build(tmp.path(), &IndexScope::default());
         let section = graph.to_prompt_section(8);
 
         assert!(section.is_some(), "to_prompt_section should return Some");
@@ -1251,7 +1660,7 @@ mod tests {
             "pub fn helper() {}\npub struct Config {}\n",
         );
 
-        let (graph, _) = ProjectGraph::load_or_build(tmp.path(), 100);
+        let (graph, _) = ProjectGraph::load_or_build(tmp.path(), &IndexScope::default());
 
         // Both files should be in the same "src" cluster
         let src_cluster = graph.clusters.iter().find(|c| c.name == "src");
@@ -1268,6 +1677,73 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_workspace_clusters_grouped_by_package() {
+        let tmp = TempDir::new().unwrap();
+        write(tmp.path(), "Cargo.toml", "[workspace]\nmembers = [\"crates/*\"]\n");
+        write(tmp.path(), "crates/core/Cargo.toml", "[package]\nname = \"app-core\"\n");
+        write(tmp.path(), "crates/core/src/lib.rs", "pub fn parse() {}\n");
+        write(tmp.path(), "crates/core/src/db/mod.rs", "pub fn connect() {}\n");
+        write(
+            tmp.path(),
+            "crates/web/Cargo.toml",
+            "[package]\nname = \"app-web\"\n[dependencies]\napp-core = { path = \"../core\" }\n",
+        );
+        write(tmp.path(), "crates/web/src/main.rs", "pub fn serve() {}\n");
+        write(tmp.path(), "scripts/gen.py", "def gen():\n    pass\n");
+
+        let (graph, _) = ProjectGraph::load_or_build(tmp.path(), &IndexScope::default());
+        assert_eq!(graph.packages.len(), 2);
+        assert_eq!(graph.packages[1].dependencies, vec!["app-core"]);
+
+        let cluster = |name: &str| graph.clusters.iter().find(|c| c.name == name);
+        assert_eq!(cluster("app-core").unwrap().files, vec!["crates/core/src/lib.rs"]);
+        assert_eq!(cluster("app-core/db").unwrap().package.as_deref(), Some("app-core"));
+        assert_eq!(cluster("app-web").unwrap().files, vec!["crates/web/src/main.rs"]);
+        assert_eq!(cluster("scripts").unwrap().package, None, "files outside every package keep directory clusters");
+
+        let section = graph.to_prompt_section(10).unwrap();
+        assert!(section.starts_with("# Project structure — 2 packages, 4 clusters, 4 files"), "got:\n{section}");
+        assert!(section.contains("## Package app-web (crates/web, cargo, 1 files) → depends on app-core"), "got:\n{section}");
+        assert!(section.contains("### app-core/db (1 files)"));
+        let core = section.find("## Package app-core").unwrap();
+        assert!(core < section.find("### app-core/db").unwrap());
+        assert!(section.find("## Outside any package").unwrap() > section.find("## Package app-web").unwrap());
+
+        let capped = graph.to_prompt_section(1).unwrap();
+        assert!(capped.contains("… and 3 more clusters"), "got:\n{capped}");
+    }
+
+    #[test]
+    fn test_cochange_coupled_files_join_focus() {
+        use crate::cochange::tests::{commit, init_repo};
+        let tmp = TempDir::new().unwrap();
+        init_repo(tmp.path());
+        write(tmp.path(), "src/lexer.rs", "pub fn tokenize() {}\n");
+        write(tmp.path(), "tests/lexer_spec.rs", "fn spec() {}\n");
+        write(tmp.path(), "src/main.rs", "fn main() {}\n");
+        commit(tmp.path(), &["src/lexer.rs", "tests/lexer_spec.rs", "src/main.rs"]);
+        commit(tmp.path(), &["src/lexer.rs", "tests/lexer_spec.rs"]);
+
+        let (graph, _) = ProjectGraph::load_or_build(tmp.path(), &IndexScope::default());
+        assert_eq!(graph.cochange.commits, 2);
+
+        // Keyword anchor pulls in its usual companion
+        let focus = focus_files_for_task("speed up tokenize", &[], &graph, &[]);
+        assert_eq!(focus, vec!["src/lexer.rs", "tests/lexer_spec.rs"]);
+
+        // So does an attached file
+        let attached = vec!["tests/lexer_spec.rs".to_string()];
+        let focus = focus_files_for_task("fix the flaky case", &attached, &graph, &[]);
+        assert_eq!(focus, vec!["tests/lexer_spec.rs", "src/lexer.rs"]);
+
+        // Warm load mines only the new commit
+        commit(tmp.path(), &["src/main.rs"]);
+        let (graph, was_warm) = ProjectGraph::load_or_build(tmp.path(), &IndexScope::default());
+        assert!(was_warm);
+        assert_eq!(graph.cochange.commits, 3);
+    }
+
     // ── Test 9 ─────────────────────────────────────────────────────────────────
 
     #[test]
@@ -1277,7 +1753,7 @@ mod tests {
         write(tmp.path(), "src/a.rs", "fn a() {}\n");
         write(tmp.path(), "src/b.rs", "fn b() {}\n");
 
-        let hashes = get_current_hashes(tmp.path());
+        let hashes = get_current_hashes(tmp.path(), &IndexScope::default());
 
         assert!(!hashes.is_empty(), "should return hashes for source files");
 
@@ -1289,7 +1765,7 @@ mod tests {
         }
 
         // Second call on unchanged files should return same values
-        let hashes2 = get_current_hashes(tmp.path());
+        let hashes2 = get_current_hashes(tmp.path(), &IndexScope::default());
         for (path, hash) in &hashes {
             let hash2 = hashes2.get(path);
             assert_eq!(
@@ -1308,7 +1784,7 @@ mod tests {
         write(tmp.path(), "src/a.rs", "pub fn process() {}\n");
         write(tmp.path(), "src/b.rs", "pub fn process() {}\n");
 
-        let (graph, _) = ProjectGraph::load_or_build(tmp.path(), 100);
+        let (graph, _) = ProjectGraph::load_or_build(tmp.path(), &IndexScope::default());
 
         let files = graph.by_name.get("process");
         assert!(files.is_some(), "process should be in by_name");
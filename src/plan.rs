@@ -12,7 +12,7 @@
 ///   5. After each step, verification runs; failure surfaces for user decision
 ///
 /// Plans are persisted to `.parecode/plans/{timestamp}.json` so they can be resumed.
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 
 use anyhow::Result;
@@ -77,6 +77,11 @@ pub struct PlanStep {
     /// Injected into subsequent steps so they know what changed.
     #[serde(default)]
     pub completed_summary: Option<String>,
+    /// 0-based indices of earlier steps this one needs finished first —
+    /// declared by the planner and/or inferred by `infer_dependencies`.
+    /// `None` (plans saved before this existed) = depends on every earlier step.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depends_on: Option<Vec<usize>>,
 }
 
 impl PlanStep {
@@ -101,6 +106,9 @@ pub struct Plan {
     pub created_at: i64,
     /// cwd basename at plan creation time
     pub project: String,
+    /// How many times a failed step has been replanned (see `replan_failed_step`)
+    #[serde(default)]
+    pub replans: u32,
 }
 
 impl Plan {
@@ -112,7 +120,124 @@ impl Plan {
             status: PlanStatus::Pending,
             created_at: chrono::Utc::now().timestamp(),
             project,
+            replans: 0,
+        }
+    }
+
+    /// Index of the first step that has not passed — where a resumed run
+    /// picks up. `None` when every step passed or was skipped.
+    pub fn resume_index(&self) -> Option<usize> {
+        self.steps
+            .iter()
+            .position(|s| !matches!(s.status, StepStatus::Pass | StepStatus::Skipped))
+    }
+
+    /// Number of steps that passed or were skipped.
+    pub fn steps_done(&self) -> usize {
+        self.steps
+            .iter()
+            .filter(|s| matches!(s.status, StepStatus::Pass | StepStatus::Skipped))
+            .count()
+    }
+
+    /// Effective dependencies of every step: `depends_on` limited to earlier
+    /// steps, or all earlier steps when the plan doesn't say.
+    pub fn dependencies(&self) -> Vec<Vec<usize>> {
+        self.steps
+            .iter()
+            .enumerate()
+            .map(|(i, step)| match &step.depends_on {
+                Some(deps) => deps.iter().copied().filter(|&d| d < i).collect(),
+                None => (0..i).collect(),
+            })
+            .collect()
+    }
+
+    /// `dependencies()` with transitive edges dropped — the arrows of the DAG
+    /// as drawn in the plan card. A step with no direct dependencies can start
+    /// as soon as the plan does.
+    pub fn direct_dependencies(&self) -> Vec<Vec<usize>> {
+        let deps = self.dependencies();
+        let mut ancestors: Vec<std::collections::BTreeSet<usize>> = Vec::with_capacity(deps.len());
+        let mut direct = Vec::with_capacity(deps.len());
+        for step_deps in &deps {
+            let implied: std::collections::BTreeSet<usize> =
+                step_deps.iter().flat_map(|&d| ancestors[d].iter().copied()).collect();
+            direct.push(step_deps.iter().copied().filter(|d| !implied.contains(d)).collect::<Vec<_>>());
+            ancestors.push(step_deps.iter().copied().chain(implied).collect());
+        }
+        direct
+    }
+
+    /// Whether any step could run alongside the one before it — i.e. the
+    /// dependency graph is not a simple chain.
+    pub fn has_parallel_steps(&self) -> bool {
+        self.direct_dependencies()
+            .iter()
+            .enumerate()
+            .skip(1)
+            .any(|(i, deps)| deps.as_slice() != [i - 1])
+    }
+
+    /// Steps after `failed` that passed — siblings from its parallel batch.
+    /// Their changes are in the tree, so a replan keeps them, moved ahead of
+    /// the replacement steps.
+    fn passed_after(&self, failed: usize) -> Vec<usize> {
+        (failed + 1..self.steps.len()).filter(|&i| self.steps[i].status == StepStatus::Pass).collect()
+    }
+
+    /// Splice a `Replan` in at the failed step `failed`: either the step is
+    /// replaced by the revised step(s), or it and every later step that
+    /// hasn't passed are. Passed steps after `failed` are kept and moved in
+    /// front of the new steps. `depends_on` is renumbered to match, a
+    /// dependency on the failed step becoming one on all of its replacements.
+    /// New steps start `Pending` so they go through review, and the plan
+    /// returns to `Pending`.
+    pub fn apply_replan(&mut self, failed: usize, replan: Replan) {
+        let added = replan.steps.len();
+        let first_new = failed + self.passed_after(failed).len();
+        // Old index → new indices
+        let mut renumber: Vec<Vec<usize>> = (0..failed).map(|i| vec![i]).collect();
+        renumber.push((first_new..first_new + added).collect());
+        let (mut passed, mut rest) = (Vec::new(), Vec::new());
+        for step in self.steps.split_off(failed).into_iter().skip(1) {
+            if step.status == StepStatus::Pass {
+                renumber.push(vec![failed + passed.len()]);
+                passed.push(step);
+            } else if replan.replace_rest {
+                renumber.push(vec![]);
+            } else {
+                renumber.push(vec![first_new + added + rest.len()]);
+                rest.push(step);
+            }
+        }
+        self.steps.extend(passed);
+        self.steps.extend(replan.steps);
+        self.steps.extend(rest);
+        for (i, step) in self.steps.iter_mut().enumerate() {
+            // The new steps are numbered for the spliced plan already
+            if (first_new..first_new + added).contains(&i) {
+                continue;
+            }
+            if let Some(deps) = step.depends_on.as_mut() {
+                *deps = deps.iter().flat_map(|&d| renumber.get(d).cloned().unwrap_or_default()).collect();
+                deps.sort_unstable();
+                deps.dedup();
+            }
         }
+        self.current = first_new;
+        self.status = PlanStatus::Pending;
+        self.replans += 1;
+    }
+
+    /// (description, completed_summary) of every passed step before `index`,
+    /// carried into later steps so they know what already changed.
+    pub fn completed_summaries(&self, index: usize) -> Vec<(String, String)> {
+        self.steps[..index.min(self.steps.len())]
+            .iter()
+            .filter(|s| s.status == StepStatus::Pass)
+            .filter_map(|s| Some((s.description.clone(), s.completed_summary.clone()?)))
+            .collect()
     }
 
     /// Estimate token cost for this plan.
@@ -173,6 +298,10 @@ impl Plan {
 pub struct PlanMetrics {
     pub total_input_tokens: u32,
     pub total_output_tokens: u32,
+    pub total_cache_read_tokens: u32,
+    pub total_cache_write_tokens: u32,
+    /// USD spent generating the plan (None if the planner model is unpriced).
+    pub cost_usd: Option<f64>,
     /// Number of API turns (one per `client.chat()` call).
     pub turns: usize,
     /// Per-tool call counts: `"orient"` → 2, `"read_symbol"` → 1, etc.
@@ -190,6 +319,35 @@ impl PlanMetrics {
     }
 }
 
+/// Telemetry record for a planning run, so planner spend is counted alongside tasks.
+pub fn metrics_record(
+    metrics: &PlanMetrics,
+    task: &str,
+    session_id: &str,
+    project: &str,
+    model: &str,
+    profile: &str,
+) -> crate::telemetry::TaskRecord {
+    let tool_calls = metrics.total_tool_calls();
+    crate::telemetry::TaskRecord {
+        timestamp: chrono::Utc::now().timestamp(),
+        session_id: session_id.to_string(),
+        cwd: project.to_string(),
+        task_preview: format!("[plan] {}", task.chars().take(73).collect::<String>()),
+        input_tokens: metrics.total_input_tokens,
+        output_tokens: metrics.total_output_tokens,
+        cache_read_tokens: metrics.total_cache_read_tokens,
+        cache_write_tokens: metrics.total_cache_write_tokens,
+        cost_usd: metrics.cost_usd,
+        tool_calls,
+        compressed_count: 0,
+        compression_ratio: 0.0,
+        duration_secs: metrics.elapsed_secs,
+        model: model.to_string(),
+        profile: profile.to_string(),
+    }
+}
+
 // ── Plan persistence ──────────────────────────────────────────────────────────
 
 /// Directory for saved plans: `.parecode/plans/` relative to cwd.
@@ -207,6 +365,66 @@ pub fn save_plan(plan: &Plan) -> Result<PathBuf> {
     Ok(path)
 }
 
+/// Write a plan as pretty JSON to an explicit path (`plan --out`, `run-plan`).
+pub fn save_plan_to(plan: &Plan, path: &std::path::Path) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(plan)?)?;
+    Ok(())
+}
+
+/// Saved plans in `plans_dir()`, newest first. Unreadable files are skipped.
+pub fn list_plans() -> Vec<(PathBuf, Plan)> {
+    list_plans_in(&plans_dir())
+}
+
+fn list_plans_in(dir: &std::path::Path) -> Vec<(PathBuf, Plan)> {
+    let Ok(rd) = std::fs::read_dir(dir) else { return Vec::new() };
+    let mut plans: Vec<(PathBuf, Plan)> = rd
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|x| x == "json"))
+        .filter_map(|p| load_plan(&p).ok().map(|plan| (p, plan)))
+        .collect();
+    plans.sort_by_key(|(_, plan)| std::cmp::Reverse(plan.created_at));
+    plans
+}
+
+pub fn load_plan(path: &std::path::Path) -> Result<Plan> {
+    let raw = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("cannot read plan {}: {e}", path.display()))?;
+    serde_json::from_str(&raw).map_err(|e| anyhow::anyhow!("invalid plan {}: {e}", path.display()))
+}
+
+/// Check a loaded plan can run from `from` (0-based) without review.
+///
+/// Every step from `from` on must be `Approved` (or already `Pass`/`Skipped`,
+/// which `run_plan` leaves alone) — the same rule the TUI applies before Enter
+/// runs a plan. With `auto_approve`, pending steps are approved instead. A step left `Running` or `Fail` by an
+/// earlier run is reset to `Approved` so it is retried.
+pub fn prepare_run(plan: &mut Plan, from: usize, auto_approve: bool) -> Result<()> {
+    if from >= plan.steps.len() {
+        anyhow::bail!("plan has {} steps — cannot start from step {}", plan.steps.len(), from + 1);
+    }
+    let mut unapproved = Vec::new();
+    for (i, step) in plan.steps.iter_mut().enumerate().skip(from) {
+        match step.status {
+            StepStatus::Approved | StepStatus::Pass | StepStatus::Skipped => {}
+            StepStatus::Running | StepStatus::Fail => step.status = StepStatus::Approved,
+            StepStatus::Pending if auto_approve => step.status = StepStatus::Approved,
+            StepStatus::Pending => unapproved.push((i + 1).to_string()),
+        }
+    }
+    if !unapproved.is_empty() {
+        anyhow::bail!(
+            "step(s) {} not approved — set their status to \"Approved\" or pass --auto-approve",
+            unapproved.join(", ")
+        );
+    }
+    Ok(())
+}
+
 // ── Plan generation ───────────────────────────────────────────────────────────
 
 /// Single system prompt covering both exploration and output.
@@ -241,7 +459,8 @@ OUTPUT: When you have all locations, immediately output JSON (no markdown fences
       "instruction": "exact file:line refs — never say 'find X' or 'locate Y'",
       "files": ["src/foo.rs", "src/bar.rs"],
       "verify": "none",
-      "tool_budget": 15
+      "tool_budget": 15,
+      "depends_on": []
     }
   ]
 }
@@ -256,7 +475,10 @@ STEP RULES:
 - NO REDUNDANT PARAMS: Check if an existing struct argument already carries the data before
   adding a new parameter. Read from the existing arg — don't duplicate data already passed.
 - RUST VERIFY: Any step adding/removing a struct field OR changing a function signature
-  MUST set verify: "command:cargo check"."#;
+  MUST set verify: "command:cargo check".
+- depends_on: 0-based indices of EARLIER steps whose changes this step needs. Steps that
+  don't depend on each other may run at the same time — leave a step out only when it
+  truly doesn't need it."#;
 
 /// Response from the model during plan generation.
 #[derive(Debug, Deserialize)]
@@ -274,6 +496,25 @@ struct PlanStepRaw {
     verify: String,
     #[serde(default = "default_tool_budget")]
     tool_budget: usize,
+    #[serde(default)]
+    depends_on: Vec<usize>,
+}
+
+impl PlanStepRaw {
+    /// A fresh `Pending` step with `files` (already resolved by the caller).
+    fn into_step(self, files: Vec<String>) -> PlanStep {
+        PlanStep {
+            description: self.description,
+            instruction: self.instruction,
+            files,
+            verify: parse_verification(&self.verify),
+            status: StepStatus::Pending,
+            tool_budget: self.tool_budget.min(25), // cap tool budget to prevent runaway steps
+            user_annotation: None,
+            completed_summary: None,
+            depends_on: Some(self.depends_on),
+        }
+    }
 }
 
 fn default_verify() -> String { "none".to_string() }
@@ -302,6 +543,17 @@ fn parse_verification(s: &str) -> Verification {
     Verification::None
 }
 
+/// Inverse of `parse_verification` — the `verify` string the planner wrote.
+fn verification_spec(v: &Verification) -> String {
+    match v {
+        Verification::None => "none".to_string(),
+        Verification::BuildSuccess => "build".to_string(),
+        Verification::CommandSuccess(cmd) => format!("command:{cmd}"),
+        Verification::FileChanged(path) => format!("changed:{path}"),
+        Verification::PatternAbsent { file, pattern } => format!("absent:{file}:{pattern}"),
+    }
+}
+
 // ── Planner tools ─────────────────────────────────────────────────────────────
 
 /// Exploration tools for the planner — orient + check_wiring + read_file from the shared registry.
@@ -322,11 +574,12 @@ fn execute_planner_tool(
     call: &ToolCall,
     graph: &ProjectGraph,
     delivered: &mut crate::tools::pie_tool::DeliveredRanges,
+    semantic: &[crate::embeddings::SemanticHit],
 ) -> String {
     let args: serde_json::Value =
         serde_json::from_str(&call.arguments).unwrap_or(serde_json::Value::Null);
     match call.name.as_str() {
-        "orient" => crate::tools::pie_tool::orient_execute(&args, graph, delivered),
+        "orient" => crate::tools::pie_tool::orient_execute(&args, graph, delivered, semantic),
         "find_symbol" | "trace_calls" => {
             let name = args["name"].as_str().unwrap_or("?");
             format!(
@@ -415,11 +668,13 @@ fn contains_word(text: &str, word: &str) -> bool {
 /// step instruction but has no line number referenced, append an index hint.
 /// Also appends construction sites for struct/enum types so executors know
 /// exactly where to find the live value, not just the definition.
+/// Steps touching functions with known tests get the commands that run them.
 fn enrich_step_instructions(steps: &mut Vec<PlanStep>, graph: &ProjectGraph) {
     use crate::index::SymbolKind;
     for step in steps.iter_mut() {
         let inst_snapshot = step.instruction.clone();
         let mut seen: std::collections::HashSet<String> = std::collections::HashSet::new();
+        let mut callables: Vec<String> = Vec::new();
         for sym in graph.symbols.iter() {
             // Skip Impl blocks — their struct/enum entry covers them.
             if matches!(sym.kind, SymbolKind::Impl) {
@@ -429,6 +684,9 @@ fn enrich_step_instructions(steps: &mut Vec<PlanStep>, graph: &ProjectGraph) {
             if !contains_word(&inst_snapshot, &sym.name) {
                 continue;
             }
+            if matches!(sym.kind, SymbolKind::Function | SymbolKind::Method) {
+                callables.push(sym.key());
+            }
             // Skip if a line reference already exists for this exact location.
             if inst_snapshot.contains(&format!("line {}", sym.line)) {
                 continue;
@@ -476,9 +734,34 @@ fn enrich_step_instructions(steps: &mut Vec<PlanStep>, graph: &ProjectGraph) {
                 }
             }
         }
+        // Targeted verification: the closest tests of the functions this step names.
+        if !callables.is_empty() && !graph.tests.is_empty() {
+            let mut commands: Vec<String> = Vec::new();
+            for related in crate::testmap::related_tests(graph, &callables) {
+                let cmd = related.test.command(&graph.packages);
+                if !commands.contains(&cmd) {
+                    commands.push(cmd);
+                }
+                if commands.len() == 3 {
+                    break;
+                }
+            }
+            if !commands.is_empty() {
+                let list: Vec<String> = commands.iter().map(|c| format!("`{c}`")).collect();
+                step.instruction.push_str(&format!("\n[related tests: {}]", list.join(", ")));
+            }
+        }
     }
 }
 
+/// What the planner knows about the project beyond its graph. Each part is
+/// optional — the graph alone is enough to plan.
+#[derive(Clone, Copy, Default)]
+pub struct PlanContext<'a> {
+    pub narrative: Option<&'a crate::narrative::ProjectNarrative>,
+    pub flow_paths: Option<&'a crate::flowpaths::FlowPathIndex>,
+    pub semantic: Option<&'a crate::embeddings::SemanticIndex>,
+}
 
 /// Call the model to generate a plan for `task`.
 ///
@@ -490,6 +773,10 @@ fn enrich_step_instructions(steps: &mut Vec<PlanStep>, graph: &ProjectGraph) {
 ///   - If its output is parseable JSON → that IS the plan, done.
 ///   - If it's prose (exploration notes) → push "now output JSON" and loop once more
 ///     with no tools so the model can commit.
+///
+/// Spend limits are checked before every turn, and each turn's usage is
+/// added to `spend` as it comes in.
+#[allow(clippy::too_many_arguments)]
 pub async fn generate_plan(
     task: &str,
     client: &Client,
@@ -497,14 +784,19 @@ pub async fn generate_plan(
     context_files: &[String],
     attached_symbols: &[crate::pie::AttachedSymbol],
     graph: &ProjectGraph,
-    narrative: Option<&crate::narrative::ProjectNarrative>,
-    flow_paths: Option<&crate::flowpaths::FlowPathIndex>,
+    context: PlanContext<'_>,
+    spend_limits: &crate::budget::SpendLimits,
+    spend: &std::sync::Mutex<crate::budget::SpendGuard>,
     ui_tx: mpsc::UnboundedSender<UiEvent>,
     on_chunk: impl Fn(&str) + Send + Sync + 'static,
 ) -> Result<(Plan, PlanMetrics)> {
     let task_start = std::time::Instant::now();
+    spend.lock().unwrap().begin_task();
     let mut total_input: u32 = 0;
     let mut total_output: u32 = 0;
+    let mut total_cache_read: u32 = 0;
+    let mut total_cache_write: u32 = 0;
+    let mut total_cost: Option<f64> = None;
     let mut tool_counts: HashMap<String, usize> = HashMap::new();
     let mut turns: usize = 0;
 
@@ -515,7 +807,9 @@ pub async fn generate_plan(
     let mut session_log = format!("=== PLAN SESSION ===\nTask: {task}\n\n");
 
     // ── PIE context assembly — same path as run_tui ───────────────────────────
-    let pie_ctx = crate::pie::build_pie_context(task, context_files, graph, narrative, flow_paths);
+    let PlanContext { narrative, flow_paths, semantic } = context;
+    let focus_hits = crate::embeddings::hits(semantic, graph, task, crate::agent::FOCUS_SEMANTIC_HITS, true).await;
+    let pie_ctx = crate::pie::build_pie_context(task, context_files, graph, narrative, flow_paths, &focus_hits);
 
     let mut messages: Vec<Message> = Vec::new();
     messages.extend(pie_ctx.injection_messages);
@@ -579,8 +873,15 @@ pub async fn generate_plan(
             ),
         );
 
+        crate::agent::enforce_limits(spend_limits, spend, total_cost, &ui_tx).await?;
         let resp = client
-            .chat(PLANNER_PROMPT, &messages, current_tools, &on_chunk)
+            .chat_with_retry(
+                PLANNER_PROMPT,
+                &messages,
+                current_tools,
+                &on_chunk,
+                crate::agent::retry_notifier(&ui_tx),
+            )
             .await?;
 
         // Append full model response text (includes <think> blocks) to log
@@ -588,6 +889,13 @@ pub async fn generate_plan(
 
         total_input += resp.input_tokens;
         total_output += resp.output_tokens;
+        total_cache_read += resp.cache_read_tokens;
+        total_cache_write += resp.cache_write_tokens;
+        let turn_cost = client.cost_of(&resp);
+        if let Some(c) = turn_cost {
+            total_cost = Some(total_cost.unwrap_or(0.0) + c);
+        }
+        spend.lock().unwrap().add(resp.input_tokens + resp.output_tokens, turn_cost);
         turns += 1;
 
         if resp.input_tokens > 0 || resp.output_tokens > 0 {
@@ -597,6 +905,9 @@ pub async fn generate_plan(
                 _output: resp.output_tokens,
                 total_input,
                 total_output,
+                total_cache_read,
+                total_cache_write,
+                total_cost,
                 tool_calls: tool_call_count,
             });
         }
@@ -688,7 +999,17 @@ pub async fn generate_plan(
 
             log_append(&mut session_log, &format!("\n\n◆ {} {}\n", tc.name, args_summary));
 
-            let result = execute_planner_tool(tc, graph, &mut delivered);
+            let orient_hits = match (tc.name.as_str(), semantic) {
+                ("orient", Some(_)) => {
+                    let query = serde_json::from_str::<serde_json::Value>(&tc.arguments)
+                        .ok()
+                        .and_then(|a| a["query"].as_str().map(str::to_string))
+                        .unwrap_or_default();
+                    crate::embeddings::hits(semantic, graph, &query, crate::agent::ORIENT_SEMANTIC_HITS, false).await
+                }
+                _ => Vec::new(),
+            };
+            let result = execute_planner_tool(tc, graph, &mut delivered, &orient_hits);
             let result_summary: String = result.lines().next().unwrap_or("").chars().take(80).collect();
             let _ = ui_tx.send(UiEvent::ToolResult { summary: result_summary });
 
@@ -707,6 +1028,9 @@ pub async fn generate_plan(
             _output: 0,
             total_input,
             total_output,
+            total_cache_read,
+            total_cache_write,
+            total_cost,
             tool_calls: tool_call_count,
         });
 
@@ -798,16 +1122,7 @@ pub async fn generate_plan(
                 resolved_files.truncate(10);
             }
 
-            PlanStep {
-                description: s.description,
-                instruction: s.instruction,
-                files: resolved_files,
-                verify: parse_verification(&s.verify),
-                status: StepStatus::Pending,
-                tool_budget: s.tool_budget.min(25), // cap tool budget to prevent runaway steps
-                user_annotation: None,
-                completed_summary: None,
-            }
+            s.into_step(resolved_files)
         })
         .collect();
 
@@ -818,10 +1133,23 @@ pub async fn generate_plan(
     let mut steps = steps;
     enrich_step_instructions(&mut steps, graph);
 
+    // Declared dependencies can miss shared files or call links — add those.
+    let inferred = infer_dependencies(&steps, Some(graph));
+    for (i, (step, extra)) in steps.iter_mut().zip(inferred).enumerate() {
+        let deps = step.depends_on.get_or_insert_with(Vec::new);
+        deps.extend(extra);
+        deps.retain(|&d| d < i);
+        deps.sort_unstable();
+        deps.dedup();
+    }
+
     let plan = Plan::new(task.to_string(), steps, project.to_string());
     let metrics = PlanMetrics {
         total_input_tokens: total_input,
         total_output_tokens: total_output,
+        total_cache_read_tokens: total_cache_read,
+        total_cache_write_tokens: total_cache_write,
+        cost_usd: total_cost,
         turns,
         tool_calls: tool_counts,
         elapsed_secs: elapsed,
@@ -829,6 +1157,49 @@ pub async fn generate_plan(
     Ok((plan, metrics))
 }
 
+/// Dependencies implied by what each step touches: step `i` depends on an
+/// earlier step `j` when they list a common file, when a symbol in one file
+/// calls into the other (per `graph.call_edges`), or when either lists no
+/// files at all (nothing to reason about — keep them ordered).
+pub fn infer_dependencies(steps: &[PlanStep], graph: Option<&ProjectGraph>) -> Vec<Vec<usize>> {
+    // file → files its symbols call into
+    let mut calls: HashMap<&str, HashSet<&str>> = HashMap::new();
+    for (key, edges) in graph.map(|g| &g.call_edges).into_iter().flatten() {
+        let Some((file, _)) = key.split_once("::") else { continue };
+        for edge in edges {
+            let callee_files: Vec<&str> = match edge.target.as_deref().and_then(|t| t.split_once("::")) {
+                Some((target_file, _)) => vec![target_file],
+                None => graph.and_then(|g| g.by_name.get(&edge.callee)).into_iter().flatten().map(String::as_str).collect(),
+            };
+            for callee_file in callee_files {
+                if callee_file != file {
+                    calls.entry(file).or_default().insert(callee_file);
+                }
+            }
+        }
+    }
+    let linked = |a: &str, b: &str| {
+        a == b
+            || calls.get(a).is_some_and(|c| c.contains(b))
+            || calls.get(b).is_some_and(|c| c.contains(a))
+    };
+
+    steps
+        .iter()
+        .enumerate()
+        .map(|(i, step)| {
+            (0..i)
+                .filter(|&j| {
+                    let earlier = &steps[j];
+                    step.files.is_empty()
+                        || earlier.files.is_empty()
+                        || step.files.iter().any(|a| earlier.files.iter().any(|b| linked(a, b)))
+                })
+                .collect()
+        })
+        .collect()
+}
+
 /// Write the plan as human-readable markdown to `.parecode/plan.md`.
 /// Overwrites any previous plan file — only the latest plan is kept.
 /// Silently ignores errors (disk write must never crash planning).
@@ -846,6 +1217,10 @@ fn try_write_plan_to_disk(plan: &Plan) -> anyhow::Result<()> {
         if !step.files.is_empty() {
             md.push_str(&format!("**Files:** {}\n\n", step.files.join(", ")));
         }
+        if let Some(deps) = step.depends_on.as_ref().filter(|d| !d.is_empty()) {
+            let deps: Vec<String> = deps.iter().map(|d| (d + 1).to_string()).collect();
+            md.push_str(&format!("**Depends on:** step {}\n\n", deps.join(", ")));
+        }
         let verify_str = match &step.verify {
             Verification::None => None,
             Verification::FileChanged(path) => Some(format!("file changed: `{path}`")),
@@ -874,6 +1249,7 @@ fn try_write_plan_to_disk(plan: &Plan) -> anyhow::Result<()> {
 /// TESTING IS ESSENtiAL TO BE IMPROVED!! 
 pub async fn execute_step(
     step: &PlanStep,
+    prior_summaries: &[(String, String)],
     client: &Client,
     config: &crate::agent::AgentConfig,
     ui_tx: tokio::sync::mpsc::UnboundedSender<UiEvent>,
@@ -889,7 +1265,7 @@ pub async fn execute_step(
     }
     
 
-    let instruction = step.effective_instruction();
+    let instruction = step_prompt(step, prior_summaries);
 
     // Run the agent with fresh context
     crate::agent::run_tui(
@@ -904,6 +1280,543 @@ pub async fn execute_step(
     .await
 }
 
+/// The instruction a step runs with: a compact list of what earlier steps did,
+/// then the step's own (annotated) instruction.
+fn step_prompt(step: &PlanStep, prior_summaries: &[(String, String)]) -> String {
+    if prior_summaries.is_empty() {
+        return step.effective_instruction();
+    }
+    let done: Vec<String> = prior_summaries
+        .iter()
+        .enumerate()
+        .map(|(i, (desc, summary))| format!("{}. {desc}\n  {summary}", i + 1))
+        .collect();
+    format!(
+        "Earlier plan steps are already done — do not redo them:\n{}\n\nThis step:\n{}",
+        done.join("\n"),
+        step.effective_instruction()
+    )
+}
+
+/// A plan step whose agent run finished but whose verification did not pass.
+#[derive(Debug)]
+pub struct VerificationFailed {
+    /// 0-based step index
+    pub step: usize,
+    pub reason: String,
+}
+
+impl std::fmt::Display for VerificationFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "step {} failed verification: {}", self.step + 1, self.reason)
+    }
+}
+
+impl std::error::Error for VerificationFailed {}
+
+/// Execute `plan.steps[from..]` in dependency order, skipping steps already
+/// `Pass` or `Skipped` — so a saved plan resumes where it stopped, with the
+/// earlier steps' `completed_summary` carried into each later step. Each step
+/// gets a git checkpoint, `execute_step`,
+/// `verify_step`, `on_plan_step_done` hooks and a per-step diff, stopping at
+/// the first failure. When several steps are ready at once (see
+/// `Plan::dependencies`) and git is available they run concurrently in their
+/// own worktrees — see `run_parallel_steps`. Progress goes out as `PlanStep*` / `Plan*` events and
+/// `persist` is called whenever the plan's status changes so it can be saved.
+///
+/// Returns `Ok(())` when every step passed (or `cancelled` was set between
+/// steps). A failing step is returned as its agent error, or as
+/// `VerificationFailed` when the step ran but its check did not pass.
+pub async fn run_plan(
+    plan: &mut Plan,
+    from: usize,
+    client: &Client,
+    config: &crate::agent::AgentConfig,
+    ui_tx: mpsc::UnboundedSender<UiEvent>,
+    cancelled: &std::sync::atomic::AtomicBool,
+    persist: impl Fn(&Plan),
+) -> Result<()> {
+    let total = plan.steps.len();
+    plan.status = PlanStatus::Running;
+    persist(plan);
+
+    // ── Plan-level git checkpoint ─────────────────────────────────────────────
+    // Capture the state before any step runs — used for cumulative diff.
+    let plan_checkpoint: Option<String> = if config.git_context {
+        std::env::current_dir().ok().and_then(|cwd| {
+            crate::git::GitRepo::open(&cwd).and_then(|repo| {
+                let summary = format!("plan: {}", plan.task.chars().take(50).collect::<String>());
+                repo.checkpoint(&summary).ok()
+            })
+        })
+    } else {
+        None
+    };
+
+    let deps = plan.dependencies();
+    // Steps before `from` count as done whatever their status says
+    let is_done = |plan: &Plan, i: usize| {
+        i < from || matches!(plan.steps[i].status, StepStatus::Pass | StepStatus::Skipped)
+    };
+
+    loop {
+        // Check cancellation before starting each step
+        if cancelled.load(std::sync::atomic::Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        let ready: Vec<usize> = (from..total)
+            .filter(|&i| !is_done(plan, i) && deps[i].iter().all(|&d| is_done(plan, d)))
+            .collect();
+        let Some(&step_idx) = ready.first() else { break };
+
+        // ── Independent steps: one worktree each, run side by side ───────────
+        if ready.len() > 1 && config.git_context {
+            let repo = std::env::current_dir().ok().and_then(|cwd| crate::git::GitRepo::open(&cwd));
+            if let Some((repo, (base, worktrees))) =
+                repo.and_then(|repo| add_step_worktrees(&repo, &ready).map(|wt| (repo, wt)))
+            {
+                let batch = ParallelBatch { steps: &ready, base: &base, worktrees: &worktrees };
+                let result = run_parallel_steps(plan, batch, &repo, client, config, &ui_tx, &persist).await;
+                for wt in &worktrees {
+                    let _ = repo.remove_worktree(wt);
+                }
+                result?;
+                continue;
+            }
+        }
+
+        let step_snapshot = plan.steps[step_idx].clone();
+        let desc = step_snapshot.description.clone();
+
+        // ── Per-step git checkpoint ───────────────────────────────────────────
+        let step_checkpoint: Option<String> = if config.git_context {
+            std::env::current_dir().ok().and_then(|cwd| {
+                crate::git::GitRepo::open(&cwd).and_then(|repo| {
+                    let summary = format!(
+                        "plan step {}/{}: {}",
+                        step_idx + 1,
+                        total,
+                        desc.chars().take(40).collect::<String>()
+                    );
+                    repo.checkpoint(&summary).ok()
+                })
+            })
+        } else {
+            None
+        };
+
+        plan.current = step_idx;
+        plan.steps[step_idx].status = StepStatus::Running;
+        persist(plan);
+        let _ = ui_tx.send(UiEvent::PlanStepStart { index: step_idx, total, desc });
+
+        // Run the step (agent call), then its verification.
+        // On failure: (message for the UI, error returned to the caller)
+        let prior_summaries = plan.completed_summaries(step_idx);
+        let failure = match execute_step(&step_snapshot, &prior_summaries, client, config, ui_tx.clone()).await {
+            Ok(()) => verify_step(&step_snapshot).err().map(|e| {
+                let reason = e.to_string();
+                (reason.clone(), anyhow::Error::new(VerificationFailed { step: step_idx, reason }))
+            }),
+            Err(e) => Some((e.to_string(), e.context(format!("step {}", step_idx + 1)))),
+        };
+
+        if let Some((msg, err)) = failure {
+            plan.steps[step_idx].status = StepStatus::Fail;
+            plan.status = PlanStatus::Failed;
+            persist(plan);
+            let _ = ui_tx.send(UiEvent::PlanStepDone {
+                index: step_idx,
+                passed: false,
+                error: Some(msg.clone()),
+            });
+            let _ = ui_tx.send(UiEvent::PlanFailed { step: step_idx, error: msg });
+            return Err(err);
+        }
+
+        plan.steps[step_idx].status = StepStatus::Pass;
+        // Inspect modified files to record what actually changed
+        plan.steps[step_idx].completed_summary = Some(summarise_completed_step(&step_snapshot));
+        persist(plan);
+        let _ = ui_tx.send(UiEvent::PlanStepDone { index: step_idx, passed: true, error: None });
+
+        // on_plan_step_done hooks
+        if config.hooks_enabled {
+            for cmd in &config.hooks.on_plan_step_done {
+                let hr = crate::hooks::run_hook(cmd).await;
+                let _ = ui_tx.send(UiEvent::HookOutput {
+                    event: "on_plan_step_done".to_string(),
+                    output: hr.output,
+                    exit_code: hr.exit_code,
+                });
+            }
+        }
+        // ── Per-step diff notification ────────────────────────────────────────
+        if config.git_context {
+            if let Some(cwd) = std::env::current_dir().ok() {
+                if let Some(repo) = crate::git::GitRepo::open(&cwd) {
+                    let ref_pt = step_checkpoint.as_deref().unwrap_or("HEAD");
+                    if let Ok(stat) = repo.diff_stat_from(ref_pt) {
+                        if !stat.trim().is_empty() {
+                            let files_changed = stat.lines().filter(|l| l.contains('|')).count();
+                            let _ = ui_tx.send(UiEvent::GitChanges {
+                                stat: stat.trim().to_string(),
+                                checkpoint_hash: step_checkpoint.clone(),
+                                files_changed,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // All steps passed
+    plan.status = PlanStatus::Complete;
+    persist(plan);
+
+    // ── Cumulative diff + auto-commit ─────────────────────────────────────────
+    if config.git_context {
+        if let Some(cwd) = std::env::current_dir().ok() {
+            if let Some(repo) = crate::git::GitRepo::open(&cwd) {
+                let ref_pt = plan_checkpoint.as_deref().unwrap_or("HEAD");
+                if let Ok(stat) = repo.diff_stat_from(ref_pt) {
+                    if !stat.trim().is_empty() {
+                        let files_changed = stat.lines().filter(|l| l.contains('|')).count();
+                        let cumulative_stat =
+                            format!("Plan complete — cumulative changes:\n{}", stat.trim());
+                        let _ = ui_tx.send(UiEvent::GitChanges {
+                            stat: cumulative_stat,
+                            checkpoint_hash: plan_checkpoint.clone(),
+                            files_changed,
+                        });
+                    }
+                }
+                if config.auto_commit {
+                    let summary: String = plan.task.chars().take(72).collect();
+                    let msg = format!("{}{}", config.auto_commit_prefix, summary);
+                    match repo.auto_commit(&msg) {
+                        Ok(()) => {
+                            let _ = ui_tx.send(UiEvent::GitAutoCommit { message: msg });
+                        }
+                        Err(e) => {
+                            let _ = ui_tx.send(UiEvent::GitError(format!("auto-commit: {e}")));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let _ = ui_tx.send(UiEvent::PlanComplete { total });
+    Ok(())
+}
+
+/// Steps that are ready together, the commit their worktrees were checked out
+/// at, and one worktree path per step (same order as `steps`).
+struct ParallelBatch<'a> {
+    steps: &'a [usize],
+    base: &'a str,
+    worktrees: &'a [PathBuf],
+}
+
+/// Checkpoint the working tree and check out a detached worktree of it per
+/// step in `steps`. Returns the checkpoint and the worktree paths, or `None`
+/// (with any worktrees already made removed) if git can't do it — the caller
+/// then falls back to running steps one at a time in the main tree.
+fn add_step_worktrees(repo: &crate::git::GitRepo, steps: &[usize]) -> Option<(String, Vec<PathBuf>)> {
+    let summary = format!(
+        "plan steps {}",
+        steps.iter().map(|i| (i + 1).to_string()).collect::<Vec<_>>().join(", ")
+    );
+    let base = repo.checkpoint(&summary).ok()?;
+    let mut worktrees: Vec<PathBuf> = Vec::with_capacity(steps.len());
+    for &i in steps {
+        let path = std::env::temp_dir()
+            .join(format!("parecode-{}-step-{}", std::process::id(), i + 1));
+        if repo.add_worktree(&path, &base).is_err() {
+            for wt in &worktrees {
+                let _ = repo.remove_worktree(wt);
+            }
+            return None;
+        }
+        worktrees.push(path);
+    }
+    Some((base, worktrees))
+}
+
+/// Run a batch of mutually independent steps concurrently, each with its own
+/// `FileCache` and worktree (`AgentConfig::workdir`), verifying each in its
+/// worktree. Passing steps are then merged back into the main tree in plan
+/// order as patches against `batch.base`; a patch that no longer applies
+/// (two steps edited the same lines after all) fails its step.
+///
+/// Every step in the batch gets its status and `PlanStepDone`; on failure the
+/// first failing step's error is returned, as in the sequential path.
+async fn run_parallel_steps(
+    plan: &mut Plan,
+    batch: ParallelBatch<'_>,
+    repo: &crate::git::GitRepo,
+    client: &Client,
+    config: &crate::agent::AgentConfig,
+    ui_tx: &mpsc::UnboundedSender<UiEvent>,
+    persist: &impl Fn(&Plan),
+) -> Result<()> {
+    let total = plan.steps.len();
+    let snapshots: Vec<PlanStep> = batch.steps.iter().map(|&i| plan.steps[i].clone()).collect();
+    let prior: Vec<Vec<(String, String)>> =
+        batch.steps.iter().map(|&i| plan.completed_summaries(i)).collect();
+    let configs: Vec<crate::agent::AgentConfig> = batch
+        .worktrees
+        .iter()
+        .map(|wt| crate::agent::AgentConfig { workdir: Some(wt.clone()), ..config.clone() })
+        .collect();
+
+    plan.current = batch.steps[0];
+    for (&i, step) in batch.steps.iter().zip(&snapshots) {
+        plan.steps[i].status = StepStatus::Running;
+        let _ = ui_tx.send(UiEvent::PlanStepStart { index: i, total, desc: step.description.clone() });
+    }
+    persist(plan);
+
+    let runs = batch.steps.iter().enumerate().map(|(k, &i)| {
+        let (step, cfg, wt) = (&snapshots[k], &configs[k], &batch.worktrees[k]);
+        let prior = &prior[k];
+        let ui_tx = ui_tx.clone();
+        async move {
+            match execute_step(step, prior, client, cfg, ui_tx).await {
+                Ok(()) => verify_step_in(step, wt).err().map(|e| {
+                    let reason = e.to_string();
+                    (reason.clone(), anyhow::Error::new(VerificationFailed { step: i, reason }))
+                }),
+                Err(e) => Some((e.to_string(), e.context(format!("step {}", i + 1)))),
+            }
+        }
+    });
+    let outcomes = futures_util::future::join_all(runs).await;
+
+    // ── Merge back, in plan order ─────────────────────────────────────────────
+    let mut first_err: Option<anyhow::Error> = None;
+    for (k, failure) in outcomes.into_iter().enumerate() {
+        let i = batch.steps[k];
+        let failure = failure.or_else(|| {
+            let merged = crate::git::GitRepo::open(&batch.worktrees[k])
+                .ok_or_else(|| anyhow::anyhow!("worktree is gone"))
+                .and_then(|wt| wt.staged_patch(batch.base))
+                .and_then(|patch| if patch.trim().is_empty() { Ok(()) } else { repo.apply_patch(&patch) });
+            merged.err().map(|e| {
+                let msg = format!("merging step {} back: {e}", i + 1);
+                (msg.clone(), anyhow::anyhow!(msg))
+            })
+        });
+
+        match failure {
+            Some((msg, err)) => {
+                plan.steps[i].status = StepStatus::Fail;
+                let _ = ui_tx.send(UiEvent::PlanStepDone { index: i, passed: false, error: Some(msg.clone()) });
+                if first_err.is_none() {
+                    let _ = ui_tx.send(UiEvent::PlanFailed { step: i, error: msg });
+                    first_err = Some(err);
+                }
+            }
+            None => {
+                plan.steps[i].status = StepStatus::Pass;
+                plan.steps[i].completed_summary = Some(summarise_completed_step(&snapshots[k]));
+                let _ = ui_tx.send(UiEvent::PlanStepDone { index: i, passed: true, error: None });
+                if config.hooks_enabled {
+                    for cmd in &config.hooks.on_plan_step_done {
+                        let hr = crate::hooks::run_hook(cmd).await;
+                        let _ = ui_tx.send(UiEvent::HookOutput {
+                            event: "on_plan_step_done".to_string(),
+                            output: hr.output,
+                            exit_code: hr.exit_code,
+                        });
+                    }
+                }
+            }
+        }
+    }
+    if first_err.is_some() {
+        plan.status = PlanStatus::Failed;
+    }
+    persist(plan);
+
+    // ── Batch diff notification ───────────────────────────────────────────────
+    if let Ok(stat) = repo.diff_stat_from(batch.base) {
+        if !stat.trim().is_empty() {
+            let files_changed = stat.lines().filter(|l| l.contains('|')).count();
+            let _ = ui_tx.send(UiEvent::GitChanges {
+                stat: stat.trim().to_string(),
+                checkpoint_hash: Some(batch.base.to_string()),
+                files_changed,
+            });
+        }
+    }
+
+    match first_err {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+// ── Replanning ────────────────────────────────────────────────────────────────
+
+const REPLANNER_PROMPT: &str = r#"You are a coding task planner. A step of an approved plan ran but failed its verification. Decide how to recover and output the new steps.
+
+You get: the overall task, the steps already completed (with what they changed), the failed step with its verification output, and the steps that were still to run.
+
+Choose ONE:
+- "replace": "step" — revise the failed step (or split it into a few steps). The remaining steps stay as they are.
+- "replace": "rest" — the remaining steps are wrong too; output replacements for the failed step AND everything after it.
+
+Each new step must fix the cause shown in the verification output — do not repeat the failed instruction unchanged. Completed steps are done; never redo them.
+
+OUTPUT: JSON only (no markdown fences, no prose):
+{
+  "replace": "step",
+  "steps": [
+    {
+      "description": "short label",
+      "instruction": "exact file:line refs — what to change and why",
+      "files": ["src/foo.rs"],
+      "verify": "none",
+      "tool_budget": 15,
+      "depends_on": []
+    }
+  ]
+}
+verify is one of: "none", "build", "command:<cmd>", "changed:<path>", "absent:<file>:<pattern>".
+depends_on lists 0-based plan indices of earlier steps — completed steps keep their numbers and the new steps are numbered from the failed step's index."#;
+
+/// Response from the model when replanning a failed step.
+#[derive(Debug, Deserialize)]
+struct ReplanResponse {
+    #[serde(default)]
+    replace: ReplanScope,
+    steps: Vec<PlanStepRaw>,
+}
+
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum ReplanScope {
+    #[default]
+    Step,
+    Rest,
+}
+
+/// New steps from the planner after a failure — see `Plan::apply_replan`.
+#[derive(Debug, Clone)]
+pub struct Replan {
+    /// true = `steps` replace the failed step and everything after it;
+    /// false = they replace only the failed step.
+    pub replace_rest: bool,
+    pub steps: Vec<PlanStep>,
+}
+
+/// The planner's view of a failure: the task, what completed steps changed,
+/// the failed step and its verification output, and what was still to run.
+/// Steps are numbered as `Plan::apply_replan` will lay them out — passed
+/// siblings from the failed step's batch count as completed, ahead of it.
+fn replan_prompt(plan: &Plan, failed: usize, failure: &str) -> String {
+    let mut out = format!("TASK: {}\n\n", plan.task);
+
+    let passed_after = plan.passed_after(failed);
+    let index = failed + passed_after.len();
+    let summaries: Vec<String> = (0..failed)
+        .filter(|&i| plan.steps[i].status == StepStatus::Pass)
+        .map(|i| (i, &plan.steps[i]))
+        .chain(passed_after.iter().enumerate().map(|(k, &i)| (failed + k, &plan.steps[i])))
+        .filter_map(|(pos, s)| Some(format!("{}. {}\n  {}\n", pos + 1, s.description, s.completed_summary.as_ref()?)))
+        .collect();
+    if !summaries.is_empty() {
+        out.push_str("COMPLETED STEPS:\n");
+        out.push_str(&summaries.concat());
+        out.push('\n');
+    }
+
+    let step = &plan.steps[failed];
+    out.push_str(&format!(
+        "FAILED STEP (index {index}): {}\ninstruction: {}\nfiles: {}\nverify: {}\n\nVERIFICATION OUTPUT:\n{failure}\n",
+        step.description,
+        step.effective_instruction(),
+        step.files.join(", "),
+        verification_spec(&step.verify),
+    ));
+
+    let remaining: Vec<&PlanStep> = plan.steps[failed + 1..].iter().filter(|s| s.status != StepStatus::Pass).collect();
+    if !remaining.is_empty() {
+        out.push_str("\nREMAINING STEPS:\n");
+        for (i, step) in remaining.iter().enumerate() {
+            out.push_str(&format!("{}. {} [{}]\n", index + 2 + i, step.description, step.files.join(", ")));
+        }
+    }
+    out
+}
+
+/// Parse the planner's replan JSON into `Pending` steps.
+fn parse_replan(text: &str) -> Result<Replan> {
+    let json = extract_json(text.trim())
+        .ok_or_else(|| anyhow::anyhow!("Replan parse error: could not extract JSON from model response.\n\nModel response:\n{text}"))?;
+    let json = sanitize_json_strings(&json);
+    let raw: ReplanResponse = serde_json::from_str(&json)
+        .map_err(|e| anyhow::anyhow!("Replan parse error: {e}\n\nModel response:\n{json}"))?;
+    if raw.steps.is_empty() {
+        return Err(anyhow::anyhow!("Model returned no replacement steps"));
+    }
+    Ok(Replan {
+        replace_rest: raw.replace == ReplanScope::Rest,
+        steps: raw
+            .steps
+            .into_iter()
+            .map(|s| {
+                let files = s.files.iter().take(10).cloned().collect();
+                s.into_step(files)
+            })
+            .collect(),
+    })
+}
+
+/// Ask the planner model (pass a client set up for `planner_model`) how to
+/// recover from `plan.steps[failed]` failing with `failure`. Single turn, no
+/// tools — the failure output and completed-step summaries are the context.
+/// The caller splices the result in with `Plan::apply_replan` and sends the
+/// plan back for review. Its usage is added to `spend`, after checking the
+/// limits.
+pub async fn replan_failed_step(
+    plan: &Plan,
+    failed: usize,
+    failure: &str,
+    client: &Client,
+    spend_limits: &crate::budget::SpendLimits,
+    spend: &std::sync::Mutex<crate::budget::SpendGuard>,
+    ui_tx: &mpsc::UnboundedSender<UiEvent>,
+) -> Result<(Replan, PlanMetrics)> {
+    let start = std::time::Instant::now();
+    spend.lock().unwrap().begin_task();
+    crate::agent::enforce_limits(spend_limits, spend, None, ui_tx).await?;
+    let messages = vec![Message {
+        role: "user".to_string(),
+        content: MessageContent::Text(replan_prompt(plan, failed, failure)),
+        tool_calls: vec![],
+    }];
+    let resp = client
+        .chat_with_retry(REPLANNER_PROMPT, &messages, &[], |_| {}, crate::agent::retry_notifier(ui_tx))
+        .await?;
+    let metrics = PlanMetrics {
+        total_input_tokens: resp.input_tokens,
+        total_output_tokens: resp.output_tokens,
+        total_cache_read_tokens: resp.cache_read_tokens,
+        total_cache_write_tokens: resp.cache_write_tokens,
+        cost_usd: client.cost_of(&resp),
+        turns: 1,
+        tool_calls: HashMap::new(),
+        elapsed_secs: start.elapsed().as_secs() as u32,
+    };
+    spend.lock().unwrap().add(metrics.total_tokens(), metrics.cost_usd);
+    Ok((parse_replan(&resp.text)?, metrics))
+}
+
 /// Build a rich summary of what a step actually did, by inspecting the files
 /// it listed. Includes: which files were modified, line count changes, new
 /// symbols added, and structural notes (test modules, etc.). This summary is
@@ -1045,12 +1958,18 @@ pub fn summarise_completed_step(step: &PlanStep) -> String {
 /// Run verification for a completed step.
 /// Returns `Ok(())` if verification passes, `Err(msg)` if it fails.
 pub fn verify_step(step: &PlanStep) -> Result<()> {
+    verify_step_in(step, std::path::Path::new("."))
+}
+
+/// `verify_step` against the tree at `root` — a step's worktree when plan
+/// steps run in parallel. Paths and commands resolve relative to `root`.
+pub fn verify_step_in(step: &PlanStep, root: &std::path::Path) -> Result<()> {
     match &step.verify {
         Verification::None => Ok(()),
 
         Verification::FileChanged(path) => {
             // Check the file exists and was modified recently (within last 60s)
-            let meta = std::fs::metadata(path)
+            let meta = std::fs::metadata(root.join(path))
                 .map_err(|e| anyhow::anyhow!("verify: cannot stat {path}: {e}"))?;
             let modified = meta
                 .modified()
@@ -1068,7 +1987,7 @@ pub fn verify_step(step: &PlanStep) -> Result<()> {
         }
 
         Verification::PatternAbsent { file, pattern } => {
-            let content = std::fs::read_to_string(file)
+            let content = std::fs::read_to_string(root.join(file))
                 .map_err(|e| anyhow::anyhow!("verify: cannot read {file}: {e}"))?;
             if content.contains(pattern.as_str()) {
                 let count = content.matches(pattern.as_str()).count();
@@ -1084,6 +2003,7 @@ pub fn verify_step(step: &PlanStep) -> Result<()> {
             let output = std::process::Command::new("sh")
                 .arg("-c")
                 .arg(cmd)
+                .current_dir(root)
                 .output()
                 .map_err(|e| anyhow::anyhow!("verify: failed to run '{cmd}': {e}"))?;
             if output.status.success() {
@@ -1334,6 +2254,253 @@ some text after"#;
         let parsed: serde_json::Value = serde_json::from_str(&json).expect("Should be valid JSON");
         assert_eq!(parsed["steps"][0]["description"], "First step");
     }
+
+    // ── Headless run preparation ────────────────────────────────────────────
+
+    fn plan_with(statuses: &[StepStatus]) -> Plan {
+        let steps = statuses
+            .iter()
+            .map(|status| PlanStep {
+                description: "d".into(),
+                instruction: "i".into(),
+                files: vec![],
+                verify: Verification::None,
+                status: status.clone(),
+                tool_budget: 8,
+                user_annotation: None,
+                completed_summary: None,
+                depends_on: None,
+            })
+            .collect();
+        Plan::new("task".into(), steps, "proj".into())
+    }
+
+    #[test]
+    fn test_prepare_run_requires_approval_from_start_step() {
+        use StepStatus::*;
+        let mut plan = plan_with(&[Pending, Approved, Pending, Pass]);
+        let err = prepare_run(&mut plan, 0, false).unwrap_err();
+        assert!(err.to_string().contains("step(s) 1, 3 not approved"), "got: {err}");
+
+        // Earlier steps are not checked when resuming later in the plan
+        let mut plan = plan_with(&[Pending, Approved, Fail]);
+        prepare_run(&mut plan, 1, false).unwrap();
+        assert_eq!(plan.steps[0].status, Pending);
+        assert_eq!(plan.steps[2].status, Approved, "failed step is retried");
+
+        let err = prepare_run(&mut plan, 3, false).unwrap_err();
+        assert!(err.to_string().contains("3 steps"), "got: {err}");
+    }
+
+    #[test]
+    fn test_prepare_run_auto_approve_and_file_round_trip() {
+        use StepStatus::*;
+        let mut plan = plan_with(&[Pending, Skipped, Pass]);
+        prepare_run(&mut plan, 0, true).unwrap();
+        let statuses: Vec<_> = plan.steps.iter().map(|s| s.status.clone()).collect();
+        assert_eq!(statuses, [Approved, Skipped, Pass]);
+
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("nested/plan.json");
+        save_plan_to(&plan, &path).unwrap();
+        let loaded = load_plan(&path).unwrap();
+        assert_eq!(loaded.steps[0].status, Approved);
+        assert_eq!(loaded.task, "task");
+        assert!(load_plan(&tmp.path().join("missing.json")).is_err());
+    }
+
+    #[test]
+    fn test_resume_point_and_carried_summaries() {
+        use StepStatus::*;
+        let mut plan = plan_with(&[Pass, Skipped, Pass, Running, Approved]);
+        plan.steps[0].completed_summary = Some("src/a.rs (10 lines)".into());
+        plan.steps[2].completed_summary = Some("src/b.rs (20 lines)".into());
+        assert_eq!(plan.resume_index(), Some(3));
+        assert_eq!(plan.steps_done(), 3);
+
+        let prior = plan.completed_summaries(3);
+        assert_eq!(prior.len(), 2);
+        let prompt = step_prompt(&plan.steps[3], &prior);
+        assert!(prompt.contains("1. d\n  src/a.rs (10 lines)"), "got: {prompt}");
+        assert!(prompt.contains("2. d\n  src/b.rs"));
+        assert!(prompt.ends_with("This step:\ni"));
+        assert_eq!(step_prompt(&plan.steps[0], &[]), "i");
+
+        plan.steps[3].status = Pass;
+        plan.steps[4].status = Pass;
+        assert_eq!(plan.resume_index(), None);
+    }
+
+    // ── Step dependencies ───────────────────────────────────────────────────
+
+    #[test]
+    fn test_dependencies_default_to_chain_and_drop_transitive_edges() {
+        use StepStatus::*;
+        let mut plan = plan_with(&[Pending, Pending, Pending, Pending]);
+        assert_eq!(plan.dependencies()[3], [0, 1, 2], "undeclared = every earlier step");
+        assert_eq!(plan.direct_dependencies(), [vec![], vec![0], vec![1], vec![2]]);
+        assert!(!plan.has_parallel_steps());
+
+        // 0 → {1, 2} → 3, with a redundant 3 → 0 edge and a bogus forward edge
+        plan.steps[0].depends_on = Some(vec![2]);
+        plan.steps[1].depends_on = Some(vec![0]);
+        plan.steps[2].depends_on = Some(vec![0]);
+        plan.steps[3].depends_on = Some(vec![0, 1, 2]);
+        assert_eq!(plan.dependencies()[0], Vec::<usize>::new());
+        assert_eq!(plan.direct_dependencies(), [vec![], vec![0], vec![0], vec![1, 2]]);
+        assert!(plan.has_parallel_steps());
+    }
+
+    #[test]
+    fn test_infer_dependencies_from_files_and_calls() {
+        use StepStatus::*;
+        let mut plan = plan_with(&[Pending, Pending, Pending, Pending]);
+        plan.steps[0].files = vec!["src/a.rs".into()];
+        plan.steps[1].files = vec!["src/b.rs".into()];
+        plan.steps[2].files = vec!["src/c.rs".into(), "src/a.rs".into()];
+        plan.steps[3].files = vec![];
+
+        let inferred = infer_dependencies(&plan.steps, None);
+        assert_eq!(inferred, [vec![], vec![], vec![0], vec![0, 1, 2]]);
+
+        // b.rs calls into c.rs → step 2 now also waits for step 1
+        let mut graph = ProjectGraph::default();
+        graph.by_name.insert("helper".into(), vec!["src/c.rs".into()]);
+        graph.call_edges.insert(
+            "src/b.rs::run".into(),
+            vec![crate::index::CallEdge { callee: "helper".into(), call_line: 3, target: None }],
+        );
+        let inferred = infer_dependencies(&plan.steps, Some(&graph));
+        assert_eq!(inferred[2], [0, 1]);
+        assert!(inferred[1].is_empty());
+    }
+
+    // ── Replanning ──────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_parse_replan_scopes() {
+        let replan = parse_replan(
+            r#"{"replace":"step","steps":[{"description":"fix","instruction":"do it","files":["src/a.rs"],"verify":"command:cargo check"}]}"#,
+        )
+        .unwrap();
+        assert!(!replan.replace_rest);
+        assert_eq!(replan.steps[0].status, StepStatus::Pending);
+        assert!(matches!(&replan.steps[0].verify, Verification::CommandSuccess(c) if c == "cargo check"));
+
+        let replan = parse_replan(r#"```json
+{"replace":"rest","steps":[{"description":"a","instruction":"i"},{"description":"b","instruction":"i"}]}
+```"#)
+        .unwrap();
+        assert!(replan.replace_rest);
+        assert_eq!(replan.steps.len(), 2);
+
+        assert!(parse_replan(r#"{"steps":[]}"#).is_err());
+        assert!(parse_replan("no idea").is_err());
+    }
+
+    #[test]
+    fn test_apply_replan_splices_and_shifts_dependencies() {
+        use StepStatus::*;
+        let new_step = |desc: &str| PlanStep { description: desc.into(), ..plan_with(&[Pending]).steps.remove(0) };
+
+        // Failed step 1 split in two; step 2 depended on it, step 3 on step 2
+        let mut plan = plan_with(&[Pass, Fail, Approved, Approved]);
+        plan.steps[2].depends_on = Some(vec![0, 1]);
+        plan.steps[3].depends_on = Some(vec![2]);
+        plan.apply_replan(1, Replan { replace_rest: false, steps: vec![new_step("x"), new_step("y")] });
+        let descs: Vec<&str> = plan.steps.iter().map(|s| s.description.as_str()).collect();
+        assert_eq!(descs, ["d", "x", "y", "d", "d"]);
+        assert_eq!(plan.steps[3].depends_on, Some(vec![0, 1, 2]));
+        assert_eq!(plan.steps[4].depends_on, Some(vec![3]));
+        assert_eq!(plan.steps[1].status, Pending);
+        assert_eq!(plan.steps[3].status, Approved, "untouched steps keep their approval");
+        assert_eq!((plan.replans, plan.status.clone()), (1, PlanStatus::Pending));
+
+        let mut plan = plan_with(&[Pass, Fail, Approved, Approved]);
+        plan.apply_replan(1, Replan { replace_rest: true, steps: vec![new_step("z")] });
+        let descs: Vec<&str> = plan.steps.iter().map(|s| s.description.as_str()).collect();
+        assert_eq!(descs, ["d", "z"]);
+    }
+
+    #[test]
+    fn test_replan_keeps_passed_batch_siblings() {
+        use StepStatus::*;
+        let new_step = |desc: &str| PlanStep { description: desc.into(), ..plan_with(&[Pending]).steps.remove(0) };
+
+        // Steps 1 and 2 ran as one batch: 1 failed, 2 passed and was merged
+        let mut plan = plan_with(&[Pass, Fail, Pass, Approved]);
+        plan.steps[1].description = "failed".into();
+        plan.steps[2].description = "sibling".into();
+        plan.steps[2].completed_summary = Some("src/c.rs (5 lines)".into());
+        plan.steps[3].description = "later".into();
+        plan.steps[3].depends_on = Some(vec![1, 2]);
+
+        let prompt = replan_prompt(&plan, 1, "boom");
+        assert!(prompt.contains("COMPLETED STEPS:\n2. sibling\n  src/c.rs (5 lines)"), "got: {prompt}");
+        assert!(prompt.contains("FAILED STEP (index 2): failed"), "got: {prompt}");
+        assert!(prompt.contains("REMAINING STEPS:\n4. later"), "got: {prompt}");
+        assert!(!prompt[prompt.find("REMAINING").unwrap()..].contains("sibling"));
+
+        let mut step_only = plan.clone();
+        step_only.apply_replan(1, Replan { replace_rest: false, steps: vec![new_step("x")] });
+        let descs: Vec<&str> = step_only.steps.iter().map(|s| s.description.as_str()).collect();
+        assert_eq!(descs, ["d", "sibling", "x", "later"]);
+        assert_eq!(step_only.steps[3].depends_on, Some(vec![1, 2]));
+        assert_eq!(step_only.current, 2);
+
+        plan.apply_replan(1, Replan { replace_rest: true, steps: vec![new_step("y"), new_step("z")] });
+        let descs: Vec<&str> = plan.steps.iter().map(|s| s.description.as_str()).collect();
+        assert_eq!(descs, ["d", "sibling", "y", "z"]);
+        assert_eq!(plan.steps[1].status, Pass, "applied work is kept");
+    }
+
+    #[test]
+    fn test_replan_prompt_carries_failure_and_context() {
+        use StepStatus::*;
+        let mut plan = plan_with(&[Pass, Fail, Approved]);
+        plan.steps[0].completed_summary = Some("src/a.rs (10 lines)".into());
+        plan.steps[1].files = vec!["src/b.rs".into()];
+        plan.steps[1].verify = Verification::CommandSuccess("cargo check".into());
+        plan.steps[2].description = "later".into();
+        let prompt = replan_prompt(&plan, 1, "error[E0308]: mismatched types");
+        assert!(prompt.contains("COMPLETED STEPS:\n1. d\n  src/a.rs (10 lines)"), "got: {prompt}");
+        assert!(prompt.contains("FAILED STEP (index 1): d"));
+        assert!(prompt.contains("verify: command:cargo check"));
+        assert!(prompt.contains("VERIFICATION OUTPUT:\nerror[E0308]"));
+        assert!(prompt.contains("REMAINING STEPS:\n3. later"));
+    }
+
+    #[tokio::test]
+    async fn test_replan_stops_at_spend_limit_before_calling_the_model() {
+        let plan = plan_with(&[StepStatus::Pass, StepStatus::Fail]);
+        // Nothing listens on this endpoint — reaching the model would be a different error
+        let client = Client::new("http://127.0.0.1:9".into(), "m".into());
+        let limits = crate::budget::SpendLimits { max_tokens_per_session: Some(100), ..Default::default() };
+        let spend = std::sync::Mutex::new(crate::budget::SpendGuard::default());
+        spend.lock().unwrap().add(150, None);
+        // Nobody answers the pause, so it aborts
+        let (tx, _) = mpsc::unbounded_channel();
+        let err = replan_failed_step(&plan, 1, "failed", &client, &limits, &spend, &tx).await.unwrap_err();
+        assert!(err.to_string().starts_with("stopped:"), "got: {err}");
+    }
+
+    #[test]
+    fn test_list_plans_newest_first() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut old = plan_with(&[StepStatus::Pass]);
+        old.created_at = 100;
+        let mut new = plan_with(&[StepStatus::Pending]);
+        new.created_at = 200;
+        save_plan_to(&old, &tmp.path().join("100-plan.json")).unwrap();
+        save_plan_to(&new, &tmp.path().join("200-plan.json")).unwrap();
+        std::fs::write(tmp.path().join("broken.json"), "{").unwrap();
+        std::fs::write(tmp.path().join("notes.md"), "x").unwrap();
+
+        let listed = list_plans_in(tmp.path());
+        let created: Vec<i64> = listed.iter().map(|(_, p)| p.created_at).collect();
+        assert_eq!(created, [200, 100]);
+        assert!(list_plans_in(&tmp.path().join("missing")).is_empty());
+    }
 }
 
 // ── Plan quality integration test ─────────────────────────────────────────────
@@ -1483,13 +2650,14 @@ mod quality_tests {
 
         // Load graph from the actual project (test runs from project root)
         let root = std::env::current_dir().expect("cwd");
-        let (graph, was_warm) = crate::pie::ProjectGraph::load_or_build(&root, 10_000);
+        let (graph, was_warm) = crate::pie::ProjectGraph::load_or_build(&root, &crate::index::IndexScope::new(&cfg.index));
         println!("Graph: {} symbols, warm={was_warm}", graph.symbols.len());
 
         let mut client = crate::client::Client::new(cfg.endpoint.clone(), plan_model.clone());
         if let Some(key) = &cfg.api_key {
             client.set_api_key(key.clone());
         }
+        client.set_provider(cfg.provider);
 
         let context_files = vec![
             "src/agent.rs".to_string(),
@@ -1505,8 +2673,9 @@ mod quality_tests {
             &context_files,
             &[],
             &graph,
-            None,
-            None,
+            PlanContext::default(),
+            &crate::budget::SpendLimits::default(),
+            &std::sync::Mutex::default(),
             tx,
             |_| {},
         )
@@ -0,0 +1,166 @@
+/// Model pricing — USD per 1M tokens for input, output and prompt-cache traffic.
+///
+/// Prices come from the profile (`cost_per_mtok_*`) when set, otherwise from a
+/// small built-in table keyed by model name. Unknown models have no pricing and
+/// their usage is recorded as unpriced rather than guessed at.
+///
+/// Token convention matches `ModelResponse`: `input_tokens` is the whole prompt,
+/// and cache reads/writes are subsets of it billed at their own rates.
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelPricing {
+    /// Uncached input tokens
+    pub input: f64,
+    pub output: f64,
+    /// Input tokens served from the prompt cache
+    pub cache_read: f64,
+    /// Input tokens written to the prompt cache
+    pub cache_write: f64,
+}
+
+impl ModelPricing {
+    const fn new(input: f64, output: f64, cache_read: f64, cache_write: f64) -> Self {
+        Self { input, output, cache_read, cache_write }
+    }
+
+    /// Fill in prices from an input rate alone, using Anthropic's ratios
+    /// (output 5×, cache read 0.1×, cache write 1.25×) for anything unspecified.
+    pub fn from_input_rate(input: f64) -> Self {
+        Self::new(input, input * 5.0, input * 0.1, input * 1.25)
+    }
+
+    /// USD cost of one call (or a sum of calls).
+    pub fn cost(&self, input_tokens: u32, output_tokens: u32, cache_read: u32, cache_write: u32) -> f64 {
+        let uncached = input_tokens.saturating_sub(cache_read).saturating_sub(cache_write);
+        (uncached as f64 * self.input
+            + output_tokens as f64 * self.output
+            + cache_read as f64 * self.cache_read
+            + cache_write as f64 * self.cache_write)
+            / 1_000_000.0
+    }
+}
+
+/// Built-in prices, matched by longest prefix of the model name.
+/// OpenAI bills cache writes as ordinary input, so `cache_write == input` there.
+const BUILTIN: &[(&str, ModelPricing)] = &[
+    // Anthropic
+    ("claude-opus-4-6", ModelPricing::new(5.0, 25.0, 0.50, 6.25)),
+    ("claude-opus-4-5", ModelPricing::new(5.0, 25.0, 0.50, 6.25)),
+    ("claude-opus-4", ModelPricing::new(15.0, 75.0, 1.50, 18.75)),
+    ("claude-sonnet-4", ModelPricing::new(3.0, 15.0, 0.30, 3.75)),
+    ("claude-3-7-sonnet", ModelPricing::new(3.0, 15.0, 0.30, 3.75)),
+    ("claude-3-5-sonnet", ModelPricing::new(3.0, 15.0, 0.30, 3.75)),
+    ("claude-haiku-4-5", ModelPricing::new(1.0, 5.0, 0.10, 1.25)),
+    ("claude-3-5-haiku", ModelPricing::new(0.80, 4.0, 0.08, 1.0)),
+    ("claude-3-haiku", ModelPricing::new(0.25, 1.25, 0.03, 0.30)),
+    // OpenAI
+    ("gpt-5-nano", ModelPricing::new(0.05, 0.40, 0.005, 0.05)),
+    ("gpt-5-mini", ModelPricing::new(0.25, 2.0, 0.025, 0.25)),
+    ("gpt-5", ModelPricing::new(1.25, 10.0, 0.125, 1.25)),
+    ("gpt-4.1-nano", ModelPricing::new(0.10, 0.40, 0.025, 0.10)),
+    ("gpt-4.1-mini", ModelPricing::new(0.40, 1.60, 0.10, 0.40)),
+    ("gpt-4.1", ModelPricing::new(2.0, 8.0, 0.50, 2.0)),
+    ("gpt-4o-mini", ModelPricing::new(0.15, 0.60, 0.075, 0.15)),
+    ("gpt-4o", ModelPricing::new(2.50, 10.0, 1.25, 2.50)),
+    ("o4-mini", ModelPricing::new(1.10, 4.40, 0.275, 1.10)),
+    ("o3", ModelPricing::new(2.0, 8.0, 0.50, 2.0)),
+];
+
+/// Look up built-in pricing for a model name. Vendor prefixes as used by
+/// OpenRouter ("anthropic/claude-sonnet-4") are ignored.
+pub fn builtin(model: &str) -> Option<ModelPricing> {
+    let name = model.to_lowercase();
+    let name = name.rsplit('/').next().unwrap_or(&name);
+    BUILTIN
+        .iter()
+        .filter(|(prefix, _)| name.starts_with(prefix))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, p)| *p)
+}
+
+/// Resolve pricing for a profile: explicit per-profile prices win, gaps are
+/// filled from the built-in table (when enabled), then from the input rate.
+pub fn resolve(
+    model: &str,
+    input: Option<f64>,
+    output: Option<f64>,
+    cache_read: Option<f64>,
+    cache_write: Option<f64>,
+    use_builtin: bool,
+) -> Option<ModelPricing> {
+    let table = if use_builtin { builtin(model) } else { None };
+    let base = match (input, table) {
+        (Some(rate), Some(t)) => ModelPricing { input: rate, ..t },
+        (Some(rate), None) => ModelPricing::from_input_rate(rate),
+        (None, Some(t)) => t,
+        (None, None) => return None,
+    };
+    Some(ModelPricing {
+        input: base.input,
+        output: output.unwrap_or(base.output),
+        cache_read: cache_read.unwrap_or(base.cache_read),
+        cache_write: cache_write.unwrap_or(base.cache_write),
+    })
+}
+
+/// Compact USD display: "<$0.001", "$0.042", "$12.30".
+pub fn fmt_usd(usd: f64) -> String {
+    if usd <= 0.0 {
+        "$0".to_string()
+    } else if usd < 0.001 {
+        "<$0.001".to_string()
+    } else if usd < 10.0 {
+        format!("${usd:.3}")
+    } else {
+        format!("${usd:.2}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_longest_prefix_and_vendor_prefix() {
+        assert_eq!(builtin("claude-opus-4-6").unwrap().input, 5.0);
+        assert_eq!(builtin("claude-opus-4-1-20250805").unwrap().input, 15.0);
+        assert_eq!(builtin("anthropic/claude-sonnet-4-5").unwrap().output, 15.0);
+        assert_eq!(builtin("gpt-4o-mini-2024-07-18").unwrap().input, 0.15);
+        assert_eq!(builtin("GPT-4o").unwrap().input, 2.50);
+        assert!(builtin("qwen3:14b").is_none());
+    }
+
+    #[test]
+    fn test_cost_splits_cached_input() {
+        let p = ModelPricing::new(3.0, 15.0, 0.30, 3.75);
+        // 1M prompt tokens: 600k cache read, 100k cache write, 300k uncached; 100k out
+        let usd = p.cost(1_000_000, 100_000, 600_000, 100_000);
+        let expected = 0.3 * 3.0 + 0.1 * 15.0 + 0.6 * 0.30 + 0.1 * 3.75;
+        assert!((usd - expected).abs() < 1e-9, "{usd} vs {expected}");
+    }
+
+    #[test]
+    fn test_resolve_precedence() {
+        // Nothing known → unpriced
+        assert_eq!(resolve("qwen3:14b", None, None, None, None, true), None);
+        // Input-only on an unknown model → ratios
+        let p = resolve("qwen3:14b", Some(1.0), None, None, None, true).unwrap();
+        assert_eq!(p, ModelPricing::from_input_rate(1.0));
+        // Input override on a known model keeps the table's other prices
+        let p = resolve("claude-sonnet-4-6", Some(2.0), None, None, None, true).unwrap();
+        assert_eq!((p.input, p.output), (2.0, 15.0));
+        // Explicit fields win; table disabled falls back to ratios
+        let p = resolve("claude-sonnet-4-6", Some(2.0), Some(9.0), None, None, false).unwrap();
+        assert_eq!((p.input, p.output, p.cache_read), (2.0, 9.0, 0.2));
+        assert_eq!(resolve("claude-sonnet-4-6", None, None, None, None, false), None);
+    }
+
+    #[test]
+    fn test_fmt_usd() {
+        assert_eq!(fmt_usd(0.0), "$0");
+        assert_eq!(fmt_usd(0.0004), "<$0.001");
+        assert_eq!(fmt_usd(0.0421), "$0.042");
+        assert_eq!(fmt_usd(12.3), "$12.30");
+    }
+}
@@ -240,7 +240,7 @@ struct ProviderPreset {
 const PRESETS: &[ProviderPreset] = &[
     ProviderPreset {
         profile_name: "claude",
-        endpoint: "https://api.anthropic.com/v1/chat/completions",
+        endpoint: "https://api.anthropic.com/v1/messages",
         model: "claude-sonnet-4-6",
         context_tokens: 200_000,
         env_var: "ANTHROPIC_API_KEY",
@@ -693,7 +693,11 @@ const PROFILE_EXAMPLES: &str = r#"
 # model                = "claude-sonnet-4-6"
 # context_tokens       = 200000
 # api_key              = "sk-ant-..."
-# cost_per_mtok_input  = 3.0   # USD per 1M input tokens — enables cost estimates in /plan
+# cost_per_mtok_input  = 3.0   # USD per 1M input tokens (known Claude/GPT models are priced built-in)
+# cost_per_mtok_output = 15.0  # also: cost_per_mtok_cache_read / cost_per_mtok_cache_write
+# max_cost_per_task    = 2.0   # pause for confirmation past these; also max_tokens_per_session
+# daily_spend_limit    = 20.0
+# max_replans          = 2     # on a failed plan step, ask the planner for revised steps
 
 # ── Anthropic Claude — Opus planner + Haiku executor ─────────────────────────
 # Uses Opus for planning (high reasoning, low token count) and Haiku for
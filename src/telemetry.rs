@@ -40,6 +40,15 @@ pub struct TaskRecord {
     pub task_preview: String,
     pub input_tokens: u32,
     pub output_tokens: u32,
+    /// Input tokens served from the provider's prompt cache (subset of input_tokens)
+    #[serde(default)]
+    pub cache_read_tokens: u32,
+    /// Input tokens written to the provider's prompt cache (subset of input_tokens)
+    #[serde(default)]
+    pub cache_write_tokens: u32,
+    /// USD cost at the model's prices when the task ran (None = unpriced model)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_usd: Option<f64>,
     pub tool_calls: usize,
     pub compressed_count: usize,
     pub compression_ratio: f32,
@@ -50,6 +59,17 @@ pub struct TaskRecord {
     pub profile: String,
 }
 
+/// `task_preview` prefix of the partial records flushed while a task runs.
+pub const IN_FLIGHT_PREFIX: &str = "[in-flight] ";
+
+impl TaskRecord {
+    /// A periodic snapshot of a running task. It carries no cost — the final
+    /// record does — so it doesn't count as unpriced.
+    pub fn is_in_flight(&self) -> bool {
+        self.task_preview.starts_with(IN_FLIGHT_PREFIX)
+    }
+}
+
 // ── Live session stats (held in AppState, reset on /new) ─────────────────────
 
 #[derive(Debug, Clone, Default)]
@@ -60,6 +80,12 @@ pub struct SessionStats {
     pub total_input_tokens: u32,
     /// Cumulative output tokens across all runs
     pub total_output_tokens: u32,
+    /// Cumulative prompt-cache reads across all runs
+    pub total_cache_read_tokens: u32,
+    /// Cumulative prompt-cache writes across all runs
+    pub total_cache_write_tokens: u32,
+    /// Cumulative USD cost of priced runs
+    pub total_cost_usd: f64,
     /// Cumulative tool calls across all runs
     pub total_tool_calls: usize,
     /// Cumulative compressed tool outputs across all runs
@@ -76,6 +102,12 @@ pub struct SessionStats {
     pub inflight_input_tokens: u32,
     /// Output tokens accumulated so far in the currently-running task
     pub inflight_output_tokens: u32,
+    /// Prompt-cache reads accumulated so far in the currently-running task
+    pub inflight_cache_read_tokens: u32,
+    /// Prompt-cache writes accumulated so far in the currently-running task
+    pub inflight_cache_write_tokens: u32,
+    /// USD cost so far in the currently-running task (None = unpriced)
+    pub inflight_cost_usd: Option<f64>,
     /// Tool calls executed so far in the currently-running task
     pub inflight_tool_calls: usize,
     /// Duration (secs) of the most recently completed task
@@ -94,6 +126,9 @@ impl SessionStats {
         task_preview: &str,
         input_tokens: u32,
         output_tokens: u32,
+        cache_read_tokens: u32,
+        cache_write_tokens: u32,
+        cost_usd: Option<f64>,
         tool_calls: usize,
         compressed_count: usize,
         duration_secs: u32,
@@ -103,6 +138,9 @@ impl SessionStats {
         self.tasks_completed += 1;
         self.total_input_tokens += input_tokens;
         self.total_output_tokens += output_tokens;
+        self.total_cache_read_tokens += cache_read_tokens;
+        self.total_cache_write_tokens += cache_write_tokens;
+        self.total_cost_usd += cost_usd.unwrap_or(0.0);
         self.total_tool_calls += tool_calls;
         self.total_compressed += compressed_count;
         self.last_task_duration = duration_secs;
@@ -120,6 +158,9 @@ impl SessionStats {
             task_preview: task_preview.chars().take(80).collect(),
             input_tokens,
             output_tokens,
+            cache_read_tokens,
+            cache_write_tokens,
+            cost_usd,
             tool_calls,
             compressed_count,
             compression_ratio,
@@ -157,9 +198,20 @@ impl SessionStats {
 
     /// Update in-flight counters from a TokenStats event.
     /// Called on every API response so stats are always current.
-    pub fn update_inflight(&mut self, total_input: u32, total_output: u32, tool_calls: usize) {
+    pub fn update_inflight(
+        &mut self,
+        total_input: u32,
+        total_output: u32,
+        total_cache_read: u32,
+        total_cache_write: u32,
+        total_cost: Option<f64>,
+        tool_calls: usize,
+    ) {
         self.inflight_input_tokens = total_input;
         self.inflight_output_tokens = total_output;
+        self.inflight_cache_read_tokens = total_cache_read;
+        self.inflight_cache_write_tokens = total_cache_write;
+        self.inflight_cost_usd = total_cost;
         self.inflight_tool_calls = tool_calls;
     }
 
@@ -167,6 +219,9 @@ impl SessionStats {
     pub fn clear_inflight(&mut self) {
         self.inflight_input_tokens = 0;
         self.inflight_output_tokens = 0;
+        self.inflight_cache_read_tokens = 0;
+        self.inflight_cache_write_tokens = 0;
+        self.inflight_cost_usd = None;
         self.inflight_tool_calls = 0;
         self.last_flush_ts = 0;
     }
@@ -240,6 +295,13 @@ pub struct Aggregate {
     pub tasks: usize,
     pub input_tokens: u32,
     pub output_tokens: u32,
+    pub cache_read_tokens: u32,
+    pub cache_write_tokens: u32,
+    /// Summed USD cost of priced records
+    pub cost_usd: f64,
+    /// Records with no cost (unpriced model, or written before cost tracking),
+    /// not counting in-flight snapshots
+    pub unpriced: usize,
     pub tool_calls: usize,
     pub compressed: usize,
     pub duration_secs: u32,
@@ -251,6 +313,10 @@ impl Aggregate {
             tasks: records.len(),
             input_tokens: records.iter().map(|r| r.input_tokens).sum(),
             output_tokens: records.iter().map(|r| r.output_tokens).sum(),
+            cache_read_tokens: records.iter().map(|r| r.cache_read_tokens).sum(),
+            cache_write_tokens: records.iter().map(|r| r.cache_write_tokens).sum(),
+            cost_usd: records.iter().filter_map(|r| r.cost_usd).sum(),
+            unpriced: records.iter().filter(|r| r.cost_usd.is_none() && !r.is_in_flight()).count(),
             tool_calls: records.iter().map(|r| r.tool_calls).sum(),
             compressed: records.iter().map(|r| r.compressed_count).sum(),
             duration_secs: records.iter().map(|r| r.duration_secs).sum(),
@@ -266,8 +332,165 @@ impl Aggregate {
         self.compressed as f32 / self.tool_calls as f32
     }
 
+    /// Fraction of input tokens served from the prompt cache (0.0–1.0).
+    pub fn cache_hit_ratio(&self) -> f32 {
+        if self.input_tokens == 0 { return 0.0; }
+        self.cache_read_tokens as f32 / self.input_tokens as f32
+    }
+
     pub fn _avg_tokens_per_task(&self) -> u32 {
         if self.tasks == 0 { return 0; }
         self.total_tokens() / self.tasks as u32
     }
-}
\ No newline at end of file
+}
+
+/// One row of a cost breakdown: group key, summed USD, task count.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CostRow {
+    pub key: String,
+    pub cost_usd: f64,
+    pub tasks: usize,
+}
+
+/// Group records by `key` and sum their cost. Rows are sorted by key
+/// descending when `by_key` (dates, newest first), else by cost descending.
+pub fn cost_breakdown(
+    records: &[TaskRecord],
+    key: impl Fn(&TaskRecord) -> String,
+    by_key: bool,
+) -> Vec<CostRow> {
+    let mut groups: std::collections::HashMap<String, CostRow> = std::collections::HashMap::new();
+    for r in records {
+        let k = key(r);
+        let row = groups.entry(k.clone()).or_insert(CostRow { key: k, cost_usd: 0.0, tasks: 0 });
+        row.cost_usd += r.cost_usd.unwrap_or(0.0);
+        row.tasks += 1;
+    }
+    let mut rows: Vec<CostRow> = groups.into_values().collect();
+    if by_key {
+        rows.sort_by(|a, b| b.key.cmp(&a.key));
+    } else {
+        rows.sort_by(|a, b| b.cost_usd.total_cmp(&a.cost_usd).then_with(|| a.key.cmp(&b.key)));
+    }
+    rows
+}
+
+/// Local calendar day of a record, e.g. "2026-03-14".
+pub fn record_day(r: &TaskRecord) -> String {
+    chrono::DateTime::from_timestamp(r.timestamp, 0)
+        .unwrap_or_default()
+        .with_timezone(&chrono::Local)
+        .format("%Y-%m-%d")
+        .to_string()
+}
+
+/// Summed USD cost of records on local calendar day `day` ("2026-03-14").
+/// In-flight snapshots carry no cost, so nothing is double-counted.
+pub fn spent_on(records: &[TaskRecord], day: &str) -> f64 {
+    records
+        .iter()
+        .filter(|r| record_day(r) == day)
+        .filter_map(|r| r.cost_usd)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_task_accumulates_cache_tokens() {
+        let mut stats = SessionStats::default();
+        let rec = stats.record_task("s", "proj", "task", 1000, 50, 800, 100, Some(0.01), 2, 0, 5, "m", "p");
+        stats.record_task("s", "proj", "task", 500, 10, 0, 0, None, 0, 0, 1, "m", "p");
+        assert_eq!(rec.cache_read_tokens, 800);
+        assert_eq!(rec.cache_write_tokens, 100);
+        assert_eq!(stats.total_cache_read_tokens, 800);
+        assert_eq!(stats.total_cache_write_tokens, 100);
+
+        let agg = Aggregate::from_records(&stats.records);
+        assert_eq!(agg.cache_read_tokens, 800);
+        assert!((agg.cache_hit_ratio() - 800.0 / 1500.0).abs() < 1e-6);
+        assert!((agg.cost_usd - 0.01).abs() < 1e-12);
+        assert_eq!(agg.unpriced, 1);
+    }
+
+    #[test]
+    fn test_inflight_counters_carry_cache_writes() {
+        let mut stats = SessionStats::default();
+        stats.update_inflight(1000, 50, 800, 120, Some(0.02), 3);
+        assert_eq!(stats.inflight_cache_write_tokens, 120);
+        stats.clear_inflight();
+        assert_eq!(stats.inflight_cache_write_tokens, 0);
+    }
+
+    #[test]
+    fn test_in_flight_snapshots_are_not_unpriced() {
+        let mut snapshot = priced(1_700_000_000, "sonnet", "a", None);
+        snapshot.task_preview = format!("{IN_FLIGHT_PREFIX}fix the parser");
+        let records = vec![snapshot, priced(1_700_000_100, "sonnet", "a", Some(0.5))];
+        assert_eq!(Aggregate::from_records(&records).unpriced, 0);
+    }
+
+    fn priced(ts: i64, model: &str, cwd: &str, cost: Option<f64>) -> TaskRecord {
+        TaskRecord {
+            timestamp: ts,
+            session_id: String::new(),
+            cwd: cwd.to_string(),
+            task_preview: String::new(),
+            input_tokens: 0,
+            output_tokens: 0,
+            cache_read_tokens: 0,
+            cache_write_tokens: 0,
+            cost_usd: cost,
+            tool_calls: 0,
+            compressed_count: 0,
+            compression_ratio: 0.0,
+            duration_secs: 0,
+            model: model.to_string(),
+            profile: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_cost_breakdown_by_model_and_day() {
+        let records = vec![
+            priced(1_700_000_000, "sonnet", "a", Some(0.50)),
+            priced(1_700_000_100, "haiku", "a", Some(0.05)),
+            priced(1_700_000_200, "sonnet", "b", Some(0.25)),
+            priced(1_700_200_000, "qwen", "b", None),
+        ];
+        let by_model = cost_breakdown(&records, |r| r.model.clone(), false);
+        assert_eq!(by_model[0], CostRow { key: "sonnet".into(), cost_usd: 0.75, tasks: 2 });
+        assert_eq!(by_model[1].key, "haiku");
+        assert_eq!(by_model[2], CostRow { key: "qwen".into(), cost_usd: 0.0, tasks: 1 });
+
+        let by_day = cost_breakdown(&records, record_day, true);
+        assert_eq!(by_day.len(), 2);
+        assert!(by_day[0].key > by_day[1].key, "newest day first");
+        assert_eq!(by_day[1].tasks, 3);
+    }
+
+    #[test]
+    fn test_spent_on_sums_priced_records_for_day() {
+        let records = vec![
+            priced(1_700_000_000, "sonnet", "a", Some(0.50)),
+            priced(1_700_000_100, "sonnet", "a", None),
+            priced(1_700_000_200, "haiku", "b", Some(0.25)),
+            priced(1_700_200_000, "sonnet", "b", Some(9.0)),
+        ];
+        let day = record_day(&records[0]);
+        assert!((spent_on(&records, &day) - 0.75).abs() < 1e-12);
+        assert_eq!(spent_on(&records, "1999-01-01"), 0.0);
+    }
+
+    #[test]
+    fn test_task_record_without_cache_fields_deserializes() {
+        // Records written before prompt caching was tracked
+        let line = r#"{"timestamp":1,"session_id":"s","task_preview":"t","input_tokens":10,"output_tokens":2,"tool_calls":0,"compressed_count":0,"compression_ratio":0.0,"model":"m","profile":"p"}"#;
+        let rec: TaskRecord = serde_json::from_str(line).unwrap();
+        assert_eq!(rec.cache_read_tokens, 0);
+        assert_eq!(rec.cache_write_tokens, 0);
+        assert_eq!(rec.cost_usd, None);
+    }
+}
@@ -0,0 +1,536 @@
+/// Test-to-code mapping — which tests exercise which symbols, and the
+/// command that runs just those tests.
+///
+/// Test cases are found during edge extraction, from the same parse
+/// (`CallExtractor::extract_tests`):
+///   - Rust: `#[test]`-style functions (`#[tokio::test]`, `#[rstest]`), with
+///     the inline modules around them (`mod tests`)
+///   - Python: pytest `test*` functions and `Test*` class methods in
+///     `test_*.py` / `*_test.py`
+///   - TypeScript/JS: Jest/Vitest `it`/`test` calls named by their `describe`
+///     chain, in `*.test.*`, `*.spec.*` and `__tests__/`
+///   - Go: `TestXxx` functions in `_test.go` files
+///
+/// Links are computed on demand from the call graph: a test covers what its
+/// body calls (its call edges, or for a Jest block the call references in its
+/// span), and indirectly whatever reaches those through up to `MAX_INDIRECT`
+/// non-test callers. Stored per file in `ProjectGraph::tests`.
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use tree_sitter::Node;
+
+use crate::callgraph::Lang;
+use crate::index::{RefKind, SymbolKind};
+use crate::pie::ProjectGraph;
+use crate::workspace::{self, Package, PackageKind};
+
+/// Callers followed from a symbol towards the tests that reach it.
+const MAX_INDIRECT: usize = 2;
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TestFramework {
+    Cargo,
+    Pytest,
+    Jest,
+    Go,
+}
+
+/// One test function or `it`/`test` block.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestCase {
+    /// Function name, or the `it`/`test` title
+    pub name: String,
+    /// Enclosing inline modules (Rust), test class (Python) or `describe`
+    /// titles (Jest), outermost first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scope: Vec<String>,
+    pub file: String,
+    pub line: usize,
+    pub end_line: usize,
+    pub framework: TestFramework,
+}
+
+/// A test that exercises a symbol.
+#[derive(Debug, Clone)]
+pub struct RelatedTest<'g> {
+    pub test: &'g TestCase,
+    /// 0 = the test calls the symbol itself; n = through n callers
+    pub hops: usize,
+    /// The caller next to the symbol on that path, for `hops > 0`
+    pub via: Option<String>,
+}
+
+impl TestCase {
+    /// Display name: `tests::test_parse`, `TestApi::test_get`, `parser > parses`.
+    pub fn qualified_name(&self) -> String {
+        let sep = if self.framework == TestFramework::Jest { " > " } else { "::" };
+        self.scope.iter().chain(std::iter::once(&self.name)).cloned().collect::<Vec<_>>().join(sep)
+    }
+
+    /// Shell command, run from the project root, that runs only this test.
+    pub fn command(&self, packages: &[Package]) -> String {
+        match self.framework {
+            TestFramework::Cargo => self.cargo_command(packages),
+            TestFramework::Pytest => {
+                let path = std::iter::once(self.file.as_str())
+                    .chain(self.scope.iter().map(String::as_str))
+                    .chain(std::iter::once(self.name.as_str()))
+                    .collect::<Vec<_>>()
+                    .join("::");
+                format!("pytest {}", quote(&path))
+            }
+            TestFramework::Jest => {
+                let title = self.scope.iter().chain(std::iter::once(&self.name)).cloned().collect::<Vec<_>>().join(" ");
+                let (cd, file) = in_package(packages, &self.file, PackageKind::Npm);
+                format!("{cd}npx jest {} -t {}", quote(file), quote(&regex_escape(&title)))
+            }
+            TestFramework::Go => {
+                let dir = self.file.rsplit_once('/').map_or(".".to_string(), |(d, _)| format!("./{d}"));
+                format!("go test {dir} -run {}", quote(&format!("^{}$", self.name)))
+            }
+        }
+    }
+
+    /// `cargo test [-p crate] [--test t | --bin b] module::tests::name`
+    fn cargo_command(&self, packages: &[Package]) -> String {
+        let (cd, _) = in_package(packages, &self.file, PackageKind::Cargo);
+        let crates = packages.iter().filter(|p| p.kind == PackageKind::Cargo).count();
+        let mut cmd = format!("{cd}cargo test");
+        // Module path from the crate's own root
+        let mut rel = self.file.as_str();
+        if let Some(pkg) = workspace::package_for(packages, &self.file).filter(|p| p.kind == PackageKind::Cargo) {
+            if crates > 1 && cd.is_empty() {
+                cmd.push_str(&format!(" -p {}", pkg.name));
+            }
+            if !pkg.dir.is_empty() {
+                rel = &self.file[pkg.dir.len() + 1..];
+            }
+        }
+        let parts: Vec<&str> = rel.trim_end_matches(".rs").split('/').collect();
+        let modules: &[&str] = match parts.as_slice() {
+            ["tests", target, rest @ ..] => {
+                cmd.push_str(&format!(" --test {target}"));
+                rest
+            }
+            ["src", "bin", target, rest @ ..] => {
+                cmd.push_str(&format!(" --bin {target}"));
+                rest
+            }
+            ["src", rest @ ..] => rest,
+            _ => &[],
+        };
+        let path: Vec<&str> = modules
+            .iter()
+            .copied()
+            .filter(|m| !matches!(*m, "lib" | "main" | "mod"))
+            .chain(self.scope.iter().map(String::as_str))
+            .chain(std::iter::once(self.name.as_str()))
+            .collect();
+        format!("{cmd} {}", path.join("::"))
+    }
+}
+
+/// (`cd dir && ` prefix, path inside it) for a file in a `kind` package that
+/// runs its own test tooling — a nested npm package, or any extra root.
+fn in_package<'f>(packages: &[Package], file: &'f str, kind: PackageKind) -> (String, &'f str) {
+    match workspace::package_for(packages, file).filter(|p| p.kind == kind && !p.dir.is_empty()) {
+        Some(p) if kind == PackageKind::Npm || p.dir.starts_with("../") => {
+            (format!("cd {} && ", p.dir), &file[p.dir.len() + 1..])
+        }
+        _ => (String::new(), file),
+    }
+}
+
+fn quote(s: &str) -> String {
+    if s.chars().all(|c| c.is_alphanumeric() || "_-./:".contains(c)) {
+        s.to_string()
+    } else {
+        format!("'{}'", s.replace('\'', r"'\''"))
+    }
+}
+
+fn regex_escape(s: &str) -> String {
+    s.chars().fold(String::new(), |mut out, c| {
+        if r"\.+*?()|[]{}^$".contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+        out
+    })
+}
+
+// ── Detection ─────────────────────────────────────────────────────────────────
+
+/// Whether `file` can hold tests its framework collects.
+pub(crate) fn is_test_file(lang: Lang, file: &str) -> bool {
+    let name = file.rsplit('/').next().unwrap_or(file);
+    match lang {
+        Lang::Rust => true,
+        Lang::Python => {
+            let stem = name.trim_end_matches(".py");
+            stem.starts_with("test_") || stem.ends_with("_test")
+        }
+        Lang::TypeScript | Lang::Tsx => {
+            name.contains(".test.") || name.contains(".spec.") || file.contains("__tests__/")
+        }
+        Lang::Go => name.ends_with("_test.go"),
+        Lang::C | Lang::Cpp => false,
+    }
+}
+
+/// Test cases in one parsed file.
+pub(crate) fn collect_tests(lang: Lang, root: Node, src: &[u8], file: &str) -> Vec<TestCase> {
+    let mut out = Vec::new();
+    let mut finder = Finder { src, file, out: &mut out };
+    match lang {
+        Lang::Rust => finder.rust(root, &mut Vec::new()),
+        Lang::Python => finder.python(root, None),
+        Lang::TypeScript | Lang::Tsx => finder.jest(root, &mut Vec::new()),
+        Lang::Go => finder.go(root),
+        Lang::C | Lang::Cpp => {}
+    }
+    out
+}
+
+struct Finder<'a> {
+    src: &'a [u8],
+    file: &'a str,
+    out: &'a mut Vec<TestCase>,
+}
+
+impl Finder<'_> {
+    fn text(&self, node: Node) -> &str {
+        node.utf8_text(self.src).unwrap_or("")
+    }
+
+    fn push(&mut self, node: Node, name: &str, scope: &[String], framework: TestFramework) {
+        let end = node.end_position();
+        // A node ending at column 0 stops before that line
+        let end_line = if end.column == 0 && end.row > node.start_position().row { end.row } else { end.row + 1 };
+        self.out.push(TestCase {
+            name: name.to_string(),
+            scope: scope.to_vec(),
+            file: self.file.to_string(),
+            line: node.start_position().row + 1,
+            end_line,
+            framework,
+        });
+    }
+
+    fn rust(&mut self, container: Node, scope: &mut Vec<String>) {
+        let mut cursor = container.walk();
+        let children: Vec<Node> = container.named_children(&mut cursor).collect();
+        let mut is_test = false;
+        for node in children {
+            match node.kind() {
+                "attribute_item" => {
+                    is_test |= is_test_attribute(self.text(node));
+                    continue;
+                }
+                "line_comment" | "block_comment" => continue,
+                "function_item" if is_test => {
+                    if let Some(name) = node.child_by_field_name("name") {
+                        let name = self.text(name).to_string();
+                        self.push(node, &name, scope, TestFramework::Cargo);
+                    }
+                }
+                "mod_item" => {
+                    if let (Some(name), Some(body)) = (node.child_by_field_name("name"), node.child_by_field_name("body")) {
+                        scope.push(self.text(name).to_string());
+                        self.rust(body, scope);
+                        scope.pop();
+                    }
+                }
+                _ => {}
+            }
+            is_test = false;
+        }
+    }
+
+    fn python(&mut self, container: Node, class: Option<&str>) {
+        let mut cursor = container.walk();
+        let children: Vec<Node> = container.named_children(&mut cursor).collect();
+        for node in children {
+            let node = match node.kind() {
+                "decorated_definition" => match node.child_by_field_name("definition") {
+                    Some(def) => def,
+                    None => continue,
+                },
+                _ => node,
+            };
+            let Some(name) = node.child_by_field_name("name").map(|n| self.text(n).to_string()) else { continue };
+            match node.kind() {
+                "function_definition" if name.starts_with("test") => {
+                    let scope: Vec<String> = class.into_iter().map(str::to_string).collect();
+                    self.push(node, &name, &scope, TestFramework::Pytest);
+                }
+                "class_definition" if class.is_none() && name.starts_with("Test") => {
+                    if let Some(body) = node.child_by_field_name("body") {
+                        self.python(body, Some(&name));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn jest(&mut self, node: Node, scope: &mut Vec<String>) {
+        if node.kind() == "call_expression" {
+            if let Some((is_suite, title)) = self.jest_call(node) {
+                if is_suite {
+                    scope.push(title);
+                    if let Some(args) = node.child_by_field_name("arguments") {
+                        self.jest(args, scope);
+                    }
+                    scope.pop();
+                } else {
+                    self.push(node, &title, scope, TestFramework::Jest);
+                }
+                return;
+            }
+        }
+        let mut cursor = node.walk();
+        let children: Vec<Node> = node.named_children(&mut cursor).collect();
+        for child in children {
+            self.jest(child, scope);
+        }
+    }
+
+    /// `describe("…", fn)` → (true, title); `it`/`test("…", fn)` → (false, title).
+    /// `.only`/`.skip` variants count; `.each` tables don't.
+    fn jest_call(&self, node: Node) -> Option<(bool, String)> {
+        let function = node.child_by_field_name("function")?;
+        let callee = match function.kind() {
+            "identifier" => function,
+            "member_expression" => function.child_by_field_name("object")?,
+            _ => return None,
+        };
+        let is_suite = match self.text(callee) {
+            "describe" | "context" => true,
+            "it" | "test" => false,
+            _ => return None,
+        };
+        let title = node.child_by_field_name("arguments")?.named_child(0)?;
+        if !matches!(title.kind(), "string" | "template_string") {
+            return None;
+        }
+        let title = self.text(title);
+        // Strip the quotes or backticks
+        Some((is_suite, title.get(1..title.len().saturating_sub(1)).unwrap_or("").to_string()))
+    }
+
+    fn go(&mut self, root: Node) {
+        let mut cursor = root.walk();
+        let children: Vec<Node> = root.named_children(&mut cursor).collect();
+        for node in children.into_iter().filter(|n| n.kind() == "function_declaration") {
+            let Some(name) = node.child_by_field_name("name").map(|n| self.text(n).to_string()) else { continue };
+            // `go test` runs TestXxx where Xxx does not start with a lowercase
+            // letter; TestMain is the package's setup hook
+            let is_test = name != "TestMain"
+                && name.strip_prefix("Test").is_some_and(|rest| !rest.starts_with(|c: char| c.is_lowercase()));
+            if is_test {
+                self.push(node, &name, &[], TestFramework::Go);
+            }
+        }
+    }
+}
+
+/// `#[test]`, `#[tokio::test]`, `#[tokio::test(flavor = "multi_thread")]`, `#[rstest]`
+fn is_test_attribute(attr: &str) -> bool {
+    let inner = attr.trim_start_matches("#[").trim_end_matches(']');
+    let path = inner.split('(').next().unwrap_or("").trim();
+    path.rsplit("::").next() == Some("test") || path == "rstest"
+}
+
+// ── Linking ───────────────────────────────────────────────────────────────────
+
+/// Tests reaching any of the symbols `keys` directly or through up to
+/// `MAX_INDIRECT` callers, closest first.
+pub fn related_tests<'g>(graph: &'g ProjectGraph, keys: &[String]) -> Vec<RelatedTest<'g>> {
+    let links = Links::new(graph);
+    let name = |k: &str| k.rsplit("::").next().unwrap_or(k).to_string();
+
+    let mut out: Vec<RelatedTest<'g>> = Vec::new();
+    let mut found: HashSet<(&str, usize)> = HashSet::new();
+    let mut visited: HashSet<String> = keys.iter().cloned().collect();
+    let mut level: Vec<(String, Option<String>)> = keys.iter().map(|k| (k.clone(), None)).collect();
+    for hops in 0..=MAX_INDIRECT {
+        let mut next = Vec::new();
+        for (k, via) in &level {
+            for test in links.tests_calling(k) {
+                if found.insert((test.file.as_str(), test.line)) {
+                    out.push(RelatedTest { test, hops, via: via.clone() });
+                }
+            }
+            if hops == MAX_INDIRECT {
+                continue;
+            }
+            for caller in links.callers(k) {
+                if links.test_keys.contains(caller) || !visited.insert(caller.to_string()) {
+                    continue;
+                }
+                next.push((caller.to_string(), via.clone().or_else(|| Some(name(caller)))));
+            }
+        }
+        level = next;
+    }
+    out.sort_by(|a, b| a.hops.cmp(&b.hops).then(a.test.file.cmp(&b.test.file)).then(a.test.line.cmp(&b.test.line)));
+    out
+}
+
+/// Reverse call tables for one query: who calls what, and which tests do.
+struct Links<'g> {
+    /// Resolved callee key → callers
+    callers_by_key: HashMap<&'g str, Vec<&'g str>>,
+    /// Unresolved callee name → callers
+    callers_by_name: HashMap<&'g str, Vec<&'g str>>,
+    tests_by_key: HashMap<&'g str, Vec<&'g TestCase>>,
+    tests_by_name: HashMap<&'g str, Vec<&'g TestCase>>,
+    /// Symbol keys of test functions — not followed as intermediate callers
+    test_keys: HashSet<String>,
+}
+
+impl<'g> Links<'g> {
+    fn new(graph: &'g ProjectGraph) -> Self {
+        let mut links = Links {
+            callers_by_key: HashMap::new(),
+            callers_by_name: HashMap::new(),
+            tests_by_key: HashMap::new(),
+            tests_by_name: HashMap::new(),
+            test_keys: HashSet::new(),
+        };
+        for (caller, edges) in &graph.call_edges {
+            for edge in edges {
+                match &edge.target {
+                    Some(target) => links.callers_by_key.entry(target).or_default().push(caller),
+                    None => links.callers_by_name.entry(&edge.callee).or_default().push(caller),
+                }
+            }
+        }
+
+        let test_symbols: HashMap<(&str, usize), String> = graph
+            .symbols
+            .iter()
+            .filter(|s| matches!(s.kind, SymbolKind::Function | SymbolKind::Method))
+            .filter(|s| graph.tests.get(&s.file).is_some_and(|t| t.iter().any(|t| t.line == s.line && t.name == s.name)))
+            .map(|s| ((s.file.as_str(), s.line), s.key()))
+            .collect();
+        for test in graph.tests.values().flatten() {
+            match test_symbols.get(&(test.file.as_str(), test.line)) {
+                // Test functions: their call edges
+                Some(key) => {
+                    for edge in graph.call_edges.get(key).into_iter().flatten() {
+                        match &edge.target {
+                            Some(target) => links.tests_by_key.entry(target).or_default().push(test),
+                            None => links.tests_by_name.entry(&edge.callee).or_default().push(test),
+                        }
+                    }
+                    links.test_keys.insert(key.clone());
+                }
+                // Jest blocks aren't symbols: the calls inside their span
+                None => {
+                    let refs = graph.references.get(&test.file).into_iter().flatten();
+                    for r in refs.filter(|r| r.kind == RefKind::Call && (test.line..=test.end_line).contains(&r.line)) {
+                        links.tests_by_name.entry(&r.name).or_default().push(test);
+                    }
+                }
+            }
+        }
+        links
+    }
+
+    /// Keys calling `key`: edges resolved to it, plus unresolved edges by name.
+    fn callers(&self, key: &str) -> Vec<&'g str> {
+        let name = key.rsplit("::").next().unwrap_or(key);
+        let mut out: Vec<&str> = self.callers_by_key.get(key).into_iter().flatten().copied().collect();
+        out.extend(self.callers_by_name.get(name).into_iter().flatten().copied());
+        out.sort_unstable();
+        out.dedup();
+        out
+    }
+
+    fn tests_calling(&self, key: &str) -> Vec<&'g TestCase> {
+        let name = key.rsplit("::").next().unwrap_or(key);
+        let mut out: Vec<&TestCase> = self.tests_by_key.get(key).into_iter().flatten().copied().collect();
+        out.extend(self.tests_by_name.get(name).into_iter().flatten().copied());
+        out
+    }
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tests_in(file: &str, src: &str) -> Vec<TestCase> {
+        let lang = Lang::from_path(file).unwrap();
+        let mut parser = tree_sitter::Parser::new();
+        parser.set_language(&lang.grammar()).unwrap();
+        let tree = parser.parse(src, None).unwrap();
+        collect_tests(lang, tree.root_node(), src.as_bytes(), file)
+    }
+
+    fn names(tests: &[TestCase]) -> Vec<String> {
+        tests.iter().map(TestCase::qualified_name).collect()
+    }
+
+    #[test]
+    fn test_detects_tests_per_framework() {
+        let rust = "fn helper() {}\n#[cfg(test)]\nmod tests {\n    #[test]\n    fn parses() {}\n    /// doc\n    #[tokio::test]\n    async fn streams() {}\n    fn setup() {}\n}\n";
+        let found = tests_in("src/parser.rs", rust);
+        assert_eq!(names(&found), vec!["tests::parses", "tests::streams"]);
+        assert_eq!((found[0].line, found[0].end_line), (5, 5));
+
+        let py = "def test_one():\n    pass\n\ndef helper():\n    pass\n\nclass TestApi:\n    def test_get(self):\n        pass\n    def setup(self):\n        pass\n";
+        assert_eq!(names(&tests_in("tests/test_api.py", py)), vec!["test_one", "TestApi::test_get"]);
+        assert!(!is_test_file(Lang::Python, "app/api.py"));
+
+        let ts = "describe('parser', () => {\n  it('parses', () => { parse(''); });\n  describe.skip('errors', () => {\n    test(`throws`, () => {});\n  });\n});\nit.each([1])('table %i', () => {});\n";
+        assert_eq!(names(&tests_in("src/parser.test.ts", ts)), vec!["parser > parses", "parser > errors > throws"]);
+        assert!(!is_test_file(Lang::Tsx, "src/parser.tsx"));
+
+        let go = "package p\nfunc TestParse(t *testing.T) {}\nfunc Testify() {}\nfunc TestMain(m *testing.M) {}\nfunc helper() {}\n";
+        assert_eq!(names(&tests_in("p/parse_test.go", go)), vec!["TestParse"]);
+    }
+
+    #[test]
+    fn test_commands_target_single_tests() {
+        let case = |file: &str, scope: &[&str], name: &str, framework| TestCase {
+            name: name.to_string(),
+            scope: scope.iter().map(|s| s.to_string()).collect(),
+            file: file.to_string(),
+            line: 1,
+            end_line: 2,
+            framework,
+        };
+        let none: &[Package] = &[];
+        assert_eq!(case("src/parser/mod.rs", &["tests"], "parses", TestFramework::Cargo).command(none), "cargo test parser::tests::parses");
+        assert_eq!(case("src/main.rs", &["tests"], "runs", TestFramework::Cargo).command(none), "cargo test tests::runs");
+        assert_eq!(case("tests/cli.rs", &[], "help", TestFramework::Cargo).command(none), "cargo test --test cli help");
+        assert_eq!(case("tests/test_api.py", &["TestApi"], "test_get", TestFramework::Pytest).command(none), "pytest tests/test_api.py::TestApi::test_get");
+        assert_eq!(case("p/parse_test.go", &[], "TestParse", TestFramework::Go).command(none), "go test ./p -run '^TestParse$'");
+        assert_eq!(
+            case("src/a.test.ts", &["parser"], "parses (fast)", TestFramework::Jest).command(none),
+            r"npx jest src/a.test.ts -t 'parser parses \(fast\)'"
+        );
+
+        let packages = vec![
+            Package { name: "app-core".into(), dir: "crates/core".into(), kind: PackageKind::Cargo, dependencies: vec![] },
+            Package { name: "app-web".into(), dir: "crates/web".into(), kind: PackageKind::Cargo, dependencies: vec![] },
+            Package { name: "ui".into(), dir: "packages/ui".into(), kind: PackageKind::Npm, dependencies: vec![] },
+        ];
+        assert_eq!(
+            case("crates/core/src/db.rs", &["tests"], "connects", TestFramework::Cargo).command(&packages),
+            "cargo test -p app-core db::tests::connects"
+        );
+        assert_eq!(
+            case("packages/ui/src/button.test.tsx", &[], "renders", TestFramework::Jest).command(&packages),
+            "cd packages/ui && npx jest src/button.test.tsx -t renders"
+        );
+    }
+}
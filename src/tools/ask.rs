@@ -11,7 +11,7 @@ pub async fn execute(args: &Value, ui_tx: tokio::sync::mpsc::UnboundedSender<UiE
 
     let (reply_tx, reply_rx) = tokio::sync::oneshot::channel::<String>();
     ui_tx
-        .send(UiEvent::AskUser { question, reply_tx })
+        .send(UiEvent::AskUser { question, choices: vec![], reply_tx })
         .map_err(|e| format!("[ask_user: failed to send UI event: {e}]"))?;
 
     reply_rx
@@ -1,5 +1,6 @@
 use anyhow::{Context, Result};
 use serde_json::Value;
+use std::path::Path;
 use tokio::process::Command;
 use tokio::time::{Duration, timeout};
 
@@ -28,7 +29,9 @@ pub fn definition() -> Value {
     })
 }
 
-pub async fn execute(args: &Value) -> Result<String> {
+/// Run the command in `dir` when given (plan steps running in a git
+/// worktree), else in the process cwd.
+pub async fn execute(args: &Value, dir: Option<&Path>) -> Result<String> {
     let command = args["command"]
         .as_str()
         .context("bash: missing 'command'")?;
@@ -37,10 +40,12 @@ pub async fn execute(args: &Value) -> Result<String> {
         args["timeout_secs"].as_u64().unwrap_or(30)
     );
 
-    let fut = Command::new("sh")
-        .arg("-c")
-        .arg(command)
-        .output();
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(command);
+    if let Some(dir) = dir {
+        cmd.current_dir(dir);
+    }
+    let fut = cmd.output();
 
     let output = match timeout(timeout_secs, fut).await {
         Ok(result) => result.with_context(|| format!("bash: failed to run '{command}'"))?,
@@ -24,7 +24,10 @@ pub const TOOL_ASK_USER: &str = "ask_user";
 pub const TOOL_FIND_SYMBOL: &str = "find_symbol";
 pub const TOOL_TRACE_CALLS: &str = "trace_calls";
 pub const TOOL_CHECK_WIRING: &str = "check_wiring";
+pub const TOOL_FIND_REFERENCES: &str = "find_references";
+pub const TOOL_RELATED_TESTS: &str = "related_tests";
 pub const TOOL_ORIENT: &str = "orient";
+pub const TOOL_SEMANTIC_SEARCH: &str = "semantic_search";
 
 // Turn thresholds for phase-adaptive tool selection
 const TURN_EXPLORATION_END: usize = 1;
@@ -37,6 +40,9 @@ pub fn all_tool_names() -> &'static [&'static str] {
         TOOL_FIND_SYMBOL,
         TOOL_TRACE_CALLS,
         TOOL_CHECK_WIRING,
+        TOOL_FIND_REFERENCES,
+        TOOL_RELATED_TESTS,
+        TOOL_SEMANTIC_SEARCH,
         TOOL_READ_FILE,
         TOOL_READ_FILES,
         TOOL_WRITE_FILE,
@@ -70,6 +76,9 @@ pub fn get_tool(name: &str) -> Option<Value> {
         TOOL_FIND_SYMBOL => Some(pie_tool::definition()),
         TOOL_TRACE_CALLS => Some(pie_tool::trace_calls_definition()),
         TOOL_CHECK_WIRING => Some(pie_tool::check_wiring_definition()),
+        TOOL_FIND_REFERENCES => Some(pie_tool::find_references_definition()),
+        TOOL_RELATED_TESTS => Some(pie_tool::related_tests_definition()),
+        TOOL_SEMANTIC_SEARCH => Some(pie_tool::semantic_search_definition()),
         TOOL_READ_FILE => Some(read::definition()),
         TOOL_READ_FILES => Some(pie_tool::read_files_definition()),
         TOOL_WRITE_FILE => Some(write::definition()),
@@ -94,9 +103,11 @@ pub fn all_definitions() -> Vec<Tool> {
 /// When `has_graph` is true, `orient` leads the list (replaces find_symbol + trace_calls).
 /// orient returns struct signatures, locations, and call connections in one call.
 ///
-/// When graph present: orient → check_wiring → read_files → edit_file → bash
+/// When graph present: orient → check_wiring → find_references → related_tests → read_files → edit_file → bash
 /// When no graph:      read_file → edit_file → bash  (original behaviour)
 ///
+/// `semantic_search` is appended by the agent when an embedding index exists.
+///
 /// Saves ~400-800 tokens/turn compared to sending all tools every turn.
 pub fn tools_for_turn(turn: usize, has_graph: bool) -> Vec<Tool> {
     let thresholds = TurnThresholds::default();
@@ -104,10 +115,12 @@ pub fn tools_for_turn(turn: usize, has_graph: bool) -> Vec<Tool> {
 
     if has_graph {
         // Discovery-first ordering — position drives model behaviour.
-        // orient + check_wiring are free (in-memory graph), read_files is batched discovery.
+        // orient + check_wiring + find_references + related_tests are free (in-memory graph), read_files is batched discovery.
         // read_file is reinstated for pre-edit hash fetches — use it freely before/after edits.
         t.push(def(pie_tool::orient_definition()));
         t.push(def(pie_tool::check_wiring_definition()));
+        t.push(def(pie_tool::find_references_definition()));
+        t.push(def(pie_tool::related_tests_definition()));
         t.push(def(pie_tool::read_files_definition()));
         t.push(def(read::definition()));
     } else {
@@ -127,7 +140,7 @@ pub fn tools_for_turn(turn: usize, has_graph: bool) -> Vec<Tool> {
     t
 }
 
-fn def(v: Value) -> Tool {
+pub(crate) fn def(v: Value) -> Tool {
     Tool {
         name: v["name"].as_str().unwrap_or("").to_string(),
         description: v["description"].as_str().unwrap_or("").to_string(),
@@ -181,7 +194,10 @@ mod tests {
         assert!(names.contains(&TOOL_TRACE_CALLS));
         assert!(names.contains(&TOOL_CHECK_WIRING));
         assert!(names.contains(&TOOL_ORIENT));
-        assert_eq!(names.len(), 11);
+        assert!(names.contains(&TOOL_FIND_REFERENCES));
+        assert!(names.contains(&TOOL_SEMANTIC_SEARCH));
+        assert!(names.contains(&TOOL_RELATED_TESTS));
+        assert_eq!(names.len(), 14);
     }
 
     #[test]
@@ -209,7 +225,7 @@ mod tests {
     #[test]
     fn test_all_definitions() {
         let defs = all_definitions();
-        assert_eq!(defs.len(), 11);
+        assert_eq!(defs.len(), 14);
         assert!(defs.iter().any(|d| d.name == TOOL_READ_FILE));
         assert!(defs.iter().any(|d| d.name == TOOL_ASK_USER));
         assert!(defs.iter().any(|d| d.name == TOOL_ORIENT));
@@ -1,10 +1,14 @@
-/// PIE graph query tools — `find_symbol` and `trace_calls`.
+/// PIE graph query tools — `find_symbol`, `trace_calls`, `find_references`,
+/// `related_tests` and `semantic_search`.
 ///
-/// Two distinct questions, two tools:
-///   find_symbol  — WHERE is a symbol defined? (file + line)
-///   trace_calls  — WHAT does it connect to? (call chain, zero disk reads)
+/// Five distinct questions, five tools:
+///   find_symbol      — WHERE is a symbol defined? (file + line)
+///   trace_calls      — WHAT does it connect to? (call chain, zero disk reads)
+///   find_references  — WHERE is a name used? (types, fields, imports, patterns)
+///   related_tests    — WHICH tests cover it? (with the command to run just those)
+///   semantic_search  — WHICH code does this? (embedding similarity, by meaning)
 ///
-/// Both are in-memory graph lookups. The model uses these to orient itself
+/// All but semantic_search are in-memory graph lookups. The model uses these to orient itself
 /// before reaching for read_file, which costs real tokens.
 use std::collections::HashSet;
 
@@ -54,14 +58,17 @@ impl DeliveredRanges {
 
 use serde_json::Value;
 
-use crate::index::SymbolKind;
+use crate::embeddings::{SemanticHit, SemanticIndex};
+use crate::index::{RefKind, Reference, SymbolKind};
 use crate::narrative::ProjectNarrative;
 use crate::pie::{Cluster, ProjectGraph};
 
 // Thresholds matching flowpaths.rs — keep in sync if those change.
 const UTILITY_THRESHOLD: usize = 6; // symbols with this many callers are utilities
-const MAX_AMBIGUITY: usize = 4;     // symbols defined in this many files are trait dispatch
+const MAX_AMBIGUITY: usize = 4;
+const MAX_REFERENCES: usize = 40;   // reference lines shown before "… +N more"     // symbols defined in this many files are trait dispatch
 const MAX_BREADTH: usize = 8;       // max callees shown per node before truncation
+const MAX_RELATED_TESTS: usize = 12;
 
 pub fn definition() -> Value {
     serde_json::json!({
@@ -118,7 +125,7 @@ pub fn execute(args: &Value, graph: &ProjectGraph) -> String {
                 s.file, s.line, end, s.kind.label(), sig_part
             );
             // Outgoing calls — where does this symbol dispatch to?
-            let key = format!("{}::{}", s.file, s.name);
+            let key = s.key();
             if let Some(edges) = graph.call_edges.get(&key) {
                 if !edges.is_empty() {
                     let callee_list: Vec<String> = edges.iter().map(|e| {
@@ -132,7 +139,7 @@ pub fn execute(args: &Value, graph: &ProjectGraph) -> String {
                 }
             }
             // Incoming callers — who calls this symbol?
-            let callers = graph.callers_of(&s.name);
+            let callers = graph.callers_of_key(&key);
             if !callers.is_empty() {
                 let shown: Vec<&str> = callers.iter().take(5).copied().collect();
                 entry.push_str(&format!("\n    called by: {}", shown.join(", ")));
@@ -502,13 +509,15 @@ pub fn trace_calls_definition() -> Value {
                         direction \"calls\": outgoing calls (default) — what does X dispatch to?\n\
                         direction \"callers\": who calls X?\n\
                         direction \"both\": outgoing + incoming\n\
-                        depth: hops to follow (default 2, max 4)",
+                        depth: hops to follow (default 2, max 4)\n\
+                        A trait/interface lists its implementors; a trait method \
+                        (\"Tool::execute\") lists each implementation.",
         "parameters": {
             "type": "object",
             "properties": {
                 "name": {
                     "type": "string",
-                    "description": "Symbol name to trace from (e.g. \"run_tui\", \"dispatch_tool\")"
+                    "description": "Symbol name to trace from (e.g. \"run_tui\", \"dispatch_tool\", \"AppState::new\")"
                 },
                 "depth": {
                     "type": "integer",
@@ -534,10 +543,14 @@ pub fn trace_calls_execute(args: &Value, graph: &ProjectGraph) -> String {
     let depth = args["depth"].as_u64().unwrap_or(2).min(4) as usize;
     let direction = args["direction"].as_str().unwrap_or("calls");
 
-    // Find starting symbol(s) — same name may live in multiple files
-    let starts: Vec<&crate::index::Symbol> = graph.symbols.iter()
-        .filter(|s| s.name == name)
-        .collect();
+    // Find starting symbol(s) — same name may live in multiple files.
+    // `Type::method` narrows to one owner.
+    let starts: Vec<&crate::index::Symbol> = match name.rsplit_once("::") {
+        Some((owner, method)) => graph.symbols.iter()
+            .filter(|s| s.name == method && s.owner() == Some(owner))
+            .collect(),
+        None => graph.symbols.iter().filter(|s| s.name == name).collect(),
+    };
 
     if starts.is_empty() {
         return format!(
@@ -554,8 +567,30 @@ pub fn trace_calls_execute(args: &Value, graph: &ProjectGraph) -> String {
     for (i, start) in starts.iter().enumerate() {
         if i > 0 { out.push('\n'); }
 
-        let key = format!("{}::{}", start.file, start.name);
-        out.push_str(&format!("{} ({}:{})\n", start.name, start.file, start.line));
+        let key = start.key();
+        out.push_str(&format!("{} ({}:{})\n", start.qualified_name(), start.file, start.line));
+
+        // Trait/interface: who implements it
+        if start.kind == SymbolKind::Trait {
+            append_implementors(&start.name, graph, &mut out);
+        }
+
+        // Trait method: which implementation runs for each implementor
+        if let Some(owner) = start.owner().filter(|o| is_trait(o, graph)) {
+            let impls: Vec<&crate::index::Symbol> = graph.implementors_of(owner).iter()
+                .flat_map(|imp| graph.symbols.iter()
+                    .filter(|s| s.name == start.name && s.file == imp.file && s.owner() == Some(imp.name.as_str())))
+                .collect();
+            if !impls.is_empty() {
+                out.push_str("  implementations:\n");
+                for imp in impls.iter().take(MAX_BREADTH * 2) {
+                    out.push_str(&format!("    {} ({}:{})\n", imp.qualified_name(), imp.file, imp.line));
+                }
+                if impls.len() > MAX_BREADTH * 2 {
+                    out.push_str(&format!("    … +{} more\n", impls.len() - MAX_BREADTH * 2));
+                }
+            }
+        }
 
         // Outgoing call chain
         if direction == "calls" || direction == "both" {
@@ -570,15 +605,14 @@ pub fn trace_calls_execute(args: &Value, graph: &ProjectGraph) -> String {
         // Incoming callers (always depth-1 — deeper chains aren't useful here)
         if direction == "callers" || direction == "both" {
             if direction == "both" { out.push_str("  ←\n"); }
-            let callers = graph.callers_of(name);
+            let callers = graph.callers_of_key(&key);
             if callers.is_empty() {
                 out.push_str("  called by: (none — entry point or external)\n");
             } else {
                 out.push_str("  called by:\n");
                 for caller_key in callers.iter().take(MAX_BREADTH) {
-                    let caller_sym_name = caller_key.split("::").last().unwrap_or(caller_key);
                     let loc = resolve_loc(caller_key, graph);
-                    out.push_str(&format!("    {caller_sym_name} ({loc})\n"));
+                    out.push_str(&format!("    {} ({loc})\n", key_name(caller_key)));
                 }
                 if callers.len() > MAX_BREADTH {
                     out.push_str(&format!(
@@ -592,6 +626,32 @@ pub fn trace_calls_execute(args: &Value, graph: &ProjectGraph) -> String {
     out
 }
 
+/// "implemented by:" lines for a trait or interface.
+fn append_implementors(trait_name: &str, graph: &ProjectGraph, out: &mut String) {
+    let impls = graph.implementors_of(trait_name);
+    if impls.is_empty() {
+        out.push_str("  implemented by: (none indexed)\n");
+        return;
+    }
+    out.push_str("  implemented by:\n");
+    for imp in impls.iter().take(MAX_BREADTH * 2) {
+        out.push_str(&format!("    {} ({}:{})\n", imp.name, imp.file, imp.line));
+    }
+    if impls.len() > MAX_BREADTH * 2 {
+        out.push_str(&format!("    … +{} more\n", impls.len() - MAX_BREADTH * 2));
+    }
+}
+
+/// True when `name` is a trait or interface defined in the project.
+fn is_trait(name: &str, graph: &ProjectGraph) -> bool {
+    graph.symbols.iter().any(|s| s.name == name && s.kind == SymbolKind::Trait)
+}
+
+/// Display name for a symbol key: `src/a.rs::Foo::run` → `Foo::run`.
+fn key_name(key: &str) -> &str {
+    key.split_once("::").map_or(key, |(_, name)| name)
+}
+
 /// Recursively append outgoing call edges as an indented tree.
 fn append_calls(
     key: &str,
@@ -613,8 +673,8 @@ fn append_calls(
         let callee = &edge.callee;
         let defs = graph.by_name.get(callee).map(|v| v.as_slice()).unwrap_or(&[]);
 
-        // Trait dispatch — don't expand, just note it
-        if defs.len() > MAX_AMBIGUITY {
+        // Trait dispatch — don't expand, just note it (a resolved receiver is followed)
+        if edge.target.is_none() && defs.len() > MAX_AMBIGUITY {
             out.push_str(&format!("{indent}→ {callee} [trait — {} impls]\n", defs.len()));
             continue;
         }
@@ -626,19 +686,16 @@ fn append_calls(
             continue;
         }
 
-        for file in defs {
-            let callee_key = format!("{}::{}", file, callee);
-            let loc = graph.symbols.iter()
-                .find(|s| s.name == *callee && &s.file == file)
-                .map(|s| format!("{}:{}", s.file, s.line))
-                .unwrap_or_else(|| file.clone());
+        for callee_key in graph.callee_keys(edge) {
+            let label = key_name(&callee_key).to_string();
+            let loc = resolve_loc(&callee_key, graph);
 
             if visited.contains(&callee_key) {
-                out.push_str(&format!("{indent}→ {callee} ({loc}) [↩ cycle]\n"));
+                out.push_str(&format!("{indent}→ {label} ({loc}) [↩ cycle]\n"));
                 continue;
             }
 
-            out.push_str(&format!("{indent}→ {callee} ({loc})\n"));
+            out.push_str(&format!("{indent}→ {label} ({loc})\n"));
 
             if current_depth < max_depth {
                 visited.insert(callee_key.clone());
@@ -669,8 +726,7 @@ fn build_caller_counts(graph: &ProjectGraph) -> std::collections::HashMap<String
 }
 
 fn resolve_loc(key: &str, graph: &ProjectGraph) -> String {
-    graph.symbols.iter()
-        .find(|s| format!("{}::{}", s.file, s.name) == key)
+    graph.symbol_by_key(key)
         .map(|s| format!("{}:{}", s.file, s.line))
         .unwrap_or_else(|| key.to_string())
 }
@@ -695,6 +751,265 @@ fn pipeline_layer_order(file: &str) -> u8 {
     5
 }
 
+// ── semantic_search ───────────────────────────────────────────────────────────
+
+const SEMANTIC_DEFAULT_LIMIT: usize = 8;
+const SEMANTIC_MAX_LIMIT: usize = 25;
+
+pub fn semantic_search_definition() -> Value {
+    serde_json::json!({
+        "name": "semantic_search",
+        "description": "Find code by what it does, not what it is called. Ranks functions, types and \
+                        traits by how close their source and doc comments are in meaning to a \
+                        plain-language query (e.g. \"where do we throttle retries\"). \
+                        Use when orient finds nothing because the task's words never appear in symbol names.",
+        "parameters": {
+            "type": "object",
+            "properties": {
+                "query": {
+                    "type": "string",
+                    "description": "What the code does, in plain words"
+                },
+                "limit": {
+                    "type": "integer",
+                    "description": "Maximum results (default 8, max 25)"
+                }
+            },
+            "required": ["query"]
+        }
+    })
+}
+
+pub async fn semantic_search_execute(args: &Value, graph: &ProjectGraph, semantic: &SemanticIndex) -> String {
+    let query = args["query"].as_str().unwrap_or("").trim();
+    if query.is_empty() {
+        return "Provide query= for semantic_search. Example: semantic_search(query=\"where do we throttle retries\")"
+            .to_string();
+    }
+    let limit = args["limit"]
+        .as_u64()
+        .map_or(SEMANTIC_DEFAULT_LIMIT, |n| (n as usize).clamp(1, SEMANTIC_MAX_LIMIT));
+    match semantic.search(query, limit).await {
+        Ok(hits) => format_semantic_hits(query, &hits, graph),
+        Err(e) => format!("[semantic_search: {e}]"),
+    }
+}
+
+/// One line per hit — similarity, symbol, location — plus its doc line.
+fn format_semantic_hits(query: &str, hits: &[SemanticHit], graph: &ProjectGraph) -> String {
+    if hits.is_empty() {
+        return format!(
+            "No code found close to '{query}'. The index may still be building — \
+             try orient(query=\"...\") with names instead."
+        );
+    }
+    let mut out = format!("# semantic_search: \"{query}\"\n");
+    for hit in hits {
+        match graph.symbol_by_key(&hit.key) {
+            Some(s) => {
+                out.push_str(&format!(
+                    "{:.2}  {} {} ({}:{}-{})\n",
+                    hit.score, s.kind.label(), s.qualified_name(), s.file, s.line, s.end_line
+                ));
+                if let Some(doc) = &s.doc {
+                    out.push_str(&format!("      {doc}\n"));
+                }
+            }
+            None => out.push_str(&format!("{:.2}  {} ({}:{})\n", hit.score, hit.key, hit.file, hit.line)),
+        }
+    }
+    out
+}
+
+// ── find_references ───────────────────────────────────────────────────────────
+
+pub fn find_references_definition() -> Value {
+    serde_json::json!({
+        "name": "find_references",
+        "description": "Find every place a name is USED — zero disk reads.\n\
+                        Covers type annotations, field reads and writes (`config.auto_commit`, \
+                        `Profile { auto_commit }`), use/import statements, match patterns and calls. \
+                        Each hit shows file:line, the kind of use, and the enclosing function.\n\n\
+                        Use this before renaming or changing a type/field to see everything that touches it.",
+        "parameters": {
+            "type": "object",
+            "properties": {
+                "name": {
+                    "type": "string",
+                    "description": "Type, field or function name (e.g. \"AppState\", \"auto_commit\", \"run_tui\")"
+                },
+                "kind": {
+                    "type": "string",
+                    "enum": ["type", "read", "write", "import", "pattern", "call"],
+                    "description": "Only show this kind of use (default: all)"
+                }
+            },
+            "required": ["name"]
+        }
+    })
+}
+
+pub fn find_references_execute(args: &Value, graph: &ProjectGraph) -> String {
+    let name = args["name"].as_str().unwrap_or("").trim();
+    if name.is_empty() {
+        return "Provide name= for find_references. Example: find_references(name=\"auto_commit\")".to_string();
+    }
+    let kind = args["kind"].as_str();
+
+    let refs: Vec<(&str, &Reference)> = graph.references_to(name).into_iter()
+        .filter(|(_, r)| kind.is_none_or(|k| r.kind.label() == k))
+        .collect();
+
+    let mut out = String::new();
+    let defs: Vec<String> = graph.symbols.iter()
+        .filter(|s| s.name == name && s.kind != SymbolKind::Impl)
+        .map(|s| format!("{} ({}:{})", s.kind.label(), s.file, s.line))
+        .collect();
+    if !defs.is_empty() {
+        out.push_str(&format!("'{name}' defined: {}\n", defs.join(", ")));
+    }
+
+    if refs.is_empty() {
+        out.push_str(&format!(
+            "No indexed {}uses of '{name}'. Try find_symbol(name=\"{name}\") for partial matches.",
+            kind.map(|k| format!("{k} ")).unwrap_or_default()
+        ));
+        return out;
+    }
+
+    let files: HashSet<&str> = refs.iter().map(|(f, _)| *f).collect();
+    out.push_str(&format!("'{name}' — {} uses in {} files\n", refs.len(), files.len()));
+    let mut current = "";
+    for (file, r) in refs.iter().take(MAX_REFERENCES) {
+        if *file != current {
+            out.push_str(&format!("{file}\n"));
+            current = file;
+        }
+        out.push_str(&format!("  {}\n", describe_reference(name, file, r, graph)));
+    }
+    if refs.len() > MAX_REFERENCES {
+        out.push_str(&format!(
+            "… +{} more (narrow with kind=\"read\"/\"write\"/…)\n",
+            refs.len() - MAX_REFERENCES
+        ));
+    }
+    out
+}
+
+/// `120 write  config.auto_commit — in run_agent`
+fn describe_reference(name: &str, file: &str, r: &Reference, graph: &ProjectGraph) -> String {
+    let site = match (&r.qualifier, r.kind) {
+        (Some(q), RefKind::FieldRead | RefKind::FieldWrite | RefKind::Call) if !q.starts_with(char::is_uppercase) => format!("{q}.{name}"),
+        (Some(q), RefKind::FieldRead | RefKind::FieldWrite) => format!("{q} {{ {name} }}"),
+        (Some(q), _) => q.clone(),
+        (None, _) => name.to_string(),
+    };
+    let within = enclosing_symbol(graph, file, r.line)
+        .map(|s| format!(" — in {}", s.qualified_name()))
+        .unwrap_or_default();
+    format!("{:>5} {:<7} {site}{within}", r.line, r.kind.label())
+}
+
+/// Innermost function/method whose span contains `line`.
+fn enclosing_symbol<'g>(graph: &'g ProjectGraph, file: &str, line: usize) -> Option<&'g crate::index::Symbol> {
+    graph.symbols.iter()
+        .filter(|s| s.file == file && matches!(s.kind, SymbolKind::Function | SymbolKind::Method))
+        .filter(|s| s.line <= line && line <= s.end_line)
+        .max_by_key(|s| s.line)
+}
+
+/// Field reads/writes of `field` for check_wiring: writes first, then reads.
+fn field_uses(field: &str, graph: &ProjectGraph, limit: usize) -> Vec<String> {
+    let mut uses: Vec<(&str, &Reference)> = graph.references_to(field).into_iter()
+        .filter(|(_, r)| matches!(r.kind, RefKind::FieldRead | RefKind::FieldWrite))
+        .collect();
+    uses.sort_by_key(|(f, r)| (r.kind != RefKind::FieldWrite, *f, r.line));
+    let total = uses.len();
+    let mut lines: Vec<String> = uses.iter().take(limit)
+        .map(|(file, r)| format!("  {file}:{}", describe_reference(field, file, r, graph).trim_start()))
+        .collect();
+    if total > limit {
+        lines.push(format!("  … +{} more — find_references(name=\"{field}\")", total - limit));
+    }
+    lines
+}
+
+// ── related_tests ─────────────────────────────────────────────────────────────
+
+pub fn related_tests_definition() -> Value {
+    serde_json::json!({
+        "name": "related_tests",
+        "description": "Find the tests that exercise a function, method or file — zero disk reads.\n\
+                        Lists test functions (Rust #[test], pytest, Jest it/test, Go TestXxx) that call \
+                        it directly or through up to two callers, each with the command that runs just \
+                        that test.\n\n\
+                        Use after an edit to verify with targeted tests instead of the whole suite.",
+        "parameters": {
+            "type": "object",
+            "properties": {
+                "name": {
+                    "type": "string",
+                    "description": "Function or method (e.g. \"parse_config\", \"AppState::apply_event\")"
+                },
+                "file": {
+                    "type": "string",
+                    "description": "Relative file path — tests covering any function in it"
+                }
+            }
+        }
+    })
+}
+
+pub fn related_tests_execute(args: &Value, graph: &ProjectGraph) -> String {
+    let name = args["name"].as_str().unwrap_or("").trim();
+    let file = args["file"].as_str().unwrap_or("").trim();
+    let callable = |s: &&crate::index::Symbol| matches!(s.kind, SymbolKind::Function | SymbolKind::Method);
+    let (label, keys): (&str, Vec<String>) = if !name.is_empty() {
+        let keys: Vec<String> = match name.rsplit_once("::") {
+            Some((owner, method)) => graph.symbols.iter()
+                .filter(|s| s.name == method && s.owner() == Some(owner))
+                .filter(callable)
+                .map(|s| s.key())
+                .collect(),
+            None => graph.symbols.iter().filter(|s| s.name == name).filter(callable).map(|s| s.key()).collect(),
+        };
+        if keys.is_empty() {
+            return format!("Function '{name}' not found. Try find_symbol(name=\"{name}\") for partial matches.");
+        }
+        (name, keys)
+    } else if !file.is_empty() {
+        let keys: Vec<String> = graph.symbols.iter().filter(|s| s.file == file).filter(callable).map(|s| s.key()).collect();
+        if keys.is_empty() {
+            return format!("No indexed functions in '{file}'. Check the path with find_symbol or orient.");
+        }
+        (file, keys)
+    } else {
+        return "Provide name= or file= for related_tests. Example: related_tests(name=\"parse_config\")".to_string();
+    };
+    if graph.tests.is_empty() {
+        return "No tests indexed in this project.".to_string();
+    }
+
+    let related = crate::testmap::related_tests(graph, &keys);
+    if related.is_empty() {
+        return format!("No indexed tests reach '{label}', directly or through two callers. Run the full suite instead.");
+    }
+    let direct = related.iter().filter(|r| r.hops == 0).count();
+    let mut out = format!(
+        "Tests for {label} — {direct} direct, {} through callers\n",
+        related.len() - direct
+    );
+    for r in related.iter().take(MAX_RELATED_TESTS) {
+        let via = r.via.as_deref().map(|v| format!(" — via {v}")).unwrap_or_default();
+        out.push_str(&format!("  {} ({}:{}){via}\n", r.test.qualified_name(), r.test.file, r.test.line));
+        out.push_str(&format!("    {}\n", r.test.command(&graph.packages)));
+    }
+    if related.len() > MAX_RELATED_TESTS {
+        out.push_str(&format!("  … +{} more\n", related.len() - MAX_RELATED_TESTS));
+    }
+    out
+}
+
 pub fn check_wiring_definition() -> Value {
     serde_json::json!({
         "name": "check_wiring",
@@ -806,6 +1121,10 @@ pub fn check_wiring_execute(args: &Value, graph: &ProjectGraph) -> String {
             }
 
             let mut out = format!("No structs found with exact '{}' field.\n", field);
+            let uses = field_uses(field, graph, 8);
+            if !uses.is_empty() {
+                out.push_str(&format!("But '.{field}' is read/written at:\n{}\n\n", uses.join("\n")));
+            }
             if !fuzzy.is_empty() {
                 fuzzy.dedup();
                 fuzzy.truncate(8);
@@ -945,6 +1264,12 @@ pub fn check_wiring_execute(args: &Value, graph: &ProjectGraph) -> String {
         out.push('\n');
     }
 
+    // ── Uses: where the field is actually read and written ─────────────────────
+    let uses = field_uses(field, graph, 12);
+    if !uses.is_empty() {
+        out.push_str(&format!("Uses of '.{field}' (writes first):\n{}\n\n", uses.join("\n")));
+    }
+
     // ── Gap section: structs that need the field added ─────────────────────────
     // For each gap struct, show: where it's defined, who constructs it, and the
     // first few fields of that construction call — so the model knows exactly where
@@ -1033,7 +1358,10 @@ pub fn orient_definition() -> Value {
     })
 }
 
-pub fn orient_execute(args: &Value, graph: &ProjectGraph, delivered: &mut DeliveredRanges) -> String {
+/// `semantic` — hits from the embedding index for the same query (empty when
+/// there is none). They join the keyword matches, so code whose name shares no
+/// word with the query can still surface.
+pub fn orient_execute(args: &Value, graph: &ProjectGraph, delivered: &mut DeliveredRanges, semantic: &[SemanticHit]) -> String {
     let query = args["query"].as_str().unwrap_or("").trim();
     if query.is_empty() {
         return "Provide query= for orient. Example: orient(query=\"cost tracking\")".to_string();
@@ -1048,7 +1376,7 @@ pub fn orient_execute(args: &Value, graph: &ProjectGraph, delivered: &mut Delive
         .into_iter()
         .collect();
 
-    if keywords.is_empty() {
+    if keywords.is_empty() && semantic.is_empty() {
         return format!("No usable keywords in '{query}'. Use terms with 3+ characters.");
     }
 
@@ -1090,6 +1418,19 @@ pub fn orient_execute(args: &Value, graph: &ProjectGraph, delivered: &mut Delive
         }
     }
 
+    // Semantic hits rank with substring matches — close in meaning, not in name
+    for hit in semantic {
+        let Some(idx) = graph.symbols.iter().position(|s| s.file == hit.file && s.line == hit.line) else { continue };
+        let sym = &graph.symbols[idx];
+        if !matches!(sym.kind, SymbolKind::Struct | SymbolKind::Enum | SymbolKind::Function | SymbolKind::Trait) {
+            continue;
+        }
+        let entry = best.entry((sym.file.clone(), sym.name.clone())).or_insert((0, idx));
+        if entry.0 < 2 {
+            *entry = (2, idx);
+        }
+    }
+
     if best.is_empty() {
         return format!(
             "No symbols found matching '{query}'.\n\
@@ -1137,6 +1478,26 @@ pub fn orient_execute(args: &Value, graph: &ProjectGraph, delivered: &mut Delive
                 out.push_str(&format!("  {} {}\n", sym.kind.label(), sym.name));
                 out.push_str(&expand_compact_sig(sig, "  "));
             }
+            // Trait ↔ implementor links
+            if sym.kind == SymbolKind::Trait {
+                let impls: Vec<String> = graph.implementors_of(&sym.name).iter()
+                    .map(|s| format!("{} ({}:{})", s.name, s.file, s.line))
+                    .take(8)
+                    .collect();
+                if !impls.is_empty() {
+                    out.push_str(&format!("  // implemented by: {}\n", impls.join(", ")));
+                }
+            } else {
+                let mut traits: Vec<&str> = graph.symbols.iter()
+                    .filter(|s| s.name == sym.name && s.kind == SymbolKind::Impl)
+                    .flat_map(|s| s.implements.iter().map(String::as_str))
+                    .collect();
+                traits.sort();
+                traits.dedup();
+                if !traits.is_empty() {
+                    out.push_str(&format!("  // implements: {}\n", traits.join(", ")));
+                }
+            }
             // Callers
             let callers: Vec<String> = graph.call_edges.iter()
                 .filter_map(|(caller_key, edges)| {
@@ -1201,16 +1562,21 @@ pub fn orient_execute(args: &Value, graph: &ProjectGraph, delivered: &mut Delive
         out.push_str("## Key functions\n");
         for (_, sym) in &fn_syms {
             let layer = pipeline_layer(sym.file.as_str());
-            out.push_str(&format!("{} fn {} ({}:{}-{})\n", layer, sym.name, sym.file, sym.line, sym.end_line));
+            out.push_str(&format!("{} fn {} ({}:{}-{})\n", layer, sym.qualified_name(), sym.file, sym.line, sym.end_line));
             // Outgoing calls
-            let key = format!("{}::{}", sym.file, sym.name);
+            let key = sym.key();
             if let Some(edges) = graph.call_edges.get(&key) {
                 let callees: Vec<String> = edges.iter().take(12).map(|e| {
-                    let loc = graph.symbols.iter()
-                        .find(|s| s.name == e.callee)
-                        .map(|s| format!("{}:{}", s.file, s.line))
-                        .unwrap_or_default();
-                    if loc.is_empty() { e.callee.clone() } else { format!("{} ({loc})", e.callee) }
+                    match e.target.as_deref().and_then(|t| graph.symbol_by_key(t)) {
+                        Some(s) => format!("{} ({}:{})", s.qualified_name(), s.file, s.line),
+                        None => {
+                            let loc = graph.symbols.iter()
+                                .find(|s| s.name == e.callee)
+                                .map(|s| format!("{}:{}", s.file, s.line))
+                                .unwrap_or_default();
+                            if loc.is_empty() { e.callee.clone() } else { format!("{} ({loc})", e.callee) }
+                        }
+                    }
                 }).collect();
                 if !callees.is_empty() {
                     out.push_str(&format!("  calls: {}\n", callees.join(", ")));
@@ -1325,8 +1691,8 @@ pub fn smart_read(args: &Value, graph: &ProjectGraph) -> String {
         );
 
         for sym in &fn_syms {
-            let key = format!("{}::{}", sym.file, sym.name);
-            overlay.push_str(&format!("fn {} ({}:{}-{}):\n", sym.name, sym.file, sym.line, sym.end_line));
+            let key = sym.key();
+            overlay.push_str(&format!("fn {} ({}:{}-{}):\n", sym.qualified_name(), sym.file, sym.line, sym.end_line));
 
             // Outgoing calls
             if let Some(edges) = graph.call_edges.get(&key) {
@@ -1342,7 +1708,7 @@ pub fn smart_read(args: &Value, graph: &ProjectGraph) -> String {
             }
 
             // Incoming callers
-            let callers = graph.callers_of(&sym.name);
+            let callers = graph.callers_of_key(&key);
             if !callers.is_empty() {
                 let shown: Vec<&str> = callers.iter().take(8).copied().collect();
                 let extra = if callers.len() > 8 { format!(" (+{})", callers.len() - 8) } else { String::new() };
@@ -1586,7 +1952,7 @@ pub fn read_files_execute(args: &Value, graph: &ProjectGraph, delivered: &mut De
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::index::{Symbol, SymbolKind};
+    use crate::index::{Symbol, SymbolKind, Visibility};
     use crate::pie::{Cluster, ProjectGraph};
     use std::collections::HashMap;
 
@@ -1604,6 +1970,7 @@ mod tests {
                 end_line: 200,
                 kind: SymbolKind::Function,
                 signature: Some("(task: &str, client: &Client) -> Result<AgentDone>".to_string()),
+                start_byte: 0, end_byte: 0, parent: None, visibility: Visibility::Public, doc: None, implements: vec![],
             },
             Symbol {
                 name: "AppState".to_string(),
@@ -1612,6 +1979,7 @@ mod tests {
                 end_line: 150,
                 kind: SymbolKind::Struct,
                 signature: Some("mode: AppMode, input: String, messages: Vec<ChatMessage>".to_string()),
+                start_byte: 0, end_byte: 0, parent: None, visibility: Visibility::Public, doc: None, implements: vec![],
             },
         ];
 
@@ -1625,12 +1993,14 @@ mod tests {
             clusters: vec![
                 Cluster {
                     name: "agent".to_string(),
+                    package: None,
                     files: vec!["src/agent.rs".to_string()],
                     entry_files: vec!["src/agent.rs".to_string()],
                     summary: Some("Handles agentic tool loop and dispatch.".to_string()),
                 },
                 Cluster {
                     name: "tui".to_string(),
+                    package: None,
                     files: vec!["src/tui/mod.rs".to_string()],
                     entry_files: vec!["src/tui/mod.rs".to_string()],
                     summary: Some("Terminal UI rendering and event handling.".to_string()),
@@ -1640,9 +2010,13 @@ mod tests {
             symbols,
             by_name,
             file_lines,
+            packages: Vec::new(),
             last_indexed: 0,
             call_edges: HashMap::new(),
             construct_edges: HashMap::new(),
+            references: HashMap::new(),
+            tests: HashMap::new(),
+            cochange: Default::default(),
         }
     }
 
@@ -1661,6 +2035,115 @@ mod tests {
         }
     }
 
+    /// Graph over real sources: a trait, two tool impls, and a dispatcher.
+    fn make_trait_graph() -> ProjectGraph {
+        let files = [
+            ("src/tools/mod.rs", "pub trait Tool {\n    fn execute(&self) -> String;\n}\npub fn dispatch(t: &ReadFile) {\n    t.execute();\n}\n"),
+            ("src/tools/read.rs", "pub struct ReadFile;\nimpl Tool for ReadFile {\n    fn execute(&self) -> String { String::new() }\n}\n"),
+            ("src/tools/write.rs", "pub struct WriteFile;\nimpl Tool for WriteFile {\n    fn execute(&self) -> String { String::new() }\n}\n"),
+        ];
+        let mut graph = make_graph();
+        graph.symbols.clear();
+        graph.by_name.clear();
+        for (file, src) in files {
+            crate::index::extract_symbols(src, file, &mut graph.symbols);
+        }
+        for s in &graph.symbols {
+            graph.by_name.entry(s.name.clone()).or_default().push(s.file.clone());
+        }
+        let resolver = crate::callgraph::CallResolver::new(&graph.symbols);
+        let mut ext = crate::callgraph::CallExtractor::with_resolver(std::sync::Arc::new(resolver)).unwrap();
+        for (file, src) in files {
+            let edges = ext.extract_file(src, file, &graph.symbols, &graph.by_name);
+            graph.call_edges.extend(edges);
+        }
+        graph
+    }
+
+    #[test]
+    fn test_orient_merges_semantic_hits() {
+        let graph = make_graph();
+        let args = serde_json::json!({"query": "conversation loop"});
+        let out = orient_execute(&args, &graph, &mut DeliveredRanges::new(), &[]);
+        assert!(out.starts_with("No symbols found"), "{out}");
+
+        let hits = [SemanticHit { key: "src/agent.rs::run_tui".to_string(), file: "src/agent.rs".to_string(), line: 42, score: 0.71 }];
+        let out = orient_execute(&args, &graph, &mut DeliveredRanges::new(), &hits);
+        assert!(out.contains("run_tui"), "{out}");
+        assert!(!out.contains("AppState"), "{out}");
+
+        let out = format_semantic_hits("conversation loop", &hits, &graph);
+        assert!(out.contains("0.71  fn run_tui (src/agent.rs:42-200)"), "{out}");
+        assert!(format_semantic_hits("conversation loop", &[], &graph).starts_with("No code found"));
+    }
+
+    #[test]
+    fn test_trace_calls_trait_implementors_and_resolved_callers() {
+        let graph = make_trait_graph();
+
+        let out = trace_calls_execute(&serde_json::json!({"name": "Tool"}), &graph);
+        assert!(out.contains("implemented by:"), "{out}");
+        assert!(out.contains("ReadFile (src/tools/read.rs:2)") && out.contains("WriteFile (src/tools/write.rs:2)"), "{out}");
+
+        let out = trace_calls_execute(&serde_json::json!({"name": "Tool::execute"}), &graph);
+        assert!(out.contains("implementations:") && out.contains("WriteFile::execute (src/tools/write.rs:3)"), "{out}");
+
+        // Only the implementation the receiver resolves to is called by dispatch
+        let args = serde_json::json!({"name": "ReadFile::execute", "direction": "callers"});
+        let out = trace_calls_execute(&args, &graph);
+        assert!(out.starts_with("ReadFile::execute (src/tools/read.rs:3)") && out.contains("dispatch"), "{out}");
+        let args = serde_json::json!({"name": "WriteFile::execute", "direction": "callers"});
+        assert!(trace_calls_execute(&args, &graph).contains("(none"));
+
+        let out = trace_calls_execute(&serde_json::json!({"name": "dispatch"}), &graph);
+        assert!(out.contains("→ ReadFile::execute (src/tools/read.rs:3)"), "{out}");
+    }
+
+    #[test]
+    fn test_find_references_and_check_wiring_uses() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(tmp.path().join("src")).unwrap();
+        std::fs::write(tmp.path().join("src/config.rs"), "pub struct Profile {\n    pub auto_commit: bool,\n}\n").unwrap();
+        std::fs::write(
+            tmp.path().join("src/agent.rs"),
+            "use crate::config::Profile;\n\npub fn run(config: &Profile) -> bool {\n    config.auto_commit\n}\n\npub fn defaults() -> Profile {\n    Profile { auto_commit: true }\n}\n",
+        ).unwrap();
+        let (graph, _) = ProjectGraph::load_or_build(tmp.path(), &crate::index::IndexScope::default());
+
+        let out = find_references_execute(&serde_json::json!({"name": "auto_commit"}), &graph);
+        assert!(out.contains("'auto_commit' — 2 uses in 1 files"), "{out}");
+        assert!(out.contains("read    config.auto_commit — in run"), "{out}");
+        assert!(out.contains("write   Profile { auto_commit } — in defaults"), "{out}");
+
+        let out = find_references_execute(&serde_json::json!({"name": "Profile", "kind": "import"}), &graph);
+        assert!(out.contains("'Profile' defined: struct (src/config.rs:1)") && out.contains("1 import"), "{out}");
+
+        let out = check_wiring_execute(&serde_json::json!({"field": "auto_commit"}), &graph);
+        assert!(out.contains("Uses of '.auto_commit' (writes first):\n  src/agent.rs:8 write"), "{out}");
+    }
+
+    #[test]
+    fn test_related_tests_direct_and_through_callers() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(tmp.path().join("src")).unwrap();
+        std::fs::write(
+            tmp.path().join("src/parser.rs"),
+            "pub fn parse(s: &str) -> usize {\n    s.len()\n}\n\npub fn parse_all(v: &[&str]) -> usize {\n    v.iter().map(|s| parse(s)).sum()\n}\n\n#[cfg(test)]\nmod tests {\n    use super::*;\n\n    #[test]\n    fn parses_one() {\n        assert_eq!(parse(\"ab\"), 2);\n    }\n\n    #[test]\n    fn parses_many() {\n        assert_eq!(parse_all(&[\"a\"]), 1);\n    }\n}\n",
+        ).unwrap();
+        let (graph, _) = ProjectGraph::load_or_build(tmp.path(), &crate::index::IndexScope::default());
+
+        let out = related_tests_execute(&serde_json::json!({"name": "parse"}), &graph);
+        assert!(out.starts_with("Tests for parse — 1 direct, 1 through callers"), "{out}");
+        assert!(out.contains("tests::parses_one (src/parser.rs:14)\n    cargo test parser::tests::parses_one"), "{out}");
+        assert!(out.contains("tests::parses_many (src/parser.rs:19) — via parse_all"), "{out}");
+
+        let out = related_tests_execute(&serde_json::json!({"file": "src/parser.rs"}), &graph);
+        assert!(out.contains("2 direct, 0 through callers"), "{out}");
+
+        let out = related_tests_execute(&serde_json::json!({"name": "missing"}), &graph);
+        assert!(out.contains("not found"), "{out}");
+    }
+
     #[test]
     fn test_find_symbol_found() {
         let graph = make_graph();
@@ -1796,7 +2279,7 @@ mod tests {
 
     #[test]
     fn test_find_symbol_field_search() {
-        use crate::index::{Symbol, SymbolKind};
+        use crate::index::{Symbol, SymbolKind, Visibility};
         use std::collections::HashMap;
         let mut graph = make_graph(); // uses existing make_graph() fixture
         // Add a struct with a signature
@@ -1807,6 +2290,7 @@ mod tests {
             end_line: 20,
             kind: SymbolKind::Struct,
             signature: Some("{ model: String, cost_per_mtok_input: Option<f64> }".to_string()),
+            start_byte: 0, end_byte: 0, parent: None, visibility: Visibility::Public, doc: None, implements: vec![],
         });
         graph.by_name.entry("Profile".to_string()).or_default().push("src/config.rs".to_string());
         graph.file_lines.insert("src/config.rs".to_string(), 100);
@@ -1819,7 +2303,7 @@ mod tests {
 
     #[test]
     fn test_check_wiring_finds_gap() {
-        use crate::index::{Symbol, SymbolKind};
+        use crate::index::{Symbol, SymbolKind, Visibility};
         let mut graph = make_graph();
 
         // Profile HAS cost_per_mtok, AgentConfig does NOT
@@ -1829,6 +2313,7 @@ mod tests {
             line: 10, end_line: 20,
             kind: SymbolKind::Struct,
             signature: Some("{ model: String, cost_per_mtok_input: Option<f64> }".to_string()),
+            start_byte: 0, end_byte: 0, parent: None, visibility: Visibility::Public, doc: None, implements: vec![],
         });
         graph.symbols.push(Symbol {
             name: "AgentConfig".to_string(),
@@ -1836,6 +2321,7 @@ mod tests {
             line: 474, end_line: 503,
             kind: SymbolKind::Struct,
             signature: Some("{ client: Client, model: String }".to_string()),
+            start_byte: 0, end_byte: 0, parent: None, visibility: Visibility::Public, doc: None, implements: vec![],
         });
         graph.file_lines.insert("src/config.rs".to_string(), 100);
 
@@ -1852,7 +2338,7 @@ mod tests {
 
     #[test]
     fn test_smart_read_redirects_type_range() {
-        use crate::index::{Symbol, SymbolKind};
+        use crate::index::{Symbol, SymbolKind, Visibility};
         let mut graph = make_graph();
         // run_tui is a Function in make_graph (line 42-200) — NOT a type
         // Add a struct with signature
@@ -1862,6 +2348,7 @@ mod tests {
             line: 10, end_line: 25,
             kind: SymbolKind::Struct,
             signature: Some("{ model: String, cost_per_mtok_input: Option<f64> }".to_string()),
+            start_byte: 0, end_byte: 0, parent: None, visibility: Visibility::Public, doc: None, implements: vec![],
         });
         graph.file_lines.insert("src/config.rs".to_string(), 100);
 
@@ -1892,7 +2379,7 @@ mod tests {
 
     #[test]
     fn test_check_wiring_fuzzy_match() {
-        use crate::index::{Symbol, SymbolKind};
+        use crate::index::{Symbol, SymbolKind, Visibility};
         let mut graph = make_graph();
         graph.symbols.push(Symbol {
             name: "Profile".to_string(),
@@ -1900,6 +2387,7 @@ mod tests {
             line: 10, end_line: 20,
             kind: SymbolKind::Struct,
             signature: Some("{ model: String, cost_per_mtok_input: Option<f64> }".to_string()),
+            start_byte: 0, end_byte: 0, parent: None, visibility: Visibility::Public, doc: None, implements: vec![],
         });
         graph.file_lines.insert("src/config.rs".to_string(), 100);
 
@@ -1915,7 +2403,7 @@ mod tests {
 
     #[test]
     fn test_check_wiring_pipeline_labels() {
-        use crate::index::{Symbol, SymbolKind};
+        use crate::index::{Symbol, SymbolKind, Visibility};
         let mut graph = make_graph();
         graph.symbols.push(Symbol {
             name: "Profile".to_string(),
@@ -1923,6 +2411,7 @@ mod tests {
             line: 10, end_line: 20,
             kind: SymbolKind::Struct,
             signature: Some("{ cost_per_mtok_input: Option<f64> }".to_string()),
+            start_byte: 0, end_byte: 0, parent: None, visibility: Visibility::Public, doc: None, implements: vec![],
         });
         graph.file_lines.insert("src/config.rs".to_string(), 100);
 
@@ -243,7 +243,7 @@ pub fn build_items(state: &AppState, term_width: u16) -> Vec<ListItem<'static>>
             }
 
             ConversationEntry::PlanCard => {
-                items.extend(build_plan_card_items(state, state.cost_per_mtok_input));
+                items.extend(build_plan_card_items(state, state.pricing.map(|p| p.input)));
             }
 
             ConversationEntry::GitNotification { files_changed, .. } => {
@@ -314,10 +314,14 @@ pub fn build_items(state: &AppState, term_width: u16) -> Vec<ListItem<'static>>
             }
 
             ConversationEntry::AskUser(question) => {
-                items.push(ListItem::new(Line::from(vec![
-                    Span::styled("? ", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
-                    Span::styled(question.clone(), Style::default().fg(Color::Yellow)),
-                ])));
+                // First line is the question; any further lines (a permission preview) are indented
+                for (i, line) in question.lines().enumerate() {
+                    let prefix = if i == 0 { "? " } else { "  " };
+                    items.push(ListItem::new(Line::from(vec![
+                        Span::styled(prefix, Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+                        Span::styled(line.to_string(), Style::default().fg(Color::Yellow)),
+                    ])));
+                }
             }
 
             ConversationEntry::AskReply(answer) => {
@@ -417,6 +421,9 @@ pub fn build_plan_card_items(state: &AppState, cost_per_mtok: Option<f64>) -> Ve
         ),
     ])));
 
+    // Dependency DAG — only drawn when it's more than a plain chain
+    let direct_deps = plan.has_parallel_steps().then(|| plan.direct_dependencies());
+
     // Steps
     for (i, step) in plan.steps.iter().enumerate() {
         let selected = !running && i == pr.selected;
@@ -457,6 +464,10 @@ pub fn build_plan_card_items(state: &AppState, cost_per_mtok: Option<f64>) -> Ve
                 format!("{}{}", step.description, ann_mark),
                 Style::default().fg(desc_fg).bg(bg),
             ),
+            Span::styled(
+                dag_label(direct_deps.as_ref().map(|d| d[i].as_slice())),
+                Style::default().fg(Color::Rgb(90, 85, 130)).bg(bg),
+            ),
         ])));
 
         // Show annotation inline if selected and annotating
@@ -521,6 +532,19 @@ pub fn build_plan_card_items(state: &AppState, cost_per_mtok: Option<f64>) -> Ve
     out
 }
 
+/// Suffix showing which steps a plan step waits on (`← 1, 3`), or `∥` for a
+/// step that can start straight away. Empty when the plan is a simple chain.
+fn dag_label(direct_deps: Option<&[usize]>) -> String {
+    match direct_deps {
+        None => String::new(),
+        Some([]) => "  ∥".to_string(),
+        Some(deps) => {
+            let deps: Vec<String> = deps.iter().map(|d| (d + 1).to_string()).collect();
+            format!("  ← {}", deps.join(", "))
+        }
+    }
+}
+
 // ── Draw functions ─────────────────────────────────────────────────────────────
 
 pub fn draw_history(f: &mut Frame, state: &AppState, area: Rect) {
@@ -643,6 +667,12 @@ mod tests {
         assert_eq!(fmt_tokens(0, 32_000), "0/32k");
     }
 
+    #[test]
+    fn test_dag_label() {
+        assert_eq!(dag_label(None), "");
+        assert_eq!(dag_label(Some(&[])), "  ∥");
+        assert_eq!(dag_label(Some(&[0, 2])), "  ← 1, 3");
+    }
 
     #[test]
     fn test_wrap_text_unicode() {
@@ -14,6 +14,7 @@ use ratatui::{
 };
 
 use super::AppState;
+use crate::mcp::{McpServerInfo, McpServerStatus};
 
 pub fn draw(f: &mut Frame, state: &AppState, area: Rect) {
     // Split area into scrollable content + fixed footer
@@ -55,6 +56,7 @@ pub fn draw(f: &mut Frame, state: &AppState, area: Rect) {
     // ── Settings ──────────────────────────────────────────────────────────────
     items.push(heading("Settings"));
     items.push(kv("endpoint", &state.endpoint));
+    items.push(kv("provider", state.provider.label()));
     items.push(kv("model", &state.model));
     items.push(kv("context_tokens", &state.context_tokens.to_string()));
     items.push(kv(
@@ -116,8 +118,13 @@ pub fn draw(f: &mut Frame, state: &AppState, area: Rect) {
     if state.mcp_server_names.is_empty() {
         items.push(dim("(none configured)".to_string()));
     } else {
+        let statuses = state.mcp.statuses();
         for name in &state.mcp_server_names {
-            items.push(kv(name, "configured"));
+            match statuses.iter().find(|s| &s.name == name) {
+                Some(info) => items.push(mcp_line(info)),
+                // Added since startup (profile switch / edit) — connects on restart
+                None => items.push(kv(name, "configured  (restart to connect)")),
+            }
         }
     }
     items.push(blank());
@@ -209,6 +216,33 @@ fn kv(k: &str, v: &str) -> ListItem<'static> {
     ]))
 }
 
+/// One MCP server: coloured status, what it offers, restarts so far.
+fn mcp_line(info: &McpServerInfo) -> ListItem<'static> {
+    let (status, color) = match &info.status {
+        McpServerStatus::Connecting => ("connecting…".to_string(), Color::Yellow),
+        McpServerStatus::Ready => ("● ready".to_string(), Color::Green),
+        McpServerStatus::Restarting { attempt, error } => {
+            (format!("↻ restarting (attempt {attempt}) — {error}"), Color::Yellow)
+        }
+        McpServerStatus::Failed(error) => (format!("✗ failed — {error}"), Color::Red),
+    };
+    let mut detail = format!(
+        "  {} tools · {} resources · {} prompts",
+        info.tools, info.resources, info.prompts
+    );
+    if info.restarts > 0 {
+        detail.push_str(&format!(" · restarted {}×", info.restarts));
+    }
+    ListItem::new(Line::from(vec![
+        Span::styled(
+            format!("    {:<18}", info.name),
+            Style::default().fg(Color::Rgb(100, 95, 140)),
+        ),
+        Span::styled(status, Style::default().fg(color)),
+        Span::styled(detail, Style::default().fg(Color::Rgb(65, 60, 95))),
+    ]))
+}
+
 fn hook_line(label: &str, cmds: &[String]) -> ListItem<'static> {
     if cmds.is_empty() {
         ListItem::new(Line::from(vec![
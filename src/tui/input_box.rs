@@ -274,10 +274,12 @@ impl InputBox {
         let (border_color, prompt_color, prompt_char, mode_label) = match mode {
             Mode::AgentRunning   => (Color::Rgb(35, 35, 55),  Color::DarkGray,           "·",  ""),
             Mode::AskingUser     => (Color::Rgb(180, 140, 0), Color::Yellow,             "?",  " answer "),
+            Mode::SpendPaused    => (Color::Rgb(200, 110, 30), Color::Rgb(230, 140, 40), "⏸",  " spend limit "),
             Mode::Palette        => (Color::Rgb(0, 180, 200), Color::Cyan,               "⌘",  " command "),
             Mode::FilePicker     => (Color::Rgb(0, 180, 100), Color::Green,              "#",  " pick file "),
             Mode::SlashComplete  => (Color::Rgb(0, 180, 200), Color::Cyan,               "/",  " command "),
             Mode::SessionBrowser => (Color::Rgb(110, 90, 200),Color::Rgb(130, 110, 220),"◈",  " sessions "),
+            Mode::PlanBrowser    => (Color::Rgb(200, 140, 0), Color::Rgb(220, 160, 0),  "◇",  " plans "),
             Mode::PlanReview     => (Color::Rgb(200, 140, 0), Color::Rgb(220, 160, 0),  "◇",  " plan review "),
             Mode::PlanRunning    => (Color::Rgb(35, 35, 55),  Color::DarkGray,          "▶",  ""),
             Mode::UndoPicker     => (Color::Rgb(200, 80, 40), Color::Rgb(220, 100, 60), "⚠",  " undo "),
@@ -386,12 +388,13 @@ impl InputBox {
 
         // Show placeholder when empty (Normal mode only)
         let show_placeholder = self.is_empty()
-            && matches!(mode, Mode::Normal | Mode::AskingUser | Mode::Palette | Mode::SlashComplete);
+            && matches!(mode, Mode::Normal | Mode::AskingUser | Mode::SpendPaused | Mode::Palette | Mode::SlashComplete);
 
         if show_placeholder {
             let hint_text = match mode {
                 Mode::Palette => "search commands…",
                 Mode::AskingUser => "type your answer · Enter to send · Esc to skip",
+                Mode::SpendPaused => "Enter/y extend the limit · Esc/n abort the task",
                 _ => "message · Alt+Enter newline · # attach file · Ctrl+B sidebar · Ctrl+P commands",
             };
             let prompt = Span::styled(
@@ -46,7 +46,7 @@ use std::sync::Arc;
 use crate::agent::AgentConfig;
 use crate::client::Client;
 use crate::config::{ConfigFile, ResolvedConfig};
-use crate::mcp::McpClient;
+use crate::mcp::{McpClient, McpPrompt, McpResource};
 use crate::plan::{self, Plan, StepStatus};
 use crate::sessions::{self, ConversationTurn, Session};
 use crate::telemetry::{self, SessionStats};
@@ -75,6 +75,13 @@ pub enum UiEvent {
     AgentDone {
         input_tokens: u32,
         output_tokens: u32,
+        /// Prompt-cache reads/writes (subsets of input_tokens)
+        cache_read_tokens: u32,
+        cache_write_tokens: u32,
+        /// USD cost of the task (None if the model is unpriced)
+        cost_usd: Option<f64>,
+        /// Model that finished the task (differs from the profile's after failover)
+        model: String,
         tool_calls: usize,
         compressed_count: usize,
         duration_secs: u32,
@@ -82,12 +89,19 @@ pub enum UiEvent {
     },
     /// Agent hit an error
     AgentError(String),
+    /// A transient API failure is being retried after a backoff
+    ApiRetry { attempt: u32, max_retries: u32, delay_ms: u64, reason: String },
+    /// Retries exhausted — switching from one profile to the next fallback
+    ModelFailover { from: String, to: String, reason: String },
     /// Verbose token stats
     TokenStats {
         _input: u32,
         _output: u32,
         total_input: u32,
         total_output: u32,
+        total_cache_read: u32,
+        total_cache_write: u32,
+        total_cost: Option<f64>,
         tool_calls: usize,
     },
     /// Context bar update (called before model API call)
@@ -106,6 +120,8 @@ pub enum UiEvent {
     PlanComplete { total: usize },
     /// Plan execution stopped at a failed step
     PlanFailed { step: usize, error: String },
+    /// The planner revised the plan after `step` failed — back to review
+    PlanReplanned { step: usize, plan: crate::plan::Plan },
     /// A hook ran (on_edit, on_task_done, on_plan_step_done, on_session_start, on_session_end)
     HookOutput { event: String, output: String, exit_code: i32 },
     /// Files changed since the last git checkpoint — drives the Git tab and chat nudge
@@ -116,16 +132,30 @@ pub enum UiEvent {
     GitError(String),
     /// System message from background tasks (e.g. update check)
     SystemMsg(String),
-    /// Model is asking the user a clarifying question — pause agent until answered
+    /// A rendered MCP prompt, ready to review in the input box
+    McpPromptReady { name: String, text: String },
+    /// Model is asking the user a clarifying question — pause agent until answered.
+    /// With `choices`, the reply is one of them (a tool waiting for permission).
     AskUser {
         question: String,
+        choices: Vec<String>,
         reply_tx: tokio::sync::oneshot::Sender<String>,
     },
+    /// A spend limit was reached — pause the task until the user extends or aborts
+    SpendLimitHit {
+        hit: crate::budget::LimitHit,
+        reply_tx: tokio::sync::oneshot::Sender<crate::budget::SpendDecision>,
+    },
     /// Background re-index completed (triggered on /new)
     IndexReady {
         graph: crate::pie::ProjectGraph,
         narrative: Option<crate::narrative::ProjectNarrative>,
     },
+    /// File watcher reindexed files edited mid-session
+    GraphUpdated {
+        graph: crate::pie::ProjectGraph,
+        files: Vec<String>,
+    },
 }
 
 // ── ConversationEntry — displayable items in history ─────────────────────────
@@ -168,12 +198,14 @@ pub enum Mode {
     FilePicker,     // @ file picker
     SlashComplete,  // / inline command autocomplete
     SessionBrowser, // Ctrl+H session history browser
+    PlanBrowser,    // /plans saved plan browser
     PlanReview,     // Plan review/annotation overlay
     PlanRunning,      // Plan step currently executing
     SymbolPicker,     // # drill-down: symbol picker for a chosen file
     UndoPicker,       // Interactive checkpoint picker in Git tab (↑↓ select, Enter confirm, Esc cancel)
     ProfilePicker,    // Interactive profile picker overlay in Config tab
     AskingUser,       // Model asked a question, waiting for user's typed answer
+    SpendPaused,      // Spend limit hit — waiting for extend (Enter/y) or abort (Esc/n)
     HookWizard,       // First-run hook setup wizard
 }
 
@@ -202,6 +234,40 @@ pub struct HookWizardState {
     pub on_session_end_input: String,
 }
 
+// ── Spend limit pause ─────────────────────────────────────────────────────────
+
+/// A task paused on a spend limit, waiting for the user's decision.
+pub struct SpendPause {
+    pub hit: crate::budget::LimitHit,
+    pub reply_tx: tokio::sync::oneshot::Sender<crate::budget::SpendDecision>,
+    /// Mode to return to once answered (AgentRunning or PlanRunning)
+    pub resume_mode: Mode,
+}
+
+/// Something a running task is waiting on the user for. Parallel plan steps
+/// can raise several at once; they are answered one at a time, in order.
+pub enum UserPrompt {
+    Spend {
+        hit: crate::budget::LimitHit,
+        reply_tx: tokio::sync::oneshot::Sender<crate::budget::SpendDecision>,
+    },
+    Ask {
+        question: String,
+        choices: Vec<String>,
+        reply_tx: tokio::sync::oneshot::Sender<String>,
+    },
+}
+
+impl UserPrompt {
+    /// The task stopped waiting (cancelled or failed) — nothing to answer.
+    fn is_stale(&self) -> bool {
+        match self {
+            UserPrompt::Spend { reply_tx, .. } => reply_tx.is_closed(),
+            UserPrompt::Ask { reply_tx, .. } => reply_tx.is_closed(),
+        }
+    }
+}
+
 // ── Symbol picker (# drill-down) ──────────────────────────────────────────────
 
 /// State for the per-file symbol picker opened when the user presses Enter in the
@@ -271,8 +337,11 @@ pub enum Tab {
 // ── FilePicker state ──────────────────────────────────────────────────────────
 
 pub struct FilePickerState {
-    /// All candidate paths (relative to cwd), gathered once on open
+    /// All candidate paths (relative to cwd), gathered once on open,
+    /// followed by MCP resource URIs
     pub all_files: Vec<String>,
+    /// MCP resources among `all_files`, for their labels
+    pub resources: Vec<McpResource>,
     /// Current filter query (text after the `@`)
     pub query: String,
     /// Index of highlighted item in filtered list
@@ -368,6 +437,21 @@ impl SessionBrowserState {
     }
 }
 
+// ── Plan browser state ────────────────────────────────────────────────────────
+
+pub struct PlanBrowserState {
+    /// Saved plans, newest first: (path, plan)
+    pub entries: Vec<(std::path::PathBuf, Plan)>,
+    pub selected: usize,
+}
+
+impl PlanBrowserState {
+    pub fn load() -> Self {
+        let entries = plan::list_plans().into_iter().take(20).collect();
+        Self { entries, selected: 0 }
+    }
+}
+
 // ── Plan review state ─────────────────────────────────────────────────────────
 
 pub struct PlanReviewState {
@@ -444,18 +528,26 @@ pub struct AppState {
     pub session_resumed: bool,
     /// Session browser overlay state (Some when Mode::SessionBrowser)
     pub session_browser: Option<SessionBrowserState>,
+    /// Saved plan browser state (Some when Mode::PlanBrowser)
+    pub plan_browser: Option<PlanBrowserState>,
     /// Plan review state (Some when Mode::PlanReview or Mode::PlanRunning)
     pub plan_review: Option<PlanReviewState>,
     /// Step index currently executing (used during PlanRunning)
     pub plan_running_step: usize,
     /// MCP client — shared across all agent runs
     pub mcp: Arc<McpClient>,
+    /// Resources listed by MCP servers — attachable via `#`
+    pub mcp_resources: Vec<McpResource>,
+    /// Prompts listed by MCP servers — run as slash commands
+    pub mcp_prompts: Vec<McpPrompt>,
+    /// `McpClient::revision` the two lists above were taken at
+    pub mcp_revision: u64,
     /// Live session telemetry — accumulated across all agent runs
     pub stats: SessionStats,
     /// First line of the current/most-recent task message (for telemetry records)
     pub current_task_preview: String,
-    /// Optional cost per 1M input tokens (from profile config) for plan estimates
-    pub cost_per_mtok_input: Option<f64>,
+    /// Active profile's model pricing (None = unpriced) for cost display and plan estimates
+    pub pricing: Option<crate::pricing::ModelPricing>,
     /// Last streamed text snippet (for live status bar display while model is thinking)
     pub last_stream_text: String,
     /// True if the model is currently in a <think> block
@@ -482,6 +574,8 @@ pub struct AppState {
     pub telemetry_history: Vec<crate::telemetry::TaskRecord>,
     /// Endpoint URL (for Config tab display)
     pub endpoint: String,
+    /// Wire protocol spoken to the endpoint (for Config tab display)
+    pub provider: crate::client::Provider,
     /// Hooks config snapshot for Config tab display
     pub hooks_config: crate::hooks::HookConfig,
     /// Whether hooks are disabled at profile level (for Config tab display)
@@ -525,6 +619,18 @@ pub struct AppState {
     pub profile_picker_entries: Vec<(String, String)>,  // (name, model)
     /// Oneshot sender to reply to an ask_user tool call (Some while Mode::AskingUser)
     pub pending_ask_reply: Option<tokio::sync::oneshot::Sender<String>>,
+    /// Task paused on a spend limit (Some while Mode::SpendPaused)
+    pub spend_pause: Option<SpendPause>,
+    /// Session/day usage checked against the profile's spend limits; shared with agent runs
+    pub spend: std::sync::Arc<std::sync::Mutex<crate::budget::SpendGuard>>,
+    /// Tool permission policy and this session's grants; shared with agent runs
+    pub permissions: std::sync::Arc<std::sync::Mutex<crate::permissions::Policy>>,
+    /// Choices offered by the pending question (empty for a free-text answer)
+    pub pending_ask_choices: Vec<String>,
+    /// Mode to return to once the pending question is answered (AgentRunning or PlanRunning)
+    pub ask_resume_mode: Mode,
+    /// Questions and spend pauses that arrived while another was open
+    pub queued_prompts: std::collections::VecDeque<UserPrompt>,
     /// Active hook config name (e.g. "rust" from [hooks.rust]), persisted in config
     pub active_hook_preset: Option<String>,
     /// Available hook config names from config (for `/hooks list`)
@@ -539,12 +645,17 @@ pub struct AppState {
     /// Persistent project graph — loaded/built at startup, injected into every agent call.
     /// None only before the first build completes (should not happen in normal flow).
     pub project_graph: Option<crate::pie::ProjectGraph>,
+    /// Mid-session graph updates, shared with the agent currently running.
+    pub live_graph: crate::pie::LiveGraph,
     /// PIE Phase 2 narrative — architecture summary + cluster summaries + conventions.
     /// Generated once on cold startup via one model call; warm runs load instantly from disk.
     pub project_narrative: Option<crate::narrative::ProjectNarrative>,
     /// PIE Phase 3 flow paths — pre-computed call chains for proactive context delivery.
     /// Loaded from `.parecode/paths.json` after graph is ready. None before first index build.
     pub flow_paths: Option<crate::flowpaths::FlowPathIndex>,
+    /// Embedding index for semantic search — None unless `[embeddings]` is
+    /// configured. Built in the background once the graph is ready.
+    pub semantic: Option<std::sync::Arc<crate::embeddings::SemanticIndex>>,
     /// PIE Phase 3 context weights — tracks which files are useful vs wasted per task.
     pub context_weights: crate::context_weights::ContextWeights,
     /// File read cache — persists across tasks in the same session so repeat reads
@@ -580,12 +691,16 @@ impl AppState {
             collecting_tools: Vec::new(),
             session_resumed: false,
             session_browser: None,
+            plan_browser: None,
             plan_review: None,
             plan_running_step: 0,
             mcp,
+            mcp_resources: Vec::new(),
+            mcp_prompts: Vec::new(),
+            mcp_revision: 0,
             stats: SessionStats::default(),
             current_task_preview: String::new(),
-            cost_per_mtok_input: None,
+            pricing: None,
             last_stream_text: String::new(),
             stream_in_think: false,
             hooks_enabled: true,
@@ -594,6 +709,7 @@ impl AppState {
             stats_scroll: 0,
             telemetry_history: telemetry::load_all(),
             endpoint: resolved.endpoint.clone(),
+            provider: resolved.provider,
             hooks_config: if !resolved.active_hook_config.is_empty() {
                 resolved.active_hook_config.clone()
             } else {
@@ -622,14 +738,25 @@ impl AppState {
             profile_picker_selected: 0,
             profile_picker_entries: Vec::new(),
             pending_ask_reply: None,
+            spend_pause: None,
+            spend: std::sync::Arc::new(std::sync::Mutex::new(crate::budget::SpendGuard::load())),
+            permissions: std::sync::Arc::new(std::sync::Mutex::new(crate::permissions::Policy::new(
+                resolved.permissions.clone(),
+            ))),
+            pending_ask_choices: Vec::new(),
+            ask_resume_mode: Mode::AgentRunning,
+            queued_prompts: std::collections::VecDeque::new(),
             active_hook_preset: resolved.active_hooks.clone(),
             available_hook_presets: resolved.available_hooks.clone(),
             hook_wizard: None,
             symbol_picker: None,
             attached_symbols: Vec::new(),
             project_graph: None,     // populated during splash in event_loop
+            live_graph: std::sync::Arc::default(),
             project_narrative: None, // populated during splash in event_loop
             flow_paths: None,        // loaded from .parecode/paths.json after graph ready
+            semantic: resolved.embeddings.as_ref()
+                .map(|e| std::sync::Arc::new(crate::embeddings::SemanticIndex::new(e, std::path::Path::new(".")))),
             context_weights: crate::context_weights::ContextWeights::load(),
             file_cache: std::sync::Arc::new(tokio::sync::Mutex::new(crate::cache::FileCache::default())),
         }
@@ -707,7 +834,23 @@ impl AppState {
                     format!("■ tool call limit ({limit}) reached"),
                 ));
             }
-            UiEvent::AgentDone { input_tokens, output_tokens, tool_calls, compressed_count, duration_secs, cwd } => {
+            UiEvent::ApiRetry { attempt, max_retries, delay_ms, reason } => {
+                let reason: String = reason.lines().next().unwrap_or("").chars().take(120).collect();
+                self.push(ConversationEntry::SystemMsg(format!(
+                    "↻ retry {attempt}/{max_retries} in {:.1}s — {reason}",
+                    delay_ms as f64 / 1000.0,
+                )));
+            }
+            UiEvent::ModelFailover { from, to, reason } => {
+                let reason: String = reason.lines().next().unwrap_or("").chars().take(120).collect();
+                self.push(ConversationEntry::SystemMsg(
+                    format!("⇄ failing over {from} → {to} — {reason}"),
+                ));
+            }
+            UiEvent::AgentDone {
+                input_tokens, output_tokens, cache_read_tokens, cache_write_tokens,
+                cost_usd, model, tool_calls, compressed_count, duration_secs, cwd,
+            } => {
                 // Clear in-flight counters — final totals are in the record
                 self.stats.clear_inflight();
                 // Record telemetry
@@ -718,10 +861,13 @@ impl AppState {
                     &self.current_task_preview.clone(),
                     input_tokens,
                     output_tokens,
+                    cache_read_tokens,
+                    cache_write_tokens,
+                    cost_usd,
                     tool_calls,
                     compressed_count,
                     duration_secs,
-                    &self.model.clone(),
+                    if model.is_empty() { &self.model } else { &model },
                     &self.profile.clone(),
                 );
                 telemetry::append_record(&record);
@@ -762,9 +908,9 @@ impl AppState {
                 self.finalize_turn();
                 self.last_stream_text.clear();
                 self.stream_in_think = false;
-                if self.mode == Mode::PlanRunning {
-                    self.mode = Mode::PlanRunning; // step_done_tx will drive transition
-                } else {
+                // step_done_tx drives plan transitions; a parallel step's open
+                // question or spend pause stays open
+                if !matches!(self.mode, Mode::PlanRunning | Mode::AskingUser | Mode::SpendPaused) {
                     self.mode = Mode::Normal;
                 }
                 self.cancel_tx = None;
@@ -775,6 +921,9 @@ impl AppState {
                 // session / daily / all-time stats.
                 let inf_in = self.stats.inflight_input_tokens;
                 let inf_out = self.stats.inflight_output_tokens;
+                let inf_cache = self.stats.inflight_cache_read_tokens;
+                let inf_cache_write = self.stats.inflight_cache_write_tokens;
+                let inf_cost = self.stats.inflight_cost_usd;
                 if inf_in > 0 || inf_out > 0 {
                     let session_id = self.session.as_ref().map(|s| s.id.clone()).unwrap_or_default();
                     let record = self.stats.record_task(
@@ -783,6 +932,9 @@ impl AppState {
                         &format!("[error] {}", self.current_task_preview),
                         inf_in,
                         inf_out,
+                        inf_cache,
+                        inf_cache_write,
+                        inf_cost,
                         self.stats.inflight_tool_calls,
                         0,
                         0,
@@ -796,16 +948,19 @@ impl AppState {
                 self.finalize_turn();
                 self.last_stream_text.clear();
                 self.stream_in_think = false;
-                if self.mode == Mode::PlanRunning {
-                    self.mode = Mode::PlanRunning; // step_done_tx will drive transition
-                } else {
+                // step_done_tx drives plan transitions; a parallel step's open
+                // question or spend pause stays open
+                if !matches!(self.mode, Mode::PlanRunning | Mode::AskingUser | Mode::SpendPaused) {
                     self.mode = Mode::Normal;
                 }
                 self.cancel_tx = None;
             }
-            UiEvent::TokenStats { _input: _, _output: _, total_input, total_output, tool_calls } => {
+            UiEvent::TokenStats {
+                _input: _, _output: _, total_input, total_output, total_cache_read, total_cache_write,
+                total_cost, tool_calls,
+            } => {
                 // Always update in-flight counters so stats tab shows live usage
-                self.stats.update_inflight(total_input, total_output, tool_calls);
+                self.stats.update_inflight(total_input, total_output, total_cache_read, total_cache_write, total_cost, tool_calls);
 
                 // Periodically flush a partial telemetry record (every 30s)
                 // so token usage survives crashes / cancellation
@@ -815,9 +970,13 @@ impl AppState {
                         timestamp: chrono::Utc::now().timestamp(),
                         session_id,
                         cwd: String::new(),
-                        task_preview: format!("[in-flight] {}", self.current_task_preview.chars().take(70).collect::<String>()),
+                        task_preview: format!("{}{}", crate::telemetry::IN_FLIGHT_PREFIX, self.current_task_preview.chars().take(70).collect::<String>()),
                         input_tokens: total_input,
                         output_tokens: total_output,
+                        cache_read_tokens: total_cache_read,
+                        cache_write_tokens: total_cache_write,
+                        // Snapshot only — spend is carried by the final record.
+                        cost_usd: None,
                         tool_calls,
                         compressed_count: 0,
                         compression_ratio: 0.0,
@@ -906,6 +1065,17 @@ impl AppState {
                 ));
                 self.mode = Mode::Normal;
             }
+            UiEvent::PlanReplanned { step, plan } => {
+                let added = plan.steps.len().saturating_sub(step);
+                self.plan_review = Some(PlanReviewState { selected: step, ..PlanReviewState::new(plan) });
+                self.push(ConversationEntry::PlanCard);
+                self.push(ConversationEntry::SystemMsg(format!(
+                    "⟳ replanned from step {} — {added} step{} to review: approve (a), then Enter resumes",
+                    step + 1,
+                    if added == 1 { "" } else { "s" },
+                )));
+                self.mode = Mode::PlanReview;
+            }
             UiEvent::HookOutput { event, output, exit_code } => {
                 let success = exit_code == 0;
                 self.push(ConversationEntry::HookOutput { event, output, success });
@@ -934,6 +1104,7 @@ impl AppState {
             UiEvent::IndexReady { graph, narrative } => {
                 let sym_count = graph.symbols.len();
                 let file_count = graph.file_lines.len();
+                *self.live_graph.write().unwrap() = Some(std::sync::Arc::new(graph.clone()));
                 self.project_graph = Some(graph);
                 if let Some(n) = narrative {
                     self.project_narrative = Some(n);
@@ -942,15 +1113,78 @@ impl AppState {
                     format!("◈ index refreshed — {file_count} files, {sym_count} symbols"),
                 ));
             }
-            UiEvent::AskUser { question, reply_tx } => {
+            UiEvent::GraphUpdated { graph, files: _ } => {
+                // Quiet swap — a running agent picks it up on its next turn
+                *self.live_graph.write().unwrap() = Some(std::sync::Arc::new(graph.clone()));
+                self.project_graph = Some(graph);
+                self.flow_paths = crate::flowpaths::FlowPathIndex::load(std::path::Path::new("."));
+            }
+            UiEvent::SpendLimitHit { hit, reply_tx } => {
+                self.prompt(UserPrompt::Spend { hit, reply_tx });
+            }
+            UiEvent::McpPromptReady { name, text } => {
+                self.push(ConversationEntry::SystemMsg(format!("◈ prompt {name} loaded — edit or press Enter to run")));
+                self.input_box.set_text(&text);
+                self.input_box.move_to_end();
+            }
+            UiEvent::AskUser { question, choices, reply_tx } => {
+                self.prompt(UserPrompt::Ask { question, choices, reply_tx });
+            }
+        }
+    }
+
+    /// Show `prompt`, or queue it behind the question or pause already open.
+    fn prompt(&mut self, prompt: UserPrompt) {
+        if matches!(self.mode, Mode::AskingUser | Mode::SpendPaused) {
+            self.queued_prompts.push_back(prompt);
+        } else {
+            let resume_mode = if self.mode == Mode::PlanRunning { Mode::PlanRunning } else { Mode::AgentRunning };
+            self.show_prompt(prompt, resume_mode);
+        }
+    }
+
+    fn show_prompt(&mut self, prompt: UserPrompt, resume_mode: Mode) {
+        match prompt {
+            UserPrompt::Spend { hit, reply_tx } => {
+                self.push(ConversationEntry::SystemMsg(format!(
+                    "⏸ paused — {hit}\n  Enter/y: extend to {} · Esc/n: abort",
+                    match hit.kind {
+                        crate::budget::LimitKind::SessionTokens => format!("{} tokens", hit.extended as u64),
+                        _ => crate::pricing::fmt_usd(hit.extended),
+                    }
+                )));
+                self.spend_pause = Some(SpendPause { hit, reply_tx, resume_mode });
+                self.mode = Mode::SpendPaused;
+            }
+            UserPrompt::Ask { question, choices, reply_tx } => {
+                let question = if choices.is_empty() {
+                    question
+                } else {
+                    let numbered: Vec<String> = choices.iter().enumerate().map(|(i, c)| format!("[{}] {c}", i + 1)).collect();
+                    format!("{question}\n{}", numbered.join("  "))
+                };
                 self.push(ConversationEntry::AskUser(question));
                 self.pending_ask_reply = Some(reply_tx);
+                self.pending_ask_choices = choices;
+                self.ask_resume_mode = resume_mode;
                 self.mode = Mode::AskingUser;
                 // Clear input so the user starts with a fresh prompt
                 self.input_box.clear();
             }
         }
     }
+
+    /// The open question or pause was answered: show the next queued one, or
+    /// go back to `resume_mode`.
+    fn prompt_answered(&mut self, resume_mode: Mode) {
+        while let Some(prompt) = self.queued_prompts.pop_front() {
+            if !prompt.is_stale() {
+                self.show_prompt(prompt, resume_mode);
+                return;
+            }
+        }
+        self.mode = resume_mode;
+    }
 }
 
 // ── Chip layout helpers ───────────────────────────────────────────────────────
@@ -1015,39 +1249,52 @@ impl AppState {
 
 #[derive(Debug, Clone)]
 struct PaletteCommand {
-    key: &'static str,
-    label: &'static str,
+    key: String,
+    label: String,
 }
 
-fn palette_commands() -> Vec<PaletteCommand> {
-    vec![
-        PaletteCommand { key: "/plan",        label: "Generate and review a plan for a task" },
-        PaletteCommand { key: "/quick",       label: "Run a lightweight single-shot query" },
-        PaletteCommand { key: "/init",        label: "Generate .parecode/conventions.md for this project" },
-        PaletteCommand { key: "/cd",          label: "Change working directory" },
-        PaletteCommand { key: "/profile",     label: "Switch profile" },
-        PaletteCommand { key: "/profiles",    label: "List profiles" },
-        PaletteCommand { key: "/ts",          label: "Toggle timestamps" },
-        PaletteCommand { key: "/stats",       label: "Open stats tab  (/stats reset to clear history)" },
-        PaletteCommand { key: "/hooks",       label: "Toggle hooks on/off or switch preset (/hooks on|off|list|<preset>)" },
-        PaletteCommand { key: "/list-hooks",  label: "Show all configured hooks and their status" },
-        PaletteCommand { key: "/pie",         label: "Show PIE status: graph health, narrative, task memory, context weights" },
-        PaletteCommand { key: "/undo",        label: "Revert to last git checkpoint (/undo N for Nth)" },
-        PaletteCommand { key: "/diff",        label: "Open full diff overlay for last task changes" },
-        PaletteCommand { key: "/clear",       label: "Clear conversation" },
-        PaletteCommand { key: "/sessions",    label: "List recent sessions  (or Ctrl+H)" },
-        PaletteCommand { key: "/resume",      label: "Resume a previous session" },
-        PaletteCommand { key: "/rollback",    label: "Roll back to previous turn" },
-        PaletteCommand { key: "/new",          label: "Start a fresh session" },
-        PaletteCommand { key: "/help",        label: "Show help" },
-        PaletteCommand { key: "/quit",        label: "Quit" },
-    ]
+impl PaletteCommand {
+    fn new(key: &str, label: &str) -> Self {
+        Self { key: key.to_string(), label: label.to_string() }
+    }
+}
+
+/// Built-in commands, then one `/<server>.<prompt>` per MCP prompt.
+fn palette_commands(prompts: &[McpPrompt]) -> Vec<PaletteCommand> {
+    let builtin = vec![
+        PaletteCommand::new("/plan",        "Generate and review a plan for a task"),
+        PaletteCommand::new("/plans",       "Browse saved plans and resume an unfinished one"),
+        PaletteCommand::new("/quick",       "Run a lightweight single-shot query"),
+        PaletteCommand::new("/init",        "Generate .parecode/conventions.md for this project"),
+        PaletteCommand::new("/cd",          "Change working directory"),
+        PaletteCommand::new("/profile",     "Switch profile"),
+        PaletteCommand::new("/profiles",    "List profiles"),
+        PaletteCommand::new("/ts",          "Toggle timestamps"),
+        PaletteCommand::new("/stats",       "Open stats tab  (/stats reset to clear history)"),
+        PaletteCommand::new("/hooks",       "Toggle hooks on/off or switch preset (/hooks on|off|list|<preset>)"),
+        PaletteCommand::new("/list-hooks",  "Show all configured hooks and their status"),
+        PaletteCommand::new("/pie",         "Show PIE status: graph health, narrative, task memory, context weights"),
+        PaletteCommand::new("/undo",        "Revert to last git checkpoint (/undo N for Nth)"),
+        PaletteCommand::new("/diff",        "Open full diff overlay for last task changes"),
+        PaletteCommand::new("/clear",       "Clear conversation"),
+        PaletteCommand::new("/sessions",    "List recent sessions  (or Ctrl+H)"),
+        PaletteCommand::new("/resume",      "Resume a previous session"),
+        PaletteCommand::new("/rollback",    "Roll back to previous turn"),
+        PaletteCommand::new("/new",          "Start a fresh session"),
+        PaletteCommand::new("/help",        "Show help"),
+        PaletteCommand::new("/quit",        "Quit"),
+    ];
+    let mcp = prompts.iter().map(|p| {
+        let label = if p.description.is_empty() { "MCP prompt".to_string() } else { format!("MCP prompt: {}", p.description) };
+        PaletteCommand { key: format!("/{}", p.qualified_name), label }
+    });
+    builtin.into_iter().chain(mcp).collect()
 }
 
 /// Returns palette commands whose key or label contains the current input query.
-fn slash_filtered(input: &str) -> Vec<PaletteCommand> {
+fn slash_filtered(input: &str, prompts: &[McpPrompt]) -> Vec<PaletteCommand> {
     let q = input.to_lowercase();
-    palette_commands()
+    palette_commands(prompts)
         .into_iter()
         .filter(|c| c.key.contains(q.as_str()) || c.label.to_lowercase().contains(q.as_str()))
         .collect()
@@ -1116,7 +1363,10 @@ async fn event_loop(
     let mcp = McpClient::new(&resolved.mcp_servers).await;
 
     let mut state = AppState::new(&resolved, show_timestamps, mcp);
-    state.cost_per_mtok_input = resolved.cost_per_mtok_input;
+    state.pricing = resolved.pricing;
+    state.mcp_revision = state.mcp.revision();
+    state.mcp_resources = state.mcp.all_resources().await;
+    state.mcp_prompts = state.mcp.all_prompts().await;
 
     // Auto-show sidebar when terminal is wide enough
     if let Ok((w, _)) = crossterm::terminal::size() {
@@ -1145,17 +1395,12 @@ async fn event_loop(
     }
 
     // ── PIE warm status ───────────────────────────────────────────────────────
-    let graph_path = std::path::Path::new(".parecode/project.graph");
-    if graph_path.exists() {
-        if let Ok(content) = std::fs::read_to_string(graph_path) {
-            if let Ok(g) = serde_json::from_str::<crate::pie::ProjectGraph>(&content) {
-                let cluster_count = g.clusters.len();
-                let file_count: usize = g.clusters.iter().map(|c| c.files.len()).sum();
-                state.push(ConversationEntry::SystemMsg(
-                    format!("◈ PIE  {cluster_count} clusters · {file_count} files · warm"),
-                ));
-            }
-        }
+    if let Some(g) = crate::pie::ProjectGraph::load(std::path::Path::new(".")) {
+        let cluster_count = g.clusters.len();
+        let file_count: usize = g.clusters.iter().map(|c| c.files.len()).sum();
+        state.push(ConversationEntry::SystemMsg(
+            format!("◈ PIE  {cluster_count} clusters · {file_count} files · warm"),
+        ));
     }
 
     // Open session (resumes existing or creates new) — loads turns and sets active_turn
@@ -1263,8 +1508,9 @@ async fn event_loop(
     let narrative_is_warm = std::path::Path::new(".parecode/narrative.json").exists();
 
     // Start graph build on thread pool immediately
-    let graph_task = tokio::task::spawn_blocking(|| {
-        crate::pie::ProjectGraph::load_or_build(std::path::Path::new("."), 500)
+    let scope = crate::index::IndexScope::new(&resolved.index);
+    let graph_task = tokio::task::spawn_blocking(move || {
+        crate::pie::ProjectGraph::load_or_build(std::path::Path::new("."), &scope)
     });
 
     let mut splash_frame: u8 = 0;
@@ -1286,10 +1532,7 @@ async fn event_loop(
 
     if let Ok((graph, _was_warm)) = graph_result {
         // Phase 2: load or generate narrative — keep spinning, no time cap.
-        let mut nar_client = Client::new(resolved.endpoint.clone(), resolved.model.clone());
-        if let Some(ref key) = resolved.api_key {
-            nar_client.set_api_key(key.clone());
-        }
+        let nar_client = Client::from_resolved(&resolved);
         let narrative_task = tokio::spawn(crate::narrative::ProjectNarrative::load_or_generate(
             graph.clone(),
             nar_client,
@@ -1310,7 +1553,26 @@ async fn event_loop(
         // Load pre-built flow paths (zero cost — instant JSON read).
         // Built by rebuild_call_edges whenever graph is refreshed.
         state.flow_paths = crate::flowpaths::FlowPathIndex::load(std::path::Path::new("."));
+        // Embed in the background — a first build against a local model can take minutes
+        if let Some(semantic) = state.semantic.clone() {
+            let graph = graph.clone();
+            let tx = ui_tx.clone();
+            tokio::spawn(async move {
+                let msg = match semantic.refresh(&graph).await {
+                    Ok(0) => return,
+                    Ok(files) => format!("◈ semantic index: {files} files embedded · {} symbols", semantic.len()),
+                    Err(e) => format!("◈ semantic index unavailable: {e}"),
+                };
+                let _ = tx.send(UiEvent::SystemMsg(msg));
+            });
+        }
         state.project_graph = Some(graph);
+        // Keep the graph current as files are edited outside the agent
+        crate::watcher::spawn(
+            std::env::current_dir().unwrap_or_else(|_| ".".into()),
+            crate::index::IndexScope::new(&resolved.index),
+            ui_tx.clone(),
+        );
     }
 
     terminal.draw(|f| render::draw(f, &mut state))?;
@@ -1319,7 +1581,15 @@ async fn event_loop(
         tokio::select! {
             // ── Animation tick ────────────────────────────────────────────────
             _ = ticker.tick() => {
-                if matches!(state.mode, Mode::AgentRunning | Mode::PlanRunning) {
+                // A server re-listed its offers (list_changed, restart)
+                let revision = state.mcp.revision();
+                if revision != state.mcp_revision {
+                    state.mcp_revision = revision;
+                    state.mcp_resources = state.mcp.all_resources().await;
+                    state.mcp_prompts = state.mcp.all_prompts().await;
+                }
+                // The Config tab shows live MCP server status
+                if matches!(state.mode, Mode::AgentRunning | Mode::PlanRunning) || state.active_tab == Tab::Config {
                     state.spinner_tick = state.spinner_tick.wrapping_add(1);
                     terminal.draw(|f| render::draw(f, &mut state))?;
                 }
@@ -1447,7 +1717,8 @@ async fn event_loop(
                                     state.model = resolved.model.clone();
                                     state.context_tokens = resolved.context_tokens;
                                     state.endpoint = resolved.endpoint.clone();
-                                    state.cost_per_mtok_input = resolved.cost_per_mtok_input;
+                                    state.provider = resolved.provider;
+                                    state.pricing = resolved.pricing;
                                     state.hooks_config = if !resolved.active_hook_config.is_empty() {
                                         resolved.active_hook_config.clone()
                                     } else {
@@ -1665,8 +1936,9 @@ fn handle_key(
                             state.profile = resolved.profile_name.clone();
                             state.model = resolved.model.clone();
                             state.context_tokens = resolved.context_tokens;
-                            state.cost_per_mtok_input = resolved.cost_per_mtok_input;
+                            state.pricing = resolved.pricing;
                             state.endpoint = resolved.endpoint.clone();
+                            state.provider = resolved.provider;
                             state.hooks_config = if !resolved.active_hook_config.is_empty() {
                                 resolved.active_hook_config.clone()
                             } else {
@@ -1802,6 +2074,30 @@ fn handle_key(
         return Ok(true);
     }
 
+    // ── PlanBrowser mode ──────────────────────────────────────────────────────
+    if state.mode == Mode::PlanBrowser {
+        if let Some(browser) = &mut state.plan_browser {
+            match key.code {
+                KeyCode::Esc => {
+                    state.mode = Mode::Normal;
+                    state.plan_browser = None;
+                }
+                KeyCode::Up if browser.selected > 0 => browser.selected -= 1,
+                KeyCode::Down if browser.selected + 1 < browser.entries.len() => browser.selected += 1,
+                KeyCode::Enter => {
+                    let picked = browser.entries.get(browser.selected).map(|(_, p)| p.clone());
+                    state.mode = Mode::Normal;
+                    state.plan_browser = None;
+                    if let Some(saved) = picked {
+                        resume_plan(saved, state, resolved, verbose, dry_run, ui_tx);
+                    }
+                }
+                _ => {}
+            }
+        }
+        return Ok(true);
+    }
+
     // ── PlanReview mode ───────────────────────────────────────────────────────
     if state.mode == Mode::PlanReview {
         if let Some(pr) = &mut state.plan_review {
@@ -1862,9 +2158,9 @@ fn handle_key(
                         // No execution yet — user approves all steps first, then Enter runs
                     }
                     KeyCode::Enter => {
-                        // Execute only if ALL steps are approved (or already passed)
+                        // Execute only if ALL steps are approved (or already passed/skipped)
                         let all_approved = pr.plan.steps.iter().all(|s| {
-                            matches!(s.status, StepStatus::Approved | StepStatus::Pass)
+                            matches!(s.status, StepStatus::Approved | StepStatus::Pass | StepStatus::Skipped)
                         });
                         if all_approved {
                             let plan = pr.plan.clone();
@@ -1892,14 +2188,14 @@ fn handle_key(
     if state.mode == Mode::SlashComplete {
         match key.code {
             KeyCode::Up => {
-                let count = slash_filtered(&state.input).len();
+                let count = slash_filtered(&state.input, &state.mcp_prompts).len();
                 if count > 0 {
                     state.slash_complete_selected =
                         (state.slash_complete_selected + count - 1) % count;
                 }
             }
             KeyCode::Down => {
-                let count = slash_filtered(&state.input).len();
+                let count = slash_filtered(&state.input, &state.mcp_prompts).len();
                 if count > 0 {
                     state.slash_complete_selected =
                         (state.slash_complete_selected + 1) % count;
@@ -1912,14 +2208,14 @@ fn handle_key(
                 state.mode = Mode::Normal;
             }
             KeyCode::Enter | KeyCode::Tab => {
-                let matches = slash_filtered(&state.input);
+                let matches = slash_filtered(&state.input, &state.mcp_prompts);
                 if let Some(cmd) = matches.get(state.slash_complete_selected) {
                     let no_arg = matches!(
-                        cmd.key,
-                        "/quit" | "/exit" | "/q" | "/clear" | "/sessions"
+                        cmd.key.as_str(),
+                        "/quit" | "/exit" | "/q" | "/clear" | "/sessions" | "/plans"
                         | "/new" | "/help" | "/h" | "/ts" | "/list-hooks"
                         | "/profiles" | "/init" | "/stats"
-                    );
+                    ) || state.mcp_prompts.iter().any(|p| cmd.key[1..] == p.qualified_name && p.arguments.is_empty());
                     let selected_cmd = cmd.key.to_string();
                     state.input.clear();
                     state.cursor = 0;
@@ -2279,12 +2575,35 @@ fn handle_key(
         return Ok(true);
     }
 
-    // ── PlanRunning — Esc cancels ─────────────────────────────────────────────
-    if state.mode == Mode::PlanRunning && key.code == KeyCode::Esc {
+    // ── SpendPaused — extend or abort the paused task ────────────────────────
+    if state.mode == Mode::SpendPaused {
+        let decision = match key.code {
+            KeyCode::Enter | KeyCode::Char('y') | KeyCode::Char('e') => crate::budget::SpendDecision::Extend,
+            KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('a') => crate::budget::SpendDecision::Abort,
+            _ => return Ok(true),
+        };
+        if let Some(pause) = state.spend_pause.take() {
+            let note = match decision {
+                crate::budget::SpendDecision::Extend => format!("limit extended ({})", pause.hit.kind.setting()),
+                crate::budget::SpendDecision::Abort => "aborting task".to_string(),
+            };
+            state.push(ConversationEntry::SystemMsg(note));
+            let _ = pause.reply_tx.send(decision);
+            state.prompt_answered(pause.resume_mode);
+        } else {
+            state.mode = Mode::Normal;
+        }
+        return Ok(true);
+    }
+
+    // ── PlanRunning — Esc / Ctrl+C cancels ───────────────────────────────────
+    let ctrl_c = key.modifiers == KeyModifiers::CONTROL && key.code == KeyCode::Char('c');
+    if state.mode == Mode::PlanRunning && (key.code == KeyCode::Esc || ctrl_c) {
         if let Some(tx) = state.cancel_tx.take() {
             let _ = tx.send(());
         }
         state.mode = Mode::Normal;
+        state.queued_prompts.clear();
         state.push(ConversationEntry::SystemMsg("plan cancelled".to_string()));
         return Ok(true);
     }
@@ -2302,8 +2621,9 @@ fn handle_key(
                 state.palette_query.clear();
                 if !cmd.is_empty() {
                     let input = if cmd.starts_with('/') { cmd } else { format!("/{cmd}") };
-                    // /plan, /quick, /new need ui_tx — handle them here before execute_command
-                    if input == "/new" || input == "/session-new" {
+                    // /plan, /quick, /new and MCP prompts need ui_tx — handle them here before execute_command
+                    let is_prompt = state.mcp_prompts.iter().any(|p| input.split_whitespace().next() == Some(format!("/{}", p.qualified_name).as_str()));
+                    if input == "/new" || input == "/session-new" || is_prompt {
                         handle_submit(input, state, resolved, file, verbose, dry_run, ui_tx.clone())?;
                     } else if input.starts_with("/plan ") || input == "/plan" {
                         let task = input.trim_start_matches("/plan").trim().to_string();
@@ -2341,11 +2661,16 @@ fn handle_key(
 
     // ── Normal / AgentRunning mode ─────────────────────────────────────────
     match (key.modifiers, key.code) {
-        // Ctrl+C — quit (only when idle)
+        // Ctrl+C — quit when idle, cancel a running agent (in-flight MCP requests are cancelled with it)
         (KeyModifiers::CONTROL, KeyCode::Char('c')) => {
             if !matches!(state.mode, Mode::AgentRunning | Mode::PlanRunning | Mode::AskingUser) {
                 return Ok(false);
             }
+            if state.mode == Mode::AgentRunning {
+                if let Some(tx) = state.cancel_tx.take() {
+                    let _ = tx.send(());
+                }
+            }
         }
         // Esc — cancel running agent/plan, or dismiss overlays
         (KeyModifiers::NONE, KeyCode::Esc) if matches!(state.mode, Mode::AgentRunning | Mode::AskingUser) => {
@@ -2353,9 +2678,10 @@ fn handle_key(
                 if let Some(tx) = state.pending_ask_reply.take() {
                     let _ = tx.send("[user cancelled the question — proceed with your best judgement]".to_string());
                 }
+                state.pending_ask_choices.clear();
                 state.push(ConversationEntry::AskReply("(cancelled)".to_string()));
                 state.input_box.clear();
-                state.mode = Mode::AgentRunning;
+                state.prompt_answered(state.ask_resume_mode.clone());
             } else {
                 // Cancel agent
                 if let Some(tx) = state.cancel_tx.take() {
@@ -2519,6 +2845,14 @@ fn handle_key(
         {
             state.input_box.insert_newline();
         }
+        // Digit — answer a question with choices in one keypress
+        (KeyModifiers::NONE, KeyCode::Char(c))
+            if state.mode == Mode::AskingUser
+                && state.input_box.is_empty()
+                && pick_choice(&state.pending_ask_choices, &c.to_string()).is_some() =>
+        {
+            return handle_submit(c.to_string(), state, resolved, file, verbose, dry_run, ui_tx);
+        }
         // All other keys in Normal/AskingUser — delegate to input_box
         _ if matches!(state.mode, Mode::Normal | Mode::AskingUser) => {
             state.focused_chip = None;
@@ -2553,8 +2887,11 @@ fn handle_key(
                             }
                             state.input = String::new();
                             state.cursor = 0;
+                            let mut all_files = gather_files();
+                            all_files.extend(state.mcp_resources.iter().map(|r| r.uri.clone()));
                             state.file_picker = Some(FilePickerState {
-                                all_files: gather_files(),
+                                all_files,
+                                resources: state.mcp_resources.clone(),
                                 query: String::new(),
                                 selected: 0,
                             });
@@ -2612,6 +2949,15 @@ impl Command {
 
 // ── Submit helper ─────────────────────────────────────────────────────────────
 
+/// The choice `input` names: its 1-based number, or its text (case-insensitive).
+fn pick_choice(choices: &[String], input: &str) -> Option<String> {
+    let input = input.trim();
+    if let Ok(n) = input.parse::<usize>() {
+        return n.checked_sub(1).and_then(|i| choices.get(i)).cloned();
+    }
+    choices.iter().find(|c| c.eq_ignore_ascii_case(input)).cloned()
+}
+
 fn handle_submit(
     input: String,
     state: &mut AppState,
@@ -2622,11 +2968,25 @@ fn handle_submit(
     ui_tx: mpsc::UnboundedSender<UiEvent>,
 ) -> Result<bool> {
     if state.mode == Mode::AskingUser {
+        // A question with choices takes a choice's number or name
+        let input = if state.pending_ask_choices.is_empty() {
+            input
+        } else {
+            match pick_choice(&state.pending_ask_choices, &input) {
+                Some(choice) => choice,
+                None => {
+                    let n = state.pending_ask_choices.len();
+                    state.push(ConversationEntry::SystemMsg(format!("  choose 1–{n}, or Esc to decline")));
+                    return Ok(true);
+                }
+            }
+        };
+        state.pending_ask_choices.clear();
         state.push(ConversationEntry::AskReply(input.clone()));
         if let Some(tx) = state.pending_ask_reply.take() {
             let _ = tx.send(input);
         }
-        state.mode = Mode::AgentRunning;
+        state.prompt_answered(state.ask_resume_mode.clone());
         return Ok(true);
     }
 
@@ -2638,7 +2998,7 @@ fn handle_submit(
         for token in re_tokens {
             let path = &token[1..];
             if !state.attached_files.iter().any(|f| f.path == path) {
-                if std::path::Path::new(path).exists() {
+                if std::path::Path::new(path).exists() || state.mcp_resources.iter().any(|r| r.uri == path) {
                     state.attached_files.push(AttachedFile { path: path.to_string() });
                 }
             }
@@ -2707,6 +3067,8 @@ fn handle_submit(
         state.conversation_turns.clear();
         state.entries.clear();
         state.scroll = 0;
+        state.spend.lock().unwrap().reset_session();
+        state.permissions.lock().unwrap().reset_session();
         // Prune before creating so the new session isn't immediately deleted
         sessions::prune_old_sessions(10);
         match sessions::new_session(&cwd_str()) {
@@ -2721,13 +3083,22 @@ fn handle_submit(
         }
         // Re-index in background — picks up files added/removed since last run
         let tx = ui_tx.clone();
+        let scope = crate::index::IndexScope::new(&resolved.index);
         tokio::task::spawn_blocking(move || {
-            let (graph, _) = crate::pie::ProjectGraph::load_or_build(std::path::Path::new("."), 500);
+            let (graph, _) = crate::pie::ProjectGraph::load_or_build(std::path::Path::new("."), &scope);
             let _ = tx.send(UiEvent::IndexReady { graph, narrative: None });
         });
         return Ok(true);
     }
 
+    // /<server>.<prompt> — render an MCP prompt
+    let head = input.split_whitespace().next().unwrap_or("");
+    if let Some(prompt) = state.mcp_prompts.iter().find(|p| head.strip_prefix('/') == Some(p.qualified_name.as_str())) {
+        let prompt = prompt.clone();
+        load_mcp_prompt(&prompt, &input[head.len()..], state, ui_tx);
+        return Ok(true);
+    }
+
     // All other slash commands (including unknown ones)
     if input.starts_with('/') {
         let keep = execute_command(&input, state, resolved, file)?;
@@ -2759,7 +3130,7 @@ fn execute_command(
         }
         "/help" | "/h" => {
             state.push(ConversationEntry::SystemMsg(
-                "Commands: /plan \"task\"  /quick \"task\"  /init  /cd  /profile  /profiles  /ts  /hooks [on|off|list|<preset>]  /list-hooks  /pie  /undo [n]  /diff  /clear  /sessions  /resume [n]  /rollback [n]  /new  /quit\nCtrl+H  session history  ·  Ctrl+P  command palette  ·  d  open diff overlay\nIn plan review: ↑↓ navigate  e annotate  d clear note  a approve & run  Esc cancel\nIn git repo: press 5 for Git tab · /undo to revert · /diff to review changes".to_string(),
+                "Commands: /plan \"task\"  /plans  /quick \"task\"  /init  /cd  /profile  /profiles  /ts  /hooks [on|off|list|<preset>]  /list-hooks  /pie  /undo [n]  /diff  /clear  /sessions  /resume [n]  /rollback [n]  /new  /quit\nCtrl+H  session history  ·  Ctrl+P  command palette  ·  d  open diff overlay\nIn plan review: ↑↓ navigate  e annotate  d clear note  a approve & run  Esc cancel\nIn git repo: press 5 for Git tab · /undo to revert · /diff to review changes".to_string(),
             ));
         }
         "/stats" => {
@@ -2888,6 +3259,34 @@ fn execute_command(
                 None => lines.push("◈ Project graph  not loaded".to_string()),
             }
 
+            // Git co-change coupling
+            match &state.project_graph {
+                Some(g) if g.cochange.commits > 0 => {
+                    let cc = &g.cochange;
+                    lines.push(format!(
+                        "◈ Co-change  {} commits mined · {} coupled pairs",
+                        cc.commits,
+                        cc.pair_count(),
+                    ));
+                    for (file, c) in cc.strongest_pairs(3) {
+                        lines.push(format!(
+                            "  {file} ↔ {}  ({}× together, {:.0}%)",
+                            c.file, c.together, c.confidence * 100.0,
+                        ));
+                    }
+                    let hot: Vec<String> = cc
+                        .hottest(chrono::Utc::now().timestamp(), 3)
+                        .into_iter()
+                        .map(|(f, n)| format!("{f} ({n})"))
+                        .collect();
+                    if !hot.is_empty() {
+                        lines.push(format!("  Recent churn: {}", hot.join(", ")));
+                    }
+                }
+                Some(_) => lines.push("◈ Co-change  no git history".to_string()),
+                None => {}
+            }
+
             // Narrative
             match &state.project_narrative {
                 Some(n) if !n.architecture_summary.is_empty() => {
@@ -3019,6 +3418,15 @@ fn execute_command(
                 }
             }
         }
+        "/plans" => {
+            let browser = PlanBrowserState::load();
+            if browser.entries.is_empty() {
+                state.push(ConversationEntry::SystemMsg("no saved plans in .parecode/plans".to_string()));
+            } else {
+                state.plan_browser = Some(browser);
+                state.mode = Mode::PlanBrowser;
+            }
+        }
         "/resume" => {
             let n: usize = parts
                 .get(1)
@@ -3161,7 +3569,7 @@ fn execute_command(
                     state.profile = resolved.profile_name.clone();
                     state.model = resolved.model.clone();
                     state.context_tokens = resolved.context_tokens;
-                    state.cost_per_mtok_input = resolved.cost_per_mtok_input;
+                    state.pricing = resolved.pricing;
                     state.push(ConversationEntry::SystemMsg(format!(
                         "✓ {} · {} · {}k ctx",
                         resolved.profile_name, resolved.model, resolved.context_tokens / 1000
@@ -3224,11 +3632,14 @@ fn launch_agent(
     let (cancel_tx, cancel_rx) = tokio::sync::oneshot::channel::<()>();
     state.cancel_tx = Some(cancel_tx);
 
+    // MCP resources are read into the first message; files stay path hints
+    let (resources, attached): (Vec<String>, Vec<String>) = state.attached_files
+        .iter()
+        .map(|f| f.path.clone())
+        .partition(|p| state.mcp_resources.iter().any(|r| &r.uri == p));
+
     // Build client + config
-    let mut client = Client::new(resolved.endpoint.clone(), resolved.model.clone());
-    if let Some(key) = &resolved.api_key {
-        client.set_api_key(key.clone());
-    }
+    let client = Client::from_resolved(resolved);
     let resolved_hooks = resolve_hooks_with_state(resolved, state);
     let agent_config = AgentConfig {
         verbose,
@@ -3246,14 +3657,17 @@ fn launch_agent(
         project_graph: state.project_graph.as_ref().map(|g| std::sync::Arc::new(g.clone())),
         project_narrative: state.project_narrative.as_ref().map(|n| std::sync::Arc::new(n.clone())),
         flow_paths: state.flow_paths.as_ref().map(|fp| std::sync::Arc::new(fp.clone())),
+        semantic: state.semantic.clone(),
         attached_symbols: state.attached_symbols.clone(),
+        attached_resources: resources,
+        fallback_clients: Client::fallbacks_for(resolved),
+        spend_limits: resolved.spend_limits,
+        spend: state.spend.clone(),
+        permissions: state.permissions.clone(),
+        live_graph: state.live_graph.clone(),
+        workdir: None,
     };
 
-    let attached: Vec<String> = state.attached_files
-        .iter()
-        .map(|f| f.path.clone())
-        .collect();
-
     // Pass the last few completed turns for session continuity (capped in agent.rs).
     let active_limit = state.session.as_ref().map(|s| s.active_turn + 1).unwrap_or(usize::MAX);
     let prior_turns: Vec<crate::sessions::ConversationTurn> = state.conversation_turns
@@ -3289,6 +3703,30 @@ fn launch_agent(
     Ok(())
 }
 
+// ── MCP prompts ───────────────────────────────────────────────────────────────
+
+/// Fetch a rendered MCP prompt in the background; it lands in the input box
+/// (`UiEvent::McpPromptReady`) for review before it runs.
+fn load_mcp_prompt(prompt: &McpPrompt, args: &str, state: &mut AppState, ui_tx: mpsc::UnboundedSender<UiEvent>) {
+    let arguments = match prompt.parse_arguments(args) {
+        Ok(a) => a,
+        Err(e) => {
+            state.push(ConversationEntry::SystemMsg(format!("✗ {e}")));
+            return;
+        }
+    };
+    state.push(ConversationEntry::SystemMsg(format!("◈ fetching prompt {}…", prompt.qualified_name)));
+    let mcp = state.mcp.clone();
+    let name = prompt.qualified_name.clone();
+    tokio::spawn(async move {
+        let event = match mcp.get_prompt(&name, arguments).await {
+            Ok(text) => UiEvent::McpPromptReady { name, text },
+            Err(e) => UiEvent::SystemMsg(format!("✗ prompt {name}: {e}")),
+        };
+        let _ = ui_tx.send(event);
+    });
+}
+
 // ── Quick mode launcher ───────────────────────────────────────────────────────
 
 fn launch_quick(
@@ -3308,10 +3746,7 @@ fn launch_quick(
     let (cancel_tx, cancel_rx) = tokio::sync::oneshot::channel::<()>();
     state.cancel_tx = Some(cancel_tx);
 
-    let mut client = Client::new(resolved.endpoint.clone(), resolved.model.clone());
-    if let Some(key) = &resolved.api_key {
-        client.set_api_key(key.clone());
-    }
+    let client = Client::from_resolved(resolved);
     // Quick mode: no on_edit hooks (single shot, no mutation loop)
     let agent_config = AgentConfig {
         verbose,
@@ -3329,7 +3764,15 @@ fn launch_quick(
         project_graph: state.project_graph.as_ref().map(|g| std::sync::Arc::new(g.clone())),
         project_narrative: state.project_narrative.as_ref().map(|n| std::sync::Arc::new(n.clone())),
         flow_paths: None, // quick mode: single shot, no path preloading
+        semantic: state.semantic.clone(),
         attached_symbols: state.attached_symbols.clone(),
+        attached_resources: vec![],
+        fallback_clients: vec![], // quick mode: single call, retries only
+        spend_limits: resolved.spend_limits,
+        spend: state.spend.clone(),
+        permissions: state.permissions.clone(),
+        live_graph: state.live_graph.clone(),
+        workdir: None,
     };
 
     state.collecting_response.clear();
@@ -3391,10 +3834,13 @@ fn generate_and_show_plan(
     ));
     // Use AgentRunning mode to get spinner while generating
     state.mode = Mode::AgentRunning;
-    let mut client = Client::new(resolved.endpoint.clone(), plan_model.clone());
-    if let Some(key) = &resolved.api_key {
-        client.set_api_key(key.clone());
-    }
+    let mut client = Client::from_resolved(resolved);
+    client.model = plan_model.clone();
+    client.pricing = resolved.planner_pricing;
+    let session_id = state.session.as_ref().map(|s| s.id.clone()).unwrap_or_default();
+    let profile = resolved.profile_name.clone();
+    let spend = state.spend.clone();
+    let spend_limits = resolved.spend_limits;
 
     let context_files: Vec<String> = state.attached_files
         .iter()
@@ -3411,10 +3857,11 @@ fn generate_and_show_plan(
 
     // Use the graph already loaded at startup; fall back to a fresh build if missing.
     let graph = state.project_graph.clone().unwrap_or_else(|| {
-        crate::pie::ProjectGraph::build_fresh(std::path::Path::new("."), 500)
+        crate::pie::ProjectGraph::build_fresh(std::path::Path::new("."), &crate::index::IndexScope::new(&resolved.index))
     });
     let narrative = state.project_narrative.clone();
     let flow_paths = state.flow_paths.clone();
+    let semantic = state.semantic.clone();
     // attached_symbols moved into the spawn (already Vec<AttachedSymbol>)
 
     tokio::spawn(async move {
@@ -3423,8 +3870,17 @@ fn generate_and_show_plan(
         let on_chunk = move |chunk: &str| {
             let _ = tx_chunk.send(UiEvent::ThinkingChunk(chunk.to_string()));
         };
-        match plan::generate_plan(&task, &client, &project, &context_files, &attached_symbols, &graph, narrative.as_ref(), flow_paths.as_ref(), tx_plan, on_chunk).await {
-            Ok((generated_plan, _metrics)) => {
+        let context = plan::PlanContext {
+            narrative: narrative.as_ref(),
+            flow_paths: flow_paths.as_ref(),
+            semantic: semantic.as_deref(),
+        };
+        match plan::generate_plan(&task, &client, &project, &context_files, &attached_symbols, &graph, context, &spend_limits, &spend, tx_plan, on_chunk).await {
+            Ok((generated_plan, metrics)) => {
+                // Planning spend is recorded as its own task so plan costs show up in stats.
+                telemetry::append_record(&plan::metrics_record(
+                    &metrics, &task, &session_id, &project, &plan_model, &profile,
+                ));
                 // Save plan to disk: JSON for machine use, Markdown for human reading
                 let _ = plan::save_plan(&generated_plan);
                 plan::write_plan_to_disk(&generated_plan);
@@ -3439,8 +3895,11 @@ fn generate_and_show_plan(
 
 // ── Plan execution ─────────────────────────────────────────────────────────────
 
-/// Spawn a background task to execute all plan steps sequentially.
-/// All state updates are communicated back to the TUI via UiEvent.
+/// Spawn a background task to execute the plan's remaining steps
+/// (steps that already passed are skipped). When a step fails verification
+/// and `max_replans` allows, the planner revises the plan and it comes back
+/// as `PlanReplanned` for review. All state updates are
+/// communicated back to the TUI via UiEvent.
 fn launch_plan(
     mut active_plan: Plan,
     cancel_rx: tokio::sync::oneshot::Receiver<()>,
@@ -3450,10 +3909,17 @@ fn launch_plan(
     dry_run: bool,
     ui_tx: mpsc::UnboundedSender<UiEvent>,
 ) {
-    let mut client = Client::new(resolved.endpoint.clone(), resolved.model.clone());
-    if let Some(key) = &resolved.api_key {
-        client.set_api_key(key.clone());
-    }
+    let client = Client::from_resolved(resolved);
+    // Replanning after a failed step goes to the planner model, like /plan does
+    let plan_model = resolved.planner_model.clone().unwrap_or_else(|| resolved.model.clone());
+    let mut planner = Client::from_resolved(resolved);
+    planner.model = plan_model.clone();
+    planner.pricing = resolved.planner_pricing;
+    let max_replans = resolved.max_replans;
+    let session_id = state.session.as_ref().map(|s| s.id.clone()).unwrap_or_default();
+    let profile = resolved.profile_name.clone();
+    let spend = state.spend.clone();
+    let spend_limits = resolved.spend_limits;
     let resolved_hooks = resolve_hooks_with_state(resolved, state);
     let agent_config = AgentConfig {
         verbose,
@@ -3471,7 +3937,15 @@ fn launch_plan(
         project_graph: None,     // executor steps have pre-digested instructions — no graph needed
         project_narrative: None,
         flow_paths: None,
+        semantic: None,
         attached_symbols: vec![], // executor steps use pre-digested instructions
+        attached_resources: vec![],
+        fallback_clients: Client::fallbacks_for(resolved),
+        spend_limits: resolved.spend_limits,
+        spend: state.spend.clone(),
+        permissions: state.permissions.clone(),
+        live_graph: std::sync::Arc::default(),
+        workdir: None,
     };
 
     tokio::spawn(async move {
@@ -3486,178 +3960,82 @@ fn launch_plan(
             let _ = ui_tx_cancel.send(UiEvent::AgentError("cancelled".to_string()));
         });
 
-        let total = active_plan.steps.len();
-        // Accumulates (description, summary) for each completed step
-        let mut prior_summaries: Vec<(String, String)> = Vec::new();
-
-        // ── Plan-level git checkpoint ─────────────────────────────────────────
-        // Capture the state before any step runs — used for cumulative diff.
-        let plan_checkpoint: Option<String> = if agent_config.git_context {
-            std::env::current_dir().ok().and_then(|cwd| {
-                crate::git::GitRepo::open(&cwd).and_then(|repo| {
-                    let summary = format!(
-                        "plan: {}",
-                        active_plan.task.chars().take(50).collect::<String>()
-                    );
-                    repo.checkpoint(&summary).ok()
-                })
-            })
-        } else {
-            None
-        };
-
-        for step_idx in 0..total {
-            // Check cancellation before starting each step
-            if cancelled.load(std::sync::atomic::Ordering::Relaxed) {
-                return;
+        let result = plan::run_plan(&mut active_plan, 0, &client, &agent_config, ui_tx.clone(), &cancelled, |p| {
+            let _ = plan::save_plan(p);
+        })
+        .await;
+
+        // ── Recovery: replan a step that failed verification ──────────────────
+        let Err(e) = result else { return };
+        let Some(failed) = e.downcast_ref::<plan::VerificationFailed>() else { return };
+        if active_plan.replans >= max_replans {
+            if max_replans > 0 {
+                let _ = ui_tx.send(UiEvent::SystemMsg(format!("replan limit reached ({max_replans}) — not replanning")));
             }
-
-            let step_snapshot = active_plan.steps[step_idx].clone();
-            let desc = step_snapshot.description.clone();
-
-            // ── Per-step git checkpoint ───────────────────────────────────────
-            let step_checkpoint: Option<String> = if agent_config.git_context {
-                std::env::current_dir().ok().and_then(|cwd| {
-                    crate::git::GitRepo::open(&cwd).and_then(|repo| {
-                        let summary = format!(
-                            "plan step {}/{}: {}",
-                            step_idx + 1,
-                            total,
-                            desc.chars().take(40).collect::<String>()
-                        );
-                        repo.checkpoint(&summary).ok()
-                    })
-                })
-            } else {
-                None
-            };
-
-            let _ = ui_tx.send(UiEvent::PlanStepStart {
-                index: step_idx,
-                total,
-                desc: desc.clone(),
-            });
-
-            // Run the step (agent call)
-            let step_result = plan::execute_step(
-                &step_snapshot,
-                &client,
-                &agent_config,
-                ui_tx.clone(),
-            ).await;
-
-            // Run verification
-            let verify_result = match &step_result {
-                Ok(()) => plan::verify_step(&step_snapshot),
-                Err(e) => Err(anyhow::anyhow!("{e}")),
-            };
-
-            match verify_result {
-                Ok(()) => {
-                    active_plan.steps[step_idx].status = StepStatus::Pass;
-                    // Inspect modified files to record what actually changed
-                    let summary = plan::summarise_completed_step(&step_snapshot);
-                    active_plan.steps[step_idx].completed_summary = Some(summary.clone());
-                    prior_summaries.push((desc, summary));
-                    let _ = ui_tx.send(UiEvent::PlanStepDone {
-                        index: step_idx,
-                        passed: true,
-                        error: None,
-                    });
-                    // on_plan_step_done hooks — shown in TUI
-                    if agent_config.hooks_enabled {
-                        for cmd in &agent_config.hooks.on_plan_step_done {
-                            let hr = crate::hooks::run_hook(cmd).await;
-                            let _ = ui_tx.send(UiEvent::HookOutput {
-                                event: "on_plan_step_done".to_string(),
-                                output: hr.output,
-                                exit_code: hr.exit_code,
-                            });
-                        }
-                    }
-                    // ── Per-step diff notification ────────────────────────────
-                    if agent_config.git_context {
-                        if let Some(cwd) = std::env::current_dir().ok() {
-                            if let Some(repo) = crate::git::GitRepo::open(&cwd) {
-                                let ref_pt = step_checkpoint.as_deref().unwrap_or("HEAD");
-                                if let Ok(stat) = repo.diff_stat_from(ref_pt) {
-                                    if !stat.trim().is_empty() {
-                                        let files_changed =
-                                            stat.lines().filter(|l| l.contains('|')).count();
-                                        let _ = ui_tx.send(UiEvent::GitChanges {
-                                            stat: stat.trim().to_string(),
-                                            checkpoint_hash: step_checkpoint.clone(),
-                                            files_changed,
-                                        });
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-                Err(e) => {
-                    active_plan.steps[step_idx].status = StepStatus::Fail;
-                    active_plan.status = crate::plan::PlanStatus::Failed;
-                    let _ = plan::save_plan(&active_plan);
-                    let _ = ui_tx.send(UiEvent::PlanStepDone {
-                        index: step_idx,
-                        passed: false,
-                        error: Some(e.to_string()),
-                    });
-                    let _ = ui_tx.send(UiEvent::PlanFailed {
-                        step: step_idx,
-                        error: e.to_string(),
-                    });
-                    return;
-                }
-            }
-        }
-
-        // All steps passed
-        active_plan.status = crate::plan::PlanStatus::Complete;
-        let _ = plan::save_plan(&active_plan);
-
-        // ── Cumulative diff + auto-commit ─────────────────────────────────────
-        if agent_config.git_context {
-            if let Some(cwd) = std::env::current_dir().ok() {
-                if let Some(repo) = crate::git::GitRepo::open(&cwd) {
-                    let ref_pt = plan_checkpoint.as_deref().unwrap_or("HEAD");
-                    if let Ok(stat) = repo.diff_stat_from(ref_pt) {
-                        if !stat.trim().is_empty() {
-                            let files_changed =
-                                stat.lines().filter(|l| l.contains('|')).count();
-                            let cumulative_stat =
-                                format!("Plan complete — cumulative changes:\n{}", stat.trim());
-                            let _ = ui_tx.send(UiEvent::GitChanges {
-                                stat: cumulative_stat,
-                                checkpoint_hash: plan_checkpoint.clone(),
-                                files_changed,
-                            });
-                        }
-                    }
-                    if agent_config.auto_commit {
-                        let summary: String =
-                            active_plan.task.chars().take(72).collect();
-                        let msg =
-                            format!("{}{}", agent_config.auto_commit_prefix, summary);
-                        match repo.auto_commit(&msg) {
-                            Ok(()) => {
-                                let _ = ui_tx.send(UiEvent::GitAutoCommit { message: msg });
-                            }
-                            Err(e) => {
-                                let _ = ui_tx
-                                    .send(UiEvent::GitError(format!("auto-commit: {e}")));
-                            }
-                        }
-                    }
-                }
+            return;
+        }
+        let step = failed.step;
+        let _ = ui_tx.send(UiEvent::SystemMsg(format!(
+            "⟳ replanning step {} via {plan_model} ({}/{max_replans})",
+            step + 1,
+            active_plan.replans + 1,
+        )));
+        match plan::replan_failed_step(&active_plan, step, &failed.reason, &planner, &spend_limits, &spend, &ui_tx).await {
+            Ok((replan, metrics)) => {
+                telemetry::append_record(&plan::metrics_record(
+                    &metrics, &active_plan.task, &session_id, &active_plan.project, &plan_model, &profile,
+                ));
+                active_plan.apply_replan(step, replan);
+                let _ = plan::save_plan(&active_plan);
+                plan::write_plan_to_disk(&active_plan);
+                let _ = ui_tx.send(UiEvent::PlanReplanned { step, plan: active_plan });
+            }
+            Err(e) => {
+                let _ = ui_tx.send(UiEvent::SystemMsg(format!("✗ replanning failed: {e}")));
             }
         }
-
-        let _ = ui_tx.send(UiEvent::PlanComplete { total });
     });
 }
 
+/// Pick up a saved plan at its first step that has not passed. Runs straight
+/// away when the remaining steps are approved; otherwise opens the review card
+/// so they can be approved first. Earlier steps' summaries are carried forward
+/// by `plan::run_plan`.
+fn resume_plan(
+    mut saved: Plan,
+    state: &mut AppState,
+    resolved: &ResolvedConfig,
+    verbose: bool,
+    dry_run: bool,
+    ui_tx: mpsc::UnboundedSender<UiEvent>,
+) {
+    let Some(from) = saved.resume_index() else {
+        state.push(ConversationEntry::SystemMsg(
+            format!("✓ plan already complete — {} steps passed", saved.steps.len()),
+        ));
+        return;
+    };
+    let total = saved.steps.len();
+    let ready = plan::prepare_run(&mut saved, from, false).is_ok();
+    state.plan_review = Some(PlanReviewState { selected: from, ..PlanReviewState::new(saved.clone()) });
+    state.plan_ever_active = true;
+    state.push(ConversationEntry::PlanCard);
+    if ready {
+        state.push(ConversationEntry::SystemMsg(
+            format!("⟳ resuming plan at step {}/{total}", from + 1),
+        ));
+        let (cancel_tx, cancel_rx) = tokio::sync::oneshot::channel::<()>();
+        state.cancel_tx = Some(cancel_tx);
+        state.mode = Mode::PlanRunning;
+        launch_plan(saved, cancel_rx, state, resolved, verbose, dry_run, ui_tx);
+    } else {
+        state.push(ConversationEntry::SystemMsg(
+            format!("plan loaded — approve the remaining steps (a), then Enter resumes at step {}/{total}", from + 1),
+        ));
+        state.mode = Mode::PlanReview;
+    }
+}
+
 // ── Sidebar helpers ───────────────────────────────────────────────────────────
 
 /// Load and switch to a sidebar session by index.  Used from both keyboard Enter
@@ -1,4 +1,4 @@
-/// Overlay/popup draw functions — palette, slash-complete, file picker, session browser.
+/// Overlay/popup draw functions — palette, slash-complete, file picker, session and plan browsers.
 use ratatui::{
     Frame,
     layout::{Constraint, Direction, Layout, Rect},
@@ -8,7 +8,7 @@ use ratatui::{
     widgets::{Block, Borders, Clear, List, ListItem, Paragraph},
 };
 
-use super::{AppState, FilePickerState, SessionBrowserState, slash_filtered,
+use super::{AppState, FilePickerState, SessionBrowserState, PlanBrowserState, slash_filtered,
             HookWizardState, WizardStep, FileSymbolPickerState};
 
 // ── Command palette ────────────────────────────────────────────────────────────
@@ -24,7 +24,7 @@ pub fn draw_palette(f: &mut Frame, state: &AppState, area: Rect) {
 
     f.render_widget(Clear, popup_area);
 
-    let commands = palette_commands();
+    let commands = palette_commands(&state.mcp_prompts);
     let query = state.palette_query.to_lowercase();
 
     let items: Vec<ListItem<'static>> = commands
@@ -77,7 +77,7 @@ pub fn draw_palette(f: &mut Frame, state: &AppState, area: Rect) {
 // ── Slash autocomplete ─────────────────────────────────────────────────────────
 
 pub fn draw_slash_complete(f: &mut Frame, state: &AppState, area: Rect) {
-    let matches = slash_filtered(&state.input); // state.input used in SlashComplete mode
+    let matches = slash_filtered(&state.input, &state.mcp_prompts); // state.input used in SlashComplete mode
     if matches.is_empty() {
         return;
     }
@@ -160,6 +160,13 @@ pub fn draw_file_picker(f: &mut Frame, fp: &FilePickerState, area: Rect) {
             } else {
                 Style::default().fg(Color::White)
             };
+            if let Some(resource) = fp.resources.iter().find(|r| &r.uri == *path) {
+                return ListItem::new(Line::from(vec![
+                    Span::raw("  "),
+                    Span::styled(resource.uri.clone(), style.fg(Color::Magenta)),
+                    Span::styled(format!("  {} · {}", resource.name, resource.server), style.fg(Color::DarkGray)),
+                ]));
+            }
             // Split into dir + filename for visual clarity
             let p = std::path::Path::new(path.as_str());
             let dir = p.parent()
@@ -346,6 +353,98 @@ pub fn draw_session_browser(f: &mut Frame, browser: &SessionBrowserState, area:
     f.render_widget(Paragraph::new(hint), hint_area);
 }
 
+// ── Plan browser ───────────────────────────────────────────────────────────────
+
+pub fn draw_plan_browser(f: &mut Frame, browser: &PlanBrowserState, area: Rect) {
+    use crate::plan::PlanStatus;
+
+    let width = 72u16.min(area.width.saturating_sub(4));
+    let height = 20u16.min(area.height.saturating_sub(4));
+    let x = area.x + (area.width.saturating_sub(width)) / 2;
+    let y = area.y + (area.height.saturating_sub(height)) / 2;
+    let popup_area = Rect { x, y, width, height };
+
+    f.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .title(Span::styled(
+            " Saved Plans ",
+            Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+        ))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Rgb(200, 140, 0)));
+
+    let inner = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+
+    // Footer hint
+    let hint_area = Rect {
+        x: inner.x,
+        y: inner.y + inner.height.saturating_sub(1),
+        width: inner.width,
+        height: 1,
+    };
+    let list_area = Rect {
+        height: inner.height.saturating_sub(1),
+        ..inner
+    };
+
+    let items: Vec<ListItem<'static>> = browser
+        .entries
+        .iter()
+        .enumerate()
+        .map(|(i, (_path, plan))| {
+            let selected = i == browser.selected;
+            let date_str = chrono::DateTime::from_timestamp(plan.created_at, 0)
+                .unwrap_or_default()
+                .with_timezone(&chrono::Local)
+                .format("%b %d %H:%M")
+                .to_string();
+            let (status_str, status_fg) = match plan.status {
+                PlanStatus::Complete => ("done   ", Color::Rgb(80, 200, 120)),
+                PlanStatus::Failed => ("failed ", Color::Rgb(220, 90, 90)),
+                PlanStatus::Running => ("stopped", Color::Rgb(220, 160, 0)),
+                PlanStatus::Pending => ("new    ", Color::DarkGray),
+            };
+            let (bg, date_fg, task_fg) = if selected {
+                (Color::Rgb(50, 40, 15), Color::Rgb(240, 190, 60), Color::White)
+            } else {
+                (Color::Reset, Color::Rgb(160, 120, 40), Color::Rgb(180, 180, 200))
+            };
+            let task: String = plan.task.chars().take(40).collect();
+
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("  {date_str}  "), Style::default().fg(date_fg).bg(bg)),
+                Span::styled(
+                    format!("{:>2}/{:<2}  ", plan.steps_done(), plan.steps.len()),
+                    Style::default().fg(Color::Rgb(200, 140, 0)).bg(bg),
+                ),
+                Span::styled(format!("{status_str}  "), Style::default().fg(status_fg).bg(bg)),
+                Span::styled(task, Style::default().fg(task_fg).bg(bg)),
+            ]))
+        })
+        .collect();
+
+    // Scroll to keep selected in view
+    let visible = list_area.height as usize;
+    let skip = if browser.selected >= visible {
+        browser.selected - visible + 1
+    } else {
+        0
+    };
+    let sliced: Vec<ListItem<'static>> = items.into_iter().skip(skip).collect();
+    f.render_widget(List::new(sliced), list_area);
+
+    let hint = Line::from(vec![
+        Span::styled("  ↑↓ navigate  ", Style::default().fg(Color::DarkGray)),
+        Span::styled("Enter", Style::default().fg(Color::Rgb(240, 190, 60))),
+        Span::styled(" resume from first unfinished step  ", Style::default().fg(Color::DarkGray)),
+        Span::styled("Esc", Style::default().fg(Color::Rgb(240, 190, 60))),
+        Span::styled(" close", Style::default().fg(Color::DarkGray)),
+    ]);
+    f.render_widget(Paragraph::new(hint), hint_area);
+}
+
 // ── Full diff overlay ──────────────────────────────────────────────────────────
 
 /// Full-screen overlay showing the complete `git diff` output with syntax colouring.
@@ -12,7 +12,7 @@ use super::AppState;
 pub fn draw(f: &mut Frame, state: &AppState, area: Rect) {
     let bg = Block::default().style(Style::default().bg(Color::Rgb(8, 8, 14)));
     if state.plan_review.is_some() {
-        let items = super::chat::build_plan_card_items(state, state.cost_per_mtok_input);
+        let items = super::chat::build_plan_card_items(state, state.pricing.map(|p| p.input));
         f.render_widget(List::new(items).block(bg), area);
     } else {
         let lines = vec![
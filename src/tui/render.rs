@@ -196,6 +196,9 @@ pub fn draw(f: &mut Frame, state: &mut AppState) {
             super::overlays::draw_session_browser(f, browser, area);
         }
     }
+    if let (Mode::PlanBrowser, Some(browser)) = (&state.mode, &state.plan_browser) {
+        super::overlays::draw_plan_browser(f, browser, area);
+    }
     if state.mode == Mode::ProfilePicker {
         super::overlays::draw_profile_picker(f, state, area);
     }
@@ -309,6 +312,8 @@ fn draw_status_bar(f: &mut Frame, state: &AppState, area: Rect) {
     // Animated spinner glyph in status bar when running
     let (status_glyph, status_color) = if state.mode == Mode::AskingUser {
         ("?", Color::Yellow)
+    } else if state.mode == Mode::SpendPaused {
+        ("⏸", Color::Rgb(230, 140, 40))
     } else if matches!(state.mode, Mode::AgentRunning | Mode::PlanRunning) {
         let g = SPINNER_GLYPHS[(state.spinner_tick as usize) % SPINNER_GLYPHS.len()];
         (g, Color::Cyan)
@@ -393,9 +398,12 @@ fn draw_stats_bar(f: &mut Frame, state: &AppState, area: Rect) {
         } else {
             String::new()
         };
-        format!("  ▶ {}tok (i:{} o:{}){}",
+        let cost_part = s.inflight_cost_usd
+            .map(|c| format!("  {}", crate::pricing::fmt_usd(c)))
+            .unwrap_or_default();
+        format!("  ▶ {}tok (i:{} o:{}){}{}",
             fmt_k(total_inf), fmt_k(s.inflight_input_tokens), fmt_k(s.inflight_output_tokens),
-            tools_part)
+            tools_part, cost_part)
     } else {
         String::new()
     };
@@ -422,7 +430,12 @@ fn draw_stats_bar(f: &mut Frame, state: &AppState, area: Rect) {
 
         (
             format!("{} task{}", s.tasks_completed, if s.tasks_completed == 1 { "" } else { "s" }),
-            format!("  {}tok  avg {}/task", fmt_k(total_tok), fmt_k(avg_tok)),
+            if s.total_cost_usd > 0.0 {
+                format!("  {}tok  avg {}/task  {}", fmt_k(total_tok), fmt_k(avg_tok),
+                    crate::pricing::fmt_usd(s.total_cost_usd))
+            } else {
+                format!("  {}tok  avg {}/task", fmt_k(total_tok), fmt_k(avg_tok))
+            },
             format!("  {} tool calls", s.total_tool_calls),
             if s.total_tool_calls > 0 {
                 format!("  {:.0}% compressed", ratio * 100.0)
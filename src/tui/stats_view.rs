@@ -8,7 +8,8 @@ use ratatui::{
 };
 
 use super::AppState;
-use crate::telemetry::Aggregate;
+use crate::pricing::fmt_usd;
+use crate::telemetry::{self, Aggregate};
 
 // ── Rendering helpers ─────────────────────────────────────────────────────────
 
@@ -53,18 +54,6 @@ fn fmt_duration(secs: u32) -> String {
     }
 }
 
-fn cost_str(tokens_in: u32, tokens_out: u32, cost_per_mtok: Option<f64>) -> Option<String> {
-    let cpm = cost_per_mtok?;
-    // assume output is ~3× input price (rough Anthropic ratio)
-    let cost = (tokens_in as f64 / 1_000_000.0) * cpm
-        + (tokens_out as f64 / 1_000_000.0) * cpm * 3.0;
-    if cost < 0.001 {
-        Some("<$0.001".to_string())
-    } else {
-        Some(format!("${:.3}", cost))
-    }
-}
-
 // ── Section builders ──────────────────────────────────────────────────────────
 
 const BAR_W: usize = 16;
@@ -86,6 +75,15 @@ fn stat_bar_row<'a>(
     ]))
 }
 
+fn truncate_key(s: &str, max: usize) -> String {
+    if s.chars().count() <= max {
+        s.to_string()
+    } else {
+        let head: String = s.chars().take(max - 1).collect();
+        format!("{head}…")
+    }
+}
+
 fn kv_row<'a>(label: &str, value: &str) -> ListItem<'a> {
     ListItem::new(Line::from(vec![
         Span::styled(format!("  {:<14}", label), Style::default().fg(Color::Rgb(100, 95, 140))),
@@ -109,7 +107,6 @@ fn aggregate_items<'a>(
     agg: &Aggregate,
     peak_context_pct: Option<u32>,
     budget_enforcements: Option<usize>,
-    cost_per_mtok: Option<f64>,
     max_tokens: u64,   // for bar scaling
     max_tools: u64,
 ) -> Vec<ListItem<'a>> {
@@ -128,6 +125,16 @@ fn aggregate_items<'a>(
     );
     items.push(stat_bar_row("tokens", total_tok, max_tokens.max(1), &tok_str, Color::Rgb(80, 140, 200)));
 
+    // Prompt cache row — share of input served from the provider's cache
+    if agg.cache_read_tokens > 0 || agg.cache_write_tokens > 0 {
+        let hit_pct = (agg.cache_hit_ratio() * 100.0).round() as u64;
+        let cache_str = format!(
+            "{}%  (read: {}  written: {})",
+            hit_pct, fmt_k(agg.cache_read_tokens as u64), fmt_k(agg.cache_write_tokens as u64)
+        );
+        items.push(stat_bar_row("prompt cache", hit_pct, 100, &cache_str, Color::Rgb(60, 190, 190)));
+    }
+
     // Tool calls row
     let avg_tools = if agg.tasks > 0 { tools / agg.tasks as u64 } else { 0 };
     let tools_str = format!("{}  (avg {}/task)", tools, avg_tools);
@@ -164,9 +171,14 @@ fn aggregate_items<'a>(
         items.push(kv_row("time", &dur_str));
     }
 
-    // Cost estimate
-    if let Some(c) = cost_str(agg.input_tokens, agg.output_tokens, cost_per_mtok) {
-        items.push(kv_row("est. cost", &c));
+    // Cost — summed from per-task records priced at run time
+    if agg.unpriced < agg.tasks {
+        let cost_str = if agg.unpriced > 0 {
+            format!("{}  ({} unpriced)", fmt_usd(agg.cost_usd), agg.unpriced)
+        } else {
+            fmt_usd(agg.cost_usd)
+        };
+        items.push(kv_row("cost", &cost_str));
     }
 
     items
@@ -178,7 +190,6 @@ pub fn draw(f: &mut Frame, state: &AppState, area: Rect) {
     let mut all_items: Vec<ListItem<'static>> = Vec::new();
 
     let s = &state.stats;
-    let cost = state.cost_per_mtok_input.map(|c| c as f64);
 
     // ── Section 0: In-Flight (shown only while agent is running) ──────────────
     if s.inflight_input_tokens > 0 || s.inflight_output_tokens > 0 {
@@ -186,10 +197,13 @@ pub fn draw(f: &mut Frame, state: &AppState, area: Rect) {
         all_items.push(section_header("▶ In-Flight"));
         all_items.push(kv_row("input",  &fmt_k(s.inflight_input_tokens as u64)));
         all_items.push(kv_row("output", &fmt_k(s.inflight_output_tokens as u64)));
+        if s.inflight_cache_read_tokens > 0 {
+            all_items.push(kv_row("cached", &fmt_k(s.inflight_cache_read_tokens as u64)));
+        }
         let total = s.inflight_input_tokens + s.inflight_output_tokens;
         all_items.push(kv_row("total",  &fmt_k(total as u64)));
-        if let Some(cost_str) = cost_str(s.inflight_input_tokens, s.inflight_output_tokens, cost) {
-            all_items.push(kv_row("est cost", &cost_str));
+        if let Some(cost) = s.inflight_cost_usd {
+            all_items.push(kv_row("cost", &fmt_usd(cost)));
         }
     }
 
@@ -207,6 +221,10 @@ pub fn draw(f: &mut Frame, state: &AppState, area: Rect) {
             tasks: s.tasks_completed,
             input_tokens: s.total_input_tokens,
             output_tokens: s.total_output_tokens,
+            cache_read_tokens: s.total_cache_read_tokens,
+            cache_write_tokens: s.total_cache_write_tokens,
+            cost_usd: s.total_cost_usd,
+            unpriced: s.records.iter().filter(|r| r.cost_usd.is_none()).count(),
             tool_calls: s.total_tool_calls,
             compressed: s.total_compressed,
             duration_secs: s.records.iter().map(|r| r.duration_secs).sum(),
@@ -221,7 +239,6 @@ pub fn draw(f: &mut Frame, state: &AppState, area: Rect) {
             &session_agg,
             Some(s.peak_context_pct),
             Some(s.budget_enforcements),
-            cost,
             max_tok,
             max_tools,
         ));
@@ -247,7 +264,7 @@ pub fn draw(f: &mut Frame, state: &AppState, area: Rect) {
         all_items.push(kv_row("tasks", &agg.tasks.to_string()));
         let max_tok = agg.total_tokens() as u64;
         let max_tools = agg.tool_calls as u64;
-        all_items.extend(aggregate_items(&agg, None, None, cost, max_tok, max_tools));
+        all_items.extend(aggregate_items(&agg, None, None, max_tok, max_tools));
     }
     all_items.push(divider());
 
@@ -263,7 +280,7 @@ pub fn draw(f: &mut Frame, state: &AppState, area: Rect) {
         all_items.push(kv_row("tasks", &agg.tasks.to_string()));
         let max_tok = agg.total_tokens() as u64;
         let max_tools = agg.tool_calls as u64;
-        all_items.extend(aggregate_items(&agg, None, None, cost, max_tok, max_tools));
+        all_items.extend(aggregate_items(&agg, None, None, max_tok, max_tools));
 
         // First-seen date
         if let Some(first) = state.telemetry_history.first() {
@@ -275,7 +292,41 @@ pub fn draw(f: &mut Frame, state: &AppState, area: Rect) {
     }
     all_items.push(divider());
 
-    // ── Section 4: Per-task table (this session) ──────────────────────────────
+    // ── Section 4: Spend breakdown (last 30 days) ─────────────────────────────
+    let month_start = now - 30 * 86400;
+    let month_records: Vec<_> = state.telemetry_history.iter()
+        .filter(|r| r.timestamp >= month_start)
+        .cloned()
+        .collect();
+    if month_records.iter().any(|r| r.cost_usd.is_some()) {
+        all_items.push(section_header("Spend — last 30 days"));
+        let groups: [(&str, Vec<telemetry::CostRow>); 3] = [
+            ("by day", telemetry::cost_breakdown(&month_records, telemetry::record_day, true)),
+            ("by model", telemetry::cost_breakdown(&month_records, |r| r.model.clone(), false)),
+            ("by project", telemetry::cost_breakdown(&month_records, |r| {
+                if r.cwd.is_empty() { "(unknown)".to_string() } else { r.cwd.clone() }
+            }, false)),
+        ];
+        for (label, rows) in groups {
+            all_items.push(kv_row(label, ""));
+            let max_cost = rows.iter().map(|r| r.cost_usd).fold(0.0, f64::max);
+            for row in rows.iter().filter(|r| r.cost_usd > 0.0) {
+                let b = bar((row.cost_usd * 1e6) as u64, (max_cost * 1e6) as u64, 10);
+                all_items.push(ListItem::new(Line::from(vec![
+                    Span::styled(format!("    {:<20}", truncate_key(&row.key, 20)), Style::default().fg(Color::Rgb(150, 145, 185))),
+                    Span::styled(b, Style::default().fg(Color::Rgb(200, 170, 60))),
+                    Span::styled(format!("  {:<9}", fmt_usd(row.cost_usd)), Style::default().fg(Color::White)),
+                    Span::styled(
+                        format!("{} task{}", row.tasks, if row.tasks == 1 { "" } else { "s" }),
+                        Style::default().fg(Color::Rgb(100, 95, 140)),
+                    ),
+                ])));
+            }
+        }
+        all_items.push(divider());
+    }
+
+    // ── Section 5: Per-task table (this session) ──────────────────────────────
     if !s.records.is_empty() {
         all_items.push(section_header("Tasks — this session"));
         all_items.push(ListItem::new(Line::from(vec![
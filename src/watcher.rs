@@ -0,0 +1,207 @@
+/// Live reindexing — keeps `.parecode/project.graph` current while the user
+/// edits files mid-session.
+///
+/// A background thread watches the project (inotify on Linux via `notify`,
+/// falling back to mtime polling when no native watcher is available),
+/// debounces bursts of events, and applies them with
+/// `ProjectGraph::apply_file_changes`. The refreshed graph is sent to the TUI
+/// as `UiEvent::GraphUpdated`, which swaps it in for the next agent turn.
+/// Only directories and files inside the `IndexScope` are watched, extra
+/// `[index] roots` included.
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, channel};
+use std::time::Duration;
+
+use notify::{EventKind, RecursiveMode, Watcher};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::index::IndexScope;
+use crate::pie::ProjectGraph;
+use crate::tui::UiEvent;
+
+/// Quiet period before a burst of events is applied — editors write a file
+/// in several steps (truncate, write, rename, chmod).
+const DEBOUNCE: Duration = Duration::from_millis(400);
+
+/// Scan interval for the polling fallback.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Start watching `root` on a background thread. Runs until `tx` is closed.
+pub fn spawn(root: PathBuf, scope: IndexScope, tx: UnboundedSender<UiEvent>) {
+    std::thread::spawn(move || {
+        if let Err(e) = watch_native(&root, &scope, &tx) {
+            let _ = tx.send(UiEvent::SystemMsg(format!(
+                "◈ file watcher unavailable ({e}) — polling every {}s",
+                POLL_INTERVAL.as_secs()
+            )));
+            watch_polling(&root, &scope, &tx);
+        }
+    });
+}
+
+/// Event-driven loop. Returns `Err` only if the watcher can't be set up;
+/// returns `Ok` when the TUI has gone away.
+fn watch_native(root: &Path, scope: &IndexScope, tx: &UnboundedSender<UiEvent>) -> notify::Result<()> {
+    let (ev_tx, ev_rx) = channel::<PathBuf>();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let Ok(event) = res else { return };
+        if matches!(event.kind, EventKind::Access(_)) {
+            return;
+        }
+        for path in event.paths {
+            let _ = ev_tx.send(path);
+        }
+    })?;
+    // Watch directories one by one rather than recursively so `target/` and
+    // `node_modules/` don't eat the inotify watch limit.
+    for (_, base) in scope.roots(root) {
+        watch_tree(&mut watcher, root, &base, scope)?;
+    }
+
+    while let Some(batch) = next_batch(&ev_rx, DEBOUNCE) {
+        for dir in batch.iter().filter(|p| p.is_dir()) {
+            if relative(root, dir).is_some_and(|rel| scope.accepts_dir(&rel)) {
+                let _ = watch_tree(&mut watcher, root, dir, scope);
+            }
+        }
+        let mut files: Vec<String> = batch.iter().filter_map(|p| source_path(root, p, scope)).collect();
+        files.sort();
+        files.dedup();
+        if !refresh(root, scope, files, tx) {
+            return Ok(());
+        }
+    }
+    Ok(())
+}
+
+/// Fallback: diff mtimes every `POLL_INTERVAL`.
+fn watch_polling(root: &Path, scope: &IndexScope, tx: &UnboundedSender<UiEvent>) {
+    let mut last = crate::pie::mtime_hashes(root, scope);
+    loop {
+        std::thread::sleep(POLL_INTERVAL);
+        let current = crate::pie::mtime_hashes(root, scope);
+        let mut files: Vec<String> = current
+            .iter()
+            .filter(|(path, mtime)| last.get(*path) != Some(*mtime))
+            .map(|(path, _)| path.clone())
+            .chain(last.keys().filter(|p| !current.contains_key(*p)).cloned())
+            .collect();
+        last = current;
+        files.sort();
+        if !refresh(root, scope, files, tx) {
+            return;
+        }
+    }
+}
+
+/// Apply `files` to the on-disk graph and publish it. Returns `false` once
+/// the receiving side is closed.
+fn refresh(root: &Path, scope: &IndexScope, files: Vec<String>, tx: &UnboundedSender<UiEvent>) -> bool {
+    if files.is_empty() {
+        return !tx.is_closed();
+    }
+    // Re-read each time so a `/new` rebuild in between isn't overwritten.
+    let Some(mut graph) = ProjectGraph::load(root) else { return !tx.is_closed() };
+    if !graph.apply_file_changes(&files, root, scope) {
+        return !tx.is_closed();
+    }
+    graph.save(root);
+    tx.send(UiEvent::GraphUpdated { graph, files }).is_ok()
+}
+
+/// Block for the next event, then gather everything that arrives until
+/// `quiet` passes with no new events. `None` when the sender is gone.
+fn next_batch(rx: &Receiver<PathBuf>, quiet: Duration) -> Option<Vec<PathBuf>> {
+    let mut batch = vec![rx.recv().ok()?];
+    loop {
+        match rx.recv_timeout(quiet) {
+            Ok(path) => batch.push(path),
+            Err(RecvTimeoutError::Timeout) => return Some(batch),
+            Err(RecvTimeoutError::Disconnected) => return Some(batch),
+        }
+    }
+}
+
+/// Watch `dir` and every directory below it that `scope` would index.
+fn watch_tree(watcher: &mut impl Watcher, root: &Path, dir: &Path, scope: &IndexScope) -> notify::Result<()> {
+    for dir in scope.dirs(root, dir) {
+        watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+    }
+    Ok(())
+}
+
+fn relative(root: &Path, path: &Path) -> Option<String> {
+    Some(path.strip_prefix(root).ok()?.to_string_lossy().to_string())
+}
+
+/// Project-relative path of an indexable source file, or `None` for build
+/// output, hidden directories, editor swap files, and other extensions.
+fn source_path(root: &Path, path: &Path, scope: &IndexScope) -> Option<String> {
+    relative(root, path).filter(|rel| scope.accepts(rel))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_path_filters_noise() {
+        let root = Path::new("/proj");
+        let scope = IndexScope::default();
+        assert_eq!(source_path(root, Path::new("/proj/src/main.rs"), &scope).as_deref(), Some("src/main.rs"));
+        assert_eq!(source_path(root, Path::new("/proj/web/app.tsx"), &scope).as_deref(), Some("web/app.tsx"));
+        assert_eq!(source_path(root, Path::new("/proj/target/debug/build.rs"), &scope), None);
+        assert_eq!(source_path(root, Path::new("/proj/.parecode/project.graph"), &scope), None);
+        assert_eq!(source_path(root, Path::new("/proj/src/.main.rs.swp"), &scope), None);
+        assert_eq!(source_path(root, Path::new("/proj/src/main.rs~"), &scope), None);
+        assert_eq!(source_path(root, Path::new("/elsewhere/main.rs"), &scope), None);
+
+        let config = crate::config::IndexConfig { roots: vec!["../shared".into()], ..Default::default() };
+        let scope = IndexScope::new(&config);
+        let sibling = Path::new("/proj/../shared/src/lib.rs");
+        assert_eq!(source_path(root, sibling, &scope).as_deref(), Some("../shared/src/lib.rs"));
+    }
+
+    #[test]
+    fn test_next_batch_debounces_until_quiet() {
+        let (tx, rx) = channel();
+        tx.send(PathBuf::from("a.rs")).unwrap();
+        tx.send(PathBuf::from("b.rs")).unwrap();
+        let batch = next_batch(&rx, Duration::from_millis(20)).unwrap();
+        assert_eq!(batch, vec![PathBuf::from("a.rs"), PathBuf::from("b.rs")]);
+
+        drop(tx);
+        assert!(next_batch(&rx, Duration::from_millis(20)).is_none(), "closed channel ends the loop");
+    }
+
+    #[test]
+    fn test_native_watcher_publishes_updated_graph() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let root = tmp.path().canonicalize().unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/lib.rs"), "pub fn alpha() {}\n").unwrap();
+        ProjectGraph::load_or_build(&root, &IndexScope::default());
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        spawn(root.clone(), IndexScope::default(), tx);
+        std::thread::sleep(Duration::from_millis(200));
+        std::fs::write(root.join("src/lib.rs"), "pub fn alpha() {}\npub fn beta() {}\n").unwrap();
+
+        let deadline = std::time::Instant::now() + Duration::from_secs(10);
+        while std::time::Instant::now() < deadline {
+            match rx.try_recv() {
+                Ok(UiEvent::GraphUpdated { graph, files }) => {
+                    assert_eq!(files, vec!["src/lib.rs"]);
+                    assert!(graph.symbols.iter().any(|s| s.name == "beta"));
+                    assert!(ProjectGraph::load(&root).unwrap().symbols.iter().any(|s| s.name == "beta"), "saved");
+                    return;
+                }
+                Ok(_) => {}
+                Err(_) => std::thread::sleep(Duration::from_millis(50)),
+            }
+        }
+        panic!("no GraphUpdated within 10s");
+    }
+}
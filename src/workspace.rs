@@ -0,0 +1,426 @@
+/// Package discovery — reads workspace manifests so PIE can cluster a
+/// monorepo by the packages its build tool knows about instead of by
+/// whatever directory happens to sit under `src/`.
+///
+/// Understood layouts:
+/// - Cargo: `[workspace] members` (globs included, `exclude` honoured) plus a
+///   root `[package]`
+/// - npm / yarn / pnpm: `package.json` `workspaces` and `pnpm-workspace.yaml`
+/// - Go: `go.work` `use` directives, or a lone `go.mod`
+///
+/// Each extra index root (`[index] roots`) is read the same way; a root with
+/// no manifest becomes one package named after its directory. Dependencies
+/// are kept only when they name another detected package, so
+/// `Package::dependencies` is exactly the inter-package edge list.
+use std::collections::HashSet;
+use std::path::Path;
+
+use globset::{GlobBuilder, GlobSetBuilder};
+use ignore::WalkBuilder;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::index::IndexScope;
+
+/// How deep member globs like `packages/**` are expanded.
+const MAX_MEMBER_DEPTH: usize = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageKind {
+    Cargo,
+    Npm,
+    Go,
+    /// An extra index root without any manifest
+    Dir,
+}
+
+impl PackageKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            PackageKind::Cargo => "cargo",
+            PackageKind::Npm => "npm",
+            PackageKind::Go => "go",
+            PackageKind::Dir => "dir",
+        }
+    }
+}
+
+/// One crate, npm package or Go module.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Package {
+    /// Crate name, `package.json` name or Go module path
+    pub name: String,
+    /// Directory relative to the project root — `""` for the root itself,
+    /// `"../shared"` for an extra root
+    pub dir: String,
+    pub kind: PackageKind,
+    /// Names of the other detected packages this one depends on, sorted
+    pub dependencies: Vec<String>,
+}
+
+impl Package {
+    /// Whether the relative file path `file` lies inside this package.
+    pub fn contains(&self, file: &str) -> bool {
+        if self.dir.is_empty() {
+            return !file.starts_with("../");
+        }
+        file.strip_prefix(self.dir.as_str()).is_some_and(|rest| rest.starts_with('/'))
+    }
+}
+
+/// The innermost package containing `file`.
+pub fn package_for<'a>(packages: &'a [Package], file: &str) -> Option<&'a Package> {
+    packages.iter().filter(|p| p.contains(file)).max_by_key(|p| p.dir.len())
+}
+
+/// Every package under `root` and the extra roots of `scope`, sorted by
+/// directory. Empty for a project without manifests (unless extra roots are
+/// configured, in which case each root is at least one package).
+pub fn detect(root: &Path, scope: &IndexScope) -> Vec<Package> {
+    let roots = scope.roots(root);
+    let multi_root = roots.len() > 1;
+    let mut found: Vec<(Package, Vec<String>)> = Vec::new();
+    for (prefix, base) in roots {
+        let start = found.len();
+        cargo_packages(&base, &mut found);
+        npm_packages(&base, &mut found);
+        go_packages(&base, &mut found);
+        for (package, _) in &mut found[start..] {
+            package.dir = join(&prefix, &package.dir);
+        }
+        if multi_root && found.len() == start {
+            let name = base
+                .canonicalize()
+                .ok()
+                .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+                .unwrap_or_else(|| prefix.rsplit('/').next().unwrap_or("root").to_string());
+            found.push((Package { name, dir: prefix, kind: PackageKind::Dir, dependencies: Vec::new() }, Vec::new()));
+        }
+    }
+
+    let mut seen = HashSet::new();
+    found.retain(|(p, _)| seen.insert((p.dir.clone(), p.kind)));
+    let names: HashSet<String> = found.iter().map(|(p, _)| p.name.clone()).collect();
+    let mut packages: Vec<Package> = found
+        .into_iter()
+        .map(|(mut package, deps)| {
+            let mut deps: Vec<String> = deps.into_iter().filter(|d| *d != package.name && names.contains(d)).collect();
+            deps.sort();
+            deps.dedup();
+            package.dependencies = deps;
+            package
+        })
+        .collect();
+    packages.sort_by(|a, b| a.dir.cmp(&b.dir).then(a.name.cmp(&b.name)));
+    packages
+}
+
+// ── Cargo ─────────────────────────────────────────────────────────────────────
+
+fn cargo_packages(base: &Path, out: &mut Vec<(Package, Vec<String>)>) {
+    let Some(manifest) = read_toml(&base.join("Cargo.toml")) else { return };
+    out.extend(cargo_package(&manifest, ""));
+    let Some(workspace) = manifest.get("workspace") else { return };
+    let members = string_list(workspace.get("members").and_then(|m| m.as_array()), |v| v.as_str());
+    let exclude = string_list(workspace.get("exclude").and_then(|m| m.as_array()), |v| v.as_str());
+    for dir in expand_members(base, &members, &exclude) {
+        if dir.is_empty() {
+            continue;
+        }
+        if let Some(member) = read_toml(&base.join(&dir).join("Cargo.toml")) {
+            out.extend(cargo_package(&member, &dir));
+        }
+    }
+}
+
+fn cargo_package(manifest: &toml::Table, dir: &str) -> Option<(Package, Vec<String>)> {
+    let name = manifest.get("package")?.get("name")?.as_str()?;
+    let mut deps = Vec::new();
+    for section in ["dependencies", "dev-dependencies", "build-dependencies"] {
+        let Some(table) = manifest.get(section).and_then(|t| t.as_table()) else { continue };
+        for (key, spec) in table {
+            // `alias = { package = "real-name", .. }` renames a dependency
+            deps.push(spec.get("package").and_then(|p| p.as_str()).unwrap_or(key).to_string());
+        }
+    }
+    let package = Package { name: name.to_string(), dir: dir.to_string(), kind: PackageKind::Cargo, dependencies: Vec::new() };
+    Some((package, deps))
+}
+
+fn read_toml(path: &Path) -> Option<toml::Table> {
+    toml::from_str(&std::fs::read_to_string(path).ok()?).ok()
+}
+
+// ── npm / yarn / pnpm ─────────────────────────────────────────────────────────
+
+fn npm_packages(base: &Path, out: &mut Vec<(Package, Vec<String>)>) {
+    let manifest = read_json(&base.join("package.json"));
+    let mut patterns = match manifest.as_ref().and_then(|m| m.get("workspaces")) {
+        Some(Value::Array(list)) => string_list(Some(list), |v| v.as_str()),
+        Some(Value::Object(obj)) => string_list(obj.get("packages").and_then(|p| p.as_array()), |v| v.as_str()),
+        _ => Vec::new(),
+    };
+    if let Ok(yaml) = std::fs::read_to_string(base.join("pnpm-workspace.yaml")) {
+        patterns.extend(pnpm_packages(&yaml));
+    }
+    if patterns.is_empty() {
+        // A single package — the root manifest of a workspace is usually a private shell
+        out.extend(manifest.as_ref().and_then(|m| npm_package(m, "")));
+        return;
+    }
+    let (exclude, include): (Vec<String>, Vec<String>) = patterns.into_iter().partition(|p| p.starts_with('!'));
+    let exclude: Vec<String> = exclude.iter().map(|p| p[1..].to_string()).collect();
+    for dir in expand_members(base, &include, &exclude) {
+        if let Some(member) = read_json(&base.join(&dir).join("package.json")) {
+            out.extend(npm_package(&member, &dir));
+        }
+    }
+}
+
+fn npm_package(manifest: &Value, dir: &str) -> Option<(Package, Vec<String>)> {
+    let name = manifest.get("name")?.as_str()?;
+    let deps = ["dependencies", "devDependencies", "peerDependencies"]
+        .iter()
+        .filter_map(|section| manifest.get(section)?.as_object())
+        .flat_map(|deps| deps.keys().cloned())
+        .collect();
+    let package = Package { name: name.to_string(), dir: dir.to_string(), kind: PackageKind::Npm, dependencies: Vec::new() };
+    Some((package, deps))
+}
+
+/// Entries of the top-level `packages:` list in `pnpm-workspace.yaml`.
+fn pnpm_packages(yaml: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut in_list = false;
+    for line in yaml.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if !line.starts_with([' ', '\t', '-']) {
+            in_list = trimmed.starts_with("packages:");
+            continue;
+        }
+        if let Some(item) = trimmed.strip_prefix('-').filter(|_| in_list) {
+            let item = item.split(" #").next().unwrap_or("").trim().trim_matches(['"', '\'']);
+            if !item.is_empty() {
+                out.push(item.to_string());
+            }
+        }
+    }
+    out
+}
+
+fn read_json(path: &Path) -> Option<Value> {
+    serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()
+}
+
+// ── Go ────────────────────────────────────────────────────────────────────────
+
+fn go_packages(base: &Path, out: &mut Vec<(Package, Vec<String>)>) {
+    match std::fs::read_to_string(base.join("go.work")) {
+        Ok(work) => {
+            for dir in go_directive(&work, "use") {
+                out.extend(go_module(base, &clean_dir(dir.trim_matches('"'))));
+            }
+        }
+        Err(_) => out.extend(go_module(base, "")),
+    }
+}
+
+fn go_module(base: &Path, dir: &str) -> Option<(Package, Vec<String>)> {
+    let text = std::fs::read_to_string(base.join(dir).join("go.mod")).ok()?;
+    let name = go_directive(&text, "module").into_iter().next()?;
+    let deps = go_directive(&text, "require")
+        .iter()
+        .filter_map(|line| line.split_whitespace().next().map(str::to_string))
+        .collect();
+    let package = Package { name: name.trim_matches('"').to_string(), dir: dir.to_string(), kind: PackageKind::Go, dependencies: Vec::new() };
+    Some((package, deps))
+}
+
+/// Arguments of `directive` in a `go.mod` / `go.work` file, from both the
+/// one-line and the parenthesised block form — one entry per line.
+fn go_directive(text: &str, directive: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut in_block = false;
+    for line in text.lines() {
+        let line = line.split("//").next().unwrap_or("").trim();
+        if in_block {
+            if line == ")" {
+                in_block = false;
+            } else if !line.is_empty() {
+                out.push(line.to_string());
+            }
+        } else if let Some(rest) = line.strip_prefix(directive).filter(|r| r.starts_with([' ', '\t', '('])) {
+            match rest.trim() {
+                "(" => in_block = true,
+                arg => out.push(arg.to_string()),
+            }
+        }
+    }
+    out
+}
+
+// ── Helpers ───────────────────────────────────────────────────────────────────
+
+/// Resolve member patterns (`crates/*`, `packages/**`, `apps/web`) to
+/// directories relative to `base`, sorted. Only existing directories are
+/// returned; callers skip the ones without a manifest.
+fn expand_members(base: &Path, include: &[String], exclude: &[String]) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut globs = GlobSetBuilder::new();
+    let mut has_globs = false;
+    for pattern in include.iter().map(|p| clean_dir(p)) {
+        if pattern.contains(['*', '?', '[', '{']) {
+            if let Ok(glob) = GlobBuilder::new(&pattern).literal_separator(true).build() {
+                globs.add(glob);
+                has_globs = true;
+            }
+        } else if base.join(&pattern).is_dir() {
+            out.push(pattern);
+        }
+    }
+    if has_globs && let Ok(set) = globs.build() {
+        let walk = WalkBuilder::new(base)
+            .hidden(true)
+            .require_git(false)
+            .max_depth(Some(MAX_MEMBER_DEPTH))
+            .filter_entry(|e| !matches!(e.file_name().to_str(), Some("node_modules" | "target")))
+            .build();
+        for entry in walk.flatten().filter(|e| e.file_type().is_some_and(|t| t.is_dir())) {
+            let Ok(rel) = entry.path().strip_prefix(base) else { continue };
+            if !rel.as_os_str().is_empty() && set.is_match(rel) {
+                out.push(rel.to_string_lossy().to_string());
+            }
+        }
+    }
+
+    let mut excluded = GlobSetBuilder::new();
+    for pattern in exclude.iter().map(|p| clean_dir(p)) {
+        if let Ok(glob) = GlobBuilder::new(&pattern).literal_separator(true).build() {
+            excluded.add(glob);
+        }
+    }
+    if let Ok(excluded) = excluded.build() {
+        out.retain(|dir| !excluded.is_match(dir));
+    }
+    out.sort();
+    out.dedup();
+    out
+}
+
+/// `./crates/a/` → `crates/a`, `.` → `""`.
+fn clean_dir(pattern: &str) -> String {
+    let pattern = pattern.trim().trim_end_matches('/');
+    let pattern = pattern.strip_prefix("./").unwrap_or(pattern);
+    if pattern == "." { String::new() } else { pattern.to_string() }
+}
+
+fn join(prefix: &str, dir: &str) -> String {
+    match (prefix.is_empty(), dir.is_empty()) {
+        (true, _) => dir.to_string(),
+        (_, true) => prefix.to_string(),
+        _ => format!("{prefix}/{dir}"),
+    }
+}
+
+fn string_list<T>(list: Option<&Vec<T>>, as_str: impl Fn(&T) -> Option<&str>) -> Vec<String> {
+    list.into_iter().flatten().filter_map(|v| as_str(v).map(str::to_string)).collect()
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::IndexConfig;
+
+    fn write(dir: &Path, rel: &str, content: &str) {
+        let path = dir.join(rel);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    fn summary(packages: &[Package]) -> Vec<(&str, &str, Vec<&str>)> {
+        packages
+            .iter()
+            .map(|p| (p.name.as_str(), p.dir.as_str(), p.dependencies.iter().map(String::as_str).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn test_cargo_workspace_members_and_dependencies() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let root = tmp.path();
+        write(root, "Cargo.toml", "[workspace]\nmembers = [\"crates/*\", \"tools/cli\"]\nexclude = [\"crates/scratch\"]\n");
+        write(root, "crates/core/Cargo.toml", "[package]\nname = \"app-core\"\n[dependencies]\nserde = \"1\"\n");
+        write(
+            root,
+            "crates/web/Cargo.toml",
+            "[package]\nname = \"app-web\"\n[dependencies]\ncore = { package = \"app-core\", path = \"../core\" }\n",
+        );
+        write(root, "crates/scratch/Cargo.toml", "[package]\nname = \"scratch\"\n");
+        write(root, "crates/notes/README.md", "");
+        write(root, "tools/cli/Cargo.toml", "[package]\nname = \"cli\"\n[dev-dependencies]\napp-web = { workspace = true }\n");
+
+        let packages = detect(root, &IndexScope::default());
+        assert_eq!(
+            summary(&packages),
+            vec![
+                ("app-core", "crates/core", vec![]),
+                ("app-web", "crates/web", vec!["app-core"]),
+                ("cli", "tools/cli", vec!["app-web"]),
+            ]
+        );
+        assert!(packages.iter().all(|p| p.kind == PackageKind::Cargo));
+        assert_eq!(package_for(&packages, "crates/web/src/lib.rs").map(|p| p.name.as_str()), Some("app-web"));
+        assert_eq!(package_for(&packages, "crates/webby/src/lib.rs"), None, "directory prefix, not string prefix");
+    }
+
+    #[test]
+    fn test_npm_pnpm_and_go_workspaces() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let root = tmp.path();
+        write(root, "package.json", r#"{"name": "mono", "private": true, "workspaces": {"packages": ["packages/*"]}}"#);
+        write(root, "pnpm-workspace.yaml", "packages:\n  - 'apps/*'   # deployables\n  - \"!apps/legacy\"\ncatalog:\n  - nope\n");
+        write(root, "packages/ui/package.json", r#"{"name": "@acme/ui", "peerDependencies": {"react": "18"}}"#);
+        write(root, "apps/site/package.json", r#"{"name": "site", "dependencies": {"@acme/ui": "workspace:*"}}"#);
+        write(root, "apps/legacy/package.json", r#"{"name": "legacy"}"#);
+        write(root, "go.work", "go 1.22\n\nuse (\n\t./svc/api // public\n\t./svc/auth\n)\n");
+        write(root, "svc/api/go.mod", "module example.com/api\n\nrequire (\n\texample.com/auth v0.0.0\n\tgithub.com/x/y v1.0.0\n)\n");
+        write(root, "svc/auth/go.mod", "module example.com/auth\n");
+
+        assert_eq!(
+            summary(&detect(root, &IndexScope::default())),
+            vec![
+                ("site", "apps/site", vec!["@acme/ui"]),
+                ("@acme/ui", "packages/ui", vec![]),
+                ("example.com/api", "svc/api", vec!["example.com/auth"]),
+                ("example.com/auth", "svc/auth", vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extra_roots_become_packages() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        write(tmp.path(), "app/package.json", r#"{"name": "app", "dependencies": {"shared-lib": "file:../shared"}}"#);
+        write(tmp.path(), "shared/package.json", r#"{"name": "shared-lib"}"#);
+        write(tmp.path(), "scripts/run.py", "");
+        let root = tmp.path().join("app");
+
+        assert_eq!(summary(&detect(&root, &IndexScope::default())), vec![("app", "", vec![])], "deps on unknown packages dropped");
+
+        let config = IndexConfig { roots: vec!["../shared".into(), "../scripts".into()], ..IndexConfig::default() };
+        let packages = detect(&root, &IndexScope::new(&config));
+        assert_eq!(
+            summary(&packages),
+            vec![("app", "", vec!["shared-lib"]), ("scripts", "../scripts", vec![]), ("shared-lib", "../shared", vec![])]
+        );
+        assert_eq!(packages[1].kind, PackageKind::Dir);
+        assert_eq!(package_for(&packages, "../shared/index.ts").map(|p| p.name.as_str()), Some("shared-lib"));
+        assert_eq!(package_for(&packages, "src/index.ts").map(|p| p.name.as_str()), Some("app"));
+    }
+}
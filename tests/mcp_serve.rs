@@ -0,0 +1,127 @@
+//! End-to-end check of `parecode mcp-serve`: the real binary, driven over
+//! stdio one JSON-RPC message per line, the way an editor would.
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+struct Server {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: u64,
+}
+
+impl Server {
+    /// Serve `root` with a config dir of its own, so the user's config stays out of it.
+    fn start(root: &std::path::Path, config_home: &std::path::Path) -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_parecode"))
+            .args(["mcp-serve", "--root"])
+            .arg(root)
+            .env("XDG_CONFIG_HOME", config_home)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        Server { child, stdin, stdout, next_id: 0 }
+    }
+
+    fn send(&mut self, message: Value) {
+        writeln!(self.stdin, "{message}").unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    /// Send a request and wait for its response, skipping anything else.
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+        loop {
+            let mut line = String::new();
+            assert!(self.stdout.read_line(&mut line).unwrap() > 0, "server exited before answering {method}");
+            let message: Value = serde_json::from_str(&line).unwrap();
+            if message["id"] == id {
+                return message;
+            }
+        }
+    }
+
+    /// Call a tool and return its text, tool errors included.
+    fn call(&mut self, name: &str, arguments: Value) -> String {
+        let reply = self.request("tools/call", json!({ "name": name, "arguments": arguments }));
+        reply["result"]["content"][0]["text"].as_str().unwrap_or_else(|| panic!("{reply}")).to_string()
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[test]
+fn test_tools_over_stdio() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_home = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(config_home.path().join("parecode")).unwrap();
+    std::fs::write(
+        config_home.path().join("parecode/config.toml"),
+        r#"
+        [profiles.default]
+        endpoint = "http://localhost:1/v1/chat/completions"
+        model = "test"
+        [profiles.default.hooks]
+        on_edit = ["echo checked"]
+        "#,
+    )
+    .unwrap();
+    let lib = dir.path().join("lib.rs");
+    std::fs::write(&lib, "pub fn alpha() -> u32 {\n    1\n}\n\npub fn beta() -> u32 {\n    alpha() + 1\n}\n").unwrap();
+
+    let mut server = Server::start(dir.path(), config_home.path());
+    let init = server.request(
+        "initialize",
+        json!({ "protocolVersion": "2025-06-18", "capabilities": {}, "clientInfo": { "name": "test", "version": "0" } }),
+    );
+    assert_eq!(init["result"]["protocolVersion"], "2025-06-18", "{init}");
+    server.send(json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }));
+
+    let listed = server.request("tools/list", json!({}));
+    let tools: Vec<&str> = listed["result"]["tools"].as_array().unwrap().iter().filter_map(|t| t["name"].as_str()).collect();
+    assert!(tools.contains(&"find_symbol"), "{tools:?}");
+    assert!(tools.contains(&"edit_file"));
+    assert!(!tools.contains(&"ask_user"));
+    assert!(!tools.contains(&"semantic_search"));
+
+    // Graph tools answer from the project graph
+    let found = server.call("find_symbol", json!({ "name": "alpha" }));
+    assert!(found.contains("lib.rs"), "{found}");
+
+    // A re-read is served from the cache while the file is unchanged...
+    let first = server.call("read_file", json!({ "path": "lib.rs" }));
+    assert!(first.contains("pub fn beta"), "{first}");
+    let again = server.call("read_file", json!({ "path": "lib.rs" }));
+    assert!(again.contains("Returning cached version"), "{again}");
+
+    // ...and re-read from disk once someone else changes it
+    std::fs::write(&lib, "pub fn alpha() -> u32 {\n    2\n}\n\npub fn beta() -> u32 {\n    alpha() + 1\n}\n").unwrap();
+    filetime::set_file_mtime(&lib, filetime::FileTime::from_unix_time(1_000_000_000, 0)).unwrap();
+    let fresh = server.call("read_file", json!({ "path": "lib.rs" }));
+    assert!(!fresh.contains("Returning cached version"), "{fresh}");
+
+    // Edits run the on_edit hooks and reindex before the next call
+    let edited = server.call("edit_file", json!({ "path": "lib.rs", "old_str": "pub fn beta()", "new_str": "pub fn gamma()" }));
+    assert!(edited.contains("✓ Edited"), "{edited}");
+    assert!(edited.contains("⚙ `echo checked`"), "{edited}");
+    assert!(std::fs::read_to_string(&lib).unwrap().contains("pub fn gamma()"));
+    let found = server.call("find_symbol", json!({ "name": "gamma" }));
+    assert!(found.contains("lib.rs"), "{found}");
+
+    // Failures come back as tool errors, not protocol errors
+    let failed = server.call("edit_file", json!({ "path": "lib.rs", "old_str": "pub fn delta()", "new_str": "x" }));
+    assert!(failed.contains("[Tool error"), "{failed}");
+    assert!(server.call("ask_user", json!({})).contains("unknown tool"));
+}